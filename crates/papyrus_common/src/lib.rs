@@ -9,6 +9,7 @@ pub mod metrics;
 pub mod patricia_hash_tree;
 pub mod pending_classes;
 pub mod state;
+pub mod state_commitment;
 pub mod state_diff_commitment;
 pub mod storage_query;
 pub mod transaction_hash;
@@ -33,6 +33,15 @@ pub const PAPYRUS_NUM_ACTIVE_INBOUND_SESSIONS: &str = "papyrus_num_active_inboun
 /// The number of active sessions this peer has in which it requests data.
 pub const PAPYRUS_NUM_ACTIVE_OUTBOUND_SESSIONS: &str = "papyrus_num_active_outbound_sessions";
 
+/// The number of state reads that were served from the state cache of the storage.
+pub const PAPYRUS_STATE_CACHE_HITS: &str = "papyrus_state_cache_hits";
+
+/// The number of state reads that weren't found in the state cache of the storage.
+pub const PAPYRUS_STATE_CACHE_MISSES: &str = "papyrus_state_cache_misses";
+
+/// The number of synced blocks that failed a verification, labeled by the failed check.
+pub const PAPYRUS_BLOCK_VERIFICATION_FAILURES: &str = "papyrus_block_verification_failures";
+
 // TODO: consider making this value non static and add a way to change this while the app is
 // running. e.g via a monitoring endpoint.
 /// Global variable set by the main config to enable collecting profiling metrics.
@@ -19,14 +19,17 @@
 //! - A leaf: The hash is the input value of its key.
 //! - A single edge: pedersen_hash(child_hash, edge_mark) + edge_length.
 //! - '0' and '1' edges: pedersen_hash(zero_child_hash, one_child_hash).
+//!
+//! The node hash functions are exposed so they can be used by other Patricia trees, such as the
+//! state tries, that may use a different height and hash function.
 
 #[cfg(test)]
 #[path = "patricia_hash_tree_test.rs"]
 mod patricia_hash_tree_test;
 
 use bitvec::prelude::{BitArray, Msb0};
-use starknet_api::hash::{pedersen_hash, StarkFelt};
-use starknet_crypto::FieldElement;
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_crypto::{poseidon_hash, FieldElement};
 
 use crate::transaction_hash::ZERO;
 
@@ -56,6 +59,47 @@ enum SubTreeSplitting {
     PartitionPoint(usize),
 }
 
+/// The hash function used for hashing the nodes of a Patricia tree.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TreeHashFunction {
+    #[default]
+    Pedersen,
+    Poseidon,
+}
+
+impl TreeHashFunction {
+    /// Hashes two felts with the hash function.
+    pub fn hash(&self, left: &StarkFelt, right: &StarkFelt) -> StarkHash {
+        match self {
+            TreeHashFunction::Pedersen => pedersen_hash(left, right),
+            TreeHashFunction::Poseidon => {
+                poseidon_hash(FieldElement::from(*left), FieldElement::from(*right)).into()
+            }
+        }
+    }
+}
+
+/// Calculates the hash of a node with two children: hash(left_child_hash, right_child_hash).
+pub fn calculate_binary_node_hash(
+    hash_function: TreeHashFunction,
+    left: &StarkHash,
+    right: &StarkHash,
+) -> StarkHash {
+    hash_function.hash(left, right)
+}
+
+/// Calculates the hash of a node with a single edge: hash(child_hash, edge_path) + edge_length.
+/// The edge path is the big-endian value of the edge's bits.
+pub fn calculate_edge_node_hash(
+    hash_function: TreeHashFunction,
+    child: &StarkHash,
+    path: &StarkFelt,
+    length: u8,
+) -> StarkHash {
+    let child_and_path_hash = hash_function.hash(child, path);
+    StarkFelt::from(FieldElement::from(child_and_path_hash) + FieldElement::from(length))
+}
+
 /// Calculates Patricia hash root on the given values.
 /// The values are keyed by consecutive numbers, starting from 0.
 pub fn calculate_root(values: Vec<StarkFelt>) -> StarkFelt {
@@ -91,8 +135,7 @@ fn get_hash(sub_tree: SubTree<'_>) -> StarkFelt {
 fn get_edge_hash(sub_tree: SubTree<'_>, n_zeros: u8) -> StarkFelt {
     let child_hash =
         get_hash(SubTree { leaves: sub_tree.leaves, height: sub_tree.height + n_zeros });
-    let child_and_path_hash = pedersen_hash(&child_hash, &ZERO);
-    StarkFelt::from(FieldElement::from(child_and_path_hash) + FieldElement::from(n_zeros))
+    calculate_edge_node_hash(TreeHashFunction::Pedersen, &child_hash, &ZERO, n_zeros)
 }
 
 // Hash on both sides: starts with '0' bit and starts with '1' bit.
@@ -106,7 +149,7 @@ fn get_binary_hash(sub_tree: SubTree<'_>, partition_point: usize) -> StarkFelt {
         leaves: &sub_tree.leaves[partition_point..],
         height: sub_tree.height + 1,
     });
-    pedersen_hash(&zero_hash, &one_hash)
+    calculate_binary_node_hash(TreeHashFunction::Pedersen, &zero_hash, &one_hash)
 }
 
 // Returns the manner the keys of a subtree are splitting: some keys start with '1' or all keys
@@ -0,0 +1,59 @@
+#[cfg(test)]
+#[path = "state_commitment_test.rs"]
+mod state_commitment_test;
+
+use lazy_static::lazy_static;
+use starknet_api::core::{ClassHash, CompiledClassHash, GlobalRoot, Nonce};
+use starknet_api::hash::{pedersen_hash, poseidon_hash_array, StarkFelt, StarkHash};
+
+use crate::patricia_hash_tree::TreeHashFunction;
+use crate::transaction_hash::{ascii_as_felt, ZERO};
+
+/// The height of the tries that make up the global state commitment (the contracts trie, the
+/// classes trie and the storage trie of each contract).
+pub const STATE_TRIE_HEIGHT: u8 = 251;
+
+/// The hash function of the contracts trie and the contracts' storage tries.
+pub const CONTRACTS_TRIE_HASH_FUNCTION: TreeHashFunction = TreeHashFunction::Pedersen;
+
+/// The hash function of the classes trie.
+pub const CLASSES_TRIE_HASH_FUNCTION: TreeHashFunction = TreeHashFunction::Poseidon;
+
+lazy_static! {
+    static ref CONTRACT_CLASS_LEAF_V0: StarkFelt = #[allow(clippy::unwrap_used)]
+    ascii_as_felt("CONTRACT_CLASS_LEAF_V0").unwrap();
+    static ref STARKNET_STATE_V0: StarkFelt = #[allow(clippy::unwrap_used)]
+    ascii_as_felt("STARKNET_STATE_V0").unwrap();
+}
+
+/// Calculates the leaf of a contract in the contracts trie:
+/// pedersen(pedersen(pedersen(class_hash, storage_root), nonce), 0).
+pub fn calculate_contract_state_hash(
+    class_hash: &ClassHash,
+    storage_root: &StarkHash,
+    nonce: &Nonce,
+) -> StarkHash {
+    let class_and_storage_hash = pedersen_hash(&class_hash.0, storage_root);
+    let with_nonce_hash = pedersen_hash(&class_and_storage_hash, &nonce.0);
+    pedersen_hash(&with_nonce_hash, &ZERO)
+}
+
+/// Calculates the leaf of a class in the classes trie:
+/// poseidon("CONTRACT_CLASS_LEAF_V0", compiled_class_hash).
+pub fn calculate_class_leaf_hash(compiled_class_hash: &CompiledClassHash) -> StarkHash {
+    CLASSES_TRIE_HASH_FUNCTION.hash(&CONTRACT_CLASS_LEAF_V0, &compiled_class_hash.0)
+}
+
+/// Calculates the global state root from the roots of the contracts trie and the classes trie.
+/// As long as there are no declared classes, the global root is the contracts trie root.
+pub fn calculate_global_root(
+    contracts_trie_root: &StarkHash,
+    classes_trie_root: &StarkHash,
+) -> GlobalRoot {
+    if *classes_trie_root == *ZERO {
+        return GlobalRoot(*contracts_trie_root);
+    }
+    GlobalRoot(
+        poseidon_hash_array(&[*STARKNET_STATE_V0, *contracts_trie_root, *classes_trie_root]).0,
+    )
+}
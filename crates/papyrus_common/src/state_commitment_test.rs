@@ -0,0 +1,72 @@
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, CompiledClassHash, GlobalRoot, Nonce};
+use starknet_api::hash::{pedersen_hash, poseidon_hash_array, StarkFelt};
+use starknet_api::stark_felt;
+use starknet_crypto::{poseidon_hash, FieldElement};
+
+use crate::state_commitment::{
+    calculate_class_leaf_hash,
+    calculate_contract_state_hash,
+    calculate_global_root,
+};
+use crate::transaction_hash::ascii_as_felt;
+
+#[test]
+fn contract_state_hash() {
+    let class_hash = ClassHash(stark_felt!("0x10"));
+    let storage_root = stark_felt!("0x20");
+    let nonce = Nonce(stark_felt!("0x1"));
+    let expected = pedersen_hash(
+        &pedersen_hash(&pedersen_hash(&class_hash.0, &storage_root), &nonce.0),
+        &StarkFelt::ZERO,
+    );
+    assert_eq!(calculate_contract_state_hash(&class_hash, &storage_root, &nonce), expected);
+}
+
+// A contract of the Starknet testnet, as used in pathfinder's tests.
+#[test]
+fn known_contract_state_hash() {
+    let class_hash =
+        ClassHash(stark_felt!("0x2ff4903e17f87b298ded00c44bfeb22874c5f73be2ced8f1d9d9556fb509779"));
+    let storage_root =
+        stark_felt!("0x4fb440e8ca9b74fc12a22ebffe0bc0658206337897226117b985434c239c028");
+    assert_eq!(
+        calculate_contract_state_hash(&class_hash, &storage_root, &Nonce::default()),
+        stark_felt!("0x7161b591c893836263a64f2a7e0d829c92f6956148a60ce5e99a3f55c7973f3")
+    );
+}
+
+#[test]
+fn class_leaf_hash() {
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x700"));
+    let expected: StarkFelt = poseidon_hash(
+        FieldElement::from(ascii_as_felt("CONTRACT_CLASS_LEAF_V0").unwrap()),
+        FieldElement::from(compiled_class_hash.0),
+    )
+    .into();
+    assert_eq!(calculate_class_leaf_hash(&compiled_class_hash), expected);
+}
+
+#[test]
+fn global_root_without_classes() {
+    let contracts_trie_root = stark_felt!("0x123");
+    assert_eq!(
+        calculate_global_root(&contracts_trie_root, &StarkFelt::ZERO),
+        GlobalRoot(contracts_trie_root)
+    );
+}
+
+#[test]
+fn global_root_with_classes() {
+    let contracts_trie_root = stark_felt!("0x123");
+    let classes_trie_root = stark_felt!("0x456");
+    let expected = poseidon_hash_array(&[
+        ascii_as_felt("STARKNET_STATE_V0").unwrap(),
+        contracts_trie_root,
+        classes_trie_root,
+    ]);
+    assert_eq!(
+        calculate_global_root(&contracts_trie_root, &classes_trie_root),
+        GlobalRoot(expected.0)
+    );
+}
@@ -1,4 +1,4 @@
-//! Contains only the [StorageQuery] struct.
+//! Contains only the [StorageQuery] struct and the [DocumentedQuery] record of its execution.
 //!
 //! The struct is used in the storage_benchmark binary and in the document_calls feature of the
 //! [papyrus_storage] library. It is not part of the latter because it is not in
@@ -9,13 +9,20 @@
 // TODO(dvir): add links to the document for the storage_benchmark binary and the
 // document_calls feature after they will be publish.
 
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
-use starknet_api::core::ContractAddress;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, ContractAddress};
 use starknet_api::state::{StateNumber, StorageKey};
+use starknet_api::transaction::{
+    EventIndexInTransactionOutput,
+    EventKey,
+    TransactionOffsetInBlock,
+};
 
 /// A storage query. Used for benchmarking in the storage_benchmark binary and in the document_calls
 /// feature of the [papyrus_storage](https://docs.rs/papyrus_storage/latest/papyrus_storage/).
-// TODO(dvir): add more queries (especially get casm).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum StorageQuery {
@@ -25,4 +32,77 @@ pub enum StorageQuery {
     GetNonceAt(StateNumber, ContractAddress),
     /// Get the storage at a given state number.
     GetStorageAt(StateNumber, ContractAddress, StorageKey),
+    /// Get the class definition at a given state number.
+    GetClassDefinitionAt(StateNumber, ClassHash),
+    /// Get the deprecated class definition at a given state number.
+    GetDeprecatedClassDefinitionAt(StateNumber, ClassHash),
+    /// Get the casm of a class.
+    GetCasm(ClassHash),
+    /// Get the header of a block.
+    GetBlockHeader(BlockNumber),
+    /// Get the transaction at a given offset in a block.
+    GetTransaction(BlockNumber, TransactionOffsetInBlock),
+    /// Get the transaction output at a given offset in a block.
+    GetTransactionOutput(BlockNumber, TransactionOffsetInBlock),
+    /// Get the state diff of a block.
+    GetStateDiff(BlockNumber),
+    /// Iterate over the events, of all the contracts or of a single contract, from a given event
+    /// up to a given block, reading the first n_events of them.
+    IterEvents {
+        /// The contract that emitted the events, or None for all the contracts.
+        address: Option<ContractAddress>,
+        /// The block, transaction offset and index in the transaction output of the first event.
+        from_event: (BlockNumber, TransactionOffsetInBlock, EventIndexInTransactionOutput),
+        /// The last block to iterate events from.
+        to_block_number: BlockNumber,
+        /// The number of events that were read.
+        n_events: usize,
+    },
+    /// Iterate over the events that have one of the given keys at the given position of their
+    /// keys, from a given event up to a given block, reading the first n_events of them.
+    IterEventsByKeys {
+        /// The position of the key in the keys of the event.
+        key_position: usize,
+        /// The keys to iterate over the events that have one of them at the key position.
+        keys: Vec<EventKey>,
+        /// The block, transaction offset and index in the transaction output of the first event.
+        from_event: (BlockNumber, TransactionOffsetInBlock, EventIndexInTransactionOutput),
+        /// The last block to iterate events from.
+        to_block_number: BlockNumber,
+        /// The number of events that were read.
+        n_events: usize,
+    },
+}
+
+impl StorageQuery {
+    /// Returns the name of the kind of the query, used for grouping queries in the benchmark
+    /// results.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageQuery::GetClassHashAt(..) => "get_class_hash_at",
+            StorageQuery::GetNonceAt(..) => "get_nonce_at",
+            StorageQuery::GetStorageAt(..) => "get_storage_at",
+            StorageQuery::GetClassDefinitionAt(..) => "get_class_definition_at",
+            StorageQuery::GetDeprecatedClassDefinitionAt(..) => {
+                "get_deprecated_class_definition_at"
+            }
+            StorageQuery::GetCasm(..) => "get_casm",
+            StorageQuery::GetBlockHeader(..) => "get_block_header",
+            StorageQuery::GetTransaction(..) => "get_transaction",
+            StorageQuery::GetTransactionOutput(..) => "get_transaction_output",
+            StorageQuery::GetStateDiff(..) => "get_state_diff",
+            StorageQuery::IterEvents { .. } => "iter_events",
+            StorageQuery::IterEventsByKeys { .. } => "iter_events_by_keys",
+        }
+    }
+}
+
+/// A query documented by the document_calls feature, with the time it took to execute it. The time
+/// of an events iteration is from the creation of the iterator until it's dropped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentedQuery {
+    /// The executed query.
+    pub query: StorageQuery,
+    /// The time it took to execute the query.
+    pub duration: Duration,
 }
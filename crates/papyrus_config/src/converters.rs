@@ -78,3 +78,17 @@ where
     }
     Ok(Some(map))
 }
+
+/// Serializes a vector to "v1 v2 ..." string structure.
+pub fn serialize_slice(slice: &[String]) -> String {
+    slice.join(" ")
+}
+
+/// Deserializes a vector from "v1 v2 ..." string structure.
+pub fn deserialize_vec<'de, D>(de: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw_str: String = Deserialize::deserialize(de)?;
+    Ok(raw_str.split_whitespace().map(str::to_string).collect())
+}
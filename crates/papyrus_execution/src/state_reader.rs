@@ -24,6 +24,8 @@ use crate::execution_utils::{get_contract_class, ExecutionUtilsError};
 use crate::objects::PendingData;
 
 /// A view into the state at a specific state number.
+/// The storage values and the nonces that are read are cached by the storage reader and shared
+/// between all the readers of the same storage (see `state_cache_size` in the storage config).
 pub struct ExecutionStateReader {
     pub storage_reader: StorageReader,
     pub state_number: StateNumber,
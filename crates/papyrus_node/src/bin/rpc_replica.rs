@@ -0,0 +1,181 @@
+// config compiler to support coverage_attribute feature when running coverage in nightly mode
+// within this crate
+#![cfg_attr(coverage_nightly, feature(coverage_attribute))]
+
+//! A node that only serves the JSON-RPC and the monitoring gateway, from a storage that is synced
+//! by another node. The storage is opened for reading only, so any number of replicas can run
+//! next to the syncing node.
+//!
+//! The replica uses the config of the node and ignores the sections of the sync, the P2P sync and
+//! the network. If the sync section is set, the replica follows the central source to get the
+//! pending data and the highest block. Otherwise there is no pending data and the highest block is
+//! the last block in the storage.
+//!
+//! Run by:
+//! cargo run --bin rpc_replica -- --storage.db_config.path_prefix=<path of the syncing node>
+
+use std::env::args;
+use std::process::exit;
+use std::sync::Arc;
+use std::time::Duration;
+
+use papyrus_common::metrics::COLLECT_PROFILING_METRICS;
+use papyrus_common::pending_classes::PendingClasses;
+use papyrus_common::BlockHashAndNumber;
+use papyrus_config::presentation::get_config_presentation;
+use papyrus_config::validators::config_validate;
+use papyrus_config::ConfigError;
+use papyrus_monitoring_gateway::MonitoringServer;
+use papyrus_node::config::NodeConfig;
+use papyrus_node::version::VERSION_FULL;
+use papyrus_rpc::run_server;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::{open_storage_read_only, StorageReader};
+use papyrus_sync::sources::central::{CentralError, CentralSource};
+use papyrus_sync::sources::pending::PendingSource;
+use papyrus_sync::PendingDataFollower;
+use starknet_api::block::BlockHash;
+use starknet_api::hash::{StarkFelt, GENESIS_HASH};
+use starknet_api::stark_felt;
+use starknet_client::reader::objects::pending_data::{PendingBlock, PendingBlockOrDeprecated};
+use starknet_client::reader::PendingData;
+use tokio::sync::RwLock;
+use tracing::metadata::LevelFilter;
+use tracing::{error, info};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+
+// Duration between updates of the highest block from the storage, when the central source isn't
+// followed.
+const HIGHEST_BLOCK_UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
+    let storage_reader = open_storage_read_only(config.storage.clone())?;
+
+    // Monitoring server.
+    let monitoring_server = MonitoringServer::new(
+        config.monitoring_gateway.clone(),
+        get_config_presentation(&config, true)?,
+        get_config_presentation(&config, false)?,
+        storage_reader.clone(),
+        VERSION_FULL,
+        "".to_string(),
+    )?;
+    let monitoring_server_handle = monitoring_server.spawn_server().await;
+
+    let shared_highest_block = Arc::new(RwLock::new(None));
+    let pending_data = Arc::new(RwLock::new(PendingData {
+        // The pending data might change later to DeprecatedPendingBlock, depending on the response
+        // from the feeder gateway.
+        block: PendingBlockOrDeprecated::Current(PendingBlock {
+            parent_block_hash: BlockHash(stark_felt!(GENESIS_HASH)),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+    let pending_classes = Arc::new(RwLock::new(PendingClasses::default()));
+
+    // JSON-RPC server.
+    let (_, server_handle) = run_server(
+        &config.rpc,
+        shared_highest_block.clone(),
+        pending_data.clone(),
+        pending_classes.clone(),
+        storage_reader.clone(),
+        VERSION_FULL,
+    )
+    .await?;
+    let server_handle_future = tokio::spawn(server_handle.stopped());
+
+    // Pending data and highest block task.
+    let pending_handle = match config.sync {
+        Some(sync_config) => {
+            let central_source =
+                CentralSource::new(config.central.clone(), VERSION_FULL, storage_reader.clone())
+                    .map_err(CentralError::ClientCreation)?;
+            let pending_source = PendingSource::new(config.central, VERSION_FULL)
+                .map_err(CentralError::ClientCreation)?;
+            let mut pending_data_follower = PendingDataFollower::new(
+                sync_config,
+                shared_highest_block,
+                pending_data,
+                pending_classes,
+                central_source,
+                pending_source,
+                storage_reader,
+            );
+            tokio::spawn(async move { Ok(pending_data_follower.run().await?) })
+        }
+        None => {
+            tokio::spawn(update_highest_block_from_storage(storage_reader, shared_highest_block))
+        }
+    };
+
+    tokio::select! {
+        res = server_handle_future => {
+            error!("RPC server stopped.");
+            res?
+        }
+        res = monitoring_server_handle => {
+            error!("Monitoring server stopped.");
+            res??
+        }
+        res = pending_handle => {
+            error!("Pending data and highest block updates stopped.");
+            res??
+        }
+    };
+    error!("Task ended with unexpected Ok.");
+    Ok(())
+}
+
+// Sets the highest block to the last block in the storage, as long as the storage is open.
+async fn update_highest_block_from_storage(
+    storage_reader: StorageReader,
+    shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+) -> anyhow::Result<()> {
+    loop {
+        let txn = storage_reader.begin_ro_txn()?;
+        let highest_block = match txn.get_header_marker()?.prev() {
+            None => None,
+            Some(block_number) => txn
+                .get_block_header(block_number)?
+                .map(|header| BlockHashAndNumber { block_hash: header.block_hash, block_number }),
+        };
+        drop(txn);
+        *shared_highest_block.write().await = highest_block;
+        tokio::time::sleep(HIGHEST_BLOCK_UPDATE_INTERVAL).await;
+    }
+}
+
+fn configure_tracing() {
+    let fmt_layer = fmt::layer().compact().with_target(false);
+    let level_filter_layer =
+        EnvFilter::builder().with_default_directive(DEFAULT_LEVEL.into()).from_env_lossy();
+    tracing_subscriber::registry().with(fmt_layer).with(level_filter_layer).init();
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let config = NodeConfig::load_and_process(args().collect());
+    if let Err(ConfigError::CommandInput(clap_err)) = config {
+        clap_err.exit();
+    }
+
+    configure_tracing();
+
+    let config = config?;
+    if let Err(errors) = config_validate(&config) {
+        error!("{}", errors);
+        exit(1);
+    }
+
+    COLLECT_PROFILING_METRICS
+        .set(config.collect_profiling_metrics)
+        .expect("This should be the first and only time we set this value.");
+
+    info!("Booting up the RPC replica.");
+    run_threads(config).await
+}
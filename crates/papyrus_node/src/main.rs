@@ -31,6 +31,7 @@ use papyrus_sync::sources::central::{CentralError, CentralSource, CentralSourceC
 use papyrus_sync::sources::pending::PendingSource;
 use papyrus_sync::{StateSync, StateSyncError, SyncConfig};
 use starknet_api::block::BlockHash;
+use starknet_api::core::ChainId;
 use starknet_api::hash::{StarkFelt, GENESIS_HASH};
 use starknet_api::stark_felt;
 use starknet_client::reader::objects::pending_data::{PendingBlock, PendingBlockOrDeprecated};
@@ -134,7 +135,12 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
             panic!("One of --sync.#is_none or --p2p_sync.#is_none must be turned on");
         }
         (Some(sync_config), None) => {
-            let configs = (sync_config, config.central, config.base_layer);
+            let configs = (
+                sync_config,
+                config.central,
+                config.base_layer,
+                config.storage.db_config.chain_id.clone(),
+            );
             let storage = (storage_reader.clone(), storage_writer);
             let sync_fut =
                 run_sync(configs, shared_highest_block, pending_data, pending_classes, storage);
@@ -190,13 +196,13 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
     return Ok(());
 
     async fn run_sync(
-        configs: (SyncConfig, CentralSourceConfig, EthereumBaseLayerConfig),
+        configs: (SyncConfig, CentralSourceConfig, EthereumBaseLayerConfig, ChainId),
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
         pending_classes: Arc<RwLock<PendingClasses>>,
         storage: (StorageReader, StorageWriter),
     ) -> Result<(), StateSyncError> {
-        let (sync_config, central_config, base_layer_config) = configs;
+        let (sync_config, central_config, base_layer_config, chain_id) = configs;
         let (storage_reader, storage_writer) = storage;
         let central_source =
             CentralSource::new(central_config.clone(), VERSION_FULL, storage_reader.clone())
@@ -215,6 +221,7 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
             base_layer_source,
             storage_reader.clone(),
             storage_writer,
+            chain_id,
         );
         sync.run().await
     }
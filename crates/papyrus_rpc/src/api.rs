@@ -59,6 +59,7 @@ pub fn get_methods_from_supported_apis(
     storage_reader: StorageReader,
     max_events_chunk_size: usize,
     max_events_keys: usize,
+    max_storage_proof_keys: usize,
     starting_block: BlockHashAndNumber,
     shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pending_data: Arc<RwLock<PendingData>>,
@@ -72,6 +73,7 @@ pub fn get_methods_from_supported_apis(
         storage_reader,
         max_events_chunk_size,
         max_events_keys,
+        max_storage_proof_keys,
         starting_block,
         shared_highest_block,
         pending_data,
@@ -114,6 +116,7 @@ pub trait JsonRpcServerTrait: Sized {
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
+        max_storage_proof_keys: usize,
         starting_block: BlockHashAndNumber,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
@@ -131,6 +134,7 @@ struct JsonRpcServerImplGenerator {
     storage_reader: StorageReader,
     max_events_chunk_size: usize,
     max_events_keys: usize,
+    max_storage_proof_keys: usize,
     starting_block: BlockHashAndNumber,
     shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pending_data: Arc<RwLock<PendingData>>,
@@ -145,6 +149,7 @@ type JsonRpcServerImplParams = (
     StorageReader,
     usize,
     usize,
+    usize,
     BlockHashAndNumber,
     Arc<RwLock<Option<BlockHashAndNumber>>>,
     Arc<RwLock<PendingData>>,
@@ -160,6 +165,7 @@ impl JsonRpcServerImplGenerator {
             self.storage_reader,
             self.max_events_chunk_size,
             self.max_events_keys,
+            self.max_storage_proof_keys,
             self.starting_block,
             self.shared_highest_block,
             self.pending_data,
@@ -178,6 +184,7 @@ impl JsonRpcServerImplGenerator {
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
+            max_storage_proof_keys,
             starting_block,
             shared_highest_block,
             pending_data,
@@ -191,6 +198,7 @@ impl JsonRpcServerImplGenerator {
                 storage_reader,
                 max_events_chunk_size,
                 max_events_keys,
+                max_storage_proof_keys,
                 starting_block,
                 shared_highest_block,
                 pending_data,
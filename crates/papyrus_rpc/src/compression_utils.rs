@@ -2,11 +2,20 @@
 #[path = "compression_utils_test.rs"]
 mod compression_utils_test;
 
-use papyrus_storage::compression_utils::serialize_and_compress;
+use std::io::Read;
+
+use flate2::bufread::GzEncoder;
+use flate2::Compression;
 use papyrus_storage::db::serialization::{StorageSerde, StorageSerdeError};
 
+// The value is gzip compressed as the gateway expects, regardless of the compression of the
+// storage.
 pub fn compress_and_encode(value: serde_json::Value) -> Result<String, StorageSerdeError> {
-    Ok(base64::encode(serialize_and_compress(&JsonValue(value))?))
+    let mut serialized = Vec::new();
+    JsonValue(value).serialize_into(&mut serialized)?;
+    let mut compressed = Vec::new();
+    GzEncoder::new(serialized.as_slice(), Compression::default()).read_to_end(&mut compressed)?;
+    Ok(base64::encode(compressed))
 }
 
 // The StorageSerde implementation for serde_json::Value writes the length (in bytes)
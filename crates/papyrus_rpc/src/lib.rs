@@ -6,6 +6,7 @@ mod api;
 mod compression_utils;
 mod middleware;
 mod pending;
+mod pruning;
 mod rpc_metrics;
 #[cfg(test)]
 mod rpc_test;
@@ -16,7 +17,7 @@ mod v0_6;
 mod v0_7;
 mod version_config;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Display;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -33,14 +34,15 @@ use papyrus_config::validators::validate_ascii;
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_execution::ExecutionConfig;
 use papyrus_storage::base_layer::BaseLayerStorageReader;
-use papyrus_storage::body::events::EventIndex;
-use papyrus_storage::db::TransactionKind;
+use papyrus_storage::body::events::{EventIndex, EventsReader};
+use papyrus_storage::db::{TransactionKind, RO};
 use papyrus_storage::state::StateStorageReader;
-use papyrus_storage::{StorageReader, StorageScope, StorageTxn};
+use papyrus_storage::{StorageReader, StorageResult, StorageScope, StorageTxn};
 use rpc_metrics::MetricLogger;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockNumber, BlockStatus};
-use starknet_api::core::ChainId;
+use starknet_api::core::{ChainId, ContractAddress};
+use starknet_api::transaction::{EventContent, EventKey};
 use starknet_client::reader::PendingData;
 use starknet_client::writer::StarknetGatewayClient;
 use starknet_client::RetryConfig;
@@ -68,6 +70,7 @@ pub struct RpcConfig {
     pub server_address: String,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
+    pub max_storage_proof_keys: usize,
     pub collect_metrics: bool,
     pub starknet_url: String,
     pub starknet_gateway_retry_config: RetryConfig,
@@ -81,6 +84,7 @@ impl Default for RpcConfig {
             server_address: String::from("0.0.0.0:8080"),
             max_events_chunk_size: 1000,
             max_events_keys: 100,
+            max_storage_proof_keys: 100,
             collect_metrics: false,
             starknet_url: String::from("https://alpha-mainnet.starknet.io/"),
             starknet_gateway_retry_config: RetryConfig {
@@ -120,6 +124,13 @@ impl SerializeConfig for RpcConfig {
                 "Maximum number of keys supported by the node in get_events requests.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "max_storage_proof_keys",
+                &self.max_storage_proof_keys,
+                "Maximum number of keys whose proofs are supported by the node in \
+                 get_storage_proof requests.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "collect_metrics",
                 &self.collect_metrics,
@@ -167,7 +178,7 @@ fn verify_storage_scope(storage_reader: &StorageReader) -> RpcResult<()> {
         StorageScope::StateOnly => {
             Err(internal_server_error_with_msg("Unsupported method in state-only scope."))
         }
-        StorageScope::FullArchive => Ok(()),
+        StorageScope::FullArchive | StorageScope::Pruned => Ok(()),
     }
 }
 
@@ -195,6 +206,85 @@ fn get_block_status<Mode: TransactionKind>(
 #[derive(Clone, Debug, PartialEq)]
 struct ContinuationTokenAsStruct(EventIndex);
 
+// The number of events read from each events index in order to estimate its selectivity.
+const EVENTS_INDEX_SAMPLE_SIZE: usize = 128;
+
+type EventsIterator<'env> =
+    Box<dyn Iterator<Item = ((ContractAddress, EventIndex), EventContent)> + 'env>;
+
+// An index of the events in the storage that can be used to iterate the events of a filter.
+enum EventsIndex {
+    Address(ContractAddress),
+    // The position of the keys in the event keys and the keys that can appear in this position.
+    Keys(usize, Vec<EventKey>),
+}
+
+/// Returns an iterator over the events from the given event index until the given block number,
+/// using the most selective index among the address of the filter and the positions of its keys.
+/// The events are ordered by their index. The iterator doesn't skip any event that matches the
+/// filter, but it may output events that don't match it.
+fn iter_filter_events<'env>(
+    txn: &'env StorageTxn<'env, RO>,
+    address: Option<ContractAddress>,
+    keys: &[HashSet<EventKey>],
+    start_event_index: EventIndex,
+    to_block_number: BlockNumber,
+) -> StorageResult<EventsIterator<'env>> {
+    let mut indices = address.map(EventsIndex::Address).into_iter().collect::<Vec<_>>();
+    for (key_position, position_keys) in keys.iter().enumerate() {
+        // An empty set of keys matches any key.
+        if !position_keys.is_empty() {
+            indices.push(EventsIndex::Keys(key_position, position_keys.iter().cloned().collect()));
+        }
+    }
+
+    // Estimate the selectivity of each index by the number of events it outputs, up to the sample
+    // size.
+    let mut most_selective_index = None;
+    let mut min_sampled_events = usize::MAX;
+    for index in indices {
+        let sampled_events = iter_events_by_index(txn, &index, start_event_index, to_block_number)?
+            .take(EVENTS_INDEX_SAMPLE_SIZE)
+            .count();
+        if sampled_events < min_sampled_events {
+            min_sampled_events = sampled_events;
+            most_selective_index = Some(index);
+        }
+    }
+
+    match most_selective_index {
+        Some(index) => iter_events_by_index(txn, &index, start_event_index, to_block_number),
+        None => Ok(Box::new(txn.iter_events(None, start_event_index, to_block_number)?)),
+    }
+}
+
+fn iter_events_by_index<'env>(
+    txn: &'env StorageTxn<'env, RO>,
+    index: &EventsIndex,
+    start_event_index: EventIndex,
+    to_block_number: BlockNumber,
+) -> StorageResult<EventsIterator<'env>> {
+    match index {
+        EventsIndex::Address(address) => {
+            let address = *address;
+            // The events of other addresses follow the events of the given address.
+            Ok(Box::new(
+                txn.iter_events(Some(address), start_event_index, to_block_number)?.take_while(
+                    move |((from_address, event_index), _)| {
+                        *from_address == address && event_index.0.0 <= to_block_number
+                    },
+                ),
+            ))
+        }
+        EventsIndex::Keys(key_position, keys) => Ok(Box::new(txn.iter_events_by_keys(
+            *key_position,
+            keys,
+            start_event_index,
+            to_block_number,
+        )?)),
+    }
+}
+
 #[instrument(skip(storage_reader), level = "debug", err)]
 pub async fn run_server(
     config: &RpcConfig,
@@ -212,6 +302,7 @@ pub async fn run_server(
         storage_reader,
         config.max_events_chunk_size,
         config.max_events_keys,
+        config.max_storage_proof_keys,
         starting_block,
         shared_highest_block,
         pending_data,
@@ -0,0 +1,38 @@
+//! Errors for requests of data that was pruned from the storage, shared by all the API versions.
+
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_storage::db::TransactionKind;
+use papyrus_storage::pruning::PruningStorageReader;
+use papyrus_storage::StorageTxn;
+use starknet_api::block::BlockNumber;
+
+use crate::internal_server_error;
+
+const BLOCK_PRUNED_CODE: i32 = 43;
+const BLOCK_PRUNED_MESSAGE: &str = "The requested data of the block was pruned from the node";
+
+/// Returns the error of a request for the transactions, events or state diff of a pruned block.
+pub(crate) fn block_pruned_error() -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(BLOCK_PRUNED_CODE, BLOCK_PRUNED_MESSAGE, None::<()>)
+}
+
+/// Returns whether the transactions, events and state diff of the given block were pruned.
+pub(crate) fn is_block_pruned<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_number: BlockNumber,
+) -> Result<bool, ErrorObjectOwned> {
+    Ok(block_number < txn.get_pruning_marker().map_err(internal_server_error)?)
+}
+
+/// Returns [`block_pruned_error`] if the transactions, events and state diff of the given block
+/// were pruned.
+pub(crate) fn verify_block_not_pruned<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_number: BlockNumber,
+) -> RpcResult<()> {
+    if is_block_pruned(txn, block_number)? {
+        return Err(block_pruned_error());
+    }
+    Ok(())
+}
@@ -39,6 +39,7 @@ pub fn get_test_rpc_config() -> RpcConfig {
         server_address: String::from("127.0.0.1:0"),
         max_events_chunk_size: 10,
         max_events_keys: 10,
+        max_storage_proof_keys: 10,
         collect_metrics: false,
         ..Default::default()
     }
@@ -84,6 +85,7 @@ pub(crate) fn get_test_rpc_server_and_storage_writer_from_params<T: JsonRpcServe
             storage_reader,
             config.max_events_chunk_size,
             config.max_events_keys,
+            config.max_storage_proof_keys,
             BlockHashAndNumber::default(),
             shared_highest_block,
             pending_data,
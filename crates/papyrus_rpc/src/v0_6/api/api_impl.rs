@@ -18,9 +18,10 @@ use papyrus_execution::{
     ExecutableTransactionInput,
     ExecutionConfig,
 };
-use papyrus_storage::body::events::{EventIndex, EventsReader};
+use papyrus_storage::body::events::EventIndex;
 use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
 use papyrus_storage::db::TransactionKind;
+use papyrus_storage::pruning::PruningStorageReader;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader, StorageTxn};
 use starknet_api::block::{BlockHash, BlockNumber, BlockStatus};
@@ -122,12 +123,14 @@ use super::{
 };
 use crate::api::{BlockHashOrNumber, JsonRpcServerTrait, Tag};
 use crate::pending::client_pending_data_to_execution_pending_data;
+use crate::pruning::verify_block_not_pruned;
 use crate::syncing_state::{get_last_synced_block, SyncStatus, SyncingState};
 use crate::version_config::VERSION_0_6 as VERSION;
 use crate::{
     get_block_status,
     get_latest_block_number,
     internal_server_error,
+    iter_filter_events,
     verify_storage_scope,
     ContinuationTokenAsStruct,
 };
@@ -146,6 +149,7 @@ pub struct JsonRpcServerImpl {
     pub storage_reader: StorageReader,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
+    pub max_storage_proof_keys: usize,
     pub starting_block: BlockHashAndNumber,
     pub shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pub pending_data: Arc<RwLock<PendingData>>,
@@ -262,6 +266,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
         let status = get_block_status(&txn, block_number)?;
         let header =
             GeneralBlockHeader::BlockHeader(get_block_header_by_number(&txn, block_number)?.into());
+        verify_block_not_pruned(&txn, block_number)?;
         // TODO(dvir): consider create a vector of (transaction, transaction_index) first and get
         // the transaction hashes by the index.
         let transactions = get_block_txs_by_number(&txn, block_number)?;
@@ -341,6 +346,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
         if let Some(transaction_index) =
             txn.get_transaction_idx_by_hash(&transaction_hash).map_err(internal_server_error)?
         {
+            verify_block_not_pruned(&txn, transaction_index.0)?;
             let transaction = txn
                 .get_transaction(transaction_index)
                 .map_err(internal_server_error)?
@@ -390,6 +396,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 (client_transaction.try_into().map_err(internal_server_error)?, transaction_hash)
             } else {
                 let block_number = get_accepted_block_number(&txn, block_id)?;
+                verify_block_not_pruned(&txn, block_number)?;
 
                 let tx_index = TransactionIndex(block_number, index);
                 let transaction = txn
@@ -454,6 +461,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
         };
 
         // Get the block state diff.
+        verify_block_not_pruned(&txn, block_number)?;
         let mut thin_state_diff = txn
             .get_state_diff(block_number)
             .map_err(internal_server_error)?
@@ -490,6 +498,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
             txn.get_transaction_idx_by_hash(&transaction_hash).map_err(internal_server_error)?
         {
             let block_number = transaction_index.0;
+            verify_block_not_pruned(&txn, block_number)?;
             let status = get_block_status(&txn, block_number)?;
 
             // rejected blocks should not be a part of the API so we early return here.
@@ -729,7 +738,8 @@ impl JsonRpcServer for JsonRpcServerImpl {
             return Ok(EventsChunk { events: vec![], continuation_token: None });
         };
         let from_block_number = match filter.from_block {
-            None => BlockNumber(0),
+            // Without an explicit block, start from the first block whose events weren't pruned.
+            None => txn.get_pruning_marker().map_err(internal_server_error)?,
             Some(BlockId::Tag(Tag::Pending)) => latest_block_number.unchecked_next(),
             Some(block_id) => get_accepted_block_number(&txn, block_id)?,
         };
@@ -751,6 +761,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 EventIndexInTransactionOutput(0),
             ),
         };
+        verify_block_not_pruned(&txn, start_event_index.0.0)?;
 
         let include_pending_block = to_block_number > latest_block_number;
         if include_pending_block {
@@ -765,20 +776,24 @@ impl JsonRpcServer for JsonRpcServerImpl {
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
         if start_event_index.0.0 <= latest_block_number {
-            for ((from_address, event_index), content) in txn
-                .iter_events(filter.address, start_event_index, to_block_number)
-                .map_err(internal_server_error)?
+            for ((from_address, event_index), content) in iter_filter_events(
+                &txn,
+                filter.address,
+                &filter.keys,
+                start_event_index,
+                to_block_number,
+            )
+            .map_err(internal_server_error)?
             {
                 let block_number = (event_index.0).0;
                 if block_number > to_block_number {
                     break;
                 }
                 if let Some(filter_address) = filter.address {
+                    // The iterator of this loop might output events of other addresses if it
+                    // iterates the events by their keys.
                     if from_address != filter_address {
-                        // The iterator of this loop outputs only events that have the filter's
-                        // address, unless there are no more such events and then it outputs other
-                        // events, and we can stop the iteration.
-                        break;
+                        continue;
                     }
                 }
                 // TODO: Consider changing empty sets in the filer keys to None.
@@ -1194,6 +1209,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 .get_transaction_idx_by_hash(&transaction_hash)
                 .map_err(internal_server_error)?
                 .ok_or(TRANSACTION_HASH_NOT_FOUND)?;
+            verify_block_not_pruned(&storage_txn, block_number)?;
 
             let block_transactions = storage_txn
                 .get_block_transactions(block_number)
@@ -1275,6 +1291,9 @@ impl JsonRpcServer for JsonRpcServerImpl {
         };
 
         let block_number = get_accepted_block_number(&storage_txn, block_id)?;
+        if maybe_client_pending_data.is_none() {
+            verify_block_not_pruned(&storage_txn, block_number)?;
+        }
 
         let block_not_reverted_validator =
             BlockNotRevertedValidator::new(block_number, &storage_txn)?;
@@ -1508,6 +1527,7 @@ impl JsonRpcServerTrait for JsonRpcServerImpl {
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
+        max_storage_proof_keys: usize,
         starting_block: BlockHashAndNumber,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
@@ -1520,6 +1540,7 @@ impl JsonRpcServerTrait for JsonRpcServerImpl {
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
+            max_storage_proof_keys,
             starting_block,
             shared_highest_block,
             pending_data,
@@ -20,6 +20,7 @@ use papyrus_storage::body::events::EventIndex;
 use papyrus_storage::body::{BodyStorageWriter, TransactionIndex};
 use papyrus_storage::class::ClassStorageWriter;
 use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::pruning::PruningStorageWriter;
 use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::StorageScope;
@@ -165,6 +166,7 @@ use super::super::write_api_result::{
 use super::api_impl::{JsonRpcServerImpl, BLOCK_HASH_TABLE_ADDRESS};
 use super::{ContinuationToken, EventFilter, GatewayContractClass};
 use crate::api::{BlockHashOrNumber, BlockId, Tag};
+use crate::pruning::block_pruned_error;
 use crate::syncing_state::SyncStatus;
 use crate::test_utils::{
     call_api_then_assert_and_validate_schema_for_err,
@@ -2062,6 +2064,99 @@ async fn get_transaction_by_hash_state_only() {
     );
 }
 
+#[tokio::test]
+async fn get_pruned_block_data() {
+    let (module, mut storage_writer) = get_test_rpc_server_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, None, None, Some(StorageScope::Pruned));
+    let block = get_test_block(1, None, None, None);
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(block.header.block_number));
+    let transaction_hash = block.body.transaction_hashes[0];
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block.header.block_number, &block.header)
+        .unwrap()
+        .append_body(block.header.block_number, block.body)
+        .unwrap()
+        .append_state_diff(block.header.block_number, starknet_api::state::ThinStateDiff::default())
+        .unwrap()
+        .prune_blocks(block.header.block_number.unchecked_next())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The transaction hashes of a pruned block are kept.
+    let res =
+        module.call::<_, Block>("starknet_V0_6_getBlockWithTxHashes", [block_id]).await.unwrap();
+    assert_eq!(res.transactions, Transactions::Hashes(vec![transaction_hash]));
+
+    call_api_then_assert_and_validate_schema_for_err::<_, Block>(
+        &module,
+        "starknet_V0_6_getBlockWithTxs",
+        vec![Box::new(block_id)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, StateUpdate>(
+        &module,
+        "starknet_V0_6_getStateUpdate",
+        vec![Box::new(block_id)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, TransactionWithHash>(
+        &module,
+        "starknet_V0_6_getTransactionByHash",
+        vec![Box::new(transaction_hash)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, GeneralTransactionReceipt>(
+        &module,
+        "starknet_V0_6_getTransactionReceipt",
+        vec![Box::new(transaction_hash)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, EventsChunk>(
+        &module,
+        "starknet_V0_6_getEvents",
+        vec![Box::new(EventFilter {
+            from_block: Some(block_id),
+            chunk_size: 10,
+            ..Default::default()
+        })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    // Without a from block, the events are returned from the first block that wasn't pruned.
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        "starknet_V0_6_getEvents",
+        vec![Box::new(EventFilter { chunk_size: 10, ..Default::default() })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &EventsChunk { events: vec![], continuation_token: None },
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn get_transaction_by_block_id_and_index() {
     let method_name = "starknet_V0_6_getTransactionByBlockIdAndIndex";
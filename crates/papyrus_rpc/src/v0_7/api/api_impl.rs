@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -15,9 +16,11 @@ use papyrus_execution::{
     ExecutableTransactionInput,
     ExecutionConfig,
 };
-use papyrus_storage::body::events::{EventIndex, EventsReader};
+use papyrus_storage::body::events::EventIndex;
 use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
 use papyrus_storage::db::{TransactionKind, RO};
+use papyrus_storage::pruning::PruningStorageReader;
+use papyrus_storage::state::trie::StateTrieStorageReader;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader, StorageTxn};
 use starknet_api::block::{BlockHash, BlockNumber, BlockStatus};
@@ -73,11 +76,23 @@ use super::super::error::{
     INVALID_TRANSACTION_INDEX,
     NO_BLOCKS,
     PAGE_SIZE_TOO_BIG,
+    STORAGE_PROOF_NOT_SUPPORTED,
     TOO_MANY_KEYS_IN_FILTER,
+    TOO_MANY_KEYS_IN_STORAGE_PROOF,
     TRANSACTION_HASH_NOT_FOUND,
 };
 use super::super::execution::TransactionTrace;
-use super::super::state::{AcceptedStateUpdate, PendingStateUpdate, StateUpdate};
+use super::super::state::{
+    AcceptedStateUpdate,
+    ContractLeafData,
+    ContractStorageKeys,
+    ContractsProof,
+    GlobalRoots,
+    NodeHashToNode,
+    PendingStateUpdate,
+    StateUpdate,
+    StorageProof,
+};
 use super::super::transaction::{
     get_block_tx_hashes_by_number,
     get_block_txs_by_number,
@@ -120,18 +135,25 @@ use super::{
     EventsChunk,
     GatewayContractClass,
     JsonRpcV0_7Server as JsonRpcServer,
+    SenderTransaction,
+    SenderTransactionsChunk,
     SimulatedTransaction,
     SimulationFlag,
+    StorageChange,
+    StorageChangesChunk,
+    StorageChangesFilter,
     TransactionTraceWithHash,
 };
 use crate::api::{BlockHashOrNumber, JsonRpcServerTrait, Tag};
 use crate::pending::client_pending_data_to_execution_pending_data;
+use crate::pruning::verify_block_not_pruned;
 use crate::syncing_state::{get_last_synced_block, SyncStatus, SyncingState};
 use crate::version_config::VERSION_0_7 as VERSION;
 use crate::{
     get_block_status,
     get_latest_block_number,
     internal_server_error,
+    iter_filter_events,
     verify_storage_scope,
     ContinuationTokenAsStruct,
 };
@@ -150,6 +172,7 @@ pub struct JsonRpcServerImpl {
     pub storage_reader: StorageReader,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
+    pub max_storage_proof_keys: usize,
     pub starting_block: BlockHashAndNumber,
     pub shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pub pending_data: Arc<RwLock<PendingData>>,
@@ -224,6 +247,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 ))
             },
             |txn, block_number| {
+                verify_block_not_pruned(txn, block_number)?;
                 // TODO(dvir): consider create a vector of (transaction, transaction_index) first
                 // and get the transaction hashes by the index.
                 let transactions = get_block_txs_by_number(txn, block_number)?;
@@ -277,6 +301,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 ))
             },
             |txn, block_number| {
+                verify_block_not_pruned(txn, block_number)?;
                 // TODO(dvir): consider create a vector of (transaction, transaction_index) first
                 // and get the transaction hashes by the index.
                 let transactions = get_block_txs_by_number(txn, block_number)?;
@@ -376,6 +401,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
         if let Some(transaction_index) =
             txn.get_transaction_idx_by_hash(&transaction_hash).map_err(internal_server_error)?
         {
+            verify_block_not_pruned(&txn, transaction_index.0)?;
             let transaction = txn
                 .get_transaction(transaction_index)
                 .map_err(internal_server_error)?
@@ -425,6 +451,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 (client_transaction.try_into().map_err(internal_server_error)?, transaction_hash)
             } else {
                 let block_number = get_accepted_block_number(&txn, block_id)?;
+                verify_block_not_pruned(&txn, block_number)?;
 
                 let tx_index = TransactionIndex(block_number, index);
                 let transaction = txn
@@ -489,6 +516,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
         };
 
         // Get the block state diff.
+        verify_block_not_pruned(&txn, block_number)?;
         let mut thin_state_diff = txn
             .get_state_diff(block_number)
             .map_err(internal_server_error)?
@@ -524,6 +552,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
         if let Some(transaction_index) =
             txn.get_transaction_idx_by_hash(&transaction_hash).map_err(internal_server_error)?
         {
+            verify_block_not_pruned(&txn, transaction_index.0)?;
             let tx = txn
                 .get_transaction(transaction_index)
                 .map_err(internal_server_error)?
@@ -709,7 +738,8 @@ impl JsonRpcServer for JsonRpcServerImpl {
             return Ok(EventsChunk { events: vec![], continuation_token: None });
         };
         let from_block_number = match filter.from_block {
-            None => BlockNumber(0),
+            // Without an explicit block, start from the first block whose events weren't pruned.
+            None => txn.get_pruning_marker().map_err(internal_server_error)?,
             Some(BlockId::Tag(Tag::Pending)) => latest_block_number.unchecked_next(),
             Some(block_id) => get_accepted_block_number(&txn, block_id)?,
         };
@@ -731,6 +761,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 EventIndexInTransactionOutput(0),
             ),
         };
+        verify_block_not_pruned(&txn, start_event_index.0.0)?;
 
         let include_pending_block = to_block_number > latest_block_number;
         if include_pending_block {
@@ -745,20 +776,24 @@ impl JsonRpcServer for JsonRpcServerImpl {
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
         if start_event_index.0.0 <= latest_block_number {
-            for ((from_address, event_index), content) in txn
-                .iter_events(filter.address, start_event_index, to_block_number)
-                .map_err(internal_server_error)?
+            for ((from_address, event_index), content) in iter_filter_events(
+                &txn,
+                filter.address,
+                &filter.keys,
+                start_event_index,
+                to_block_number,
+            )
+            .map_err(internal_server_error)?
             {
                 let block_number = (event_index.0).0;
                 if block_number > to_block_number {
                     break;
                 }
                 if let Some(filter_address) = filter.address {
+                    // The iterator of this loop might output events of other addresses if it
+                    // iterates the events by their keys.
                     if from_address != filter_address {
-                        // The iterator of this loop outputs only events that have the filter's
-                        // address, unless there are no more such events and then it outputs other
-                        // events, and we can stop the iteration.
-                        break;
+                        continue;
                     }
                 }
                 // TODO: Consider changing empty sets in the filer keys to None.
@@ -1169,6 +1204,7 @@ impl JsonRpcServer for JsonRpcServerImpl {
                 .get_transaction_idx_by_hash(&transaction_hash)
                 .map_err(internal_server_error)?
                 .ok_or(TRANSACTION_HASH_NOT_FOUND)?;
+            verify_block_not_pruned(&storage_txn, block_number)?;
 
             let block_transactions = storage_txn
                 .get_block_transactions(block_number)
@@ -1248,6 +1284,9 @@ impl JsonRpcServer for JsonRpcServerImpl {
         };
 
         let block_number = get_accepted_block_number(&storage_txn, block_id)?;
+        if maybe_client_pending_data.is_none() {
+            verify_block_not_pruned(&storage_txn, block_number)?;
+        }
 
         let block_not_reverted_validator =
             BlockNotRevertedValidator::new(block_number, &storage_txn)?;
@@ -1433,6 +1472,238 @@ impl JsonRpcServer for JsonRpcServerImpl {
             Err(err) => Err(internal_server_error(err)),
         }
     }
+
+    #[instrument(skip(self), level = "debug", err)]
+    async fn get_storage_proof(
+        &self,
+        block_id: BlockId,
+        class_hashes: Option<Vec<ClassHash>>,
+        contract_addresses: Option<Vec<ContractAddress>>,
+        contracts_storage_keys: Option<Vec<ContractStorageKeys>>,
+    ) -> RpcResult<StorageProof> {
+        // The state tries are calculated only for accepted blocks.
+        if let BlockId::Tag(Tag::Pending) = block_id {
+            return Err(ErrorObjectOwned::from(STORAGE_PROOF_NOT_SUPPORTED));
+        }
+        // Check the number of keys, as each of them requires walking a path in a trie.
+        let class_hashes = class_hashes.unwrap_or_default();
+        let contract_addresses = contract_addresses.unwrap_or_default();
+        let contracts_storage_keys = contracts_storage_keys.unwrap_or_default();
+        let n_keys = class_hashes.len()
+            + contract_addresses.len()
+            + contracts_storage_keys
+                .iter()
+                .map(|contract_storage_keys| contract_storage_keys.storage_keys.len())
+                .sum::<usize>();
+        if n_keys > self.max_storage_proof_keys {
+            return Err(ErrorObjectOwned::from(TOO_MANY_KEYS_IN_STORAGE_PROOF));
+        }
+
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        let block_hash = get_block_header_by_number(&txn, block_number)?.block_hash;
+        let state_roots = txn
+            .get_state_roots(block_number)
+            .map_err(internal_server_error)?
+            // The block exists, but its state wasn't synced yet.
+            .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+        let state_number = StateNumber::unchecked_right_after_block(block_number);
+        let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
+
+        let classes_proof = get_proof_nodes(
+            &txn,
+            &state_roots.classes_trie_root,
+            class_hashes.iter().map(|class_hash| &class_hash.0),
+        )?;
+
+        let contracts_proof_nodes = get_proof_nodes(
+            &txn,
+            &state_roots.contracts_trie_root,
+            contract_addresses.iter().map(|address| address.0.key()),
+        )?;
+        let mut contract_leaves_data = Vec::with_capacity(contract_addresses.len());
+        for address in &contract_addresses {
+            contract_leaves_data.push(ContractLeafData {
+                nonce: state_reader
+                    .get_nonce_at(state_number, address)
+                    .map_err(internal_server_error)?
+                    .unwrap_or_default(),
+                class_hash: state_reader
+                    .get_class_hash_at(state_number, address)
+                    .map_err(internal_server_error)?
+                    .unwrap_or_default(),
+                storage_root: txn
+                    .get_contract_storage_root(block_number, address)
+                    .map_err(internal_server_error)?,
+            });
+        }
+
+        let mut contracts_storage_proofs = vec![];
+        for ContractStorageKeys { contract_address, storage_keys } in contracts_storage_keys {
+            let storage_root = txn
+                .get_contract_storage_root(block_number, &contract_address)
+                .map_err(internal_server_error)?;
+            contracts_storage_proofs.push(get_proof_nodes(
+                &txn,
+                &storage_root,
+                storage_keys.iter().map(|key| key.0.key()),
+            )?);
+        }
+
+        Ok(StorageProof {
+            classes_proof,
+            contracts_proof: ContractsProof { nodes: contracts_proof_nodes, contract_leaves_data },
+            contracts_storage_proofs,
+            global_roots: GlobalRoots {
+                contracts_tree_root: state_roots.contracts_trie_root,
+                classes_tree_root: state_roots.classes_trie_root,
+                global_root: state_roots.global_root,
+                block_hash,
+            },
+        })
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn get_transactions_by_sender(
+        &self,
+        sender_address: ContractAddress,
+        chunk_size: usize,
+        continuation_token: Option<Nonce>,
+    ) -> RpcResult<SenderTransactionsChunk> {
+        verify_storage_scope(&self.storage_reader)?;
+
+        if chunk_size > self.max_events_chunk_size {
+            return Err(ErrorObjectOwned::from(PAGE_SIZE_TOO_BIG));
+        }
+
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        // Read one more transaction in order to know whether there are more transactions.
+        let mut sender_transactions = txn
+            .get_sender_transactions(
+                &sender_address,
+                continuation_token.unwrap_or_default(),
+                chunk_size + 1,
+            )
+            .map_err(internal_server_error)?;
+        let continuation_token = if sender_transactions.len() > chunk_size {
+            sender_transactions.pop().map(|(nonce, _)| nonce)
+        } else {
+            None
+        };
+
+        let mut transactions = Vec::with_capacity(sender_transactions.len());
+        for (nonce, transaction_index) in sender_transactions {
+            let block_number = transaction_index.0;
+            let transaction = txn
+                .get_transaction(transaction_index)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
+            let transaction_hash = txn
+                .get_transaction_hash_by_idx(&transaction_index)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
+            let header = get_block_header_by_number(&txn, block_number)?;
+            transactions.push(SenderTransaction {
+                block_hash: header.block_hash,
+                block_number,
+                nonce,
+                transaction: TransactionWithHash {
+                    transaction: transaction.try_into()?,
+                    transaction_hash,
+                },
+            });
+        }
+
+        Ok(SenderTransactionsChunk { transactions, continuation_token })
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn get_storage_changes(
+        &self,
+        filter: StorageChangesFilter,
+    ) -> RpcResult<StorageChangesChunk> {
+        verify_storage_scope(&self.storage_reader)?;
+
+        if filter.chunk_size > self.max_events_chunk_size {
+            return Err(ErrorObjectOwned::from(PAGE_SIZE_TOO_BIG));
+        }
+
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let Some(latest_block_number) = get_latest_block_number(&txn)? else {
+            // There are no blocks.
+            return Ok(StorageChangesChunk { storage_changes: vec![], continuation_token: None });
+        };
+        let from_block_number = match filter.from_block {
+            None => BlockNumber(0),
+            Some(BlockId::Tag(Tag::Pending)) => latest_block_number.unchecked_next(),
+            Some(block_id) => get_accepted_block_number(&txn, block_id)?,
+        };
+        let to_block_number = match filter.to_block {
+            Some(BlockId::Tag(Tag::Pending)) | None => latest_block_number,
+            Some(block_id) => get_accepted_block_number(&txn, block_id)?,
+        };
+        if from_block_number > to_block_number {
+            return Ok(StorageChangesChunk { storage_changes: vec![], continuation_token: None });
+        }
+        // Pruning doesn't delete the storage changes, so they are returned for pruned blocks too.
+
+        // Take the position of the first change from the continuation token if there is one.
+        let start = match &filter.continuation_token {
+            Some(token) => token.parse_storage_change_position()?,
+            None => (filter.key.unwrap_or_default(), from_block_number),
+        };
+
+        // Read one more change than requested in order to know whether to return a continuation
+        // token.
+        let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
+        let mut storage_changes = state_reader
+            .iter_storage_diffs(filter.contract_address, start, from_block_number, to_block_number)
+            .map_err(internal_server_error)?
+            .take_while(|change| {
+                change.as_ref().map_or(true, |((key, _), _)| {
+                    filter.key.map_or(true, |filter_key| *key == filter_key)
+                })
+            })
+            .take(filter.chunk_size + 1)
+            .map(|change| {
+                change.map(|((key, block_number), value)| StorageChange {
+                    key,
+                    block_number,
+                    value,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(internal_server_error)?;
+        let continuation_token = if storage_changes.len() > filter.chunk_size {
+            let next_change = storage_changes.pop().expect("Should have more than 0 changes.");
+            Some(ContinuationToken::from_storage_change_position((
+                next_change.key,
+                next_change.block_number,
+            ))?)
+        } else {
+            None
+        };
+
+        Ok(StorageChangesChunk { storage_changes, continuation_token })
+    }
+}
+
+// Returns the nodes of the proofs of all the given keys in the trie with the given root, without
+// duplicates.
+fn get_proof_nodes<'a, Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    root: &StarkHash,
+    keys: impl Iterator<Item = &'a StarkFelt>,
+) -> RpcResult<Vec<NodeHashToNode>> {
+    let mut nodes = BTreeMap::new();
+    for key in keys {
+        let proof = txn.get_trie_proof(root, key).map_err(internal_server_error)?;
+        nodes.extend(proof);
+    }
+    Ok(nodes
+        .into_iter()
+        .map(|(node_hash, node)| NodeHashToNode { node_hash, node: node.into() })
+        .collect())
 }
 
 async fn read_pending_data<Mode: TransactionKind>(
@@ -1602,6 +1873,7 @@ impl JsonRpcServerTrait for JsonRpcServerImpl {
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
+        max_storage_proof_keys: usize,
         starting_block: BlockHashAndNumber,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
@@ -1614,6 +1886,7 @@ impl JsonRpcServerTrait for JsonRpcServerImpl {
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
+            max_storage_proof_keys,
             starting_block,
             shared_highest_block,
             pending_data,
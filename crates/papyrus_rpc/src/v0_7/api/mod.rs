@@ -17,7 +17,7 @@ use papyrus_storage::db::RO;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::StorageTxn;
 use serde::{Deserialize, Serialize};
-use starknet_api::block::BlockNumber;
+use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::{ClassHash, ContractAddress, Nonce};
 use starknet_api::deprecated_contract_class::Program;
 use starknet_api::hash::StarkFelt;
@@ -40,7 +40,7 @@ use super::error::{
     INVALID_CONTINUATION_TOKEN,
 };
 use super::execution::TransactionTrace;
-use super::state::{ContractClass, StateUpdate};
+use super::state::{ContractClass, ContractStorageKeys, StateUpdate, StorageProof};
 use super::transaction::{
     DeployAccountTransaction,
     DeployAccountTransactionV1,
@@ -252,6 +252,35 @@ pub trait JsonRpc {
         &self,
         block_id: BlockId,
     ) -> RpcResult<Vec<TransactionTraceWithHash>>;
+
+    /// Gets Merkle proofs of the given classes, contracts and storage keys against the global
+    /// state root of the given block.
+    #[method(name = "getStorageProof")]
+    async fn get_storage_proof(
+        &self,
+        block_id: BlockId,
+        class_hashes: Option<Vec<ClassHash>>,
+        contract_addresses: Option<Vec<ContractAddress>>,
+        contracts_storage_keys: Option<Vec<ContractStorageKeys>>,
+    ) -> RpcResult<StorageProof>;
+
+    /// Returns the transactions that the given account sent with a nonce, ordered by their nonce.
+    /// The continuation token is the nonce to start from.
+    #[method(name = "getTransactionsBySender")]
+    async fn get_transactions_by_sender(
+        &self,
+        sender_address: ContractAddress,
+        chunk_size: usize,
+        continuation_token: Option<Nonce>,
+    ) -> RpcResult<SenderTransactionsChunk>;
+
+    /// Returns the changes to the storage of a contract in the given blocks range, ordered by the
+    /// storage key and then by the block number. Changes in the pending block aren't returned.
+    #[method(name = "getStorageChanges")]
+    async fn get_storage_changes(
+        &self,
+        filter: StorageChangesFilter,
+    ) -> RpcResult<StorageChangesChunk>;
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -262,6 +291,21 @@ pub enum GatewayContractClass {
     Sierra(ContractClass),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SenderTransaction {
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    pub nonce: Nonce,
+    pub transaction: TransactionWithHash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SenderTransactionsChunk {
+    pub transactions: Vec<SenderTransaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<Nonce>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct EventsChunk {
     pub events: Vec<Event>,
@@ -284,6 +328,34 @@ pub struct EventFilter {
     pub keys: Vec<HashSet<EventKey>>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageChange {
+    pub key: StorageKey,
+    pub block_number: BlockNumber,
+    pub value: StarkFelt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageChangesChunk {
+    pub storage_changes: Vec<StorageChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<ContinuationToken>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageChangesFilter {
+    pub contract_address: ContractAddress,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<StorageKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_block: Option<BlockId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_block: Option<BlockId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<ContinuationToken>,
+    pub chunk_size: usize,
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ContinuationToken(pub String);
 
@@ -298,6 +370,19 @@ impl ContinuationToken {
     fn new(ct: ContinuationTokenAsStruct) -> Result<Self, ErrorObjectOwned> {
         Ok(Self(serde_json::to_string(&ct.0).map_err(internal_server_error)?))
     }
+
+    // The continuation token of storage changes is the storage key and block number of the next
+    // change.
+    fn parse_storage_change_position(&self) -> Result<(StorageKey, BlockNumber), ErrorObjectOwned> {
+        serde_json::from_str(&self.0)
+            .map_err(|_| ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN))
+    }
+
+    fn from_storage_change_position(
+        position: (StorageKey, BlockNumber),
+    ) -> Result<Self, ErrorObjectOwned> {
+        Ok(Self(serde_json::to_string(&position).map_err(internal_server_error)?))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
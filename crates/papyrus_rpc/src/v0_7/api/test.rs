@@ -13,13 +13,21 @@ use jsonrpsee::Methods;
 use jsonschema::JSONSchema;
 use lazy_static::lazy_static;
 use mockall::predicate::eq;
+use papyrus_common::patricia_hash_tree::calculate_edge_node_hash;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClassesTrait};
+use papyrus_common::state_commitment::{
+    calculate_contract_state_hash,
+    calculate_global_root,
+    CONTRACTS_TRIE_HASH_FUNCTION,
+    STATE_TRIE_HEIGHT,
+};
 use papyrus_common::BlockHashAndNumber;
 use papyrus_storage::base_layer::BaseLayerStorageWriter;
 use papyrus_storage::body::events::EventIndex;
 use papyrus_storage::body::{BodyStorageWriter, TransactionIndex};
 use papyrus_storage::class::ClassStorageWriter;
 use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::pruning::PruningStorageWriter;
 use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::StorageScope;
@@ -41,6 +49,7 @@ use starknet_api::block::{
 };
 use starknet_api::core::{
     ClassHash,
+    CompiledClassHash,
     ContractAddress,
     GlobalRoot,
     Nonce,
@@ -61,6 +70,9 @@ use starknet_api::transaction::{
     EventData,
     EventIndexInTransactionOutput,
     EventKey,
+    InvokeTransaction as StarknetApiInvokeTransaction,
+    InvokeTransactionOutput as StarknetApiInvokeTransactionOutput,
+    InvokeTransactionV1 as StarknetApiInvokeTransactionV1,
     Transaction as StarknetApiTransaction,
     TransactionHash,
     TransactionOffsetInBlock,
@@ -123,20 +135,29 @@ use super::super::error::{
     INVALID_TRANSACTION_INDEX,
     NO_BLOCKS,
     PAGE_SIZE_TOO_BIG,
+    STORAGE_PROOF_NOT_SUPPORTED,
     TOO_MANY_KEYS_IN_FILTER,
+    TOO_MANY_KEYS_IN_STORAGE_PROOF,
     TRANSACTION_HASH_NOT_FOUND,
 };
 use super::super::state::{
     AcceptedStateUpdate,
     ClassHashes,
     ContractClass,
+    ContractLeafData,
     ContractNonce,
+    ContractStorageKeys,
     DeployedContract,
+    EdgeNode,
+    GlobalRoots,
+    MerkleNode,
+    NodeHashToNode,
     PendingStateUpdate,
     ReplacedClasses,
     StateUpdate,
     StorageDiff,
     StorageEntry,
+    StorageProof,
     ThinStateDiff,
 };
 use super::super::transaction::{
@@ -166,8 +187,18 @@ use super::super::write_api_result::{
     AddInvokeOkResult,
 };
 use super::api_impl::{JsonRpcServerImpl, BLOCK_HASH_TABLE_ADDRESS};
-use super::{ContinuationToken, EventFilter, GatewayContractClass};
+use super::{
+    ContinuationToken,
+    EventFilter,
+    GatewayContractClass,
+    SenderTransaction,
+    SenderTransactionsChunk,
+    StorageChange,
+    StorageChangesChunk,
+    StorageChangesFilter,
+};
 use crate::api::{BlockHashOrNumber, BlockId, Tag};
+use crate::pruning::block_pruned_error;
 use crate::syncing_state::SyncStatus;
 use crate::test_utils::{
     call_and_validate_schema_for_result,
@@ -1909,6 +1940,204 @@ async fn get_nonce() {
     assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
 }
 
+#[tokio::test]
+async fn get_storage_proof() {
+    let method_name = "starknet_V0_7_getStorageProof";
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let address = ContractAddress(patricia_key!("0x100"));
+    let key = StorageKey(patricia_key!("0x5"));
+    let class_hash = ClassHash(stark_felt!("0x10"));
+    let declared_class_hash = ClassHash(stark_felt!("0x20"));
+    let state_diffs = [
+        starknet_api::state::ThinStateDiff {
+            deployed_contracts: indexmap! { address => class_hash },
+            storage_diffs: indexmap! { address => indexmap! { key => stark_felt!("0x1") } },
+            declared_classes: indexmap! {
+                declared_class_hash => CompiledClassHash(stark_felt!("0x200"))
+            },
+            ..Default::default()
+        },
+        starknet_api::state::ThinStateDiff {
+            storage_diffs: indexmap! { address => indexmap! { key => stark_felt!("0x2") } },
+            nonces: indexmap! { address => Nonce(stark_felt!("0x1")) },
+            ..Default::default()
+        },
+    ];
+    let mut headers = vec![];
+    for (i, state_diff) in state_diffs.into_iter().enumerate() {
+        let header = BlockHeader {
+            block_number: BlockNumber(i as u64),
+            block_hash: BlockHash(StarkHash::from(i as u64 + 1)),
+            ..Default::default()
+        };
+        storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(header.block_number, &header)
+            .unwrap()
+            .append_state_diff(header.block_number, state_diff)
+            .unwrap()
+            .commit()
+            .unwrap();
+        headers.push(header);
+    }
+
+    // Each trie has a single leaf, so each proof is a single edge from the root to the leaf.
+    for (header, value, nonce) in [
+        (&headers[0], stark_felt!("0x1"), Nonce::default()),
+        (&headers[1], stark_felt!("0x2"), Nonce(stark_felt!("0x1"))),
+    ] {
+        let res = call_and_validate_schema_for_result::<_, StorageProof>(
+            &module,
+            method_name,
+            vec![
+                Box::new(BlockId::HashOrNumber(BlockHashOrNumber::Number(header.block_number))),
+                Box::new(vec![declared_class_hash]),
+                Box::new(vec![address]),
+                Box::new(vec![ContractStorageKeys {
+                    contract_address: address,
+                    storage_keys: vec![key],
+                }]),
+            ],
+            &VERSION,
+            SpecFile::StarknetApiOpenrpc,
+        )
+        .await;
+
+        let storage_root = calculate_edge_node_hash(
+            CONTRACTS_TRIE_HASH_FUNCTION,
+            &value,
+            key.0.key(),
+            STATE_TRIE_HEIGHT,
+        );
+        assert_eq!(
+            res.contracts_storage_proofs,
+            vec![vec![NodeHashToNode {
+                node_hash: storage_root,
+                node: MerkleNode::EdgeNode(EdgeNode {
+                    path: *key.0.key(),
+                    length: STATE_TRIE_HEIGHT,
+                    child: value,
+                }),
+            }]]
+        );
+
+        let contract_leaf_data = ContractLeafData { nonce, class_hash, storage_root };
+        assert_eq!(res.contracts_proof.contract_leaves_data, vec![contract_leaf_data]);
+        let contract_state_hash = calculate_contract_state_hash(&class_hash, &storage_root, &nonce);
+        let contracts_tree_root = calculate_edge_node_hash(
+            CONTRACTS_TRIE_HASH_FUNCTION,
+            &contract_state_hash,
+            address.0.key(),
+            STATE_TRIE_HEIGHT,
+        );
+        assert_eq!(
+            res.contracts_proof.nodes,
+            vec![NodeHashToNode {
+                node_hash: contracts_tree_root,
+                node: MerkleNode::EdgeNode(EdgeNode {
+                    path: *address.0.key(),
+                    length: STATE_TRIE_HEIGHT,
+                    child: contract_state_hash,
+                }),
+            }]
+        );
+
+        let classes_tree_root = res.classes_proof[0].node_hash;
+        assert_eq!(res.classes_proof.len(), 1);
+        assert_eq!(
+            res.global_roots,
+            GlobalRoots {
+                contracts_tree_root,
+                classes_tree_root,
+                global_root: calculate_global_root(&contracts_tree_root, &classes_tree_root),
+                block_hash: header.block_hash,
+            }
+        );
+    }
+
+    // Storage proofs are not supported for the pending block.
+    call_api_then_assert_and_validate_schema_for_err::<_, StorageProof>(
+        &module,
+        method_name,
+        vec![
+            Box::new(BlockId::Tag(Tag::Pending)),
+            Box::<Vec<ClassHash>>::default(),
+            Box::new(vec![address]),
+            Box::<Vec<ContractStorageKeys>>::default(),
+        ],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &STORAGE_PROOF_NOT_SUPPORTED.into(),
+    )
+    .await;
+
+    // Ask for more keys than the node supports.
+    let max_storage_proof_keys = get_test_rpc_config().max_storage_proof_keys;
+    call_api_then_assert_and_validate_schema_for_err::<_, StorageProof>(
+        &module,
+        method_name,
+        vec![
+            Box::new(BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))),
+            Box::new(vec![declared_class_hash]),
+            Box::new(vec![address]),
+            Box::new(vec![ContractStorageKeys {
+                contract_address: address,
+                storage_keys: vec![key; max_storage_proof_keys - 1],
+            }]),
+        ],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &TOO_MANY_KEYS_IN_STORAGE_PROOF.into(),
+    )
+    .await;
+
+    // Ask for an invalid block number.
+    call_api_then_assert_and_validate_schema_for_err::<_, StorageProof>(
+        &module,
+        method_name,
+        vec![
+            Box::new(BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(2)))),
+            Box::<Vec<ClassHash>>::default(),
+            Box::new(vec![address]),
+            Box::<Vec<ContractStorageKeys>>::default(),
+        ],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &BLOCK_NOT_FOUND.into(),
+    )
+    .await;
+
+    // Ask for a block whose state wasn't synced yet.
+    let header = BlockHeader {
+        block_number: BlockNumber(2),
+        block_hash: BlockHash(StarkHash::from(3_u64)),
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_number, &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+    call_api_then_assert_and_validate_schema_for_err::<_, StorageProof>(
+        &module,
+        method_name,
+        vec![
+            Box::new(BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(2)))),
+            Box::<Vec<ClassHash>>::default(),
+            Box::new(vec![address]),
+            Box::<Vec<ContractStorageKeys>>::default(),
+        ],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &BLOCK_NOT_FOUND.into(),
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn get_storage_at() {
     let method_name = "starknet_V0_7_getStorageAt";
@@ -2166,6 +2395,244 @@ fn generate_client_transaction_and_rpc_transaction(
     }
 }
 
+#[tokio::test]
+async fn get_transactions_by_sender() {
+    let method_name = "starknet_V0_7_getTransactionsBySender";
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let sender_address = ContractAddress(patricia_key!("0x11"));
+    let mut expected_transactions = vec![];
+    for i in 0..2_u8 {
+        let header = BlockHeader {
+            block_number: BlockNumber(i.into()),
+            block_hash: BlockHash(StarkHash::from(i + 1)),
+            ..Default::default()
+        };
+        let nonce = Nonce(StarkFelt::from(i));
+        let transaction = StarknetApiTransaction::Invoke(StarknetApiInvokeTransaction::V1(
+            StarknetApiInvokeTransactionV1 { sender_address, nonce, ..Default::default() },
+        ));
+        let transaction_hash = TransactionHash(StarkHash::from(i + 10));
+        let body = starknet_api::block::BlockBody {
+            transactions: vec![transaction.clone()],
+            transaction_outputs: vec![StarknetApiTransactionOutput::Invoke(
+                StarknetApiInvokeTransactionOutput::default(),
+            )],
+            transaction_hashes: vec![transaction_hash],
+        };
+        storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(header.block_number, &header)
+            .unwrap()
+            .append_body(header.block_number, body)
+            .unwrap()
+            .commit()
+            .unwrap();
+        expected_transactions.push(SenderTransaction {
+            block_hash: header.block_hash,
+            block_number: header.block_number,
+            nonce,
+            transaction: TransactionWithHash {
+                transaction: transaction.try_into().unwrap(),
+                transaction_hash,
+            },
+        });
+    }
+
+    // Iterate the transactions one by one with the continuation token.
+    let res =
+        module.call::<_, SenderTransactionsChunk>(method_name, (sender_address, 1)).await.unwrap();
+    assert_eq!(
+        res,
+        SenderTransactionsChunk {
+            transactions: vec![expected_transactions[0].clone()],
+            continuation_token: Some(Nonce(StarkFelt::ONE)),
+        }
+    );
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        method_name,
+        vec![Box::new(sender_address), Box::new(1), Box::new(Nonce(StarkFelt::ONE))],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &SenderTransactionsChunk {
+            transactions: vec![expected_transactions[1].clone()],
+            continuation_token: None,
+        },
+    )
+    .await;
+
+    // An account that didn't send transactions.
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        method_name,
+        vec![
+            Box::new(ContractAddress(patricia_key!("0x12"))),
+            Box::new(1),
+            Box::new(Nonce(StarkFelt::ZERO)),
+        ],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &SenderTransactionsChunk { transactions: vec![], continuation_token: None },
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, SenderTransactionsChunk>(
+        &module,
+        method_name,
+        vec![Box::new(sender_address), Box::new(usize::MAX), Box::new(Nonce(StarkFelt::ZERO))],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &PAGE_SIZE_TOO_BIG.into(),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn get_storage_changes() {
+    let method_name = "starknet_V0_7_getStorageChanges";
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let contract_address = ContractAddress(patricia_key!("0x11"));
+    let key1 = StorageKey(patricia_key!("0x1"));
+    let key2 = StorageKey(patricia_key!("0x2"));
+    let storage_diffs = vec![
+        indexmap! {key1 => stark_felt!("0x1")},
+        indexmap! {key1 => stark_felt!("0x2"), key2 => stark_felt!("0x3")},
+        indexmap! {key2 => stark_felt!("0x4")},
+    ];
+    for (i, storage_diffs) in storage_diffs.into_iter().enumerate() {
+        let block_number = BlockNumber(i as u64);
+        storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(
+                block_number,
+                &BlockHeader {
+                    block_number,
+                    block_hash: BlockHash(StarkHash::from(block_number.0 + 1)),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .append_state_diff(
+                block_number,
+                starknet_api::state::ThinStateDiff {
+                    storage_diffs: indexmap! {contract_address => storage_diffs},
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    // Iterate the changes of all the keys from block 1 with a continuation token.
+    let filter = StorageChangesFilter {
+        contract_address,
+        from_block: Some(BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))),
+        chunk_size: 2,
+        ..Default::default()
+    };
+    let continuation_token =
+        ContinuationToken(serde_json::to_string(&(key2, BlockNumber(2))).unwrap());
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        method_name,
+        vec![Box::new(filter.clone())],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &StorageChangesChunk {
+            storage_changes: vec![
+                StorageChange {
+                    key: key1,
+                    block_number: BlockNumber(1),
+                    value: stark_felt!("0x2"),
+                },
+                StorageChange {
+                    key: key2,
+                    block_number: BlockNumber(1),
+                    value: stark_felt!("0x3"),
+                },
+            ],
+            continuation_token: Some(continuation_token.clone()),
+        },
+    )
+    .await;
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        method_name,
+        vec![Box::new(StorageChangesFilter {
+            continuation_token: Some(continuation_token),
+            ..filter.clone()
+        })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &StorageChangesChunk {
+            storage_changes: vec![StorageChange {
+                key: key2,
+                block_number: BlockNumber(2),
+                value: stark_felt!("0x4"),
+            }],
+            continuation_token: None,
+        },
+    )
+    .await;
+
+    // The changes of a single key.
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        method_name,
+        vec![Box::new(StorageChangesFilter {
+            contract_address,
+            key: Some(key1),
+            chunk_size: 2,
+            ..Default::default()
+        })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &StorageChangesChunk {
+            storage_changes: vec![
+                StorageChange {
+                    key: key1,
+                    block_number: BlockNumber(0),
+                    value: stark_felt!("0x1"),
+                },
+                StorageChange {
+                    key: key1,
+                    block_number: BlockNumber(1),
+                    value: stark_felt!("0x2"),
+                },
+            ],
+            continuation_token: None,
+        },
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, StorageChangesChunk>(
+        &module,
+        method_name,
+        vec![Box::new(StorageChangesFilter {
+            continuation_token: Some(ContinuationToken("not a token".to_owned())),
+            ..filter.clone()
+        })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &INVALID_CONTINUATION_TOKEN.into(),
+    )
+    .await;
+    call_api_then_assert_and_validate_schema_for_err::<_, StorageChangesChunk>(
+        &module,
+        method_name,
+        vec![Box::new(StorageChangesFilter { chunk_size: usize::MAX, ..filter })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &PAGE_SIZE_TOO_BIG.into(),
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn get_transaction_by_hash() {
     let method_name = "starknet_V0_7_getTransactionByHash";
@@ -2257,6 +2724,130 @@ async fn get_transaction_by_hash_state_only() {
     );
 }
 
+#[tokio::test]
+async fn get_pruned_block_data() {
+    let (module, mut storage_writer) = get_test_rpc_server_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, None, None, Some(StorageScope::Pruned));
+    let block = get_test_block(1, None, None, None);
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(block.header.block_number));
+    let transaction_hash = block.body.transaction_hashes[0];
+    let contract_address = ContractAddress(patricia_key!("0x11"));
+    let key = StorageKey(patricia_key!("0x1"));
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block.header.block_number, &block.header)
+        .unwrap()
+        .append_body(block.header.block_number, block.body)
+        .unwrap()
+        .append_state_diff(
+            block.header.block_number,
+            starknet_api::state::ThinStateDiff {
+                storage_diffs: indexmap! {contract_address => indexmap! {key => stark_felt!("0x1")}},
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .prune_blocks(block.header.block_number.unchecked_next())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The transaction hashes of a pruned block are kept.
+    let res =
+        module.call::<_, Block>("starknet_V0_7_getBlockWithTxHashes", [block_id]).await.unwrap();
+    assert_eq!(res.transactions, Transactions::Hashes(vec![transaction_hash]));
+
+    call_api_then_assert_and_validate_schema_for_err::<_, Block>(
+        &module,
+        "starknet_V0_7_getBlockWithTxs",
+        vec![Box::new(block_id)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, StateUpdate>(
+        &module,
+        "starknet_V0_7_getStateUpdate",
+        vec![Box::new(block_id)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, TransactionWithHash>(
+        &module,
+        "starknet_V0_7_getTransactionByHash",
+        vec![Box::new(transaction_hash)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, GeneralTransactionReceipt>(
+        &module,
+        "starknet_V0_7_getTransactionReceipt",
+        vec![Box::new(transaction_hash)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    call_api_then_assert_and_validate_schema_for_err::<_, EventsChunk>(
+        &module,
+        "starknet_V0_7_getEvents",
+        vec![Box::new(EventFilter {
+            from_block: Some(block_id),
+            chunk_size: 10,
+            ..Default::default()
+        })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &block_pruned_error(),
+    )
+    .await;
+
+    // Without a from block, the events are returned from the first block that wasn't pruned.
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        "starknet_V0_7_getEvents",
+        vec![Box::new(EventFilter { chunk_size: 10, ..Default::default() })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &EventsChunk { events: vec![], continuation_token: None },
+    )
+    .await;
+
+    // Pruning doesn't delete the storage changes.
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        "starknet_V0_7_getStorageChanges",
+        vec![Box::new(StorageChangesFilter {
+            contract_address,
+            from_block: Some(block_id),
+            chunk_size: 10,
+            ..Default::default()
+        })],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &StorageChangesChunk {
+            storage_changes: vec![StorageChange {
+                key,
+                block_number: block.header.block_number,
+                value: stark_felt!("0x1"),
+            }],
+            continuation_token: None,
+        },
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn get_transaction_by_block_id_and_index() {
     let method_name = "starknet_V0_7_getTransactionByBlockIdAndIndex";
@@ -3029,6 +3620,55 @@ async fn get_events_pending_keys_filter() {
     .await;
 }
 
+#[tokio::test]
+async fn get_events_address_and_keys_filter() {
+    let address = ContractAddress(patricia_key!("0x22"));
+    let unrelated_event = EventMetadata { address: Some(address), keys: None };
+    let key_event = EventMetadata { address: Some(address), keys: Some(vec![KEY0_0.clone()]) };
+    let blocks_metadata = vec![
+        BlockMetadata(vec![vec![
+            unrelated_event.clone(),
+            key_event.clone(),
+            EventMetadata { address: None, keys: Some(vec![KEY0_0.clone()]) },
+        ]]),
+        BlockMetadata(vec![vec![unrelated_event.clone(), unrelated_event], vec![key_event]]),
+    ];
+    let pending_block_metadata = None;
+    let is_pending_up_to_date = true;
+    let expected_result_by_index = vec![
+        (
+            vec![EventIndex(
+                TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0)),
+                EventIndexInTransactionOutput(1),
+            )],
+            Some(ContinuationTokenAsStruct(EventIndex(
+                TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(1)),
+                EventIndexInTransactionOutput(0),
+            ))),
+        ),
+        (
+            vec![EventIndex(
+                TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(1)),
+                EventIndexInTransactionOutput(0),
+            )],
+            None,
+        ),
+    ];
+    test_get_events(
+        blocks_metadata,
+        pending_block_metadata,
+        is_pending_up_to_date,
+        EventFilter {
+            chunk_size: 1,
+            address: Some(address),
+            keys: vec![HashSet::from([KEY0_0.clone()])],
+            ..Default::default()
+        },
+        expected_result_by_index,
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn get_events_from_block() {
     let blocks_metadata = vec![
@@ -72,6 +72,19 @@ impl From<TransactionExecutionError> for JsonRpcError<TransactionExecutionError>
         Self { code: 41, message: "Transaction execution error", data: Some(tx_execution_error) }
     }
 }
+
+pub const STORAGE_PROOF_NOT_SUPPORTED: JsonRpcError<String> = JsonRpcError {
+    code: 42,
+    message: "The node doesn't support storage proofs for the requested block",
+    data: None,
+};
+
+pub const TOO_MANY_KEYS_IN_STORAGE_PROOF: JsonRpcError<String> = JsonRpcError {
+    code: 44,
+    message: "Too many keys provided in a storage proof request",
+    data: None,
+};
+
 pub const CLASS_ALREADY_DECLARED: JsonRpcError<String> =
     JsonRpcError { code: 51, message: "Class already declared", data: None };
 
@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 
+use papyrus_storage::state::trie::TrieNode;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockHash;
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, GlobalRoot, Nonce};
-use starknet_api::hash::StarkFelt;
+use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::{
     EntryPoint,
     EntryPointType,
@@ -240,3 +241,78 @@ pub struct ReplacedClasses {
     pub contract_address: ContractAddress,
     pub class_hash: ClassHash,
 }
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ContractStorageKeys {
+    pub contract_address: ContractAddress,
+    pub storage_keys: Vec<StorageKey>,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct StorageProof {
+    pub classes_proof: Vec<NodeHashToNode>,
+    pub contracts_proof: ContractsProof,
+    pub contracts_storage_proofs: Vec<Vec<NodeHashToNode>>,
+    pub global_roots: GlobalRoots,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ContractsProof {
+    pub nodes: Vec<NodeHashToNode>,
+    pub contract_leaves_data: Vec<ContractLeafData>,
+}
+
+// The data that is hashed into the leaf of a contract in the contracts trie.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ContractLeafData {
+    pub nonce: Nonce,
+    pub class_hash: ClassHash,
+    pub storage_root: StarkHash,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct GlobalRoots {
+    pub contracts_tree_root: StarkHash,
+    pub classes_tree_root: StarkHash,
+    pub global_root: GlobalRoot,
+    pub block_hash: BlockHash,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct NodeHashToNode {
+    pub node_hash: StarkHash,
+    pub node: MerkleNode,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MerkleNode {
+    BinaryNode(BinaryNode),
+    EdgeNode(EdgeNode),
+}
+
+impl From<TrieNode> for MerkleNode {
+    fn from(node: TrieNode) -> Self {
+        match node {
+            TrieNode::Binary(node) => {
+                Self::BinaryNode(BinaryNode { left: node.left, right: node.right })
+            }
+            TrieNode::Edge(node) => {
+                Self::EdgeNode(EdgeNode { path: node.path, length: node.length, child: node.child })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BinaryNode {
+    pub left: StarkHash,
+    pub right: StarkHash,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct EdgeNode {
+    pub path: StarkFelt,
+    pub length: u8,
+    pub child: StarkHash,
+}
@@ -0,0 +1,24 @@
+use std::process::exit;
+
+use clap::Command;
+use papyrus_storage::cli::{get_storage_config, parse_args, storage_args};
+use papyrus_storage::compaction::compact_storage;
+
+/// This executable compacts the memory mapped files of the storage and prints a JSON report of the
+/// bytes reclaimed from each file. Fails if the storage is opened by another process, e.g. the node
+/// or a read-only replica.
+fn main() {
+    let storage_config = parse_args(storage_args(Command::new("Compact storage")), |matches| {
+        get_storage_config(matches)
+    });
+    match compact_storage(storage_config) {
+        Ok(stats) => println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).expect("Failed serializing the report")
+        ),
+        Err(e) => {
+            eprintln!("Failed compacting the storage with error: {}", e);
+            exit(1);
+        }
+    }
+}
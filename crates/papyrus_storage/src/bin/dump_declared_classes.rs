@@ -1,32 +1,108 @@
-use clap::{Arg, Command};
+#[cfg(feature = "columnar_export")]
+use std::path::PathBuf;
+use std::process::exit;
+
+#[cfg(feature = "columnar_export")]
+use clap::value_parser;
+use clap::{Arg, ArgMatches, Command};
+use papyrus_storage::cli::{
+    block_number_arg,
+    get_block_number,
+    get_required,
+    parse_args,
+    verify_block_range,
+    CliError,
+};
+#[cfg(feature = "columnar_export")]
+use papyrus_storage::cli::{get_storage_config, storage_args};
+#[cfg(feature = "columnar_export")]
+use papyrus_storage::columnar_export::{export_to_columnar_files, ColumnarFormat};
 use papyrus_storage::utils::dump_declared_classes_table_by_block_range;
+#[cfg(feature = "columnar_export")]
+use papyrus_storage::{open_storage_read_only, StorageConfig};
+#[cfg(feature = "columnar_export")]
+use starknet_api::block::BlockNumber;
 
-/// This executable dumps the declared_classes table from the storage to a file.
+/// This executable dumps the declared_classes table from the storage to a file. With the
+/// columnar_export feature, its columnar subcommand exports the headers, transactions, receipts,
+/// events and state diffs from the storage to Parquet or Arrow IPC files. The storage is opened
+/// read only by the columnar subcommand, so it can run alongside the node.
 fn main() {
-    let cli_params = get_cli_params();
-    match dump_declared_classes_table_by_block_range(
-        cli_params.start_block,
-        cli_params.end_block,
-        &cli_params.file_path,
-        &cli_params.chain_id,
-    ) {
-        Ok(_) => println!("Dumped declared_classes table to file: {} .", cli_params.file_path),
-        Err(e) => println!("Failed dumping declared_classes table with error: {}", e),
+    match parse_args(get_command(), get_cli_command) {
+        CliCommand::DumpDeclaredClasses { start_block, end_block, file_path, chain_id } => {
+            match dump_declared_classes_table_by_block_range(
+                start_block,
+                end_block,
+                &file_path,
+                &chain_id,
+            ) {
+                Ok(_) => println!("Dumped declared_classes table to file: {} .", file_path),
+                Err(e) => {
+                    eprintln!("Failed dumping declared_classes table with error: {}", e);
+                    exit(1);
+                }
+            }
+        }
+        #[cfg(feature = "columnar_export")]
+        CliCommand::Columnar {
+            storage_config,
+            output_dir,
+            format,
+            start_block,
+            end_block,
+            blocks_per_file,
+        } => {
+            let result =
+                open_storage_read_only(storage_config).map_err(Into::into).and_then(|reader| {
+                    export_to_columnar_files(
+                        &reader,
+                        &output_dir,
+                        format,
+                        start_block,
+                        end_block,
+                        blocks_per_file,
+                    )
+                });
+            match result {
+                Ok(next_block) => println!(
+                    "Exported the storage to {:?}, the next block to export is {}.",
+                    output_dir, next_block
+                ),
+                Err(e) => {
+                    eprintln!("Failed exporting the storage with error: {}", e);
+                    exit(1);
+                }
+            }
+        }
     }
 }
 
-struct CliParams {
-    start_block: u64,
-    end_block: u64,
-    file_path: String,
-    chain_id: String,
+enum CliCommand {
+    DumpDeclaredClasses {
+        start_block: u64,
+        end_block: u64,
+        file_path: String,
+        chain_id: String,
+    },
+    #[cfg(feature = "columnar_export")]
+    Columnar {
+        storage_config: StorageConfig,
+        output_dir: PathBuf,
+        format: ColumnarFormat,
+        start_block: Option<BlockNumber>,
+        end_block: Option<BlockNumber>,
+        blocks_per_file: u64,
+    },
 }
 
 /// The start_block and end_block arguments are mandatory and define the block range to dump,
 /// start_block is inclusive and end_block is exclusive. The file_path is an optional parameter,
 /// otherwise the data will be dumped to "dump_declared_classes.json".
-fn get_cli_params() -> CliParams {
-    let matches = Command::new("Dump declared classes")
+fn get_command() -> Command {
+    Command::new("Dump declared classes")
+        .subcommands(subcommands())
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
         .arg(
             Arg::new("file_path")
                 .short('f')
@@ -35,16 +111,14 @@ fn get_cli_params() -> CliParams {
                 .help("The file path to dump the declared classes table to."),
         )
         .arg(
-            Arg::new("start_block")
+            block_number_arg("start_block")
                 .short('s')
-                .long("start_block")
                 .required(true)
                 .help("The block number to start dumping from."),
         )
         .arg(
-            Arg::new("end_block")
+            block_number_arg("end_block")
                 .short('e')
-                .long("end_block")
                 .required(true)
                 .help("The block number to end dumping at."),
         )
@@ -55,24 +129,94 @@ fn get_cli_params() -> CliParams {
                 .required(true)
                 .help("The chain id SN_MAIN/SN_SEPOLIA, default value is SN_MAIN."),
         )
-        .get_matches();
+}
+
+// The subcommands of the enabled features.
+fn subcommands() -> Vec<Command> {
+    vec![
+        #[cfg(feature = "columnar_export")]
+        columnar_command(),
+    ]
+}
+
+fn get_cli_command(matches: &ArgMatches) -> Result<CliCommand, CliError> {
+    match matches.subcommand() {
+        #[cfg(feature = "columnar_export")]
+        Some(("columnar", matches)) => get_columnar_command(matches),
+        _ => {
+            let start_block = get_block_number(matches, "start_block")?
+                .ok_or(CliError::MissingArgument("start_block"))?;
+            let end_block = get_block_number(matches, "end_block")?
+                .ok_or(CliError::MissingArgument("end_block"))?;
+            verify_block_range(start_block, end_block)?;
+            Ok(CliCommand::DumpDeclaredClasses {
+                start_block: start_block.0,
+                end_block: end_block.0,
+                file_path: get_required::<String>(matches, "file_path")?.clone(),
+                chain_id: get_required::<String>(matches, "chain_id")?.clone(),
+            })
+        }
+    }
+}
+
+/// The storage is given by the arguments of [`storage_args`], and the output_dir argument is
+/// mandatory. Without start_block, the export resumes from the last block that was exported to
+/// output_dir, and without end_block, it continues up to the last block in the storage. end_block
+/// is exclusive.
+#[cfg(feature = "columnar_export")]
+fn columnar_command() -> Command {
+    storage_args(Command::new("columnar"))
+        .about("Exports the storage to Parquet or Arrow IPC files.")
+        .arg(
+            Arg::new("output_dir")
+                .short('o')
+                .long("output_dir")
+                .required(true)
+                .value_parser(value_parser!(PathBuf))
+                .help("The directory to export the files to."),
+        )
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .default_value("parquet")
+                .value_parser(parse_format)
+                .help("The format of the files: parquet or arrow."),
+        )
+        .arg(block_number_arg("start_block").help("The block number to start exporting from."))
+        .arg(block_number_arg("end_block").short('e').help("The block number to end exporting at."))
+        .arg(
+            Arg::new("blocks_per_file")
+                .short('b')
+                .long("blocks_per_file")
+                .default_value("10000")
+                .value_parser(value_parser!(u64).range(1..))
+                .help("The maximal number of blocks in each file."),
+        )
+}
+
+#[cfg(feature = "columnar_export")]
+fn get_columnar_command(matches: &ArgMatches) -> Result<CliCommand, CliError> {
+    let start_block = get_block_number(matches, "start_block")?;
+    let end_block = get_block_number(matches, "end_block")?;
+    if let (Some(start_block), Some(end_block)) = (start_block, end_block) {
+        verify_block_range(start_block, end_block)?;
+    }
+    Ok(CliCommand::Columnar {
+        storage_config: get_storage_config(matches)?,
+        output_dir: get_required::<PathBuf>(matches, "output_dir")?.clone(),
+        format: *get_required(matches, "format")?,
+        start_block,
+        end_block,
+        blocks_per_file: *get_required(matches, "blocks_per_file")?,
+    })
+}
 
-    let file_path =
-        matches.get_one::<String>("file_path").expect("Failed parsing file_path").to_string();
-    let start_block = matches
-        .get_one::<String>("start_block")
-        .expect("Failed parsing start_block")
-        .parse::<u64>()
-        .expect("Failed parsing start_block");
-    let end_block = matches
-        .get_one::<String>("end_block")
-        .expect("Failed parsing end_block")
-        .parse::<u64>()
-        .expect("Failed parsing end_block");
-    if start_block >= end_block {
-        panic!("start_block must be smaller than end_block");
+#[cfg(feature = "columnar_export")]
+fn parse_format(format: &str) -> Result<ColumnarFormat, String> {
+    match format {
+        "parquet" => Ok(ColumnarFormat::Parquet),
+        "arrow" => Ok(ColumnarFormat::ArrowIpc),
+        _ => Err(format!("Unknown format {format}, should be parquet or arrow.")),
     }
-    let chain_id =
-        matches.get_one::<String>("chain_id").expect("Failed parsing chain_id").to_string();
-    CliParams { start_block, end_block, file_path, chain_id }
 }
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::{value_parser, Arg, Command};
+use papyrus_storage::cli::{
+    block_number_arg,
+    get_block_number,
+    get_required,
+    get_storage_config,
+    parse_args,
+    storage_args,
+};
+use papyrus_storage::snapshot::export_snapshot;
+use papyrus_storage::StorageConfig;
+use starknet_api::block::BlockNumber;
+
+/// This executable exports a snapshot of the storage to a file. The snapshot can be restored by
+/// setting the storage.snapshot_to_import config of a node with an empty storage. The node must be
+/// stopped if the snapshot is taken at an earlier block than the markers of the storage.
+fn main() {
+    let cli_params = get_cli_params();
+    match export_snapshot(cli_params.storage_config, &cli_params.file_path, cli_params.block_number)
+    {
+        Ok(manifest) => println!(
+            "Exported a snapshot of the storage to file: {} . Snapshot manifest: {:?}",
+            cli_params.file_path.display(),
+            manifest
+        ),
+        Err(e) => {
+            eprintln!("Failed exporting a snapshot of the storage with error: {}", e);
+            exit(1);
+        }
+    }
+}
+
+struct CliParams {
+    storage_config: StorageConfig,
+    file_path: PathBuf,
+    block_number: Option<BlockNumber>,
+}
+
+/// The storage is given by the arguments of [`storage_args`]. The file_path argument is optional,
+/// otherwise the snapshot is written to "storage_snapshot". The block_number argument is optional,
+/// otherwise the snapshot is taken at the markers of the storage.
+fn get_cli_params() -> CliParams {
+    let command = storage_args(Command::new("Export storage snapshot"))
+        .arg(
+            Arg::new("file_path")
+                .short('f')
+                .long("file_path")
+                .default_value("storage_snapshot")
+                .value_parser(value_parser!(PathBuf))
+                .help("The file path to write the snapshot to."),
+        )
+        .arg(
+            block_number_arg("block_number")
+                .short('b')
+                .help("The block to take the snapshot at, the later blocks aren't exported."),
+        );
+    parse_args(command, |matches| {
+        Ok(CliParams {
+            storage_config: get_storage_config(matches)?,
+            file_path: get_required::<PathBuf>(matches, "file_path")?.clone(),
+            block_number: get_block_number(matches, "block_number")?,
+        })
+    })
+}
@@ -0,0 +1,60 @@
+use std::process::exit;
+
+use clap::Command;
+use papyrus_storage::cli::{
+    block_number_arg,
+    get_block_number,
+    get_storage_config,
+    parse_args,
+    storage_args,
+    CliError,
+};
+use papyrus_storage::revert::revert_to_block;
+use papyrus_storage::{open_storage_without_migrations, StorageConfig};
+use starknet_api::block::BlockNumber;
+
+/// This executable reverts the storage back to a given block, so the node resyncs the blocks from
+/// it when it's started again. The node must be stopped while the storage is reverted.
+fn main() {
+    let cli_params = get_cli_params();
+    let result = open_storage_without_migrations(cli_params.storage_config)
+        .and_then(|(_, mut writer)| revert_to_block(&mut writer, cli_params.block_number));
+    match result {
+        Ok(header_marker) if header_marker <= cli_params.block_number => println!(
+            "The storage has no blocks from block {}, nothing was reverted.",
+            cli_params.block_number
+        ),
+        Ok(header_marker) => println!(
+            "Reverted blocks {} to {} of the storage.",
+            cli_params.block_number,
+            header_marker.prev().expect("The header marker should be positive")
+        ),
+        Err(e) => {
+            eprintln!("Failed reverting the storage with error: {}", e);
+            exit(1);
+        }
+    }
+}
+
+struct CliParams {
+    storage_config: StorageConfig,
+    block_number: BlockNumber,
+}
+
+/// The storage is given by the arguments of [`storage_args`]. The block_number argument is
+/// mandatory, the blocks from it onwards are reverted.
+fn get_cli_params() -> CliParams {
+    let command = storage_args(Command::new("Revert storage")).arg(
+        block_number_arg("block_number")
+            .short('b')
+            .required(true)
+            .help("The first block to revert, the storage is reverted back to this block."),
+    );
+    parse_args(command, |matches| {
+        Ok(CliParams {
+            storage_config: get_storage_config(matches)?,
+            block_number: get_block_number(matches, "block_number")?
+                .ok_or(CliError::MissingArgument("block_number"))?,
+        })
+    })
+}
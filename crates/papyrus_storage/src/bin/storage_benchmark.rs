@@ -1,12 +1,19 @@
+use std::collections::BTreeMap;
 use std::fs::{read_to_string, File};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use clap::{Arg, Command};
-use papyrus_common::storage_query::StorageQuery;
-use papyrus_storage::db::DbConfig;
-use papyrus_storage::state::StateStorageReader;
-use papyrus_storage::StorageConfig;
-use serde::{Deserialize, Serialize};
+use papyrus_common::storage_query::{DocumentedQuery, StorageQuery};
+use papyrus_storage::body::events::{EventIndex, EventsReader};
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::compiled_class::CasmStorageReader;
+use papyrus_storage::compression_utils::{CompressionCodec, CompressionContext};
+use papyrus_storage::db::{DbConfig, RO};
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::{StateReader, StateStorageReader};
+use papyrus_storage::utils::get_contract_class_compression_samples;
+use papyrus_storage::{StorageConfig, StorageTxn};
+use serde::Serialize;
 use starknet_api::core::ChainId;
 use statistical::median;
 
@@ -17,15 +24,22 @@ use statistical::median;
 pub fn main() {
     let cli_params = get_cli_params();
 
-    // Creates List of queries to be executed.
+    // Creates List of queries to be executed. The queries are documented with the time they took
+    // when they were recorded, unless they were recorded before the times were documented.
     println!("Creating queries");
-    let mut queries: Vec<StorageQuery> = Vec::new();
+    let mut queries: Vec<(StorageQuery, Option<Duration>)> = Vec::new();
     for line in read_to_string(cli_params.queries_file_path)
         .expect("Should be able to read the queries file")
         .lines()
     {
-        queries
-            .push(serde_json::from_str(line).expect("Query should be a valid query json object"));
+        let query = match serde_json::from_str::<DocumentedQuery>(line) {
+            Ok(DocumentedQuery { query, duration }) => (query, Some(duration)),
+            Err(_) => (
+                serde_json::from_str(line).expect("Query should be a valid query json object"),
+                None,
+            ),
+        };
+        queries.push(query);
     }
 
     // Open storage to execute the queries.
@@ -37,8 +51,8 @@ pub fn main() {
     };
     let config = StorageConfig { db_config, ..Default::default() };
 
-    let (reader, mut _writer) =
-        papyrus_storage::open_storage(config).expect("Should be able to open storage");
+    let (reader, mut _writer) = papyrus_storage::open_storage_without_migrations(config)
+        .expect("Should be able to open storage");
     let txn = reader.begin_ro_txn().expect("Should be able to begin read only transaction");
     let state_reader = txn.get_state_reader().expect("Should be able to get state reader");
 
@@ -46,108 +60,184 @@ pub fn main() {
 
     // Execute the queries and measure the time it takes to execute them.
     println!("Executing queries");
-    for q in queries {
-        let exec_time;
-        match q {
-            StorageQuery::GetClassHashAt(state_number, contract_address) => {
-                let now = std::time::Instant::now();
-                let _class_hash = state_reader.get_class_hash_at(state_number, &contract_address);
-                exec_time = now.elapsed();
-                times.get_class_hash_at.push(exec_time);
-            }
-            StorageQuery::GetNonceAt(state_number, contract_address) => {
-                let now = std::time::Instant::now();
-                let _nonce = state_reader.get_nonce_at(state_number, &contract_address);
-                exec_time = now.elapsed();
-                times.get_nonce_at.push(exec_time);
-            }
-            StorageQuery::GetStorageAt(state_number, contract_address, storage_key) => {
-                let now = std::time::Instant::now();
-                let _storage =
-                    state_reader.get_storage_at(state_number, &contract_address, &storage_key);
-                exec_time = now.elapsed();
-                times.get_storage_at.push(exec_time);
-            }
-        }
+    for (q, recorded_time) in queries {
+        let now = Instant::now();
+        execute_query(&txn, &state_reader, &q);
+        let exec_time = now.elapsed();
+        times.0.entry(q.name()).or_default().push(exec_time);
         println!("{}", serde_json::to_string(&q).expect("Should be able to serialize the query"));
         println!("time in microseconds: {}", exec_time.as_micros());
+        if let Some(recorded_time) = recorded_time {
+            println!("recorded time in microseconds: {}", recorded_time.as_micros());
+        }
     }
 
+    // Compress and decompress contract classes with each codec.
+    println!("Executing compression");
+    let samples = get_contract_class_compression_samples(&txn, cli_params.compression_samples)
+        .expect("Should be able to get contract classes samples");
+
     println!("Writing results to file");
     let results_file = File::create(cli_params.output_file_path)
         .expect("Should be able to create the output file");
-    let final_results = times.get_final_results();
+    let mut final_results = times.get_final_results();
+    final_results.extend(get_compression_results(&samples, reader.compression_context()));
     serde_json::to_writer(results_file, &final_results)
         .expect("Should be able to write to the output file");
 }
 
-// Records the time it takes to execute the queries.
-#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-struct Times {
-    get_class_hash_at: Vec<Duration>,
-    get_nonce_at: Vec<Duration>,
-    get_storage_at: Vec<Duration>,
+// Executes a query, reading the same data as the documented call of the query.
+fn execute_query(
+    txn: &StorageTxn<'_, RO>,
+    state_reader: &StateReader<'_, RO>,
+    query: &StorageQuery,
+) {
+    match query {
+        StorageQuery::GetClassHashAt(state_number, contract_address) => {
+            let _class_hash = state_reader.get_class_hash_at(*state_number, contract_address);
+        }
+        StorageQuery::GetNonceAt(state_number, contract_address) => {
+            let _nonce = state_reader.get_nonce_at(*state_number, contract_address);
+        }
+        StorageQuery::GetStorageAt(state_number, contract_address, storage_key) => {
+            let _storage =
+                state_reader.get_storage_at(*state_number, contract_address, storage_key);
+        }
+        StorageQuery::GetClassDefinitionAt(state_number, class_hash) => {
+            let _class = state_reader.get_class_definition_at(*state_number, class_hash);
+        }
+        StorageQuery::GetDeprecatedClassDefinitionAt(state_number, class_hash) => {
+            let _class = state_reader.get_deprecated_class_definition_at(*state_number, class_hash);
+        }
+        StorageQuery::GetCasm(class_hash) => {
+            let _casm = txn.get_casm(class_hash);
+        }
+        StorageQuery::GetBlockHeader(block_number) => {
+            let _header = txn.get_block_header(*block_number);
+        }
+        StorageQuery::GetTransaction(block_number, tx_offset) => {
+            let _transaction = txn.get_transaction(TransactionIndex(*block_number, *tx_offset));
+        }
+        StorageQuery::GetTransactionOutput(block_number, tx_offset) => {
+            let _transaction_output =
+                txn.get_transaction_output(TransactionIndex(*block_number, *tx_offset));
+        }
+        StorageQuery::GetStateDiff(block_number) => {
+            let _state_diff = txn.get_state_diff(*block_number);
+        }
+        StorageQuery::IterEvents {
+            address,
+            from_event: (block_number, tx_offset, event_index_in_tx),
+            to_block_number,
+            n_events,
+        } => {
+            let event_index =
+                EventIndex(TransactionIndex(*block_number, *tx_offset), *event_index_in_tx);
+            if let Ok(events) = txn.iter_events(*address, event_index, *to_block_number) {
+                events.take(*n_events).for_each(drop);
+            }
+        }
+        StorageQuery::IterEventsByKeys {
+            key_position,
+            keys,
+            from_event: (block_number, tx_offset, event_index_in_tx),
+            to_block_number,
+            n_events,
+        } => {
+            let event_index =
+                EventIndex(TransactionIndex(*block_number, *tx_offset), *event_index_in_tx);
+            if let Ok(events) =
+                txn.iter_events_by_keys(*key_position, keys, event_index, *to_block_number)
+            {
+                events.take(*n_events).for_each(drop);
+            }
+        }
+    }
 }
 
+// Records the time it takes to execute the queries, by the name of the kind of the query.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+struct Times(BTreeMap<&'static str, Vec<Duration>>);
+
 impl Times {
     // Returns statics about the executing times of the queries in a format that can be use in
     // github action.
     fn get_final_results(&self) -> Vec<Entry> {
-        let mut results: Vec<Entry> = vec![];
+        self.0
+            .iter()
+            .map(|(name, times)| Entry {
+                name: name.to_string(),
+                units: "Microseconds".to_string(),
+                value: median(&times.iter().map(|x| x.as_micros()).collect::<Vec<u128>>()) as usize,
+            })
+            .collect()
+    }
 
-        let get_class_hash_at_median = if self.get_class_hash_at.is_empty() {
-            0
-        } else {
-            median(&self.get_class_hash_at.iter().map(|x| x.as_micros()).collect::<Vec<u128>>())
-        };
-        results.push(Entry {
-            name: "get_class_hash_at".to_string(),
-            units: "Microseconds".to_string(),
-            value: get_class_hash_at_median as usize,
-        });
+    #[allow(dead_code)]
+    fn print_times(&self) {
+        println!("Times:");
+        let mut total_time = Duration::ZERO;
+        for (name, times) in &self.0 {
+            let time_sum = times.iter().sum::<Duration>();
+            println!(" - {name}: {:?}", time_sum.as_nanos());
+            total_time += time_sum;
+        }
+        println!(" - total time: {:?}", total_time.as_nanos());
+    }
+}
 
-        let get_nonce_at_median = if self.get_nonce_at.is_empty() {
-            0
-        } else {
-            median(&self.get_nonce_at.iter().map(|x| x.as_micros()).collect::<Vec<u128>>())
-        };
-        results.push(Entry {
-            name: "get_nonce_at".to_string(),
-            units: "Microseconds".to_string(),
-            value: get_nonce_at_median as usize,
-        });
+// Returns the total compressed size of the samples and the median latencies of compressing and
+// decompressing a sample with each codec. The dictionary of zstd is the latest dictionary of the
+// storage.
+fn get_compression_results(
+    samples: &[Vec<u8>],
+    compression_context: &CompressionContext,
+) -> Vec<Entry> {
+    let mut results = vec![Entry {
+        name: "uncompressed_size".to_string(),
+        units: "Bytes".to_string(),
+        value: samples.iter().map(Vec::len).sum(),
+    }];
 
-        let get_storage_at_median = if self.get_storage_at.is_empty() {
-            0
-        } else {
-            median(&self.get_storage_at.iter().map(|x| x.as_micros()).collect::<Vec<u128>>())
-        };
+    let mut codecs = vec![
+        ("gzip", CompressionCodec::Gzip, false),
+        ("zstd", CompressionCodec::Zstd, false),
+        ("lz4", CompressionCodec::Lz4, false),
+    ];
+    if compression_context.has_zstd_dictionary() {
+        codecs.push(("zstd_dictionary", CompressionCodec::Zstd, true));
+    }
+    for (codec_name, codec, use_dictionary) in codecs {
+        let mut compressed_size = 0;
+        let mut compress_times = vec![];
+        let mut decompress_times = vec![];
+        for sample in samples {
+            let now = Instant::now();
+            let compressed = compression_context
+                .compress_with_codec(sample, codec, use_dictionary)
+                .expect("Should be able to compress the sample");
+            compress_times.push(now.elapsed().as_micros());
+            let now = Instant::now();
+            compression_context.decompress(&compressed).expect("Should be able to decompress the sample");
+            decompress_times.push(now.elapsed().as_micros());
+            compressed_size += compressed.len();
+        }
 
         results.push(Entry {
-            name: "get_storage_at".to_string(),
-            units: "Microseconds".to_string(),
-            value: get_storage_at_median as usize,
+            name: format!("{codec_name}_compressed_size"),
+            units: "Bytes".to_string(),
+            value: compressed_size,
         });
-
-        results
-    }
-
-    #[allow(dead_code)]
-    fn print_times(&self) {
-        let get_class_hash_at_time_sum = self.get_class_hash_at.iter().sum::<Duration>();
-        let get_nonce_at_time_sum = self.get_nonce_at.iter().sum::<Duration>();
-        let get_storage_at_time_sum = self.get_storage_at.iter().sum::<Duration>();
-
-        println!("Times:");
-        println!(" - GetClassHashAt: {:?}", get_class_hash_at_time_sum.as_nanos());
-        println!(" - GetNonceAt: {:?}", get_nonce_at_time_sum.as_nanos());
-        println!(" - GetStorageAt: {:?}", get_storage_at_time_sum.as_nanos());
-        println!(
-            " - total time: {:?}",
-            (get_class_hash_at_time_sum + get_nonce_at_time_sum + get_storage_at_time_sum)
-                .as_nanos()
-        );
+        for (operation, times) in [("compress", compress_times), ("decompress", decompress_times)] {
+            let times_median = if times.is_empty() { 0 } else { median(&times) };
+            results.push(Entry {
+                name: format!("{codec_name}_{operation}"),
+                units: "Microseconds".to_string(),
+                value: times_median as usize,
+            });
+        }
     }
+    results
 }
 
 // Represents a single entry in the results file.
@@ -163,6 +253,7 @@ struct CliParams {
     db_file_path: String,
     output_file_path: String,
     chain_id: String,
+    compression_samples: usize,
 }
 
 fn get_cli_params() -> CliParams {
@@ -195,6 +286,13 @@ fn get_cli_params() -> CliParams {
                 .required(true)
                 .help("The chain id SN_MAIN/SN_SEPOLIA for example"),
         )
+        .arg(
+            Arg::new("compression_samples")
+                .short('s')
+                .long("compression_samples")
+                .default_value("100")
+                .help("The maximal number of contract classes to benchmark the compression on"),
+        )
         .get_matches();
 
     let queries_file_path = matches
@@ -210,5 +308,11 @@ fn get_cli_params() -> CliParams {
     let chain_id =
         matches.get_one::<String>("chain_id").expect("Missing parse chain_id").to_string();
 
-    CliParams { queries_file_path, db_file_path, output_file_path, chain_id }
+    let compression_samples = matches
+        .get_one::<String>("compression_samples")
+        .expect("Missing compression_samples")
+        .parse::<usize>()
+        .expect("Failed parsing compression_samples");
+
+    CliParams { queries_file_path, db_file_path, output_file_path, chain_id, compression_samples }
 }
@@ -0,0 +1,50 @@
+use std::process::exit;
+
+use clap::{Arg, ArgAction, Command};
+use papyrus_storage::cli::{get_storage_config, parse_args, storage_args};
+use papyrus_storage::fsck::check_storage;
+use papyrus_storage::StorageConfig;
+
+/// This executable checks the integrity of the storage and prints a JSON report of the
+/// inconsistencies it found. Exits with a non-zero code if the storage is inconsistent.
+fn main() {
+    let cli_params = get_cli_params();
+    match check_storage(cli_params.storage_config, cli_params.truncate) {
+        Ok(report) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("Failed serializing the report")
+            );
+            if !report.inconsistencies.is_empty() {
+                exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed checking the storage with error: {}", e);
+            exit(2);
+        }
+    }
+}
+
+struct CliParams {
+    storage_config: StorageConfig,
+    truncate: bool,
+}
+
+/// The storage is given by the arguments of [`storage_args`]. The storage is truncated to its last
+/// consistent block only if the truncate flag is set.
+fn get_cli_params() -> CliParams {
+    let command = storage_args(Command::new("Storage fsck")).arg(
+        Arg::new("truncate")
+            .short('t')
+            .long("truncate")
+            .action(ArgAction::SetTrue)
+            .help("Truncate the storage to the last consistent block."),
+    );
+    parse_args(command, |matches| {
+        Ok(CliParams {
+            storage_config: get_storage_config(matches)?,
+            truncate: matches.get_flag("truncate"),
+        })
+    })
+}
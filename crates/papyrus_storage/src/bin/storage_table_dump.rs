@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::{value_parser, Arg, ArgMatches, Command};
+use papyrus_storage::cli::{
+    block_number_arg,
+    get_block_number,
+    get_required,
+    get_storage_config,
+    parse_args,
+    storage_args,
+    CliError,
+};
+use papyrus_storage::table_dump::{dump_tables, load_table_dump};
+use papyrus_storage::{open_storage_read_only, table_names, StorageConfig};
+use starknet_api::block::BlockNumber;
+
+/// This executable dumps tables of the storage for a block range to JSON lines files, or loads
+/// such files into an empty storage. Used for reproducing bugs with a slice of the data of a real
+/// network.
+fn main() {
+    let command = Command::new("Storage table dump")
+        .subcommand_required(true)
+        .subcommand(
+            storage_command("dump")
+                .about("Dumps tables of the storage to JSON lines files.")
+                .arg(Arg::new("tables").short('t').long("tables").value_delimiter(',').help(
+                    "Comma separated names of tables or memory mapped files to dump, all the \
+                     tables are dumped by default.",
+                ))
+                .arg(
+                    block_number_arg("end_block")
+                        .short('e')
+                        .required(true)
+                        .value_parser(value_parser!(u64).range(1..))
+                        .help("The block number to end dumping at, the blocks before it are dumped."),
+                ),
+        )
+        .subcommand(storage_command("load").about("Loads a dump into an empty storage."));
+
+    let result = match parse_args(command, get_cli_command) {
+        CliCommand::Dump { storage_config, table_names, end_block, dump_dir } => {
+            open_storage_read_only(storage_config)
+                .and_then(|reader| dump_tables(&reader, &table_names, end_block, &dump_dir))
+        }
+        CliCommand::Load { storage_config, dump_dir } => load_table_dump(storage_config, &dump_dir),
+    };
+    match result {
+        Ok(()) => println!("Done."),
+        Err(e) => {
+            eprintln!("Failed with error: {}", e);
+            exit(1);
+        }
+    }
+}
+
+enum CliCommand {
+    Dump {
+        storage_config: StorageConfig,
+        table_names: Vec<String>,
+        end_block: BlockNumber,
+        dump_dir: PathBuf,
+    },
+    Load {
+        storage_config: StorageConfig,
+        dump_dir: PathBuf,
+    },
+}
+
+/// The storage is given by the arguments of [`storage_args`]. The dump_dir argument is optional,
+/// otherwise the dump at "./table_dump" is used. The dump command dumps the blocks before its
+/// end_block argument.
+fn storage_command(name: &'static str) -> Command {
+    storage_args(Command::new(name)).arg(
+        Arg::new("dump_dir")
+            .short('d')
+            .long("dump_dir")
+            .default_value("./table_dump")
+            .value_parser(value_parser!(PathBuf))
+            .help("The directory of the dump files."),
+    )
+}
+
+fn get_cli_command(matches: &ArgMatches) -> Result<CliCommand, CliError> {
+    match matches.subcommand() {
+        Some(("dump", matches)) => {
+            let table_names = match matches.try_get_many::<String>("tables")? {
+                Some(tables) => tables.cloned().collect(),
+                None => table_names().iter().map(|table_name| table_name.to_string()).collect(),
+            };
+            let end_block = get_block_number(matches, "end_block")?
+                .ok_or(CliError::MissingArgument("end_block"))?;
+            Ok(CliCommand::Dump {
+                storage_config: get_storage_config(matches)?,
+                table_names,
+                end_block,
+                dump_dir: get_required::<PathBuf>(matches, "dump_dir")?.clone(),
+            })
+        }
+        Some(("load", matches)) => Ok(CliCommand::Load {
+            storage_config: get_storage_config(matches)?,
+            dump_dir: get_required::<PathBuf>(matches, "dump_dir")?.clone(),
+        }),
+        _ => Err(CliError::MissingArgument("subcommand")),
+    }
+}
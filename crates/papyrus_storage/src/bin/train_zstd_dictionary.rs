@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use clap::{Arg, Command};
+use papyrus_storage::utils::train_zstd_dictionary;
+use papyrus_storage::StorageConfig;
+use starknet_api::core::ChainId;
+
+/// This executable trains a zstd dictionary on the contract classes of the storage and stores it
+/// in the storage. Nodes whose storage.compression_codec config is Zstd compress new contract
+/// classes with the dictionary once they are restarted.
+fn main() {
+    let cli_params = get_cli_params();
+    let mut storage_config = StorageConfig::default();
+    storage_config.db_config.path_prefix = cli_params.path_prefix;
+    storage_config.db_config.chain_id = cli_params.chain_id;
+    match train_zstd_dictionary(storage_config, cli_params.max_samples, cli_params.dictionary_size)
+    {
+        Ok(dictionary_id) => println!("Trained zstd dictionary {dictionary_id}."),
+        Err(e) => println!("Failed training a zstd dictionary with error: {}", e),
+    }
+}
+
+struct CliParams {
+    path_prefix: PathBuf,
+    chain_id: ChainId,
+    max_samples: usize,
+    dictionary_size: usize,
+}
+
+/// The chain_id argument is mandatory. The path_prefix, max_samples and dictionary_size arguments
+/// are optional, otherwise a dictionary of 112640 bytes is trained on up to 1000 contract classes
+/// of the storage at "./data".
+fn get_cli_params() -> CliParams {
+    let matches = Command::new("Train zstd dictionary")
+        .arg(
+            Arg::new("path_prefix")
+                .short('p')
+                .long("path_prefix")
+                .default_value("./data")
+                .help("The path prefix of the storage directory."),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id of the storage, e.g. SN_MAIN or SN_SEPOLIA."),
+        )
+        .arg(
+            Arg::new("max_samples")
+                .short('m')
+                .long("max_samples")
+                .default_value("1000")
+                .help("The maximal number of contract classes to train the dictionary on."),
+        )
+        .arg(
+            Arg::new("dictionary_size")
+                .short('d')
+                .long("dictionary_size")
+                .default_value("112640")
+                .help("The size of the dictionary in bytes."),
+        )
+        .get_matches();
+
+    let path_prefix = PathBuf::from(
+        matches.get_one::<String>("path_prefix").expect("Failed parsing path_prefix"),
+    );
+    let chain_id =
+        ChainId(matches.get_one::<String>("chain_id").expect("Failed parsing chain_id").clone());
+    let max_samples = matches
+        .get_one::<String>("max_samples")
+        .expect("Failed parsing max_samples")
+        .parse::<usize>()
+        .expect("Failed parsing max_samples");
+    let dictionary_size = matches
+        .get_one::<String>("dictionary_size")
+        .expect("Failed parsing dictionary_size")
+        .parse::<usize>()
+        .expect("Failed parsing dictionary_size");
+    CliParams { path_prefix, chain_id, max_samples, dictionary_size }
+}
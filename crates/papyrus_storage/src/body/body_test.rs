@@ -1,7 +1,25 @@
 use assert_matches::assert_matches;
 use pretty_assertions::assert_eq;
 use starknet_api::block::{BlockBody, BlockNumber};
-use starknet_api::transaction::TransactionOffsetInBlock;
+use starknet_api::core::{ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::transaction::{
+    DeclareTransaction,
+    DeclareTransactionOutput,
+    DeclareTransactionV2,
+    DeployAccountTransaction,
+    DeployAccountTransactionOutput,
+    DeployAccountTransactionV1,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV0,
+    InvokeTransactionV1,
+    Transaction,
+    TransactionHash,
+    TransactionOffsetInBlock,
+    TransactionOutput,
+};
+use starknet_api::{patricia_key, stark_felt};
 use test_case::test_case;
 use test_utils::{get_test_block, get_test_body};
 
@@ -387,6 +405,108 @@ async fn revert_transactions() {
     );
 }
 
+#[tokio::test]
+async fn get_transactions_by_sender() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let account = ContractAddress(patricia_key!("0x11"));
+    let deployed_account = ContractAddress(patricia_key!("0x12"));
+    let invoke = |nonce: u8| {
+        Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+            sender_address: account,
+            nonce: Nonce(StarkFelt::from(nonce)),
+            ..Default::default()
+        }))
+    };
+    let invoke_output = TransactionOutput::Invoke(InvokeTransactionOutput::default());
+    let body0 = BlockBody {
+        transactions: vec![
+            invoke(0),
+            Transaction::DeployAccount(DeployAccountTransaction::V1(
+                DeployAccountTransactionV1::default(),
+            )),
+            // Transactions of version 0 aren't sent with a nonce.
+            Transaction::Invoke(InvokeTransaction::V0(InvokeTransactionV0 {
+                contract_address: account,
+                ..Default::default()
+            })),
+        ],
+        transaction_outputs: vec![
+            invoke_output.clone(),
+            TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
+                contract_address: deployed_account,
+                ..Default::default()
+            }),
+            invoke_output.clone(),
+        ],
+        transaction_hashes: (0_u8..3).map(|i| TransactionHash(StarkHash::from(i))).collect(),
+    };
+    let body1 = BlockBody {
+        transactions: vec![
+            invoke(1),
+            Transaction::Declare(DeclareTransaction::V2(DeclareTransactionV2 {
+                sender_address: account,
+                nonce: Nonce(stark_felt!("0x2")),
+                ..Default::default()
+            })),
+        ],
+        transaction_outputs: vec![
+            invoke_output,
+            TransactionOutput::Declare(DeclareTransactionOutput::default()),
+        ],
+        transaction_hashes: (3_u8..5).map(|i| TransactionHash(StarkHash::from(i))).collect(),
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), body0)
+        .unwrap()
+        .append_body(BlockNumber(1), body1)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let tx_index = |block_number: u64, offset: usize| {
+        TransactionIndex(BlockNumber(block_number), TransactionOffsetInBlock(offset))
+    };
+    let nonce = |nonce: u8| Nonce(StarkFelt::from(nonce));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.get_transaction_idx_by_sender_and_nonce(&account, &nonce(1)).unwrap(),
+        Some(tx_index(1, 0))
+    );
+    assert_eq!(
+        txn.get_transaction_idx_by_sender_and_nonce(&deployed_account, &nonce(0)).unwrap(),
+        Some(tx_index(0, 1))
+    );
+    assert_eq!(txn.get_transaction_idx_by_sender_and_nonce(&account, &nonce(3)).unwrap(), None);
+    assert_eq!(
+        txn.get_sender_transactions(&account, nonce(0), 10).unwrap(),
+        vec![(nonce(0), tx_index(0, 0)), (nonce(1), tx_index(1, 0)), (nonce(2), tx_index(1, 1))]
+    );
+    assert_eq!(
+        txn.get_sender_transactions(&account, nonce(1), 1).unwrap(),
+        vec![(nonce(1), tx_index(1, 0))]
+    );
+    drop(txn);
+
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(1)).unwrap().0.commit().unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_sender_transactions(&account, nonce(0), 10).unwrap(),
+        vec![(nonce(0), tx_index(0, 0))]
+    );
+
+    // Reverting the deploy account transaction removes it from the index of the deployed account.
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(0)).unwrap().0.commit().unwrap();
+    assert_eq!(
+        reader
+            .begin_ro_txn()
+            .unwrap()
+            .get_transaction_idx_by_sender_and_nonce(&deployed_account, &nonce(0))
+            .unwrap(),
+        None
+    );
+}
+
 fn append_2_bodies(writer: &mut StorageWriter) {
     writer
         .begin_rw_txn()
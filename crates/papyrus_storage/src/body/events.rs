@@ -14,6 +14,7 @@
 //! use starknet_api::core::ContractAddress;
 //! use starknet_api::transaction::TransactionOffsetInBlock;
 //! use starknet_api::transaction::EventIndexInTransactionOutput;
+//! use starknet_api::transaction::EventKey;
 //!
 //! # let dir_handle = tempfile::tempdir().unwrap();
 //! # let dir = dir_handle.path().to_path_buf();
@@ -43,6 +44,12 @@
 //! for ((contract_address, event_index), event_content) in contract_events_iterator {
 //!    // Do something with the event.
 //! }
+//! // iterate events whose first key is one of the given keys.
+//! let keys = [EventKey::default()];
+//! let key_events_iterator = txn.iter_events_by_keys(0, &keys, event_index, BlockNumber(0))?;
+//! for ((contract_address, event_index), event_content) in key_events_iterator {
+//!    // Do something with the event.
+//! }
 //! # Ok::<(), papyrus_storage::StorageError>(())
 #[cfg(test)]
 #[path = "events_test.rs"]
@@ -54,18 +61,28 @@ use starknet_api::core::ContractAddress;
 use starknet_api::transaction::{
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
     ExecutionResources,
     Fee,
     MessageToL1,
     TransactionExecutionStatus,
     TransactionOutput,
 };
+use tracing::error;
 
-use crate::body::{EventsTable, EventsTableKey, TransactionIndex};
+use crate::body::{
+    write_event_keys,
+    EventKeysTableKey,
+    EventsTable,
+    EventsTableKey,
+    TransactionIndex,
+};
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{DbCursor, DbCursorTrait, SimpleTable, Table};
-use crate::db::{DbTransaction, RO};
-use crate::{StorageResult, StorageTxn};
+use crate::db::{DbTransaction, RO, RW};
+#[cfg(feature = "document_calls")]
+use crate::document_calls::{DocumentedCall, StorageQuery};
+use crate::{StorageError, StorageResult, StorageScope, StorageTxn};
 
 /// An identifier of an event.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -91,6 +108,25 @@ pub trait EventsReader<'txn, 'env> {
         event_index: EventIndex,
         to_block_number: BlockNumber,
     ) -> StorageResult<EventIter<'txn, 'env>>;
+
+    /// Returns an iterator over the events that have one of the given keys at the given position
+    /// of their keys. The events are iterated by the order of the event index.
+    ///
+    /// # Arguments
+    /// * key_position - the position of the key in the keys of the event.
+    /// * keys - the keys to iterate over the events that have one of them at the key position.
+    /// * event_index - event index to start iterate from it.
+    /// * to_block_number - block number to stop iterate at it.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn iter_events_by_keys(
+        &'env self,
+        key_position: usize,
+        keys: &[EventKey],
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIter<'txn, 'env>>;
 }
 
 // TODO: support all read transactions (including RW).
@@ -101,25 +137,88 @@ impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
         event_index: EventIndex,
         to_block_number: BlockNumber,
     ) -> StorageResult<EventIter<'txn, 'env>> {
-        if let Some(address) = optional_address {
-            return Ok(EventIter::ByContractAddress(
+        #[cfg(feature = "document_calls")]
+        let documented_call = DocumentedCall::new(StorageQuery::IterEvents {
+            address: optional_address,
+            from_event: (event_index.0.0, event_index.0.1, event_index.1),
+            to_block_number,
+            n_events: 0,
+        });
+
+        let event_iter = match optional_address {
+            Some(address) => EventIter::ByContractAddress(
                 self.iter_events_by_contract_address((address, event_index))?,
-            ));
+            ),
+            None => EventIter::ByEventIndex(
+                self.iter_events_by_event_index(event_index, to_block_number)?,
+            ),
+        };
+
+        #[cfg(feature = "document_calls")]
+        let event_iter = EventIter::Documented(Box::new(event_iter), documented_call);
+        Ok(event_iter)
+    }
+
+    fn iter_events_by_keys(
+        &'env self,
+        key_position: usize,
+        keys: &[EventKey],
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIter<'txn, 'env>> {
+        #[cfg(feature = "document_calls")]
+        let documented_call = DocumentedCall::new(StorageQuery::IterEventsByKeys {
+            key_position,
+            keys: keys.to_vec(),
+            from_event: (event_index.0.0, event_index.0.1, event_index.1),
+            to_block_number,
+            n_events: 0,
+        });
+
+        let event_keys_table = self.open_table(&self.tables.event_keys)?;
+        let mut cursors: Vec<(Option<EventKeysTableKeyValue>, EventKeysTableCursor<'txn>)> =
+            Vec::new();
+        let mut iterated_keys: Vec<&EventKey> = Vec::new();
+        for key in keys {
+            if iterated_keys.contains(&key) {
+                continue;
+            }
+            iterated_keys.push(key);
+            let mut cursor = event_keys_table.cursor(&self.txn)?;
+            let current = cursor
+                .lower_bound(&(key_position, key.clone(), event_index))?
+                .filter(|entry| is_event_key_in_range(entry, key_position, key, to_block_number));
+            cursors.push((current, cursor));
         }
+        let events_table = self.open_table(&self.tables.events)?;
+        let event_iter = EventIter::ByKeys(EventIterByKeys {
+            txn: &self.txn,
+            cursors,
+            events_table,
+            to_block_number,
+        });
 
-        Ok(EventIter::ByEventIndex(self.iter_events_by_event_index(event_index, to_block_number)?))
+        #[cfg(feature = "document_calls")]
+        let event_iter = EventIter::Documented(Box::new(event_iter), documented_call);
+        Ok(event_iter)
     }
 }
 
 #[allow(missing_docs)]
-/// A wrapper of two iterators [`EventIterByContractAddress`] and [`EventIterByEventIndex`].
+/// A wrapper of the iterators [`EventIterByContractAddress`], [`EventIterByEventIndex`] and
+/// [`EventIterByKeys`]. With the document_calls feature, the iterators are wrapped with the
+/// documentation of the iteration, which counts the read events and is documented when the
+/// iterator is dropped.
 pub enum EventIter<'txn, 'env> {
     ByContractAddress(EventIterByContractAddress<'txn>),
     ByEventIndex(EventIterByEventIndex<'txn, 'env>),
+    ByKeys(EventIterByKeys<'txn, 'env>),
+    #[cfg(feature = "document_calls")]
+    Documented(Box<EventIter<'txn, 'env>>, DocumentedCall),
 }
 
-/// This iterator is a wrapper of two iterators [`EventIterByContractAddress`]
-/// and [`EventIterByEventIndex`].
+/// This iterator is a wrapper of the iterators [`EventIterByContractAddress`],
+/// [`EventIterByEventIndex`] and [`EventIterByKeys`].
 /// With this wrapper we can execute the same code, regardless the
 /// type of iteration used.
 impl Iterator for EventIter<'_, '_> {
@@ -129,8 +228,22 @@ impl Iterator for EventIter<'_, '_> {
         match self {
             EventIter::ByContractAddress(it) => it.next(),
             EventIter::ByEventIndex(it) => it.next(),
+            EventIter::ByKeys(it) => it.next(),
+            #[cfg(feature = "document_calls")]
+            EventIter::Documented(it, documented_call) => {
+                let event = it.next();
+                if event.is_some() {
+                    documented_call.count_event();
+                }
+                Ok(event)
+            }
         }
-        .unwrap_or(None)
+        .unwrap_or_else(|err| {
+            // The iterator has no way to return the error, so it is logged and the iteration
+            // ends.
+            error!("Failed to iterate the events: {err}.");
+            None
+        })
     }
 }
 
@@ -214,6 +327,99 @@ impl EventIterByEventIndex<'_, '_> {
     }
 }
 
+/// This iterator goes over the events that have one of the given keys at the given position of
+/// their keys, in the order of the event index. It holds a cursor of the event keys table for each
+/// of the keys and each time outputs the event with the lowest event index among them.
+pub struct EventIterByKeys<'txn, 'env> {
+    txn: &'txn DbTransaction<'env, RO>,
+    cursors: Vec<(Option<EventKeysTableKeyValue>, EventKeysTableCursor<'txn>)>,
+    events_table: EventsTable<'env>,
+    to_block_number: BlockNumber,
+}
+
+impl EventIterByKeys<'_, '_> {
+    /// Returns the next event. If there are no more events, returns None.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn next(&mut self) -> StorageResult<Option<EventsTableKeyValue>> {
+        let Some((current, cursor)) =
+            self.cursors.iter_mut().filter(|(current, _)| current.is_some()).min_by_key(
+                |(current, _)| current.as_ref().map(|((_, _, event_index), _)| *event_index),
+            )
+        else {
+            return Ok(None);
+        };
+        let Some(((key_position, key, event_index), from_address)) = current.take() else {
+            return Ok(None);
+        };
+        *current = cursor
+            .next()?
+            .filter(|entry| is_event_key_in_range(entry, key_position, &key, self.to_block_number));
+        let events_table_key = (from_address, event_index);
+        // The keys of an event are indexed and deleted together with the event.
+        let Some(content) = self.events_table.get(self.txn, &events_table_key)? else {
+            return Err(StorageError::DBInconsistency {
+                msg: format!("Event {events_table_key:?} of an indexed event key not found."),
+            });
+        };
+        Ok(Some((events_table_key, content)))
+    }
+}
+
+// Returns whether the entry of the event keys table belongs to the given key and position and its
+// event is not after the given block number.
+fn is_event_key_in_range(
+    ((entry_key_position, entry_key, event_index), _): &EventKeysTableKeyValue,
+    key_position: usize,
+    key: &EventKey,
+    to_block_number: BlockNumber,
+) -> bool {
+    *entry_key_position == key_position && entry_key == key && event_index.0.0 <= to_block_number
+}
+
+impl<'env> StorageTxn<'env, RW> {
+    // Indexes the keys of at most `batch_size` events, starting from the given events table key
+    // (or from the first event if it's None). Returns the key of the next event to index, or None
+    // if there are no more events, and the number of indexed events. Used for storages that were
+    // created before the event keys index was added.
+    pub(crate) fn index_event_keys(
+        self,
+        start: Option<EventsTableKey>,
+        batch_size: usize,
+    ) -> StorageResult<(Self, Option<EventsTableKey>, usize)> {
+        if self.scope == StorageScope::StateOnly {
+            return Ok((self, None, 0));
+        }
+        let mut n_indexed = 0;
+        let next = {
+            let events_table = self.open_table(&self.tables.events)?;
+            let event_keys_table = self.open_table(&self.tables.event_keys)?;
+            let mut cursor = events_table.cursor(&self.txn)?;
+            let mut current = match start {
+                Some(key) => cursor.lower_bound(&key)?,
+                None => cursor.next()?,
+            };
+            for _ in 0..batch_size {
+                let Some(((from_address, event_index), content)) = current else {
+                    break;
+                };
+                write_event_keys(
+                    &self.txn,
+                    &event_keys_table,
+                    &content,
+                    from_address,
+                    event_index,
+                )?;
+                n_indexed += 1;
+                current = cursor.next()?;
+            }
+            current.map(|(key, _)| key)
+        };
+        Ok((self, next, n_indexed))
+    }
+}
+
 impl<'txn, 'env> StorageTxn<'env, RO> {
     /// Returns an events iterator that iterates events by the events table key from the given key.
     ///
@@ -301,6 +507,17 @@ impl ThinTransactionOutput {
             ThinTransactionOutput::L1Handler(tx_output) => &tx_output.events_contract_addresses,
         }
     }
+    /// Returns the address of the contract that the transaction deployed, if it's a deploy or a
+    /// deploy account transaction.
+    pub fn deployed_contract_address(&self) -> Option<ContractAddress> {
+        match self {
+            ThinTransactionOutput::Deploy(tx_output) => Some(tx_output.contract_address),
+            ThinTransactionOutput::DeployAccount(tx_output) => Some(tx_output.contract_address),
+            ThinTransactionOutput::Declare(_)
+            | ThinTransactionOutput::Invoke(_)
+            | ThinTransactionOutput::L1Handler(_) => None,
+        }
+    }
     /// Returns the execution status.
     pub fn execution_status(&self) -> &TransactionExecutionStatus {
         match self {
@@ -472,6 +689,11 @@ type EventsTableKeyValue = (EventsTableKey, EventContent);
 /// A cursor of the events table.
 type EventsTableCursor<'txn> =
     DbCursor<'txn, RO, EventsTableKey, NoVersionValueWrapper<EventContent>, SimpleTable>;
+/// A key-value pair of the event keys table.
+type EventKeysTableKeyValue = (EventKeysTableKey, ContractAddress);
+/// A cursor of the event keys table.
+type EventKeysTableCursor<'txn> =
+    DbCursor<'txn, RO, EventKeysTableKey, NoVersionValueWrapper<ContractAddress>, SimpleTable>;
 /// A key-value pair of the transaction outputs table.
 type TransactionOutputsKeyValue = (TransactionIndex, ThinTransactionOutput);
 /// A cursor of the transaction outputs table.
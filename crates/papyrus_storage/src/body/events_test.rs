@@ -4,13 +4,18 @@ use assert_matches::assert_matches;
 use camelpaste::paste;
 use pretty_assertions::assert_eq;
 use starknet_api::core::{ContractAddress, PatriciaKey};
-use starknet_api::hash::StarkHash;
+use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::patricia_key;
-use starknet_api::transaction::{EventIndexInTransactionOutput, TransactionOffsetInBlock};
+use starknet_api::transaction::{
+    EventIndexInTransactionOutput,
+    EventKey,
+    TransactionOffsetInBlock,
+};
 use test_utils::get_test_block;
 
 use crate::body::events::{
     EventIndex,
+    EventIter,
     EventsReader,
     ThinDeclareTransactionOutput,
     ThinDeployAccountTransactionOutput,
@@ -20,9 +25,10 @@ use crate::body::events::{
     ThinTransactionOutput,
 };
 use crate::body::{BodyStorageWriter, TransactionIndex};
-use crate::db::table_types::Table;
+use crate::db::table_types::{DbCursorTrait, Table};
 use crate::header::HeaderStorageWriter;
 use crate::test_utils::get_test_storage;
+use crate::StorageError;
 
 #[tokio::test]
 async fn iter_events_by_key() {
@@ -190,6 +196,163 @@ async fn revert_events() {
     }
 }
 
+#[tokio::test]
+async fn iter_events_by_keys() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let event_keys = |felts: &[u128]| {
+        felts.iter().map(|felt| EventKey(StarkFelt::from(*felt))).collect::<Vec<_>>()
+    };
+    let block =
+        get_test_block(5, Some(4), None, Some(vec![event_keys(&[1, 2, 3]), event_keys(&[4])]));
+    let block_number = block.header.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Create the events emitted starting from event index ((0,1),0) whose first key is 1 or 3.
+    let keys = event_keys(&[3, 1, 3]);
+    let mut emitted_events = vec![];
+    for (tx_i, tx_output) in block.body.transaction_outputs.iter().enumerate().skip(1) {
+        for (event_i, event) in tx_output.events().iter().enumerate() {
+            if keys.contains(&event.content.keys[0]) {
+                let event_index = EventIndex(
+                    TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
+                    EventIndexInTransactionOutput(event_i),
+                );
+                emitted_events.push(((event.from_address, event_index), event.content.clone()))
+            }
+        }
+    }
+    assert!(!emitted_events.is_empty());
+
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(1)),
+        EventIndexInTransactionOutput(0),
+    );
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let events =
+        txn.iter_events_by_keys(0, &keys, event_index, block_number).unwrap().collect::<Vec<_>>();
+    assert_eq!(events, emitted_events);
+
+    // There are no events with the key at the second position.
+    assert!(txn.iter_events_by_keys(1, &keys, event_index, block_number).unwrap().next().is_none());
+    drop(txn);
+
+    // Reverting the block removes its events from the keys index.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .revert_header(block_number)
+        .unwrap()
+        .0
+        .revert_body(block_number)
+        .unwrap()
+        .0
+        .commit()
+        .unwrap();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let event_keys_table = txn.txn.open_table(&txn.tables.event_keys).unwrap();
+    assert!(event_keys_table.cursor(&txn.txn).unwrap().next().unwrap().is_none());
+}
+
+#[tokio::test]
+async fn iter_events_by_keys_missing_event() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let key = EventKey(StarkFelt::from(1_u128));
+    let block = get_test_block(1, Some(1), None, Some(vec![vec![key.clone()]]));
+    let block_number = block.header.block_number;
+    let event = &block.body.transaction_outputs[0].events()[0];
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let txn = storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap();
+    let events_table = txn.txn.open_table(&txn.tables.events).unwrap();
+    events_table.delete(&txn.txn, &(event.from_address, event_index)).unwrap();
+    txn.commit().unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let event_iter = txn.iter_events_by_keys(0, &[key], event_index, block_number).unwrap();
+    #[cfg(feature = "document_calls")]
+    let EventIter::Documented(event_iter, _) = event_iter else {
+        panic!("Expected a documented iterator.");
+    };
+    #[cfg(feature = "document_calls")]
+    let event_iter = *event_iter;
+    let EventIter::ByKeys(mut event_iter) = event_iter else {
+        panic!("Expected an iterator by keys.");
+    };
+    assert_matches!(event_iter.next(), Err(StorageError::DBInconsistency { .. }));
+}
+
+#[tokio::test]
+async fn index_event_keys() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let block = get_test_block(2, Some(5), None, None);
+    let block_number = block.header.block_number;
+    let n_events: usize =
+        block.body.transaction_outputs.iter().map(|tx_output| tx_output.events().len()).sum();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let get_event_keys_entries = || {
+        let txn = storage_reader.begin_ro_txn().unwrap();
+        let event_keys_table = txn.txn.open_table(&txn.tables.event_keys).unwrap();
+        let mut cursor = event_keys_table.cursor(&txn.txn).unwrap();
+        let mut entries = vec![];
+        while let Some(entry) = cursor.next().unwrap() {
+            entries.push(entry);
+        }
+        entries
+    };
+    let event_keys_entries = get_event_keys_entries();
+    assert!(!event_keys_entries.is_empty());
+
+    // Clear the index as in storages that were created before it was added, and rebuild it.
+    let txn = storage_writer.begin_rw_txn().unwrap();
+    let event_keys_table = txn.open_table(&txn.tables.event_keys).unwrap();
+    for (key, _) in &event_keys_entries {
+        event_keys_table.delete(&txn.txn, key).unwrap();
+    }
+    txn.commit().unwrap();
+    assert!(get_event_keys_entries().is_empty());
+
+    // Index the events in batches of 2 events.
+    let mut start = None;
+    let mut n_indexed_events = 0;
+    loop {
+        let (txn, next, n_indexed) =
+            storage_writer.begin_rw_txn().unwrap().index_event_keys(start, 2).unwrap();
+        n_indexed_events += n_indexed;
+        txn.commit().unwrap();
+        match next {
+            Some(next) => start = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(get_event_keys_entries(), event_keys_entries);
+    assert_eq!(n_indexed_events, n_events);
+}
+
 /// macro for testing events_contract_addresses on all the variants of ThinTransactionOutput
 macro_rules! test_events_contract_addresses_macro {
     ($variant:ident, $variant_input:ident) => {
@@ -47,11 +47,14 @@ use std::fmt::Debug;
 use papyrus_proc_macros::latency_histogram;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockBody, BlockNumber};
-use starknet_api::core::ContractAddress;
+use starknet_api::core::{ContractAddress, Nonce};
 use starknet_api::transaction::{
+    DeclareTransaction,
     Event,
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
+    InvokeTransaction,
     Transaction,
     TransactionHash,
     TransactionOffsetInBlock,
@@ -63,6 +66,8 @@ use crate::body::events::{EventIndex, ThinTransactionOutput};
 use crate::db::serialization::{NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
 use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+#[cfg(feature = "document_calls")]
+use crate::document_calls::{DocumentedCall, StorageQuery};
 use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageScope, StorageTxn};
 
 type TransactionsTable<'env> =
@@ -73,9 +78,19 @@ type TransactionHashToIdxTable<'env> =
     TableHandle<'env, TransactionHash, NoVersionValueWrapper<TransactionIndex>, SimpleTable>;
 type TransactionIdxToHashTable<'env> =
     TableHandle<'env, TransactionIndex, NoVersionValueWrapper<TransactionHash>, SimpleTable>;
-type EventsTableKey = (ContractAddress, EventIndex);
+type SenderNonceToTransactionIdxTable<'env> = TableHandle<
+    'env,
+    (ContractAddress, Nonce),
+    NoVersionValueWrapper<TransactionIndex>,
+    SimpleTable,
+>;
+pub(crate) type EventsTableKey = (ContractAddress, EventIndex);
 type EventsTable<'env> =
     TableHandle<'env, EventsTableKey, NoVersionValueWrapper<EventContent>, SimpleTable>;
+// The position of the key in the event keys, the key and the index of the event.
+type EventKeysTableKey = (usize, EventKey, EventIndex);
+type EventKeysTable<'env> =
+    TableHandle<'env, EventKeysTableKey, NoVersionValueWrapper<ContractAddress>, SimpleTable>;
 
 /// The index of a transaction in a block.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -117,6 +132,23 @@ pub trait BodyStorageReader {
         tx_index: &TransactionIndex,
     ) -> StorageResult<Option<TransactionHash>>;
 
+    /// Returns the index of the transaction that the given account sent with the given nonce.
+    /// Only transactions of version 1 and above are sent with a nonce.
+    fn get_transaction_idx_by_sender_and_nonce(
+        &self,
+        sender_address: &ContractAddress,
+        nonce: &Nonce,
+    ) -> StorageResult<Option<TransactionIndex>>;
+
+    /// Returns the nonces and indices of up to `max_transactions` transactions that the given
+    /// account sent, starting from the given nonce. The transactions are ordered by their nonce.
+    fn get_sender_transactions(
+        &self,
+        sender_address: &ContractAddress,
+        from_nonce: Nonce,
+        max_transactions: usize,
+    ) -> StorageResult<Vec<(Nonce, TransactionIndex)>>;
+
     /// Returns the transactions and their execution status of the block with the given number.
     fn get_block_transactions(
         &self,
@@ -174,6 +206,12 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
         &self,
         transaction_index: TransactionIndex,
     ) -> StorageResult<Option<Transaction>> {
+        #[cfg(feature = "document_calls")]
+        let _documented_call = DocumentedCall::new(StorageQuery::GetTransaction(
+            transaction_index.0,
+            transaction_index.1,
+        ));
+
         let transactions_table = self.open_table(&self.tables.transactions)?;
         let transaction = transactions_table.get(&self.txn, &transaction_index)?;
         Ok(transaction)
@@ -183,6 +221,12 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
         &self,
         transaction_index: TransactionIndex,
     ) -> StorageResult<Option<ThinTransactionOutput>> {
+        #[cfg(feature = "document_calls")]
+        let _documented_call = DocumentedCall::new(StorageQuery::GetTransactionOutput(
+            transaction_index.0,
+            transaction_index.1,
+        ));
+
         let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
         let transaction_output = transaction_outputs_table.get(&self.txn, &transaction_index)?;
         Ok(transaction_output)
@@ -232,6 +276,39 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
         Ok(idx)
     }
 
+    fn get_transaction_idx_by_sender_and_nonce(
+        &self,
+        sender_address: &ContractAddress,
+        nonce: &Nonce,
+    ) -> StorageResult<Option<TransactionIndex>> {
+        let sender_nonce_to_transaction_idx_table =
+            self.open_table(&self.tables.sender_nonce_to_transaction_idx)?;
+        let idx =
+            sender_nonce_to_transaction_idx_table.get(&self.txn, &(*sender_address, *nonce))?;
+        Ok(idx)
+    }
+
+    fn get_sender_transactions(
+        &self,
+        sender_address: &ContractAddress,
+        from_nonce: Nonce,
+        max_transactions: usize,
+    ) -> StorageResult<Vec<(Nonce, TransactionIndex)>> {
+        let sender_nonce_to_transaction_idx_table =
+            self.open_table(&self.tables.sender_nonce_to_transaction_idx)?;
+        let mut cursor = sender_nonce_to_transaction_idx_table.cursor(&self.txn)?;
+        let mut current = cursor.lower_bound(&(*sender_address, from_nonce))?;
+        let mut res = Vec::new();
+        while let Some(((current_sender_address, nonce), tx_index)) = current {
+            if current_sender_address != *sender_address || res.len() == max_transactions {
+                break;
+            }
+            res.push((nonce, tx_index));
+            current = cursor.next()?;
+        }
+        Ok(res)
+    }
+
     fn get_block_transactions(
         &self,
         block_number: BlockNumber,
@@ -324,10 +401,13 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
             let transactions_table = self.open_table(&self.tables.transactions)?;
             let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
             let events_table = self.open_table(&self.tables.events)?;
+            let event_keys_table = self.open_table(&self.tables.event_keys)?;
             let transaction_hash_to_idx_table =
                 self.open_table(&self.tables.transaction_hash_to_idx)?;
             let transaction_idx_to_hash_table =
                 self.open_table(&self.tables.transaction_idx_to_hash)?;
+            let sender_nonce_to_transaction_idx_table =
+                self.open_table(&self.tables.sender_nonce_to_transaction_idx)?;
 
             write_transactions(
                 &block_body,
@@ -342,6 +422,8 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
                 &self.txn,
                 &transaction_outputs_table,
                 &events_table,
+                &event_keys_table,
+                &sender_nonce_to_transaction_idx_table,
                 block_number,
             )?;
         }
@@ -381,6 +463,9 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
             let transaction_idx_to_hash_table =
                 self.open_table(&self.tables.transaction_idx_to_hash)?;
             let events_table = self.open_table(&self.tables.events)?;
+            let event_keys_table = self.open_table(&self.tables.event_keys)?;
+            let sender_nonce_to_transaction_idx_table =
+                self.open_table(&self.tables.sender_nonce_to_transaction_idx)?;
 
             let transactions = self
                 .get_block_transactions(block_number)?
@@ -394,7 +479,9 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
 
             // Delete the transactions data.
             let mut events = vec![];
-            for (offset, tx_output) in transaction_outputs.iter().enumerate() {
+            for (offset, (tx, tx_output)) in
+                transactions.iter().zip(transaction_outputs.iter()).enumerate()
+            {
                 let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
                 let tx_hash = self.get_transaction_hash_by_idx(&tx_index)?.unwrap_or_else(|| {
                     panic!("Missing transaction hash for transaction index {tx_index:?}.")
@@ -403,14 +490,23 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
                 for (index, from_address) in
                     tx_output.events_contract_addresses_as_ref().iter().enumerate()
                 {
-                    let key =
-                        (*from_address, EventIndex(tx_index, EventIndexInTransactionOutput(index)));
-                    tx_events.push(events_table.get(&self.txn, &key)?.unwrap_or_else(|| {
+                    let event_index = EventIndex(tx_index, EventIndexInTransactionOutput(index));
+                    let key = (*from_address, event_index);
+                    let content = events_table.get(&self.txn, &key)?.unwrap_or_else(|| {
                         panic!("Missing events for transaction output {tx_index:?}.")
-                    }));
+                    });
+                    delete_event_keys(&self.txn, &event_keys_table, &content, event_index)?;
                     events_table.delete(&self.txn, &key)?;
+                    tx_events.push(content);
                 }
                 events.push(tx_events);
+                delete_sender_nonce(
+                    &self.txn,
+                    &sender_nonce_to_transaction_idx_table,
+                    tx,
+                    tx_output,
+                    tx_index,
+                )?;
                 transactions_table.delete(&self.txn, &tx_index)?;
                 transaction_outputs_table.delete(&self.txn, &tx_index)?;
                 transaction_hash_to_idx_table.delete(&self.txn, &tx_hash)?;
@@ -425,6 +521,55 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
     }
 }
 
+impl<'env> StorageTxn<'env, RW> {
+    // Indexes the sender and nonce of at most `batch_size` transactions, starting from the given
+    // transaction index (or from the first transaction if it's None). Returns the index of the next
+    // transaction to index, or None if there are no more transactions, and the number of indexed
+    // transactions. Used for storages that were created before the index was added.
+    pub(crate) fn index_transaction_senders(
+        self,
+        start: Option<TransactionIndex>,
+        batch_size: usize,
+    ) -> StorageResult<(Self, Option<TransactionIndex>, usize)> {
+        if self.scope == StorageScope::StateOnly {
+            return Ok((self, None, 0));
+        }
+        let mut n_indexed = 0;
+        let next = {
+            let transactions_table = self.open_table(&self.tables.transactions)?;
+            let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
+            let sender_nonce_to_transaction_idx_table =
+                self.open_table(&self.tables.sender_nonce_to_transaction_idx)?;
+            let mut cursor = transactions_table.cursor(&self.txn)?;
+            let mut current = match start {
+                Some(tx_index) => cursor.lower_bound(&tx_index)?,
+                None => cursor.next()?,
+            };
+            for _ in 0..batch_size {
+                let Some((tx_index, tx)) = current else {
+                    break;
+                };
+                let tx_output = transaction_outputs_table.get(&self.txn, &tx_index)?.ok_or(
+                    StorageError::DBInconsistency {
+                        msg: format!("Missing transaction output for transaction {tx_index:?}."),
+                    },
+                )?;
+                write_sender_nonce(
+                    &self.txn,
+                    &sender_nonce_to_transaction_idx_table,
+                    &tx,
+                    &tx_output,
+                    tx_index,
+                )?;
+                n_indexed += 1;
+                current = cursor.next()?;
+            }
+            current.map(|(tx_index, _)| tx_index)
+        };
+        Ok((self, next, n_indexed))
+    }
+}
+
 fn write_transactions<'env>(
     block_body: &BlockBody,
     txn: &DbTransaction<'env, RW>,
@@ -450,22 +595,83 @@ fn write_transactions<'env>(
     Ok(())
 }
 
+// Returns the account that sent the transaction and the nonce it was sent with. Transactions of
+// version 0 aren't sent with a nonce, and deploy and L1 handler transactions aren't sent by an
+// account. The address of a deploy account transaction is the address of the contract it deployed,
+// which is taken from its output.
+fn get_sender_and_nonce(
+    tx: &Transaction,
+    tx_output: &ThinTransactionOutput,
+) -> Option<(ContractAddress, Nonce)> {
+    match tx {
+        Transaction::Declare(DeclareTransaction::V0(_))
+        | Transaction::Invoke(InvokeTransaction::V0(_))
+        | Transaction::Deploy(_)
+        | Transaction::L1Handler(_) => None,
+        Transaction::Declare(tx) => Some((tx.sender_address(), tx.nonce())),
+        Transaction::Invoke(tx) => Some((tx.sender_address(), tx.nonce())),
+        Transaction::DeployAccount(tx) => tx_output
+            .deployed_contract_address()
+            .map(|contract_address| (contract_address, tx.nonce())),
+    }
+}
+
+fn write_sender_nonce<'env>(
+    txn: &DbTransaction<'env, RW>,
+    sender_nonce_to_transaction_idx_table: &'env SenderNonceToTransactionIdxTable<'env>,
+    tx: &Transaction,
+    tx_output: &ThinTransactionOutput,
+    transaction_index: TransactionIndex,
+) -> StorageResult<()> {
+    if let Some(sender_and_nonce) = get_sender_and_nonce(tx, tx_output) {
+        sender_nonce_to_transaction_idx_table.upsert(txn, &sender_and_nonce, &transaction_index)?;
+    }
+    Ok(())
+}
+
+// Removes the sender and nonce of the transaction, unless they were overridden by another
+// transaction.
+pub(crate) fn delete_sender_nonce<'env>(
+    txn: &DbTransaction<'env, RW>,
+    sender_nonce_to_transaction_idx_table: &'env SenderNonceToTransactionIdxTable<'env>,
+    tx: &Transaction,
+    tx_output: &ThinTransactionOutput,
+    transaction_index: TransactionIndex,
+) -> StorageResult<()> {
+    let Some(sender_and_nonce) = get_sender_and_nonce(tx, tx_output) else {
+        return Ok(());
+    };
+    if sender_nonce_to_transaction_idx_table.get(txn, &sender_and_nonce)? == Some(transaction_index)
+    {
+        sender_nonce_to_transaction_idx_table.delete(txn, &sender_and_nonce)?;
+    }
+    Ok(())
+}
+
 fn write_transaction_outputs<'env>(
     block_body: BlockBody,
     txn: &DbTransaction<'env, RW>,
     transaction_outputs_table: &'env TransactionOutputsTable<'env>,
     events_table: &'env EventsTable<'env>,
+    event_keys_table: &'env EventKeysTable<'env>,
+    sender_nonce_to_transaction_idx_table: &'env SenderNonceToTransactionIdxTable<'env>,
     block_number: BlockNumber,
 ) -> StorageResult<()> {
-    for (index, tx_output) in block_body.transaction_outputs.into_iter().enumerate() {
+    for (index, (tx, tx_output)) in
+        block_body.transactions.iter().zip(block_body.transaction_outputs).enumerate()
+    {
         let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(index));
 
-        write_events(&tx_output, txn, events_table, transaction_index)?;
-        transaction_outputs_table.insert(
+        write_events(&tx_output, txn, events_table, event_keys_table, transaction_index)?;
+        let thin_tx_output = ThinTransactionOutput::from(tx_output);
+        write_sender_nonce(
             txn,
-            &transaction_index,
-            &ThinTransactionOutput::from(tx_output),
+            sender_nonce_to_transaction_idx_table,
+            tx,
+            &thin_tx_output,
+            transaction_index,
         )?;
+        transaction_outputs_table.insert(txn, &transaction_index, &thin_tx_output)?;
     }
     Ok(())
 }
@@ -474,11 +680,40 @@ fn write_events<'env>(
     tx_output: &TransactionOutput,
     txn: &DbTransaction<'env, RW>,
     events_table: &'env EventsTable<'env>,
+    event_keys_table: &'env EventKeysTable<'env>,
     transaction_index: TransactionIndex,
 ) -> StorageResult<()> {
     for (index, event) in tx_output.events().iter().enumerate() {
         let event_index = EventIndex(transaction_index, EventIndexInTransactionOutput(index));
         events_table.insert(txn, &(event.from_address, event_index), &event.content)?;
+        write_event_keys(txn, event_keys_table, &event.content, event.from_address, event_index)?;
+    }
+    Ok(())
+}
+
+// Indexes the event by each of its keys.
+pub(crate) fn write_event_keys<'env>(
+    txn: &DbTransaction<'env, RW>,
+    event_keys_table: &'env EventKeysTable<'env>,
+    content: &EventContent,
+    from_address: ContractAddress,
+    event_index: EventIndex,
+) -> StorageResult<()> {
+    for (position, key) in content.keys.iter().enumerate() {
+        event_keys_table.upsert(txn, &(position, key.clone(), event_index), &from_address)?;
+    }
+    Ok(())
+}
+
+// Removes the event from the index of its keys.
+pub(crate) fn delete_event_keys<'env>(
+    txn: &DbTransaction<'env, RW>,
+    event_keys_table: &'env EventKeysTable<'env>,
+    content: &EventContent,
+    event_index: EventIndex,
+) -> StorageResult<()> {
+    for (position, key) in content.keys.iter().enumerate() {
+        event_keys_table.delete(txn, &(position, key.clone(), event_index))?;
     }
     Ok(())
 }
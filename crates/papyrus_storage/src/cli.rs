@@ -0,0 +1,160 @@
+//! Command line arguments that are shared by the storage executables.
+//!
+//! [`storage_args`] adds the arguments that locate the storage (`path_prefix`, `chain_id` and
+//! `scope`) and that decode its values (`compression_codec` and `encryption_key_file`) to a
+//! command, and [`get_storage_config`] builds a [`StorageConfig`] from them. The values are
+//! validated by the parsers of the arguments, and the errors are returned as [`CliError`], so
+//! [`parse_args`] can report them with the usage of the command instead of panicking.
+//!
+//! The encryption key isn't taken from the arguments, since they're visible to the other users of
+//! the machine. It's read from the file given by `encryption_key_file`, or otherwise from the
+//! [`ENCRYPTION_KEY_ENV_VAR`] environment variable.
+
+use std::env::{self, VarError};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::error::ErrorKind;
+use clap::parser::MatchesError;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ChainId;
+
+use crate::compression_utils::CompressionCodec;
+use crate::encryption::EncryptionKey;
+use crate::{StorageConfig, StorageScope};
+
+/// The environment variable that holds the encryption key of the storage in hex, if the
+/// encryption_key_file argument isn't given.
+pub const ENCRYPTION_KEY_ENV_VAR: &str = "PAPYRUS_STORAGE_ENCRYPTION_KEY";
+
+/// An error in the command line arguments of a storage executable.
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug)]
+pub enum CliError {
+    #[error(transparent)]
+    Matches(#[from] MatchesError),
+    #[error("The argument {0} is required.")]
+    MissingArgument(&'static str),
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+}
+
+/// Parses the arguments of the command with the given function. If the arguments are invalid,
+/// prints the error with the usage of the command and exits.
+pub fn parse_args<T>(
+    mut command: Command,
+    parse: impl FnOnce(&ArgMatches) -> Result<T, CliError>,
+) -> T {
+    let matches = command.get_matches_mut();
+    parse(&matches).unwrap_or_else(|err| command.error(ErrorKind::ValueValidation, err).exit())
+}
+
+/// Adds the path_prefix, chain_id, scope, compression_codec and encryption_key_file arguments to
+/// the command. The chain_id argument is mandatory. The other arguments are optional, otherwise the
+/// storage at "./data" is used in the FullArchive scope, with the Gzip codec and the encryption key
+/// of [`ENCRYPTION_KEY_ENV_VAR`] if it's set.
+pub fn storage_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("path_prefix")
+                .short('p')
+                .long("path_prefix")
+                .default_value("./data")
+                .value_parser(value_parser!(PathBuf))
+                .help("The path prefix of the storage directory."),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id of the storage, e.g. SN_MAIN or SN_SEPOLIA."),
+        )
+        .arg(
+            Arg::new("scope")
+                .short('s')
+                .long("scope")
+                .default_value("FullArchive")
+                .value_parser(StorageScope::from_str)
+                .help("The scope of the storage: FullArchive, StateOnly or Pruned."),
+        )
+        .arg(
+            Arg::new("compression_codec")
+                .long("compression_codec")
+                .default_value("Gzip")
+                .value_parser(CompressionCodec::from_str)
+                .help("The codec of the new values of the storage: Gzip, Zstd or Lz4."),
+        )
+        .arg(
+            Arg::new("encryption_key_file")
+                .long("encryption_key_file")
+                .value_parser(value_parser!(PathBuf))
+                .help("A file that holds the encryption key of the storage in hex."),
+        )
+}
+
+/// Returns the config of the storage given by the arguments of [`storage_args`].
+pub fn get_storage_config(matches: &ArgMatches) -> Result<StorageConfig, CliError> {
+    let mut storage_config =
+        StorageConfig { scope: *get_required(matches, "scope")?, ..Default::default() };
+    storage_config.db_config.path_prefix = get_required::<PathBuf>(matches, "path_prefix")?.clone();
+    storage_config.db_config.chain_id =
+        ChainId(get_required::<String>(matches, "chain_id")?.clone());
+    storage_config.compression_codec = *get_required(matches, "compression_codec")?;
+    storage_config.encryption_key = get_encryption_key(matches)?;
+    Ok(storage_config)
+}
+
+// Returns the encryption key from the file of the encryption_key_file argument, or from the
+// ENCRYPTION_KEY_ENV_VAR environment variable if the argument isn't given.
+fn get_encryption_key(matches: &ArgMatches) -> Result<Option<EncryptionKey>, CliError> {
+    let hex_key = match matches.try_get_one::<PathBuf>("encryption_key_file")? {
+        Some(path) => fs::read_to_string(path).map_err(|err| {
+            CliError::InvalidArguments(format!("Failed reading the encryption key: {err}"))
+        })?,
+        None => match env::var(ENCRYPTION_KEY_ENV_VAR) {
+            Ok(hex_key) => hex_key,
+            Err(VarError::NotPresent) => return Ok(None),
+            Err(err) => {
+                return Err(CliError::InvalidArguments(format!("{ENCRYPTION_KEY_ENV_VAR}: {err}")));
+            }
+        },
+    };
+    EncryptionKey::from_str(hex_key.trim()).map(Some).map_err(CliError::InvalidArguments)
+}
+
+/// Returns an argument that holds a block number.
+pub fn block_number_arg(name: &'static str) -> Arg {
+    Arg::new(name).long(name).value_parser(value_parser!(u64))
+}
+
+/// Returns the block number of an argument of [`block_number_arg`], if it was given.
+pub fn get_block_number(
+    matches: &ArgMatches,
+    name: &'static str,
+) -> Result<Option<BlockNumber>, CliError> {
+    Ok(matches.try_get_one::<u64>(name)?.copied().map(BlockNumber))
+}
+
+/// Returns the value of a required argument, or of an argument with a default value.
+pub fn get_required<'a, T: Clone + Send + Sync + 'static>(
+    matches: &'a ArgMatches,
+    name: &'static str,
+) -> Result<&'a T, CliError> {
+    matches.try_get_one::<T>(name)?.ok_or(CliError::MissingArgument(name))
+}
+
+/// Returns an error if the start block of a block range isn't smaller than its end block.
+pub fn verify_block_range(
+    start_block: BlockNumber,
+    end_block: BlockNumber,
+) -> Result<(), CliError> {
+    if start_block >= end_block {
+        return Err(CliError::InvalidArguments(format!(
+            "start_block {start_block} must be smaller than end_block {end_block}"
+        )));
+    }
+    Ok(())
+}
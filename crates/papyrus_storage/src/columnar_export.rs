@@ -0,0 +1,643 @@
+//! Export of the storage data to columnar files for analytics.
+//!
+//! [`export_to_columnar_files`] exports the headers, transactions, receipts, events and state diffs
+//! of a range of blocks into Parquet or Arrow IPC files, so the data can be loaded by analytics
+//! tools without decoding the storage. The files of each entity are written to a directory named
+//! after the entity, and each file holds the rows of a range of blocks, named by the first and the
+//! last block of the range, e.g. `events/0000000000-0000009999.parquet`.
+//!
+//! The schemas of the entities are stable, new columns are only appended. Felts are written as
+//! 0x-prefixed hex strings of 64 digits and 128 bit numbers (fees and gas prices) as decimal
+//! strings. The columns with the `_json` suffix hold the JSON serialization of the whole object.
+//!
+//! | entity         | columns                                                                |
+//! |----------------|------------------------------------------------------------------------|
+//! | `headers`      | block_number, block_hash, parent_hash, timestamp, sequencer_address,   |
+//! |                | state_root, l1_gas_price_wei, l1_gas_price_fri, l1_data_gas_price_wei, |
+//! |                | l1_data_gas_price_fri, l1_da_mode, starknet_version                    |
+//! | `transactions` | block_number, transaction_index, transaction_hash, type,               |
+//! |                | transaction_json                                                       |
+//! | `receipts`     | block_number, transaction_index, transaction_hash, type, actual_fee,   |
+//! |                | execution_status, revert_reason, events_count, receipt_json            |
+//! | `events`       | block_number, transaction_index, event_index, transaction_hash,        |
+//! |                | from_address, keys, data                                               |
+//! | `state_diffs`  | block_number, kind, address, key, value                                |
+//!
+//! Each item of a state diff is a row in `state_diffs`, with the following kinds:
+//! `storage` (address, key, value), `deployed_contract` (address, value = class hash),
+//! `replaced_class` (address, value = class hash), `nonce` (address, value = nonce),
+//! `declared_class` (key = class hash, value = compiled class hash) and
+//! `deprecated_declared_class` (key = class hash).
+//!
+//! The export is incremental: the next block to export is kept in the `export_progress.json` file
+//! of the output directory, and the next export resumes from it.
+
+#[cfg(test)]
+#[path = "columnar_export_test.rs"]
+mod columnar_export_test;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arrow_array::builder::{ListBuilder, StringBuilder, UInt64Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{
+    Event,
+    Transaction,
+    TransactionExecutionStatus,
+    TransactionHash,
+    TransactionOffsetInBlock,
+};
+use tracing::info;
+
+use crate::body::events::ThinTransactionOutput;
+use crate::body::{BodyStorageReader, TransactionIndex};
+use crate::header::HeaderStorageReader;
+use crate::state::StateStorageReader;
+use crate::{StorageError, StorageReader, StorageTxn};
+
+const PROGRESS_FILE_NAME: &str = "export_progress.json";
+
+/// Errors that may be returned when exporting the storage to columnar files.
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug)]
+pub enum ColumnarExportError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error("The export in {path:?} is in {existing:?} format, not in {requested:?} format.")]
+    FormatMismatch { path: PathBuf, existing: ColumnarFormat, requested: ColumnarFormat },
+    #[error("Block {block_number} is missing from the storage.")]
+    MissingBlock { block_number: BlockNumber },
+}
+
+/// A result type for exporting the storage to columnar files.
+pub type ColumnarExportResult<V> = Result<V, ColumnarExportError>;
+
+/// The formats of the exported files.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum ColumnarFormat {
+    /// Apache Parquet files, with the `.parquet` extension.
+    Parquet,
+    /// Apache Arrow IPC files, with the `.arrow` extension.
+    ArrowIpc,
+}
+
+impl ColumnarFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ColumnarFormat::Parquet => "parquet",
+            ColumnarFormat::ArrowIpc => "arrow",
+        }
+    }
+}
+
+// The progress of the export in an output directory.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+struct ExportProgress {
+    format: ColumnarFormat,
+    next_block: BlockNumber,
+}
+
+/// Exports the blocks from the last exported block (or from `start_block`, if it's given) up to
+/// `end_block` (exclusive) into columnar files in `output_dir`, and returns the next block to
+/// export. The export stops at the first block that isn't fully in the storage, and at most
+/// `blocks_per_file` blocks are written to each file.
+///
+/// # Errors
+/// Returns [`ColumnarExportError`] if there was an error reading the storage or writing the files,
+/// or if the output directory holds an export in another format.
+pub fn export_to_columnar_files(
+    reader: &StorageReader,
+    output_dir: &Path,
+    format: ColumnarFormat,
+    start_block: Option<BlockNumber>,
+    end_block: Option<BlockNumber>,
+    blocks_per_file: u64,
+) -> ColumnarExportResult<BlockNumber> {
+    fs::create_dir_all(output_dir)?;
+    let progress_path = output_dir.join(PROGRESS_FILE_NAME);
+    let mut next_block = match read_progress(&progress_path)? {
+        Some(progress) if progress.format != format => {
+            return Err(ColumnarExportError::FormatMismatch {
+                path: output_dir.to_path_buf(),
+                existing: progress.format,
+                requested: format,
+            });
+        }
+        Some(progress) => start_block.unwrap_or(progress.next_block),
+        None => start_block.unwrap_or_default(),
+    };
+
+    let txn = reader.begin_ro_txn()?;
+    let last_stored_block =
+        txn.get_header_marker()?.min(txn.get_body_marker()?).min(txn.get_state_marker()?);
+    drop(txn);
+    let end_block =
+        end_block.map_or(last_stored_block, |end_block| end_block.min(last_stored_block));
+    while next_block < end_block {
+        let chunk_end = BlockNumber((next_block.0 + blocks_per_file.max(1)).min(end_block.0));
+        info!("Exporting blocks {} to {} to {:?}.", next_block, chunk_end, output_dir);
+        // A transaction for each chunk, so a long export doesn't keep an old snapshot of the
+        // storage open while the node writes to it.
+        let txn = reader.begin_ro_txn()?;
+        export_blocks(&txn, output_dir, format, next_block, chunk_end)?;
+        next_block = chunk_end;
+        write_progress(&progress_path, &ExportProgress { format, next_block })?;
+    }
+    Ok(next_block)
+}
+
+fn read_progress(path: &Path) -> ColumnarExportResult<Option<ExportProgress>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_slice(&fs::read(path)?)?))
+}
+
+// Writes the progress to a temporary file first, so the progress file is never left partially
+// written.
+fn write_progress(path: &Path, progress: &ExportProgress) -> ColumnarExportResult<()> {
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec(progress)?)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+// Exports the blocks in [start_block, end_block) into a single file for each entity.
+fn export_blocks(
+    txn: &StorageTxn<'_, crate::db::RO>,
+    output_dir: &Path,
+    format: ColumnarFormat,
+    start_block: BlockNumber,
+    end_block: BlockNumber,
+) -> ColumnarExportResult<()> {
+    let mut headers = HeadersBuilder::default();
+    let mut transactions = TransactionsBuilder::default();
+    let mut receipts = ReceiptsBuilder::default();
+    let mut events = EventsBuilder::default();
+    let mut state_diffs = StateDiffsBuilder::default();
+    for block_number in start_block.iter_up_to(end_block) {
+        let missing_block = || ColumnarExportError::MissingBlock { block_number };
+        let header = txn.get_block_header(block_number)?.ok_or_else(missing_block)?;
+        let block_transactions =
+            txn.get_block_transactions(block_number)?.ok_or_else(missing_block)?;
+        let transaction_hashes =
+            txn.get_block_transaction_hashes(block_number)?.ok_or_else(missing_block)?;
+        let transaction_outputs =
+            txn.get_block_transaction_outputs(block_number)?.ok_or_else(missing_block)?;
+        let state_diff = txn.get_state_diff(block_number)?.ok_or_else(missing_block)?;
+
+        headers.append(&header);
+        for (offset, ((transaction, transaction_hash), transaction_output)) in block_transactions
+            .iter()
+            .zip(transaction_hashes.iter())
+            .zip(transaction_outputs.iter())
+            .enumerate()
+        {
+            transactions.append(block_number, offset, transaction_hash, transaction)?;
+            receipts.append(block_number, offset, transaction_hash, transaction_output)?;
+            let transaction_index =
+                TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            let transaction_events =
+                txn.get_transaction_events(transaction_index)?.ok_or_else(missing_block)?;
+            for (event_index, event) in transaction_events.iter().enumerate() {
+                events.append(block_number, offset, event_index, transaction_hash, event);
+            }
+        }
+        state_diffs.append(block_number, &state_diff);
+    }
+
+    let file_name = format!("{:010}-{:010}.{}", start_block.0, end_block.0 - 1, format.extension());
+    write_batch(&output_dir.join("headers"), &file_name, format, headers.finish()?)?;
+    write_batch(&output_dir.join("transactions"), &file_name, format, transactions.finish()?)?;
+    write_batch(&output_dir.join("receipts"), &file_name, format, receipts.finish()?)?;
+    write_batch(&output_dir.join("events"), &file_name, format, events.finish()?)?;
+    write_batch(&output_dir.join("state_diffs"), &file_name, format, state_diffs.finish()?)?;
+    Ok(())
+}
+
+fn write_batch(
+    dir: &Path,
+    file_name: &str,
+    format: ColumnarFormat,
+    batch: RecordBatch,
+) -> ColumnarExportResult<()> {
+    fs::create_dir_all(dir)?;
+    let file = File::create(dir.join(file_name))?;
+    match format {
+        ColumnarFormat::Parquet => {
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+        }
+        ColumnarFormat::ArrowIpc => {
+            let mut writer = FileWriter::try_new(file, &batch.schema())?;
+            writer.write(&batch)?;
+            writer.finish()?;
+        }
+    }
+    Ok(())
+}
+
+fn utf8_field(name: &str, nullable: bool) -> Field {
+    Field::new(name, DataType::Utf8, nullable)
+}
+
+fn u64_field(name: &str) -> Field {
+    Field::new(name, DataType::UInt64, false)
+}
+
+fn utf8_list_field(name: &str) -> Field {
+    Field::new(name, DataType::List(Arc::new(utf8_field("item", true))), false)
+}
+
+/// Returns the schema of the exported headers.
+pub fn headers_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        u64_field("block_number"),
+        utf8_field("block_hash", false),
+        utf8_field("parent_hash", false),
+        u64_field("timestamp"),
+        utf8_field("sequencer_address", false),
+        utf8_field("state_root", false),
+        utf8_field("l1_gas_price_wei", false),
+        utf8_field("l1_gas_price_fri", false),
+        utf8_field("l1_data_gas_price_wei", false),
+        utf8_field("l1_data_gas_price_fri", false),
+        utf8_field("l1_da_mode", false),
+        utf8_field("starknet_version", false),
+    ]))
+}
+
+/// Returns the schema of the exported transactions.
+pub fn transactions_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        u64_field("block_number"),
+        u64_field("transaction_index"),
+        utf8_field("transaction_hash", false),
+        utf8_field("type", false),
+        utf8_field("transaction_json", false),
+    ]))
+}
+
+/// Returns the schema of the exported receipts.
+pub fn receipts_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        u64_field("block_number"),
+        u64_field("transaction_index"),
+        utf8_field("transaction_hash", false),
+        utf8_field("type", false),
+        utf8_field("actual_fee", false),
+        utf8_field("execution_status", false),
+        utf8_field("revert_reason", true),
+        u64_field("events_count"),
+        utf8_field("receipt_json", false),
+    ]))
+}
+
+/// Returns the schema of the exported events.
+pub fn events_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        u64_field("block_number"),
+        u64_field("transaction_index"),
+        u64_field("event_index"),
+        utf8_field("transaction_hash", false),
+        utf8_field("from_address", false),
+        utf8_list_field("keys"),
+        utf8_list_field("data"),
+    ]))
+}
+
+/// Returns the schema of the exported state diffs.
+pub fn state_diffs_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        u64_field("block_number"),
+        utf8_field("kind", false),
+        utf8_field("address", true),
+        utf8_field("key", true),
+        utf8_field("value", true),
+    ]))
+}
+
+#[derive(Default)]
+struct HeadersBuilder {
+    block_number: UInt64Builder,
+    block_hash: StringBuilder,
+    parent_hash: StringBuilder,
+    timestamp: UInt64Builder,
+    sequencer_address: StringBuilder,
+    state_root: StringBuilder,
+    l1_gas_price_wei: StringBuilder,
+    l1_gas_price_fri: StringBuilder,
+    l1_data_gas_price_wei: StringBuilder,
+    l1_data_gas_price_fri: StringBuilder,
+    l1_da_mode: StringBuilder,
+    starknet_version: StringBuilder,
+}
+
+impl HeadersBuilder {
+    fn append(&mut self, header: &BlockHeader) {
+        self.block_number.append_value(header.block_number.0);
+        self.block_hash.append_value(header.block_hash.0.to_string());
+        self.parent_hash.append_value(header.parent_hash.0.to_string());
+        self.timestamp.append_value(header.timestamp.0);
+        self.sequencer_address.append_value(header.sequencer.0.0.key().to_string());
+        self.state_root.append_value(header.state_root.0.to_string());
+        self.l1_gas_price_wei.append_value(header.l1_gas_price.price_in_wei.0.to_string());
+        self.l1_gas_price_fri.append_value(header.l1_gas_price.price_in_fri.0.to_string());
+        self.l1_data_gas_price_wei
+            .append_value(header.l1_data_gas_price.price_in_wei.0.to_string());
+        self.l1_data_gas_price_fri
+            .append_value(header.l1_data_gas_price.price_in_fri.0.to_string());
+        self.l1_da_mode.append_value(format!("{:?}", header.l1_da_mode));
+        self.starknet_version.append_value(&header.starknet_version.0);
+    }
+
+    fn finish(mut self) -> ColumnarExportResult<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.block_number.finish()),
+            Arc::new(self.block_hash.finish()),
+            Arc::new(self.parent_hash.finish()),
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.sequencer_address.finish()),
+            Arc::new(self.state_root.finish()),
+            Arc::new(self.l1_gas_price_wei.finish()),
+            Arc::new(self.l1_gas_price_fri.finish()),
+            Arc::new(self.l1_data_gas_price_wei.finish()),
+            Arc::new(self.l1_data_gas_price_fri.finish()),
+            Arc::new(self.l1_da_mode.finish()),
+            Arc::new(self.starknet_version.finish()),
+        ];
+        Ok(RecordBatch::try_new(headers_schema(), columns)?)
+    }
+}
+
+#[derive(Default)]
+struct TransactionsBuilder {
+    block_number: UInt64Builder,
+    transaction_index: UInt64Builder,
+    transaction_hash: StringBuilder,
+    transaction_type: StringBuilder,
+    transaction_json: StringBuilder,
+}
+
+impl TransactionsBuilder {
+    fn append(
+        &mut self,
+        block_number: BlockNumber,
+        offset: usize,
+        transaction_hash: &TransactionHash,
+        transaction: &Transaction,
+    ) -> ColumnarExportResult<()> {
+        self.block_number.append_value(block_number.0);
+        self.transaction_index.append_value(offset as u64);
+        self.transaction_hash.append_value(transaction_hash.0.to_string());
+        self.transaction_type.append_value(transaction_type(transaction));
+        self.transaction_json.append_value(serde_json::to_string(transaction)?);
+        Ok(())
+    }
+
+    fn finish(mut self) -> ColumnarExportResult<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.block_number.finish()),
+            Arc::new(self.transaction_index.finish()),
+            Arc::new(self.transaction_hash.finish()),
+            Arc::new(self.transaction_type.finish()),
+            Arc::new(self.transaction_json.finish()),
+        ];
+        Ok(RecordBatch::try_new(transactions_schema(), columns)?)
+    }
+}
+
+#[derive(Default)]
+struct ReceiptsBuilder {
+    block_number: UInt64Builder,
+    transaction_index: UInt64Builder,
+    transaction_hash: StringBuilder,
+    transaction_type: StringBuilder,
+    actual_fee: StringBuilder,
+    execution_status: StringBuilder,
+    revert_reason: StringBuilder,
+    events_count: UInt64Builder,
+    receipt_json: StringBuilder,
+}
+
+impl ReceiptsBuilder {
+    fn append(
+        &mut self,
+        block_number: BlockNumber,
+        offset: usize,
+        transaction_hash: &TransactionHash,
+        transaction_output: &ThinTransactionOutput,
+    ) -> ColumnarExportResult<()> {
+        self.block_number.append_value(block_number.0);
+        self.transaction_index.append_value(offset as u64);
+        self.transaction_hash.append_value(transaction_hash.0.to_string());
+        self.transaction_type.append_value(transaction_output_type(transaction_output));
+        self.actual_fee.append_value(transaction_output.actual_fee().0.to_string());
+        match transaction_output.execution_status() {
+            TransactionExecutionStatus::Succeeded => {
+                self.execution_status.append_value("SUCCEEDED");
+                self.revert_reason.append_null();
+            }
+            TransactionExecutionStatus::Reverted(reverted_status) => {
+                self.execution_status.append_value("REVERTED");
+                self.revert_reason.append_value(&reverted_status.revert_reason);
+            }
+        }
+        self.events_count
+            .append_value(transaction_output.events_contract_addresses_as_ref().len() as u64);
+        self.receipt_json.append_value(serde_json::to_string(transaction_output)?);
+        Ok(())
+    }
+
+    fn finish(mut self) -> ColumnarExportResult<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.block_number.finish()),
+            Arc::new(self.transaction_index.finish()),
+            Arc::new(self.transaction_hash.finish()),
+            Arc::new(self.transaction_type.finish()),
+            Arc::new(self.actual_fee.finish()),
+            Arc::new(self.execution_status.finish()),
+            Arc::new(self.revert_reason.finish()),
+            Arc::new(self.events_count.finish()),
+            Arc::new(self.receipt_json.finish()),
+        ];
+        Ok(RecordBatch::try_new(receipts_schema(), columns)?)
+    }
+}
+
+#[derive(Default)]
+struct EventsBuilder {
+    block_number: UInt64Builder,
+    transaction_index: UInt64Builder,
+    event_index: UInt64Builder,
+    transaction_hash: StringBuilder,
+    from_address: StringBuilder,
+    keys: ListBuilder<StringBuilder>,
+    data: ListBuilder<StringBuilder>,
+}
+
+impl EventsBuilder {
+    fn append(
+        &mut self,
+        block_number: BlockNumber,
+        offset: usize,
+        event_index: usize,
+        transaction_hash: &TransactionHash,
+        event: &Event,
+    ) {
+        self.block_number.append_value(block_number.0);
+        self.transaction_index.append_value(offset as u64);
+        self.event_index.append_value(event_index as u64);
+        self.transaction_hash.append_value(transaction_hash.0.to_string());
+        self.from_address.append_value(event.from_address.0.key().to_string());
+        self.keys.append_value(event.content.keys.iter().map(|key| Some(key.0.to_string())));
+        self.data.append_value(event.content.data.0.iter().map(|felt| Some(felt.to_string())));
+    }
+
+    fn finish(mut self) -> ColumnarExportResult<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.block_number.finish()),
+            Arc::new(self.transaction_index.finish()),
+            Arc::new(self.event_index.finish()),
+            Arc::new(self.transaction_hash.finish()),
+            Arc::new(self.from_address.finish()),
+            Arc::new(self.keys.finish()),
+            Arc::new(self.data.finish()),
+        ];
+        Ok(RecordBatch::try_new(events_schema(), columns)?)
+    }
+}
+
+#[derive(Default)]
+struct StateDiffsBuilder {
+    block_number: UInt64Builder,
+    kind: StringBuilder,
+    address: StringBuilder,
+    key: StringBuilder,
+    value: StringBuilder,
+}
+
+impl StateDiffsBuilder {
+    fn append(&mut self, block_number: BlockNumber, state_diff: &ThinStateDiff) {
+        for (address, storage_diffs) in &state_diff.storage_diffs {
+            for (key, value) in storage_diffs {
+                self.append_row(
+                    block_number,
+                    "storage",
+                    Some(address.0.key().to_string()),
+                    Some(key.0.key().to_string()),
+                    Some(value.to_string()),
+                );
+            }
+        }
+        for (address, class_hash) in &state_diff.deployed_contracts {
+            self.append_row(
+                block_number,
+                "deployed_contract",
+                Some(address.0.key().to_string()),
+                None,
+                Some(class_hash.0.to_string()),
+            );
+        }
+        for (address, class_hash) in &state_diff.replaced_classes {
+            self.append_row(
+                block_number,
+                "replaced_class",
+                Some(address.0.key().to_string()),
+                None,
+                Some(class_hash.0.to_string()),
+            );
+        }
+        for (address, nonce) in &state_diff.nonces {
+            self.append_row(
+                block_number,
+                "nonce",
+                Some(address.0.key().to_string()),
+                None,
+                Some(nonce.0.to_string()),
+            );
+        }
+        for (class_hash, compiled_class_hash) in &state_diff.declared_classes {
+            self.append_row(
+                block_number,
+                "declared_class",
+                None,
+                Some(class_hash.0.to_string()),
+                Some(compiled_class_hash.0.to_string()),
+            );
+        }
+        for class_hash in &state_diff.deprecated_declared_classes {
+            self.append_row(
+                block_number,
+                "deprecated_declared_class",
+                None,
+                Some(class_hash.0.to_string()),
+                None,
+            );
+        }
+    }
+
+    fn append_row(
+        &mut self,
+        block_number: BlockNumber,
+        kind: &str,
+        address: Option<String>,
+        key: Option<String>,
+        value: Option<String>,
+    ) {
+        self.block_number.append_value(block_number.0);
+        self.kind.append_value(kind);
+        self.address.append_option(address);
+        self.key.append_option(key);
+        self.value.append_option(value);
+    }
+
+    fn finish(mut self) -> ColumnarExportResult<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.block_number.finish()),
+            Arc::new(self.kind.finish()),
+            Arc::new(self.address.finish()),
+            Arc::new(self.key.finish()),
+            Arc::new(self.value.finish()),
+        ];
+        Ok(RecordBatch::try_new(state_diffs_schema(), columns)?)
+    }
+}
+
+fn transaction_type(transaction: &Transaction) -> &'static str {
+    match transaction {
+        Transaction::Declare(_) => "DECLARE",
+        Transaction::Deploy(_) => "DEPLOY",
+        Transaction::DeployAccount(_) => "DEPLOY_ACCOUNT",
+        Transaction::Invoke(_) => "INVOKE",
+        Transaction::L1Handler(_) => "L1_HANDLER",
+    }
+}
+
+fn transaction_output_type(transaction_output: &ThinTransactionOutput) -> &'static str {
+    match transaction_output {
+        ThinTransactionOutput::Declare(_) => "DECLARE",
+        ThinTransactionOutput::Deploy(_) => "DEPLOY",
+        ThinTransactionOutput::DeployAccount(_) => "DEPLOY_ACCOUNT",
+        ThinTransactionOutput::Invoke(_) => "INVOKE",
+        ThinTransactionOutput::L1Handler(_) => "L1_HANDLER",
+    }
+}
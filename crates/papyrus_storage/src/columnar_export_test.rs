@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::path::Path;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::UInt64Type;
+use arrow_array::RecordBatch;
+use arrow_ipc::reader::FileReader;
+use assert_matches::assert_matches;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use test_utils::get_test_state_diff;
+
+use super::{
+    events_schema,
+    export_to_columnar_files,
+    headers_schema,
+    receipts_schema,
+    state_diffs_schema,
+    transactions_schema,
+    ColumnarExportError,
+    ColumnarFormat,
+};
+use crate::test_utils::{
+    append_test_blocks_with_states,
+    get_test_storage,
+    TestBlockState,
+    TEST_EVENTS_PER_TX,
+    TEST_TXS_PER_BLOCK,
+};
+
+// Returns the state of a test block with the test state diff, where the classes and contracts are
+// declared and deployed only in the first block since they can be declared and deployed only once.
+fn test_block_state(block_number: u64) -> TestBlockState {
+    let mut state = TestBlockState::from(get_test_state_diff());
+    if block_number > 0 {
+        state.state_diff.deployed_contracts.clear();
+        state.state_diff.declared_classes.clear();
+        state.state_diff.deprecated_declared_classes.clear();
+        state.classes.clear();
+        state.deprecated_classes.clear();
+    }
+    state
+}
+
+fn read_file(path: &Path, format: ColumnarFormat) -> RecordBatch {
+    let file = File::open(path).unwrap();
+    let mut batches: Vec<RecordBatch> = match format {
+        ColumnarFormat::Parquet => ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect(),
+        ColumnarFormat::ArrowIpc => {
+            FileReader::try_new(file, None).unwrap().map(Result::unwrap).collect()
+        }
+    };
+    assert_eq!(batches.len(), 1);
+    batches.pop().unwrap()
+}
+
+fn block_numbers(batch: &RecordBatch) -> Vec<u64> {
+    batch.column(0).as_primitive::<UInt64Type>().values().to_vec()
+}
+
+#[test]
+fn export_is_incremental() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let output_dir = tempfile::tempdir().unwrap();
+    let format = ColumnarFormat::Parquet;
+    let states = (0..3).map(test_block_state).collect::<Vec<_>>();
+    let state_diff_items = states
+        .iter()
+        .map(|TestBlockState { state_diff, .. }| {
+            state_diff.storage_diffs.values().map(|diffs| diffs.len()).sum::<usize>()
+                + state_diff.deployed_contracts.len()
+                + state_diff.replaced_classes.len()
+                + state_diff.nonces.len()
+                + state_diff.declared_classes.len()
+                + state_diff.deprecated_declared_classes.len()
+        })
+        .sum::<usize>();
+    append_test_blocks_with_states(&mut writer, BlockNumber(0), states);
+
+    let next_block =
+        export_to_columnar_files(&reader, output_dir.path(), format, None, None, 2).unwrap();
+    assert_eq!(next_block, BlockNumber(3));
+
+    let headers =
+        read_file(&output_dir.path().join("headers/0000000000-0000000001.parquet"), format);
+    assert_eq!(headers.schema(), headers_schema());
+    assert_eq!(block_numbers(&headers), vec![0, 1]);
+    let transactions =
+        read_file(&output_dir.path().join("transactions/0000000000-0000000001.parquet"), format);
+    assert_eq!(transactions.schema(), transactions_schema());
+    assert_eq!(transactions.num_rows(), 2 * TEST_TXS_PER_BLOCK);
+    let receipts =
+        read_file(&output_dir.path().join("receipts/0000000000-0000000001.parquet"), format);
+    assert_eq!(receipts.schema(), receipts_schema());
+    assert_eq!(receipts.num_rows(), 2 * TEST_TXS_PER_BLOCK);
+    let events = read_file(&output_dir.path().join("events/0000000002-0000000002.parquet"), format);
+    assert_eq!(events.schema(), events_schema());
+    assert_eq!(block_numbers(&events), vec![2; TEST_TXS_PER_BLOCK * TEST_EVENTS_PER_TX]);
+    let mut exported_state_diff_items = 0;
+    for file_name in ["0000000000-0000000001.parquet", "0000000002-0000000002.parquet"] {
+        let state_diffs = read_file(&output_dir.path().join("state_diffs").join(file_name), format);
+        assert_eq!(state_diffs.schema(), state_diffs_schema());
+        exported_state_diff_items += state_diffs.num_rows();
+    }
+    assert_eq!(exported_state_diff_items, state_diff_items);
+
+    // Nothing is exported until a new block is stored.
+    let next_block =
+        export_to_columnar_files(&reader, output_dir.path(), format, None, None, 2).unwrap();
+    assert_eq!(next_block, BlockNumber(3));
+    append_test_blocks_with_states(&mut writer, BlockNumber(3), vec![test_block_state(3)]);
+    let next_block =
+        export_to_columnar_files(&reader, output_dir.path(), format, None, None, 2).unwrap();
+    assert_eq!(next_block, BlockNumber(4));
+    let headers =
+        read_file(&output_dir.path().join("headers/0000000003-0000000003.parquet"), format);
+    assert_eq!(block_numbers(&headers), vec![3]);
+}
+
+#[test]
+fn export_arrow_ipc_block_range() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let output_dir = tempfile::tempdir().unwrap();
+    let format = ColumnarFormat::ArrowIpc;
+    append_test_blocks_with_states(
+        &mut writer,
+        BlockNumber(0),
+        (0..3).map(test_block_state).collect(),
+    );
+
+    let next_block = export_to_columnar_files(
+        &reader,
+        output_dir.path(),
+        format,
+        Some(BlockNumber(1)),
+        Some(BlockNumber(2)),
+        10,
+    )
+    .unwrap();
+    assert_eq!(next_block, BlockNumber(2));
+    let events = read_file(&output_dir.path().join("events/0000000001-0000000001.arrow"), format);
+    assert_eq!(events.schema(), events_schema());
+    assert_eq!(events.num_rows(), TEST_TXS_PER_BLOCK * TEST_EVENTS_PER_TX);
+    assert!(!output_dir.path().join("events/0000000000-0000000000.arrow").exists());
+
+    // The export can't continue in another format.
+    assert_matches!(
+        export_to_columnar_files(
+            &reader,
+            output_dir.path(),
+            ColumnarFormat::Parquet,
+            None,
+            None,
+            10
+        ),
+        Err(ColumnarExportError::FormatMismatch {
+            existing: ColumnarFormat::ArrowIpc,
+            requested: ColumnarFormat::Parquet,
+            ..
+        })
+    );
+}
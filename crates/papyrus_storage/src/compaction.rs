@@ -0,0 +1,210 @@
+//! Offline compaction of the memory mapped files of the storage.
+//!
+//! The memory mapped files are append only, so objects that are no longer referenced from the DB
+//! tables (e.g., the state diffs and classes of reverted blocks) keep occupying space in the files.
+//! [`compact_storage`] rewrites each file with only the referenced objects, and updates their
+//! locations and the offset of the file in a single transaction.
+//!
+//! The compacted files are written next to the original files, and the kinds of the compacted
+//! files are recorded in the `compacted_files` table in the same transaction. When the storage is
+//! opened, [`finish_compaction`] replaces the recorded files with their compacted versions, and
+//! discards the compacted files that aren't recorded (i.e., the compaction was interrupted before
+//! the transaction was committed).
+//!
+//! The compaction must not run while the storage is opened by another process, so the storage is
+//! opened exclusively and the compaction fails if the node or a read-only replica has it open.
+
+#[cfg(test)]
+#[path = "compaction_test.rs"]
+mod compaction_test;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument};
+
+use crate::db::serialization::{Key, NoVersionValueWrapper, ValueSerde};
+use crate::db::table_types::{SimpleTable, Table};
+use crate::db::{DbIter, DbWriter, TableIdentifier, RW};
+use crate::mmap_file::{
+    discard_compacted_file,
+    replace_with_compacted_file,
+    write_compacted_file,
+    LocationInFile,
+    MMapFileStats,
+};
+use crate::{
+    open_storage_exclusively,
+    OffsetKind,
+    StorageConfig,
+    StorageError,
+    StorageResult,
+    StorageTxn,
+};
+
+// The kinds of the memory mapped files of the storage.
+const OFFSET_KINDS: [OffsetKind; 4] = [
+    OffsetKind::ThinStateDiff,
+    OffsetKind::ContractClass,
+    OffsetKind::Casm,
+    OffsetKind::DeprecatedContractClass,
+];
+
+/// The stats of a memory mapped file before and after its compaction.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct FileCompactionStats {
+    /// The stats of the file before the compaction.
+    pub before: MMapFileStats,
+    /// The stats of the file after the compaction.
+    pub after: MMapFileStats,
+    /// The number of bytes that were reclaimed by the compaction.
+    pub reclaimed_bytes: usize,
+}
+
+/// Compacts the memory mapped files of the storage and returns the stats of each file. Fails with
+/// [`DbError::EnvironmentInUse`](crate::db::DbError::EnvironmentInUse) if the storage is opened by
+/// another process.
+#[instrument(skip(storage_config), level = "debug", err)]
+pub fn compact_storage(
+    storage_config: StorageConfig,
+) -> StorageResult<HashMap<String, FileCompactionStats>> {
+    let (reader, mut writer) = open_storage_exclusively(storage_config.clone())?;
+    let stats_before = reader.mmap_files_stats();
+    let db_path = storage_config.db_config.path();
+
+    let txn = writer.begin_rw_txn()?;
+    let tables = txn.tables.clone();
+    let reclaimed_bytes = HashMap::from([
+        (
+            OffsetKind::ThinStateDiff.file_name(),
+            compact_file(
+                &txn,
+                &tables.state_diffs,
+                OffsetKind::ThinStateDiff,
+                &db_path,
+                |location| *location,
+                |location, compacted_location| *location = compacted_location,
+            )?,
+        ),
+        (
+            OffsetKind::ContractClass.file_name(),
+            compact_file(
+                &txn,
+                &tables.declared_classes,
+                OffsetKind::ContractClass,
+                &db_path,
+                |location| *location,
+                |location, compacted_location| *location = compacted_location,
+            )?,
+        ),
+        (
+            OffsetKind::Casm.file_name(),
+            compact_file(
+                &txn,
+                &tables.casms,
+                OffsetKind::Casm,
+                &db_path,
+                |location| *location,
+                |location, compacted_location| *location = compacted_location,
+            )?,
+        ),
+        (
+            OffsetKind::DeprecatedContractClass.file_name(),
+            compact_file(
+                &txn,
+                &tables.deprecated_declared_classes,
+                OffsetKind::DeprecatedContractClass,
+                &db_path,
+                |indexed_class| indexed_class.location_in_file,
+                |indexed_class, compacted_location| {
+                    indexed_class.location_in_file = compacted_location
+                },
+            )?,
+        ),
+    ]);
+    txn.commit()?;
+    drop((reader, writer));
+
+    // Reopening the storage finishes the compaction.
+    let (reader, _writer) = open_storage_exclusively(storage_config)?;
+    let stats_after = reader.mmap_files_stats();
+    stats_before
+        .into_iter()
+        .map(|(name, before)| {
+            let missing_stats =
+                || StorageError::CompactionError { msg: format!("No stats for the file {name}") };
+            let after = *stats_after.get(&name).ok_or_else(missing_stats)?;
+            let reclaimed_bytes = *reclaimed_bytes.get(name.as_str()).ok_or_else(missing_stats)?;
+            Ok((name, FileCompactionStats { before, after, reclaimed_bytes }))
+        })
+        .collect()
+}
+
+/// Replaces the files that were compacted in a committed compaction with their compacted versions,
+/// and discards the compacted files of an uncommitted compaction. Must be called before the files
+/// are opened.
+pub(crate) fn finish_compaction(
+    db_writer: &mut DbWriter,
+    compacted_files_table: &TableIdentifier<OffsetKind, NoVersionValueWrapper<()>, SimpleTable>,
+    db_path: PathBuf,
+) -> StorageResult<()> {
+    let txn = db_writer.begin_rw_txn()?;
+    let table = txn.open_table(compacted_files_table)?;
+    for offset_kind in OFFSET_KINDS {
+        let path = file_path(&db_path, offset_kind);
+        if table.get(&txn, &offset_kind)?.is_some() {
+            replace_with_compacted_file(&path)?;
+            table.delete(&txn, &offset_kind)?;
+        } else {
+            discard_compacted_file(&path)?;
+        }
+    }
+    Ok(txn.commit_and_sync()?)
+}
+
+fn file_path(db_path: &Path, offset_kind: OffsetKind) -> PathBuf {
+    db_path.join(format!("{}.dat", offset_kind.file_name()))
+}
+
+// Writes a compacted version of the file with only the objects that are referenced from the given
+// table, updates their locations in the table and the offset of the file, and records the file as
+// compacted. Returns the number of reclaimed bytes.
+fn compact_file<K: Key + Debug, V: ValueSerde + Debug>(
+    txn: &StorageTxn<'_, RW>,
+    table_id: &TableIdentifier<K, V, SimpleTable>,
+    offset_kind: OffsetKind,
+    db_path: &Path,
+    get_location: impl Fn(&V::Value) -> LocationInFile,
+    set_location: impl Fn(&mut V::Value, LocationInFile),
+) -> StorageResult<usize> {
+    let table = txn.open_table(table_id)?;
+    let mut cursor = table.cursor(&txn.txn)?;
+    let entries = DbIter::new(&mut cursor).collect::<Result<Vec<_>, _>>()?;
+
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets)?;
+    let offset = file_offsets_table.get(&txn.txn, &offset_kind)?.unwrap_or_default();
+    let path = file_path(db_path, offset_kind);
+    let Some(compacted_locations) =
+        write_compacted_file(&path, offset, entries.iter().map(|(_, value)| get_location(value)))?
+    else {
+        debug!("Nothing to reclaim in {path:?}.");
+        return Ok(0);
+    };
+
+    for (key, mut value) in entries {
+        let location = get_location(&value);
+        let compacted_location =
+            *compacted_locations.get(&location).ok_or_else(|| StorageError::CompactionError {
+                msg: format!("The location {location:?} wasn't written to {path:?}"),
+            })?;
+        set_location(&mut value, compacted_location);
+        table.upsert(&txn.txn, &key, &value)?;
+    }
+    let compacted_offset =
+        compacted_locations.values().map(LocationInFile::next_offset).max().unwrap_or_default();
+    file_offsets_table.upsert(&txn.txn, &offset_kind, &compacted_offset)?;
+    txn.open_table(&txn.tables.compacted_files)?.upsert(&txn.txn, &offset_kind, &())?;
+    Ok(offset - compacted_offset)
+}
@@ -0,0 +1,157 @@
+use std::fs;
+
+use assert_matches::assert_matches;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, CompiledClassHash};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_api::state::{ContractClass, ThinStateDiff};
+use test_utils::get_test_state_diff;
+
+use crate::class::ClassStorageReader;
+use crate::compaction::compact_storage;
+use crate::compiled_class::CasmStorageReader;
+use crate::db::serialization::{SerdeContext, ValueSerde, VersionZeroWrapper};
+use crate::db::DbError;
+use crate::mmap_file::compacted_file_path;
+use crate::read_only::open_storage_read_only;
+use crate::revert::revert_block;
+use crate::state::StateStorageReader;
+use crate::test_utils::{
+    append_test_blocks,
+    append_test_blocks_with_states,
+    get_test_storage_with_config_by_scope,
+    TestBlockState,
+};
+use crate::{open_storage, StorageError, StorageScope};
+
+fn serialized_len<T: ValueSerde>(value: &T::Value) -> usize {
+    T::serialize(value, &SerdeContext::default()).unwrap().len()
+}
+
+#[test]
+fn compact_storage_after_revert() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+
+    let block0 = TestBlockState::from(get_test_state_diff());
+    let class_hash = ClassHash(stark_felt!("0x11"));
+    let deprecated_class_hash = ClassHash(stark_felt!("0x22"));
+    let block1 = TestBlockState {
+        state_diff: ThinStateDiff {
+            declared_classes: IndexMap::from([(class_hash, CompiledClassHash::default())]),
+            deprecated_declared_classes: vec![deprecated_class_hash],
+            ..Default::default()
+        },
+        classes: vec![(class_hash, ContractClass::default())],
+        deprecated_classes: vec![(deprecated_class_hash, DeprecatedContractClass::default())],
+        casms: vec![(class_hash, CasmContractClass::default())],
+    };
+    let state_diff1 = block1.state_diff.clone();
+    append_test_blocks_with_states(&mut writer, BlockNumber(0), vec![block0.clone()]);
+
+    // Revert block 1 and append it again, leaving the data of the reverted block unreferenced.
+    append_test_blocks_with_states(&mut writer, BlockNumber(1), vec![block1.clone()]);
+    revert_block(writer.begin_rw_txn().unwrap(), BlockNumber(1)).unwrap().commit().unwrap();
+    append_test_blocks_with_states(&mut writer, BlockNumber(1), vec![block1]);
+
+    let stats_before = reader.mmap_files_stats();
+    drop((reader, writer));
+
+    let stats = compact_storage(config.clone()).unwrap();
+    for (name, expected_reclaimed_bytes) in [
+        ("thin_state_diff", serialized_len::<VersionZeroWrapper<ThinStateDiff>>(&state_diff1)),
+        (
+            "contract_class",
+            serialized_len::<VersionZeroWrapper<ContractClass>>(&ContractClass::default()),
+        ),
+        (
+            "casm",
+            serialized_len::<VersionZeroWrapper<CasmContractClass>>(&CasmContractClass::default()),
+        ),
+        (
+            "deprecated_contract_class",
+            serialized_len::<VersionZeroWrapper<DeprecatedContractClass>>(
+                &DeprecatedContractClass::default(),
+            ),
+        ),
+    ] {
+        assert_eq!(stats[name].before, stats_before[name]);
+        assert_eq!(stats[name].reclaimed_bytes, expected_reclaimed_bytes);
+    }
+
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap().unwrap(), block0.state_diff);
+    assert_eq!(txn.get_state_diff(BlockNumber(1)).unwrap().unwrap(), state_diff1);
+    for (class_hash, class) in &block0.classes {
+        assert_eq!(&txn.get_class(class_hash).unwrap().unwrap(), class);
+    }
+    for (class_hash, deprecated_class) in &block0.deprecated_classes {
+        assert_eq!(&txn.get_deprecated_class(class_hash).unwrap().unwrap(), deprecated_class);
+    }
+    assert_eq!(txn.get_class(&class_hash).unwrap().unwrap(), ContractClass::default());
+    assert_eq!(
+        txn.get_deprecated_class(&deprecated_class_hash).unwrap().unwrap(),
+        DeprecatedContractClass::default()
+    );
+    assert_eq!(txn.get_casm(&class_hash).unwrap().unwrap(), CasmContractClass::default());
+    drop(txn);
+
+    // New data is appended after the compacted data.
+    append_test_blocks(&mut writer, 2..3);
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_diff(BlockNumber(1)).unwrap().unwrap(), state_diff1);
+    assert_eq!(txn.get_state_diff(BlockNumber(2)).unwrap().unwrap(), ThinStateDiff::default());
+    drop(txn);
+    drop((reader, writer));
+
+    // There is nothing left to reclaim.
+    let stats = compact_storage(config).unwrap();
+    assert!(stats.values().all(|file_stats| file_stats.reclaimed_bytes == 0));
+}
+
+#[test]
+fn compact_storage_while_storage_is_open() {
+    let ((reader, writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    assert_matches!(
+        compact_storage(config.clone()),
+        Err(StorageError::InnerError(DbError::EnvironmentInUse(_)))
+    );
+    drop((reader, writer));
+
+    // A read-only replica also prevents the compaction.
+    let read_only_reader = open_storage_read_only(config.clone()).unwrap();
+    assert_matches!(
+        compact_storage(config.clone()),
+        Err(StorageError::InnerError(DbError::EnvironmentInUse(_)))
+    );
+    drop(read_only_reader);
+
+    compact_storage(config).unwrap();
+}
+
+#[test]
+fn open_storage_discards_uncommitted_compaction() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let block = TestBlockState::from(get_test_state_diff());
+    append_test_blocks_with_states(&mut writer, BlockNumber(0), vec![block.clone()]);
+    drop((reader, writer));
+
+    // A compacted file whose compaction wasn't committed.
+    let compacted_path = compacted_file_path(&config.db_config.path().join("thin_state_diff.dat"));
+    fs::write(&compacted_path, [0_u8; 8]).unwrap();
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    assert!(!compacted_path.exists());
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_state_diff(BlockNumber(0)).unwrap().unwrap(),
+        block.state_diff
+    );
+}
@@ -45,6 +45,8 @@ use starknet_api::core::ClassHash;
 use crate::db::serialization::VersionZeroWrapper;
 use crate::db::table_types::{SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+#[cfg(feature = "document_calls")]
+use crate::document_calls::{DocumentedCall, StorageQuery};
 use crate::mmap_file::LocationInFile;
 use crate::{FileHandlers, MarkerKind, MarkersTable, OffsetKind, StorageResult, StorageTxn};
 
@@ -71,6 +73,9 @@ where
 
 impl<'env, Mode: TransactionKind> CasmStorageReader for StorageTxn<'env, Mode> {
     fn get_casm(&self, class_hash: &ClassHash) -> StorageResult<Option<CasmContractClass>> {
+        #[cfg(feature = "document_calls")]
+        let _documented_call = DocumentedCall::new(StorageQuery::GetCasm(*class_hash));
+
         let casm_table = self.open_table(&self.tables.casms)?;
         let casm_location = casm_table.get(&self.txn, class_hash)?;
         casm_location.map(|location| self.file_handlers.get_casm_unchecked(location)).transpose()
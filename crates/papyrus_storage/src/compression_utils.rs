@@ -1,15 +1,270 @@
+//! Compression of the storage values.
+//!
+//! The values are compressed with the [`CompressionCodec`] of [`crate::StorageConfig`]. Each
+//! compressed value starts with a byte that identifies its codec, so values that were written with
+//! any codec stay readable after the codec for new writes changes. The values that were written
+//! before the codec was stored in the data are gzip compressed, and are identified by the first
+//! byte of the gzip magic number, which isn't the byte of any codec.
+//!
+//! Contract classes compressed with zstd use the latest zstd dictionary trained on the contract
+//! classes of the storage (see [`crate::utils::train_zstd_dictionary`]), if there is one.
+//!
+//! The codec and the dictionaries are the [`CompressionContext`] of the storage, which is shared by
+//! its reader and writer. The values are compressed inside their serialization, so the storage
+//! passes its context to the (de)serialization of each value with [`CompressionContext::scope`].
+//! Values that are (de)serialized outside of a storage are compressed with gzip, without
+//! dictionaries.
+
 #[cfg(test)]
 #[path = "compression_utils_test.rs"]
 mod compression_utils_test;
 
-use std::io::Read;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Read};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
 use flate2::bufread::{GzDecoder, GzEncoder};
 use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
 
 use crate::db::serialization::{StorageSerde, StorageSerdeError};
 
-// TODO: consider changing the compression hyperparameters: compression level and algorithm.
+// The first byte of the gzip magic number, which starts the values that were written before the
+// codec was stored in the data.
+const LEGACY_GZIP_MAGIC: u8 = 0x1f;
+const ZSTD_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+/// The codecs for compressing the storage values.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[repr(u8)]
+pub enum CompressionCodec {
+    /// Gzip with the default compression level.
+    #[default]
+    Gzip = 1,
+    /// Zstd with the default compression level.
+    Zstd = 2,
+    /// Lz4, which is faster than the other codecs but compresses less.
+    Lz4 = 3,
+}
+
+impl CompressionCodec {
+    fn from_byte(byte: u8) -> Option<Self> {
+        [Self::Gzip, Self::Zstd, Self::Lz4].into_iter().find(|codec| *codec as u8 == byte)
+    }
+}
+
+impl FromStr for CompressionCodec {
+    type Err = String;
+
+    fn from_str(codec: &str) -> Result<Self, Self::Err> {
+        match codec {
+            "Gzip" => Ok(CompressionCodec::Gzip),
+            "Zstd" => Ok(CompressionCodec::Zstd),
+            "Lz4" => Ok(CompressionCodec::Lz4),
+            _ => Err(format!("Unknown compression codec {codec}, expected Gzip, Zstd or Lz4.")),
+        }
+    }
+}
+
+thread_local! {
+    // The context of the storage whose value is (de)serialized on this thread, if any.
+    static CURRENT_CONTEXT: RefCell<Option<Arc<CompressionContext>>> = const { RefCell::new(None) };
+}
+
+// The context of the values that are (de)serialized outside of a storage.
+static DEFAULT_CONTEXT: CompressionContext = CompressionContext {
+    codec: CompressionCodec::Gzip,
+    zstd_dictionaries: RwLock::new(ZstdDictionaries { decoders: BTreeMap::new(), encoder: None }),
+};
+
+/// The compression state of a storage: the codec for new values and the zstd dictionaries for
+/// contract classes.
+#[derive(Debug)]
+pub struct CompressionContext {
+    codec: CompressionCodec,
+    zstd_dictionaries: RwLock<ZstdDictionaries>,
+}
+
+struct ZstdDictionaries {
+    // The dictionaries for decompression by their id.
+    decoders: BTreeMap<u32, Arc<DecoderDictionary<'static>>>,
+    // The dictionary for new writes, which is the last registered dictionary.
+    encoder: Option<Arc<EncoderDictionary<'static>>>,
+}
+
+impl Debug for ZstdDictionaries {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZstdDictionaries")
+            .field("decoders", &self.decoders.keys().collect::<Vec<_>>())
+            .field("encoder", &self.encoder.is_some())
+            .finish()
+    }
+}
+
+impl Default for CompressionContext {
+    fn default() -> Self {
+        Self::new(CompressionCodec::default())
+    }
+}
+
+impl CompressionContext {
+    /// Returns a context that compresses new values with the given codec, without dictionaries.
+    pub fn new(codec: CompressionCodec) -> Self {
+        Self {
+            codec,
+            zstd_dictionaries: RwLock::new(ZstdDictionaries {
+                decoders: BTreeMap::new(),
+                encoder: None,
+            }),
+        }
+    }
+
+    /// Calls the given function with this context as the context of the values that are
+    /// (de)serialized on the current thread.
+    pub(crate) fn scope<T>(self: &Arc<Self>, f: impl FnOnce() -> T) -> T {
+        struct ScopeGuard(Option<Arc<CompressionContext>>);
+        impl Drop for ScopeGuard {
+            fn drop(&mut self) {
+                CURRENT_CONTEXT.with(|current| *current.borrow_mut() = self.0.take());
+            }
+        }
+
+        let _guard =
+            ScopeGuard(CURRENT_CONTEXT.with(|current| current.borrow_mut().replace(self.clone())));
+        f()
+    }
+
+    /// Registers a zstd dictionary for contract classes and returns its id. The dictionary is used
+    /// for decompressing the values that were compressed with it, and for compressing new contract
+    /// classes until another dictionary is registered.
+    pub(crate) fn register_zstd_dictionary(&self, dictionary: &[u8]) -> u32 {
+        let dictionary_id = zstd::zstd_safe::get_dict_id_from_dict(dictionary);
+        let mut dictionaries = self.zstd_dictionaries.write().expect("Lock should not be poisoned");
+        dictionaries.decoders.insert(dictionary_id, Arc::new(DecoderDictionary::copy(dictionary)));
+        dictionaries.encoder = Some(Arc::new(EncoderDictionary::copy(dictionary, ZSTD_LEVEL)));
+        dictionary_id
+    }
+
+    /// Returns whether a zstd dictionary for contract classes is registered.
+    pub fn has_zstd_dictionary(&self) -> bool {
+        self.zstd_dictionaries.read().expect("Lock should not be poisoned").encoder.is_some()
+    }
+
+    /// Returns the data compressed with the given codec, starting with the byte of the codec. If
+    /// `use_dictionary` is set, zstd uses the latest registered dictionary for contract classes,
+    /// if there is one.
+    ///
+    /// # Errors
+    /// Returns [`std::io::Error`] if any read error is encountered.
+    pub fn compress_with_codec(
+        &self,
+        data: &[u8],
+        codec: CompressionCodec,
+        use_dictionary: bool,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let mut compressed_data = vec![codec as u8];
+        match codec {
+            CompressionCodec::Gzip => {
+                GzEncoder::new(data, Compression::default()).read_to_end(&mut compressed_data)?;
+            }
+            CompressionCodec::Zstd => {
+                let dictionary = use_dictionary
+                    .then(|| {
+                        self.zstd_dictionaries
+                            .read()
+                            .expect("Lock should not be poisoned")
+                            .encoder
+                            .clone()
+                    })
+                    .flatten();
+                match dictionary {
+                    Some(dictionary) => {
+                        zstd::stream::read::Encoder::with_prepared_dictionary(data, &dictionary)?
+                            .read_to_end(&mut compressed_data)?
+                    }
+                    None => zstd::stream::read::Encoder::new(data, ZSTD_LEVEL)?
+                        .read_to_end(&mut compressed_data)?,
+                };
+            }
+            CompressionCodec::Lz4 => {
+                compressed_data.extend(lz4_flex::compress_prepend_size(data));
+            }
+        }
+        Ok(compressed_data)
+    }
+
+    /// Decompress data and returns it as bytes in a vector. The codec is identified by the first
+    /// byte of the data.
+    ///
+    /// # Errors
+    /// Returns [`std::io::Error`] if any read error is encountered, if the codec is unknown or if
+    /// the data was compressed with a zstd dictionary that isn't registered.
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let mut uncompressed = Vec::new();
+        let Some((&codec_byte, compressed_data)) = data.split_first() else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Empty compressed data."));
+        };
+        if codec_byte == LEGACY_GZIP_MAGIC {
+            GzDecoder::new(data).read_to_end(&mut uncompressed)?;
+            return Ok(uncompressed);
+        }
+        match CompressionCodec::from_byte(codec_byte) {
+            Some(CompressionCodec::Gzip) => {
+                GzDecoder::new(compressed_data).read_to_end(&mut uncompressed)?;
+            }
+            Some(CompressionCodec::Zstd) => {
+                match zstd::zstd_safe::get_dict_id_from_frame(compressed_data) {
+                    0 => zstd::stream::read::Decoder::new(compressed_data)?
+                        .read_to_end(&mut uncompressed)?,
+                    dictionary_id => {
+                        let dictionary = self
+                            .zstd_dictionaries
+                            .read()
+                            .expect("Lock should not be poisoned")
+                            .decoders
+                            .get(&dictionary_id)
+                            .cloned()
+                            .ok_or_else(|| {
+                                io::Error::new(
+                                    io::ErrorKind::NotFound,
+                                    format!("Missing zstd dictionary {dictionary_id}."),
+                                )
+                            })?;
+                        zstd::stream::read::Decoder::with_prepared_dictionary(
+                            compressed_data,
+                            &dictionary,
+                        )?
+                        .read_to_end(&mut uncompressed)?
+                    }
+                };
+            }
+            Some(CompressionCodec::Lz4) => {
+                uncompressed = lz4_flex::decompress_size_prepended(compressed_data)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown compression codec {codec_byte}."),
+                ));
+            }
+        }
+        Ok(uncompressed)
+    }
+}
+
+// Calls the given function with the context of the values that are (de)serialized on the current
+// thread.
+fn with_current_context<T>(f: impl FnOnce(&CompressionContext) -> T) -> T {
+    CURRENT_CONTEXT.with(|current| match current.borrow().as_deref() {
+        Some(context) => f(context),
+        None => f(&DEFAULT_CONTEXT),
+    })
+}
 
 /// Returns the compressed data in a vector.
 ///
@@ -19,10 +274,18 @@ use crate::db::serialization::{StorageSerde, StorageSerdeError};
 /// # Errors
 /// Returns [`std::io::Error`] if any read error is encountered.
 pub fn compress(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
-    let mut encoder = GzEncoder::new(data, Compression::default());
-    let mut compressed_data = Vec::new();
-    encoder.read_to_end(&mut compressed_data)?;
-    Ok(compressed_data)
+    with_current_context(|context| context.compress_with_codec(data, context.codec, false))
+}
+
+/// Returns the compressed data of a contract class in a vector.
+///
+/// # Arguments
+/// * data - bytes to compress.
+///
+/// # Errors
+/// Returns [`std::io::Error`] if any read error is encountered.
+pub fn compress_class(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    with_current_context(|context| context.compress_with_codec(data, context.codec, true))
 }
 
 /// Serialized and then compress object.
@@ -38,18 +301,32 @@ pub fn serialize_and_compress(object: &impl StorageSerde) -> Result<Vec<u8>, Sto
     Ok(compress(buf.as_slice())?)
 }
 
-/// Decompress data and returns it as bytes in a vector.
+/// Serialized and then compress an object of a contract class.
+///
+/// # Arguments
+/// * object - the object to serialize and compress.
+///
+/// # Errors
+/// Returns [`StorageSerdeError`] if any error is encountered in the serialization or compression.
+pub fn serialize_and_compress_class(
+    object: &impl StorageSerde,
+) -> Result<Vec<u8>, StorageSerdeError> {
+    let mut buf = Vec::new();
+    object.serialize_into(&mut buf)?;
+    Ok(compress_class(buf.as_slice())?)
+}
+
+/// Decompress data and returns it as bytes in a vector. The codec is identified by the first byte
+/// of the data.
 ///
 /// # Arguments
 /// * data - bytes to decompress.
 ///
 /// # Errors
-/// Returns [`std::io::Error`] if any read error is encountered.
+/// Returns [`std::io::Error`] if any read error is encountered, if the codec is unknown or if the
+/// data was compressed with a zstd dictionary that isn't registered.
 pub fn decompress(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
-    let mut decoder = GzDecoder::new(data);
-    let mut uncompressed = Vec::new();
-    decoder.read_to_end(&mut uncompressed)?;
-    Ok(uncompressed)
+    with_current_context(|context| context.decompress(data))
 }
 
 /// Decompress a vector directly from a reader.
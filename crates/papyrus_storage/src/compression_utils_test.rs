@@ -1,9 +1,26 @@
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use flate2::bufread::GzEncoder;
+use flate2::Compression;
 use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
 use starknet_api::deprecated_contract_class::Program;
-use test_utils::read_json_file;
+use starknet_api::state::ThinStateDiff;
+use test_utils::{get_test_state_diff, read_json_file};
 
-use super::{compress, decompress, decompress_from_reader, serialize_and_compress};
+use super::{
+    compress,
+    decompress,
+    decompress_from_reader,
+    serialize_and_compress,
+    CompressionCodec,
+    CompressionContext,
+};
 use crate::db::serialization::StorageSerde;
+use crate::open_storage;
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::get_test_config;
 
 #[test]
 fn bytes_compression() {
@@ -23,3 +40,97 @@ fn object_compression() {
     let restored_program = Program::deserialize_from(&mut decompressed.as_slice()).unwrap();
     assert_eq!(program, restored_program);
 }
+
+#[test]
+fn codecs_compression() {
+    let program_json = read_json_file("program.json");
+    let bytes = serde_json::to_vec(&program_json).unwrap();
+    let context = CompressionContext::default();
+    for codec in [CompressionCodec::Gzip, CompressionCodec::Zstd, CompressionCodec::Lz4] {
+        let compressed = context.compress_with_codec(bytes.as_slice(), codec, false).unwrap();
+        assert_eq!(compressed[0], codec as u8, "{codec:?}");
+        assert!(compressed.len() < bytes.len(), "{codec:?}");
+        assert_eq!(context.decompress(compressed.as_slice()).unwrap(), bytes, "{codec:?}");
+    }
+}
+
+#[test]
+fn decompress_legacy_gzip() {
+    let bytes = vec![30, 5, 23, 12, 47];
+    let mut compressed = Vec::new();
+    GzEncoder::new(bytes.as_slice(), Compression::default()).read_to_end(&mut compressed).unwrap();
+    assert_eq!(decompress(compressed.as_slice()).unwrap(), bytes);
+}
+
+#[test]
+fn compress_with_scoped_context() {
+    let bytes = vec![30, 5, 23, 12, 47];
+    let context = Arc::new(CompressionContext::new(CompressionCodec::Lz4));
+    let compressed = context.scope(|| compress(bytes.as_slice())).unwrap();
+    assert_eq!(compressed[0], CompressionCodec::Lz4 as u8);
+    assert_eq!(decompress(compressed.as_slice()).unwrap(), bytes);
+
+    // Outside of the scope, the values are compressed with gzip.
+    assert_eq!(compress(bytes.as_slice()).unwrap()[0], CompressionCodec::Gzip as u8);
+}
+
+#[test]
+fn decompress_unknown_codec() {
+    let err = decompress(&[30, 5, 23, 12, 47]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn zstd_dictionary_compression() {
+    let samples = (0..1000_u32)
+        .map(|i| format!("{{\"selector\": \"{i:#066x}\", \"offset\": {}}}", i * 7).into_bytes())
+        .collect::<Vec<_>>();
+    let dictionary = zstd::dict::from_samples(&samples, 4096).unwrap();
+    let context = CompressionContext::new(CompressionCodec::Zstd);
+    let dictionary_id = context.register_zstd_dictionary(&dictionary);
+
+    let data = samples[17].as_slice();
+    let compressed = context.compress_with_codec(data, CompressionCodec::Zstd, true).unwrap();
+    assert_eq!(zstd::zstd_safe::get_dict_id_from_frame(&compressed[1..]), dictionary_id);
+    assert_eq!(context.decompress(compressed.as_slice()).unwrap(), data);
+
+    // The dictionaries of a storage aren't used by other storages.
+    let err = CompressionContext::default().decompress(compressed.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+    // A frame that references an unknown dictionary can't be decompressed.
+    let mut unknown_dictionary = dictionary.clone();
+    unknown_dictionary[4..8].copy_from_slice(&(dictionary_id.wrapping_add(1)).to_le_bytes());
+    let mut encoder = zstd::stream::write::Encoder::with_dictionary(
+        vec![CompressionCodec::Zstd as u8],
+        0,
+        &unknown_dictionary,
+    )
+    .unwrap();
+    encoder.write_all(data).unwrap();
+    let compressed = encoder.finish().unwrap();
+    let err = context.decompress(compressed.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn storages_with_different_codecs() {
+    let state_diff = ThinStateDiff::from(get_test_state_diff());
+    let storages = [CompressionCodec::Zstd, CompressionCodec::Lz4].map(|codec| {
+        let (mut config, temp_dir) = get_test_config(None);
+        config.compression_codec = codec;
+        let (reader, mut writer) = open_storage(config).unwrap();
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(BlockNumber(0), state_diff.clone())
+            .unwrap()
+            .commit()
+            .unwrap();
+        (reader, writer, temp_dir)
+    });
+    for (reader, _, _) in &storages {
+        let txn = reader.begin_ro_txn().unwrap();
+        assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diff.clone()));
+    }
+}
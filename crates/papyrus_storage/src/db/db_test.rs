@@ -3,14 +3,23 @@ use libmdbx::PageSize;
 use pretty_assertions::assert_eq;
 use tempfile::TempDir;
 
-use crate::db::serialization::{NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
+use crate::db::serialization::{
+    NoVersionValueWrapper,
+    SerdeContext,
+    ValueSerde,
+    VersionZeroWrapper,
+};
 use crate::db::table_types::Table;
 use crate::db::{get_page_size, open_env, DbError, DbIter, DbReader, DbResult, DbWriter};
 use crate::test_utils::get_test_config;
 
 pub(crate) fn get_test_env() -> ((DbReader, DbWriter), TempDir) {
     let (config, temp_dir) = get_test_config(None);
-    (open_env(&config.db_config).expect("Failed to open environment."), temp_dir)
+    (
+        open_env(&config.db_config, true, false, SerdeContext::default())
+            .expect("Failed to open environment."),
+        temp_dir,
+    )
 }
 
 #[test]
@@ -27,7 +36,7 @@ fn open_env_with_enforce_file_exists() {
     // First call to `open_env` with `enforce_file_exists` set to `true` should fail because
     // the file does not exist yet. This equals to starting a new chain, where this flag must be
     // off.
-    let result = open_env(&db_config);
+    let result = open_env(&db_config, true, false, SerdeContext::default());
     assert_matches!(result, Err(DbError::FileDoesNotExist(_)));
 
     // Make sure that file in the expected file indeed does not exist.
@@ -41,7 +50,8 @@ fn open_env_with_enforce_file_exists() {
     // Second call to `open_env` should succeed and create the mdbx.dat file in the new env.
     // Called inside a block to drop the db handlers before the next call.
     {
-        let result: DbResult<(DbReader, DbWriter)> = open_env(&db_config);
+        let result: DbResult<(DbReader, DbWriter)> =
+            open_env(&db_config, true, false, SerdeContext::default());
         assert_matches!(result, Ok(_));
     }
 
@@ -51,14 +61,15 @@ fn open_env_with_enforce_file_exists() {
     assert_eq!(mdbx_file_exists, true);
 
     db_config.enforce_file_exists = true;
-    let result: DbResult<(DbReader, DbWriter)> = open_env(&db_config);
+    let result: DbResult<(DbReader, DbWriter)> =
+        open_env(&db_config, true, false, SerdeContext::default());
     assert_matches!(result, Ok(_));
 
     // Add some charachter to the path to make it invalid.
     // Fourth and final call to `open_env` with path enforcement should fail because the path is
     // invalid.
     db_config.path_prefix = db_config.path_prefix.join("2");
-    let result = open_env(&db_config);
+    let result = open_env(&db_config, true, false, SerdeContext::default());
     assert_matches!(result, Err(DbError::FileDoesNotExist(_)));
 }
 
@@ -230,18 +241,24 @@ fn with_version_zero_serialization() {
     // TODO: move to serialization tests.
     const A_RANDOM_U8: u8 = 123;
     let with_zero_version_serialization =
-        VersionZeroWrapper::<u8>::serialize(&A_RANDOM_U8).unwrap();
+        VersionZeroWrapper::<u8>::serialize(&A_RANDOM_U8, &SerdeContext::default()).unwrap();
     assert_eq!(with_zero_version_serialization, vec![0, 123]);
     assert_eq!(
-        VersionZeroWrapper::<u8>::deserialize(&mut with_zero_version_serialization.as_slice()),
+        VersionZeroWrapper::<u8>::deserialize(
+            &mut with_zero_version_serialization.as_slice(),
+            &SerdeContext::default()
+        ),
         Some(A_RANDOM_U8)
     );
 
     let with_no_version_serialization =
-        NoVersionValueWrapper::<u8>::serialize(&A_RANDOM_U8).unwrap();
+        NoVersionValueWrapper::<u8>::serialize(&A_RANDOM_U8, &SerdeContext::default()).unwrap();
     assert_eq!(with_no_version_serialization, vec![123]);
     assert_eq!(
-        NoVersionValueWrapper::<u8>::deserialize(&mut with_no_version_serialization.as_slice()),
+        NoVersionValueWrapper::<u8>::deserialize(
+            &mut with_no_version_serialization.as_slice(),
+            &SerdeContext::default()
+        ),
         Some(A_RANDOM_U8)
     );
 }
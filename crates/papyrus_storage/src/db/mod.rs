@@ -25,11 +25,12 @@ use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::mem::{size_of, zeroed};
 use std::path::PathBuf;
-use std::result;
 use std::sync::Arc;
+use std::{ptr, result};
 
-use libmdbx::{Geometry, PageSize, WriteMap};
+use libmdbx::{DatabaseFlags, Geometry, Mode, PageSize, SyncMode, WriteFlags, WriteMap};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::validators::{validate_ascii, validate_path_exists};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
@@ -37,12 +38,15 @@ use serde::{Deserialize, Serialize};
 use starknet_api::core::ChainId;
 use validator::Validate;
 
-use self::serialization::{Key, ValueSerde};
+use self::serialization::{Key, SerdeContext, ValueSerde};
 use self::table_types::{DbCursor, DbCursorTrait};
 use crate::db::table_types::TableType;
 
 // Maximum number of Sub-Databases.
-const MAX_DBS: usize = 20;
+const MAX_DBS: usize = 29;
+
+// Maximum number of concurrent read transactions.
+const MAX_READERS: u32 = 1 << 13; // 8K readers
 
 // Note that NO_TLS mode is used by default.
 type EnvironmentKind = WriteMap;
@@ -159,6 +163,10 @@ pub enum DbError {
     /// An error that occurred when trying to open a db file that does not exist.
     #[error("The file '{0}' does not exist.")]
     FileDoesNotExist(PathBuf),
+    /// An error that occurred when trying to exclusively open an environment that is opened by
+    /// another process.
+    #[error("The environment at '{0}' is opened by another process.")]
+    EnvironmentInUse(PathBuf),
 }
 
 type DbResult<V> = result::Result<V, DbError>;
@@ -184,13 +192,22 @@ impl KeyAlreadyExistsError {
 /// Tries to open an MDBX environment and returns a reader and a writer to it.
 /// There is a single non clonable writer instance, to make sure there is only one write transaction
 ///  at any given moment.
-pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
+/// If `durable_commits` is false, the commits aren't synced to the disk until a commit is done with
+/// [`DbWriteTransaction::commit_and_sync`].
+/// If `exclusive` is true, fails with [`DbError::EnvironmentInUse`] if the environment is opened by
+/// another process, including in read-only mode, and prevents other processes from opening it.
+/// The values are (de)serialized with the given context.
+pub(crate) fn open_env(
+    config: &DbConfig,
+    durable_commits: bool,
+    exclusive: bool,
+    serde_context: SerdeContext,
+) -> DbResult<(DbReader, DbWriter)> {
     let db_file_path = config.path().join("mdbx.dat");
     // Checks if path exists if enforce_file_exists is true.
     if config.enforce_file_exists && !db_file_path.exists() {
         return Err(DbError::FileDoesNotExist(db_file_path));
     }
-    const MAX_READERS: u32 = 1 << 13; // 8K readers
     let env = Arc::new(
         Environment::new()
             .set_geometry(Geometry {
@@ -199,11 +216,50 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
                 page_size: Some(get_page_size(page_size::get())),
                 ..Default::default()
             })
+            .set_flags(DatabaseFlags {
+                mode: Mode::ReadWrite {
+                    sync_mode: if durable_commits {
+                        SyncMode::Durable
+                    } else {
+                        SyncMode::SafeNoSync
+                    },
+                },
+                exclusive,
+                ..Default::default()
+            })
+            .set_max_tables(MAX_DBS)
+            .set_max_readers(MAX_READERS)
+            .open(&config.path())
+            .map_err(|err| match err {
+                libmdbx::Error::Busy if exclusive => DbError::EnvironmentInUse(config.path()),
+                err => err.into(),
+            })?,
+    );
+    Ok((
+        DbReader { env: env.clone(), serde_context: serde_context.clone() },
+        DbWriter { env, serde_context },
+    ))
+}
+
+/// Tries to open an existing MDBX environment in read-only mode and returns a reader to it.
+/// The environment can be written at the same time by another process that opened it with
+/// [`open_env`], and each read transaction sees the data committed by that process.
+pub(crate) fn open_env_read_only(
+    config: &DbConfig,
+    serde_context: SerdeContext,
+) -> DbResult<DbReader> {
+    let db_file_path = config.path().join("mdbx.dat");
+    if !db_file_path.exists() {
+        return Err(DbError::FileDoesNotExist(db_file_path));
+    }
+    let env = Arc::new(
+        Environment::new()
+            .set_flags(DatabaseFlags { mode: Mode::ReadOnly, ..Default::default() })
             .set_max_tables(MAX_DBS)
             .set_max_readers(MAX_READERS)
             .open(&config.path())?,
     );
-    Ok((DbReader { env: env.clone() }, DbWriter { env }))
+    Ok(DbReader { env, serde_context })
 }
 
 // Size in bytes.
@@ -224,16 +280,26 @@ fn get_page_size(os_page_size: usize) -> PageSize {
 #[derive(Clone, Debug)]
 pub(crate) struct DbReader {
     env: Arc<Environment>,
+    serde_context: SerdeContext,
 }
 
 #[derive(Debug)]
 pub(crate) struct DbWriter {
     env: Arc<Environment>,
+    serde_context: SerdeContext,
 }
 
 impl DbReader {
     pub(crate) fn begin_ro_txn(&self) -> DbResult<DbReadTransaction<'_>> {
-        Ok(DbReadTransaction { txn: self.env.begin_ro_txn()? })
+        Ok(DbReadTransaction {
+            txn: self.env.begin_ro_txn()?,
+            env: &self.env,
+            serde_context: &self.serde_context,
+        })
+    }
+
+    pub(crate) fn serde_context(&self) -> &SerdeContext {
+        &self.serde_context
     }
 }
 
@@ -241,7 +307,17 @@ type DbReadTransaction<'env> = DbTransaction<'env, RO>;
 
 impl DbWriter {
     pub(crate) fn begin_rw_txn(&mut self) -> DbResult<DbWriteTransaction<'_>> {
-        Ok(DbWriteTransaction { txn: self.env.begin_rw_txn()? })
+        Ok(DbWriteTransaction {
+            txn: self.env.begin_rw_txn()?,
+            env: &self.env,
+            serde_context: &self.serde_context,
+        })
+    }
+
+    // Syncs the database to the disk, so all the commits are durable.
+    pub(crate) fn sync(&self) -> DbResult<()> {
+        self.env.sync(true)?;
+        Ok(())
     }
 }
 
@@ -252,6 +328,41 @@ impl<'a> DbWriteTransaction<'a> {
         self.txn.commit()?;
         Ok(())
     }
+
+    // Commits the transaction and syncs the database to the disk, so this commit and the commits
+    // before it are durable.
+    pub(crate) fn commit_and_sync(self) -> DbResult<()> {
+        self.txn.commit()?;
+        self.env.sync(true)?;
+        Ok(())
+    }
+}
+
+// The readers of the database, of this process and of the other processes that opened it.
+pub(crate) struct DbReaders<'env>(&'env Environment);
+
+impl DbReaders<'_> {
+    // Returns the id of the transaction seen by the oldest running read transaction, or the id of
+    // the last committed transaction if there are no read transactions. No read transaction sees
+    // data that was deleted by a transaction with a lower or equal id.
+    pub(crate) fn oldest_txn_id(&self) -> DbResult<u64> {
+        // SAFETY: The environment is open for the lifetime of the handle, the info is plain data
+        // and its size matches the struct that mdbx fills.
+        let (code, info) = unsafe {
+            let mut info: mdbx_sys::MDBX_envinfo = zeroed();
+            let code = mdbx_sys::mdbx_env_info_ex(
+                self.0.ptr(),
+                ptr::null(),
+                &mut info,
+                size_of::<mdbx_sys::MDBX_envinfo>(),
+            );
+            (code, info)
+        };
+        if code != mdbx_sys::MDBX_SUCCESS {
+            return Err(libmdbx::Error::from_err_code(code).into());
+        }
+        Ok(info.mi_latter_reader_txnid)
+    }
 }
 
 #[doc(hidden)]
@@ -262,9 +373,26 @@ pub trait TransactionKind {
 
 pub(crate) struct DbTransaction<'env, Mode: TransactionKind> {
     txn: libmdbx::Transaction<'env, Mode::Internal, EnvironmentKind>,
+    env: &'env Environment,
+    serde_context: &'env SerdeContext,
 }
 
 impl<'a, Mode: TransactionKind> DbTransaction<'a, Mode> {
+    pub(crate) fn serde_context(&self) -> &'a SerdeContext {
+        self.serde_context
+    }
+
+    // Returns the id of the transaction. The id of a write transaction is the id it gets once
+    // committed, and a read transaction has the id of the last transaction it sees.
+    pub(crate) fn id(&self) -> u64 {
+        self.txn.id()
+    }
+
+    // Returns a handle for querying the readers of the database, that outlives the transaction.
+    pub(crate) fn readers(&self) -> DbReaders<'a> {
+        DbReaders(self.env)
+    }
+
     pub fn open_table<'env, K: Key + Debug, V: ValueSerde + Debug, T: TableType>(
         &'env self,
         table_id: &TableIdentifier<K, V, T>,
@@ -273,12 +401,53 @@ impl<'a, Mode: TransactionKind> DbTransaction<'a, Mode> {
         Ok(TableHandle {
             database,
             name: table_id.name,
+            serde_context: self.serde_context,
             _key_type: PhantomData {},
             _value_type: PhantomData {},
             _table_type: PhantomData {},
         })
     }
+
+    // Calls the given function with each serialized entry of the table with the given name,
+    // ordered by the key.
+    pub(crate) fn for_each_serialized_entry<E: From<DbError>>(
+        &self,
+        table_name: &str,
+        mut f: impl FnMut(&[u8], &[u8]) -> result::Result<(), E>,
+    ) -> result::Result<(), E> {
+        let table = self.txn.open_table(Some(table_name)).map_err(DbError::from)?;
+        let mut cursor = self.txn.cursor(&table).map_err(DbError::from)?;
+        for entry in cursor.iter::<DbKeyType<'_>, DbValueType<'_>>() {
+            let (key, value) = entry.map_err(DbError::from)?;
+            f(&key, &value)?;
+        }
+        Ok(())
+    }
+
+    // Returns the number of entries in the given table.
+    pub(crate) fn get_entries_count<K: Key + Debug, V: ValueSerde + Debug, T: TableType>(
+        &self,
+        table: &TableHandle<'_, K, V, T>,
+    ) -> DbResult<usize> {
+        Ok(self.txn.table_stat(&table.database)?.entries())
+    }
 }
+
+impl<'a> DbWriteTransaction<'a> {
+    // Appends a serialized entry to the table with the given name. The key must be greater than
+    // all the keys in the table.
+    pub(crate) fn append_serialized_entry(
+        &self,
+        table_name: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> DbResult<()> {
+        let table = self.txn.open_table(Some(table_name))?;
+        self.txn.put(&table, key, value, WriteFlags::APPEND)?;
+        Ok(())
+    }
+}
+
 pub(crate) struct TableIdentifier<K: Key + Debug, V: ValueSerde + Debug, T: TableType> {
     pub(crate) name: &'static str,
     _key_type: PhantomData<K>,
@@ -289,6 +458,7 @@ pub(crate) struct TableIdentifier<K: Key + Debug, V: ValueSerde + Debug, T: Tabl
 pub(crate) struct TableHandle<'env, K: Key + Debug, V: ValueSerde + Debug, T: TableType> {
     database: libmdbx::Table<'env>,
     name: &'static str,
+    serde_context: &'env SerdeContext,
     _key_type: PhantomData<K>,
     _value_type: PhantomData<V>,
     _table_type: PhantomData<T>,
@@ -305,7 +475,6 @@ pub(crate) struct DbIter<'cursor, 'txn, Mode: TransactionKind, K: Key, V: ValueS
 impl<'cursor, 'txn, Mode: TransactionKind, K: Key, V: ValueSerde, T: TableType>
     DbIter<'cursor, 'txn, Mode, K, V, T>
 {
-    #[allow(dead_code)]
     pub(crate) fn new(cursor: &'cursor mut DbCursor<'txn, Mode, K, V, T>) -> Self {
         Self { cursor, _key_type: PhantomData {}, _value_type: PhantomData {} }
     }
@@ -1,10 +1,13 @@
 use std::fmt::Debug;
 use std::io::Write;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 use tracing::{debug, error};
 
+use crate::compression_utils::CompressionContext;
 use crate::db::DbError;
+use crate::encryption::EncryptionContext;
 
 /// Trait for serializing and deserializing values.
 pub(crate) trait StorageSerdeEx: StorageSerde {
@@ -42,13 +45,37 @@ pub trait StorageSerde: Sized {
 pub(crate) trait Key: StorageSerdeEx + Ord + Clone {}
 impl<T> Key for T where T: StorageSerdeEx + Ord + Clone {}
 
+/// The state of the (de)serialization of the values of a storage, which is shared by its readers
+/// and writer.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SerdeContext {
+    pub(crate) compression: Arc<CompressionContext>,
+    pub(crate) encryption: Arc<EncryptionContext>,
+}
+
 /// Trait for serializing and deserializing values from the database.
+/// The values are encrypted if the storage has an encryption key (see [`crate::encryption`]).
 pub(crate) trait ValueSerde {
     type Value: StorageSerde + Debug;
 
-    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError>;
+    fn serialize_unencrypted(obj: &Self::Value) -> Result<Vec<u8>, DbError>;
+    fn deserialize_unencrypted(bytes: &mut impl std::io::Read) -> Option<Self::Value>;
+
+    fn serialize(obj: &Self::Value, context: &SerdeContext) -> Result<Vec<u8>, DbError> {
+        let serialized = context.compression.scope(|| Self::serialize_unencrypted(obj))?;
+        context.encryption.encrypt_if_enabled(serialized).ok_or(DbError::Serialization)
+    }
+
     // TODO(yair): Return a result here.
-    fn deserialize(bytes: &mut impl std::io::Read) -> Option<Self::Value>;
+    fn deserialize(bytes: &mut impl std::io::Read, context: &SerdeContext) -> Option<Self::Value> {
+        if !context.encryption.is_enabled() {
+            return context.compression.scope(|| Self::deserialize_unencrypted(bytes));
+        }
+        let mut encrypted_bytes = Vec::new();
+        bytes.read_to_end(&mut encrypted_bytes).ok()?;
+        let decrypted_bytes = context.encryption.decrypt(&encrypted_bytes)?;
+        context.compression.scope(|| Self::deserialize_unencrypted(&mut decrypted_bytes.as_slice()))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -60,11 +87,11 @@ pub(crate) struct NoVersionValueWrapper<T: StorageSerde> {
 impl<T: StorageSerde + Debug> ValueSerde for NoVersionValueWrapper<T> {
     type Value = T;
 
-    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
+    fn serialize_unencrypted(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
         StorageSerdeEx::serialize(obj)
     }
 
-    fn deserialize(bytes: &mut impl std::io::Read) -> Option<Self::Value> {
+    fn deserialize_unencrypted(bytes: &mut impl std::io::Read) -> Option<Self::Value> {
         StorageSerdeEx::deserialize(bytes)
     }
 }
@@ -82,14 +109,14 @@ const VERSION_ZERO: u8 = 0;
 impl<T: StorageSerde + Debug> ValueSerde for VersionZeroWrapper<T> {
     type Value = T;
 
-    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
+    fn serialize_unencrypted(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
         let mut res = Vec::new();
         res.write_all(&[VERSION_ZERO]).expect("Failed to write version");
         obj.serialize_into(&mut res).map_err(|_| DbError::Serialization)?;
         Ok(res)
     }
 
-    fn deserialize(bytes: &mut impl std::io::Read) -> Option<Self::Value> {
+    fn deserialize_unencrypted(bytes: &mut impl std::io::Read) -> Option<Self::Value> {
         let mut version = [0u8; 1];
         bytes.read_exact(&mut version[..]).ok()?;
         if version[0] != VERSION_ZERO {
@@ -130,14 +157,14 @@ impl<T: StorageSerde + Debug + Migratable, const VERSION: u8> ValueSerde
 {
     type Value = T;
 
-    fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
+    fn serialize_unencrypted(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
         let mut res = Vec::new();
         res.write_all(&[VERSION]).expect("Failed to write version");
         obj.serialize_into(&mut res).map_err(|_| DbError::Serialization)?;
         Ok(res)
     }
 
-    fn deserialize(bytes: &mut impl std::io::Read) -> Option<Self::Value> {
+    fn deserialize_unencrypted(bytes: &mut impl std::io::Read) -> Option<Self::Value> {
         let mut version = [0u8; 1];
         bytes.read_exact(&mut version[..]).ok()?;
         if version[0] > VERSION {
@@ -9,7 +9,13 @@ use std::marker::PhantomData;
 use libmdbx::{TableFlags, WriteFlags};
 
 use super::{DbResult, Table, TableType};
-use crate::db::serialization::{Key as KeyTrait, StorageSerde, StorageSerdeEx, ValueSerde};
+use crate::db::serialization::{
+    Key as KeyTrait,
+    SerdeContext,
+    StorageSerde,
+    StorageSerdeEx,
+    ValueSerde,
+};
 use crate::db::{
     DbCursor,
     DbCursorTrait,
@@ -159,6 +165,7 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug, T: DupSortTableType + Dup
         let cursor = txn.txn.cursor(&self.database)?;
         Ok(DbCursor {
             cursor,
+            serde_context: txn.serde_context.clone(),
             _key_type: PhantomData {},
             _value_type: PhantomData {},
             _table_type: PhantomData {},
@@ -180,7 +187,8 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug, T: DupSortTableType + Dup
 
         let sub_key = T::get_sub_key(key)?;
         if let Some(mut bytes) = bytes.strip_prefix(sub_key.as_slice()) {
-            let value = V::deserialize(&mut bytes).ok_or(DbError::InnerDeserialization)?;
+            let value = V::deserialize(&mut bytes, self.serde_context)
+                .ok_or(DbError::InnerDeserialization)?;
             return Ok(Some(value));
         }
         Ok(None)
@@ -193,7 +201,8 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug, T: DupSortTableType + Dup
         value: &<Self::Value as ValueSerde>::Value,
     ) -> DbResult<()> {
         let main_key = T::get_main_key(key)?;
-        let sub_key_value = T::get_sub_key_and_value(key, value)?;
+        let sub_key_value =
+            self.serde_context.compression.scope(|| T::get_sub_key_and_value(key, value))?;
 
         let mut cursor = txn.txn.cursor(&self.database)?;
         cursor.put(&main_key, &sub_key_value, WriteFlags::UPSERT)?;
@@ -231,7 +240,8 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug, T: DupSortTableType + Dup
         value: &<Self::Value as ValueSerde>::Value,
     ) -> DbResult<()> {
         let main_key = T::get_main_key(key)?;
-        let sub_key_value = T::get_sub_key_and_value(key, value)?;
+        let sub_key_value =
+            self.serde_context.compression.scope(|| T::get_sub_key_and_value(key, value))?;
 
         let mut cursor = txn.txn.cursor(&self.database)?;
         cursor.put(&main_key, &sub_key_value, WriteFlags::NO_DUP_DATA).map_err(
@@ -306,9 +316,11 @@ impl<
         let prev_cursor_res = self.cursor.prev::<DbKeyType<'_>, DbValueType<'_>>()?;
         match prev_cursor_res {
             None => Ok(None),
-            Some((main_key_bytes, sub_key_value_bytes)) => {
-                Ok(T::get_key_value_pair(&main_key_bytes, &sub_key_value_bytes))
-            }
+            Some((main_key_bytes, sub_key_value_bytes)) => Ok(get_key_value_pair::<_, _, T>(
+                &self.serde_context,
+                &main_key_bytes,
+                &sub_key_value_bytes,
+            )),
         }
     }
 
@@ -316,9 +328,11 @@ impl<
         let prev_cursor_res = self.cursor.next::<DbKeyType<'_>, DbValueType<'_>>()?;
         match prev_cursor_res {
             None => Ok(None),
-            Some((main_key_bytes, sub_key_value_bytes)) => {
-                Ok(T::get_key_value_pair(&main_key_bytes, &sub_key_value_bytes))
-            }
+            Some((main_key_bytes, sub_key_value_bytes)) => Ok(get_key_value_pair::<_, _, T>(
+                &self.serde_context,
+                &main_key_bytes,
+                &sub_key_value_bytes,
+            )),
         }
     }
 
@@ -336,7 +350,7 @@ impl<
         if let Some(value_bytes) =
             self.cursor.get_both_range::<DbValueType<'_>>(&main_key, &first_sub_key)?
         {
-            return Ok(T::get_key_value_pair(&main_key, &value_bytes));
+            return Ok(get_key_value_pair::<_, _, T>(&self.serde_context, &main_key, &value_bytes));
         }
 
         // The next main key bytes.
@@ -348,6 +362,20 @@ impl<
             return Ok(None);
         };
 
-        Ok(T::get_key_value_pair(&main_key_bytes, &sub_key_value_bytes))
+        Ok(get_key_value_pair::<_, _, T>(
+            &self.serde_context,
+            &main_key_bytes,
+            &sub_key_value_bytes,
+        ))
     }
 }
+
+// Returns a key value pair from main_key bytes and sub_key_value bytes, deserialized with the
+// context of the storage.
+fn get_key_value_pair<K: KeyTrait, V: ValueSerde, T: DupSortUtils<K, V>>(
+    serde_context: &SerdeContext,
+    main_key: &[u8],
+    sub_key_and_value: &[u8],
+) -> Option<(K, V::Value)> {
+    serde_context.compression.scope(|| T::get_key_value_pair(main_key, sub_key_and_value))
+}
@@ -1,7 +1,7 @@
+use crate::db::DbWriter;
 use crate::db::db_test::get_test_env;
 use crate::db::table_types::dup_sort_tables::add_one;
 use crate::db::table_types::test_utils::{random_table_test, table_test};
-use crate::db::DbWriter;
 
 #[test]
 fn common_prefix_table() {
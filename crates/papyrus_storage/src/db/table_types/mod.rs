@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 
 use libmdbx::Cursor;
 
-use super::serialization::{Key as KeyTrait, ValueSerde};
+use super::serialization::{Key as KeyTrait, SerdeContext, ValueSerde};
 use super::{DbResult, DbTransaction, TransactionKind, RW};
 
 mod dup_sort_tables;
@@ -66,6 +66,7 @@ pub(crate) trait DbCursorTrait {
 
 pub(crate) struct DbCursor<'txn, Mode: TransactionKind, K: KeyTrait, V: ValueSerde, T: TableType> {
     cursor: Cursor<'txn, Mode::Internal>,
+    serde_context: SerdeContext,
     _key_type: PhantomData<K>,
     _value_type: PhantomData<V>,
     _table_type: PhantomData<T>,
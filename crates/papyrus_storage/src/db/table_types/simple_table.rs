@@ -15,6 +15,7 @@ use crate::db::{
     DbCursor,
     DbError,
     DbKeyType,
+    DbReader,
     DbTransaction,
     DbValueType,
     DbWriter,
@@ -47,6 +48,24 @@ impl DbWriter {
     }
 }
 
+impl DbReader {
+    // Returns the identifier of an existing table, without creating it. Fails if the table doesn't
+    // exist.
+    pub(crate) fn open_simple_table<K: KeyTrait + Debug, V: ValueSerde + Debug>(
+        &self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<K, V, SimpleTable>> {
+        let txn = self.env.begin_ro_txn()?;
+        txn.open_table(Some(name))?;
+        Ok(TableIdentifier {
+            name,
+            _key_type: PhantomData {},
+            _value_type: PhantomData {},
+            _table_type: PhantomData {},
+        })
+    }
+}
+
 impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
     for TableHandle<'env, K, V, SimpleTable>
 {
@@ -61,6 +80,7 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
         let cursor = txn.txn.cursor(&self.database)?;
         Ok(DbCursor {
             cursor,
+            serde_context: txn.serde_context.clone(),
             _key_type: PhantomData {},
             _value_type: PhantomData {},
             _table_type: PhantomData {},
@@ -78,7 +98,8 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
             return Ok(None);
         };
         let value =
-            <Self::Value>::deserialize(&mut bytes.as_ref()).ok_or(DbError::InnerDeserialization)?;
+            <Self::Value>::deserialize(&mut bytes.as_ref(), self.serde_context)
+            .ok_or(DbError::InnerDeserialization)?;
         Ok(Some(value))
     }
 
@@ -88,7 +109,7 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
         key: &Self::Key,
         value: &<Self::Value as ValueSerde>::Value,
     ) -> DbResult<()> {
-        let data = <Self::Value>::serialize(value)?;
+        let data = <Self::Value>::serialize(value, self.serde_context)?;
         let bin_key = key.serialize()?;
         txn.txn.put(&self.database, bin_key, data, WriteFlags::UPSERT)?;
         Ok(())
@@ -100,7 +121,7 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
         key: &Self::Key,
         value: &<Self::Value as ValueSerde>::Value,
     ) -> DbResult<()> {
-        let data = <Self::Value>::serialize(value)?;
+        let data = <Self::Value>::serialize(value, self.serde_context)?;
         let bin_key = key.serialize()?;
         txn.txn.put(&self.database, bin_key, data, WriteFlags::NO_OVERWRITE).map_err(|err| {
             match err {
@@ -133,7 +154,7 @@ impl<'txn, Mode: TransactionKind, K: KeyTrait + Debug, V: ValueSerde + Debug> Db
             Some((key_bytes, value_bytes)) => {
                 let key =
                     K::deserialize(&mut key_bytes.as_ref()).ok_or(DbError::InnerDeserialization)?;
-                let value = V::deserialize(&mut value_bytes.as_ref())
+                let value = V::deserialize(&mut value_bytes.as_ref(), &self.serde_context)
                     .ok_or(DbError::InnerDeserialization)?;
                 Ok(Some((key, value)))
             }
@@ -147,7 +168,7 @@ impl<'txn, Mode: TransactionKind, K: KeyTrait + Debug, V: ValueSerde + Debug> Db
             Some((key_bytes, value_bytes)) => {
                 let key =
                     K::deserialize(&mut key_bytes.as_ref()).ok_or(DbError::InnerDeserialization)?;
-                let value = V::deserialize(&mut value_bytes.as_ref())
+                let value = V::deserialize(&mut value_bytes.as_ref(), &self.serde_context)
                     .ok_or(DbError::InnerDeserialization)?;
                 Ok(Some((key, value)))
             }
@@ -167,7 +188,7 @@ impl<'txn, Mode: TransactionKind, K: KeyTrait + Debug, V: ValueSerde + Debug> Db
             Some((key_bytes, value_bytes)) => {
                 let key =
                     K::deserialize(&mut key_bytes.as_ref()).ok_or(DbError::InnerDeserialization)?;
-                let value = V::deserialize(&mut value_bytes.as_ref())
+                let value = V::deserialize(&mut value_bytes.as_ref(), &self.serde_context)
                     .ok_or(DbError::InnerDeserialization)?;
                 Ok(Some((key, value)))
             }
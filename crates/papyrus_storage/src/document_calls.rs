@@ -6,9 +6,10 @@
 use std::fs::File;
 use std::io::Write;
 use std::sync::Mutex;
+use std::time::Instant;
 
 use lazy_static::lazy_static;
-pub use papyrus_common::storage_query::StorageQuery;
+pub use papyrus_common::storage_query::{DocumentedQuery, StorageQuery};
 
 // TODO(dvir): consider enabling the user to choose the file path using an environment variable.
 const QUERY_FILE_PATH: &str = "./document_calls.txt";
@@ -19,9 +20,38 @@ lazy_static! {
     );
 }
 
+/// A call to the storage that is being executed. The call is documented with the time it took when
+/// it's dropped.
+pub struct DocumentedCall {
+    query: StorageQuery,
+    start: Instant,
+}
+
+impl DocumentedCall {
+    pub(crate) fn new(query: StorageQuery) -> Self {
+        Self { query, start: Instant::now() }
+    }
+
+    // Counts an event that was read by a documented events iteration.
+    pub(crate) fn count_event(&mut self) {
+        if let StorageQuery::IterEvents { n_events, .. }
+        | StorageQuery::IterEventsByKeys { n_events, .. } = &mut self.query
+        {
+            *n_events += 1;
+        }
+    }
+}
+
+impl Drop for DocumentedCall {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        add_query(&DocumentedQuery { query: self.query.clone(), duration });
+    }
+}
+
 // Adds a query to the document_calls file.
-pub(crate) fn add_query(query: StorageQuery) {
-    let query_string = serde_json::to_string(&query).expect("Should be able to serialize query");
+fn add_query(query: &DocumentedQuery) {
+    let query_string = serde_json::to_string(query).expect("Should be able to serialize query");
     let mut file = QUERY_FILE.lock().expect("Should be able to lock the queries file");
     file.write_all(query_string.as_bytes()).expect("Should be able to write to the queries file");
     file.write_all(b"\n").expect("Should be able to write to the queries file");
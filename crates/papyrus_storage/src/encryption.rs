@@ -0,0 +1,138 @@
+//! Encryption at rest of the storage values.
+//!
+//! If an [`EncryptionKey`] is set in [`crate::StorageConfig`], the values of all the tables and of
+//! the memory mapped files are encrypted with AES-256-GCM when they are written. The keys of the
+//! tables aren't encrypted, since the tables are ordered by them.
+//!
+//! The encryption is a property of the storage that is chosen when it is created: either all of
+//! its values are encrypted or none of them. Values that were written without a key are never
+//! encrypted afterwards, so a storage that was created without a key can't be opened with one, and
+//! an encrypted storage can't be opened without its key.
+//!
+//! Each encrypted value starts with a byte of the version of its encryption, followed by a random
+//! nonce and the encrypted data.
+
+#[cfg(test)]
+#[path = "encryption_test.rs"]
+mod encryption_test;
+
+use std::fmt::{self, Debug, Formatter};
+use std::str::FromStr;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use serde::de::Error as DeserializationError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::error;
+
+/// The version byte of the values that are encrypted with AES-256-GCM.
+const AES_256_GCM_VERSION: u8 = 1;
+const NONCE_LENGTH: usize = 12;
+
+/// A 256 bit key for encrypting the storage values, serialized as a hex string.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct EncryptionKey(pub [u8; 32]);
+
+// The key is never printed.
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(<redacted>)")
+    }
+}
+
+impl Serialize for EncryptionKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(self.0)))
+    }
+}
+
+impl<'de> Deserialize<'de> for EncryptionKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(DeserializationError::custom)
+    }
+}
+
+impl FromStr for EncryptionKey {
+    type Err = String;
+
+    fn from_str(hex_str: &str) -> Result<Self, Self::Err> {
+        let mut key = [0u8; 32];
+        hex::decode_to_slice(hex_str.trim_start_matches("0x"), &mut key)
+            .map_err(|_| "The encryption key should be 32 bytes in hex.".to_owned())?;
+        Ok(Self(key))
+    }
+}
+
+/// The encryption of the values of a storage, which is shared by its readers and writer.
+#[derive(Clone, Default)]
+pub(crate) struct EncryptionContext {
+    cipher: Option<Aes256Gcm>,
+}
+
+// The cipher holds the key, so only whether the encryption is enabled is printed.
+impl Debug for EncryptionContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionContext").field("enabled", &self.cipher.is_some()).finish()
+    }
+}
+
+impl EncryptionContext {
+    /// Returns a context that encrypts the values with the given key, or that doesn't encrypt them
+    /// if the key is `None`.
+    pub(crate) fn new(key: Option<&EncryptionKey>) -> Self {
+        Self { cipher: key.map(|key| Aes256Gcm::new(&key.0.into())) }
+    }
+
+    /// Returns the data encrypted with the key of the storage, or the data itself if there is no
+    /// key.
+    pub(crate) fn encrypt_if_enabled(&self, data: Vec<u8>) -> Option<Vec<u8>> {
+        match &self.cipher {
+            Some(cipher) => encrypt(cipher, &data),
+            None => Some(data),
+        }
+    }
+
+    /// Returns whether the values of the storage are encrypted.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Returns the decrypted data of a value that was encrypted by [`Self::encrypt_if_enabled`].
+    pub(crate) fn decrypt(&self, data: &[u8]) -> Option<Vec<u8>> {
+        match &self.cipher {
+            Some(cipher) => decrypt_with_cipher(cipher, data),
+            None => {
+                error!("Unable to decrypt a storage value, the encryption key is not set.");
+                None
+            }
+        }
+    }
+}
+
+fn encrypt(cipher: &Aes256Gcm, data: &[u8]) -> Option<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let encrypted_data = cipher.encrypt(&nonce, data).ok()?;
+    let mut res = Vec::with_capacity(1 + NONCE_LENGTH + encrypted_data.len());
+    res.push(AES_256_GCM_VERSION);
+    res.extend_from_slice(&nonce);
+    res.extend_from_slice(&encrypted_data);
+    Some(res)
+}
+
+fn decrypt_with_cipher(cipher: &Aes256Gcm, data: &[u8]) -> Option<Vec<u8>> {
+    let Some((&AES_256_GCM_VERSION, data)) = data.split_first() else {
+        error!(
+            "A storage value isn't encrypted, the storage might have been created without a key."
+        );
+        return None;
+    };
+    if data.len() < NONCE_LENGTH {
+        return None;
+    }
+    let (nonce, encrypted_data) = data.split_at(NONCE_LENGTH);
+    let res = cipher.decrypt(Nonce::from_slice(nonce), encrypted_data).ok();
+    if res.is_none() {
+        error!("Unable to decrypt a storage value, the encryption key might be wrong.");
+    }
+    res
+}
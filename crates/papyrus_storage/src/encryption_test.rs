@@ -0,0 +1,92 @@
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use test_utils::get_test_state_diff;
+
+use super::{EncryptionContext, EncryptionKey, AES_256_GCM_VERSION};
+use crate::header::HeaderStorageReader;
+use crate::open_storage;
+use crate::state::StateStorageReader;
+use crate::test_utils::{
+    append_test_blocks,
+    append_test_blocks_with_states,
+    get_test_config,
+    TestBlockState,
+};
+
+const KEY: &str = "0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+#[test]
+fn encryption_key_serde() {
+    let key: EncryptionKey = serde_json::from_value(serde_json::json!(KEY)).unwrap();
+    assert_eq!(key.0[31], 0x1f);
+    assert_eq!(serde_json::to_value(&key).unwrap(), serde_json::json!(KEY));
+    assert_eq!(format!("{key:?}"), "EncryptionKey(<redacted>)");
+    assert!(serde_json::from_value::<EncryptionKey>(serde_json::json!("0x01")).is_err());
+    assert_eq!(KEY.parse::<EncryptionKey>().unwrap(), key);
+}
+
+#[test]
+fn encrypt_and_decrypt() {
+    let context = EncryptionContext::new(Some(&EncryptionKey([1u8; 32])));
+    let data = b"storage value".to_vec();
+    let encrypted = context.encrypt_if_enabled(data.clone()).unwrap();
+    assert_eq!(encrypted[0], AES_256_GCM_VERSION);
+    // A random nonce is used for each value.
+    assert_ne!(encrypted, context.encrypt_if_enabled(data.clone()).unwrap());
+    assert_eq!(context.decrypt(&encrypted).unwrap(), data);
+    // Values that weren't encrypted aren't decrypted.
+    assert!(context.decrypt(&data).is_none());
+
+    let other_context = EncryptionContext::new(Some(&EncryptionKey([2u8; 32])));
+    assert!(other_context.decrypt(&encrypted).is_none());
+
+    let disabled_context = EncryptionContext::default();
+    assert_eq!(disabled_context.encrypt_if_enabled(data.clone()).unwrap(), data);
+    assert!(disabled_context.decrypt(&encrypted).is_none());
+}
+
+#[test]
+fn encrypted_storage() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.encryption_key = Some(serde_json::from_value(serde_json::json!(KEY)).unwrap());
+    let block1 = TestBlockState::from(get_test_state_diff());
+
+    // A storage with another key is open at the same time.
+    let (mut other_config, _other_temp_dir) = get_test_config(None);
+    other_config.encryption_key = Some(EncryptionKey([1u8; 32]));
+    let (other_reader, mut other_writer) = open_storage(other_config).unwrap();
+
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    append_test_blocks(&mut writer, 0..1);
+    append_test_blocks(&mut other_writer, 0..1);
+    append_test_blocks_with_states(&mut writer, BlockNumber(1), vec![block1.clone()]);
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_block_header(BlockNumber(0)).unwrap().unwrap().block_number, BlockNumber(0));
+    assert_eq!(txn.get_block_header(BlockNumber(1)).unwrap().unwrap().block_number, BlockNumber(1));
+    assert_eq!(txn.get_state_diff(BlockNumber(1)).unwrap(), Some(block1.state_diff));
+    assert_eq!(reader.db_tables_stats().unwrap().tables_stats["headers"].entries, 2);
+    let other_txn = other_reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        other_txn.get_block_header(BlockNumber(0)).unwrap().unwrap().block_number,
+        BlockNumber(0)
+    );
+    drop((txn, other_txn));
+    drop((reader, writer));
+
+    // The storage can't be opened without its key.
+    config.encryption_key = None;
+    assert!(open_storage(config).is_err());
+}
+
+#[test]
+fn plaintext_storage_is_not_encrypted() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    let (_, mut writer) = open_storage(config.clone()).unwrap();
+    append_test_blocks(&mut writer, 0..1);
+    drop(writer);
+
+    // The values that were written without a key are never encrypted, so the storage can't be
+    // opened with a key.
+    config.encryption_key = Some(serde_json::from_value(serde_json::json!(KEY)).unwrap());
+    assert!(open_storage(config).is_err());
+}
@@ -0,0 +1,473 @@
+//! Offline integrity check of the storage.
+//!
+//! [`check_storage`] walks the tables and the memory mapped files of the storage and reports the
+//! inconsistencies it finds:
+//! * Markers that break the invariants of [`MarkerKind`].
+//! * Locations in the memory mapped files that exceed the offset of the file or that don't
+//!   deserialize.
+//! * Entries of `transaction_hash_to_idx` and `transaction_idx_to_hash` that aren't the inverse of
+//!   each other.
+//! * Headers that are missing or whose parent hash isn't the hash of the previous block.
+//!
+//! Each inconsistency is attributed to the first block it affects, so all the blocks before the
+//! earliest of them are consistent. The storage can optionally be truncated back to these blocks,
+//! after which the node resyncs the truncated blocks. The truncation fails if the data of a
+//! truncated block exists but can't be read. The blocks are truncated in batches of
+//! [`TRUNCATE_BATCH_SIZE`] blocks, one transaction each, and the storage is consistent after each
+//! batch. An interrupted truncation is resumed by running the check again.
+//!
+//! The storage is opened read-only, so the check can run while the node is running, unless it's
+//! truncated. In that case the storage is opened exclusively, and the check fails if the storage is
+//! opened by another process.
+
+#[cfg(test)]
+#[path = "fsck_test.rs"]
+mod fsck_test;
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ClassHash;
+use tracing::{debug, info, instrument};
+
+use crate::base_layer::BaseLayerStorageReader;
+use crate::body::BodyStorageReader;
+use crate::class::ClassStorageReader;
+use crate::compiled_class::CasmStorageReader;
+use crate::db::serialization::{Key, ValueSerde};
+use crate::db::table_types::{SimpleTable, Table};
+use crate::db::{DbIter, TableIdentifier, RO, RW};
+use crate::header::HeaderStorageReader;
+use crate::mmap_file::LocationInFile;
+use crate::pruning::PruningStorageReader;
+use crate::read_only::open_storage_read_only;
+use crate::revert::revert_block;
+use crate::state::StateStorageReader;
+use crate::{
+    open_storage_exclusively,
+    MarkerKind,
+    OffsetKind,
+    StorageConfig,
+    StorageError,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    StorageWriter,
+};
+
+/// The number of blocks that are truncated in each transaction.
+pub const TRUNCATE_BATCH_SIZE: u64 = 100;
+
+// The markers that are lowered when the storage is truncated.
+const TRUNCATED_MARKERS: [MarkerKind; 7] = [
+    MarkerKind::Header,
+    MarkerKind::Body,
+    MarkerKind::Event,
+    MarkerKind::State,
+    MarkerKind::Class,
+    MarkerKind::CompiledClass,
+    MarkerKind::BaseLayerBlock,
+];
+
+/// The kinds of inconsistencies [`check_storage`] looks for.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum InconsistencyKind {
+    /// A marker is greater than a marker it must not exceed.
+    MarkersOrder,
+    /// A location in a memory mapped file is out of the written part of the file or doesn't
+    /// deserialize.
+    InvalidLocation,
+    /// The transaction hash and transaction index tables aren't the inverse of each other.
+    TransactionIndexMismatch,
+    /// A header is missing or its parent hash isn't the hash of the previous block.
+    BrokenHeaderChain,
+}
+
+/// An inconsistency that was found in the storage.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Inconsistency {
+    /// The kind of the inconsistency.
+    pub kind: InconsistencyKind,
+    /// The first block affected by the inconsistency, if it is known.
+    pub block_number: Option<BlockNumber>,
+    /// A description of the inconsistency.
+    pub msg: String,
+}
+
+/// The result of an integrity check of the storage.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct FsckReport {
+    /// The markers of the storage when it was checked.
+    pub markers: BTreeMap<String, BlockNumber>,
+    /// The inconsistencies that were found.
+    pub inconsistencies: Vec<Inconsistency>,
+    /// The first block affected by an inconsistency. The blocks before it are consistent.
+    pub first_inconsistent_block: Option<BlockNumber>,
+    /// The block the storage was truncated to, if it was truncated.
+    pub truncated_to: Option<BlockNumber>,
+}
+
+/// Checks the integrity of the storage and returns a report of the inconsistencies found. If
+/// `truncate` is set, the blocks from the first inconsistent block onwards are reverted and the
+/// markers are lowered to it.
+///
+/// # Errors
+/// Returns [`DbError::EnvironmentInUse`](crate::db::DbError::EnvironmentInUse) if `truncate` is set
+/// and the storage is opened by another process.
+#[instrument(skip(storage_config), level = "debug", err)]
+pub fn check_storage(storage_config: StorageConfig, truncate: bool) -> StorageResult<FsckReport> {
+    let (reader, writer) = if truncate {
+        let (reader, writer) = open_storage_exclusively(storage_config)?;
+        (reader, Some(writer))
+    } else {
+        (open_storage_read_only(storage_config)?, None)
+    };
+    let txn = reader.begin_ro_txn()?;
+    let markers = get_markers(&txn)?;
+
+    let mut inconsistencies = check_markers_order(&markers);
+    check_locations(&txn, &mut inconsistencies)?;
+    if txn.scope != StorageScope::StateOnly {
+        check_transaction_indices(&txn, &mut inconsistencies)?;
+    }
+    check_header_chain(&txn, markers[&MarkerKind::Header], &mut inconsistencies)?;
+    drop(txn);
+
+    let first_inconsistent_block =
+        inconsistencies.iter().filter_map(|inconsistency| inconsistency.block_number).min();
+    let truncated_to = match (first_inconsistent_block, writer) {
+        (Some(block_number), Some(mut writer)) => {
+            truncate_storage(&mut writer, &markers, block_number, TRUNCATE_BATCH_SIZE)?;
+            Some(block_number)
+        }
+        _ => None,
+    };
+
+    let report = FsckReport {
+        markers: markers
+            .into_iter()
+            .map(|(marker_kind, block_number)| (format!("{marker_kind:?}"), block_number))
+            .collect(),
+        inconsistencies,
+        first_inconsistent_block,
+        truncated_to,
+    };
+    info!("Checked the storage: {report:?}.");
+    Ok(report)
+}
+
+fn get_markers(txn: &StorageTxn<'_, RO>) -> StorageResult<BTreeMap<MarkerKind, BlockNumber>> {
+    Ok(BTreeMap::from([
+        (MarkerKind::Header, txn.get_header_marker()?),
+        (MarkerKind::Body, txn.get_body_marker()?),
+        (MarkerKind::State, txn.get_state_marker()?),
+        (MarkerKind::Class, txn.get_class_marker()?),
+        (MarkerKind::CompiledClass, txn.get_compiled_class_marker()?),
+        (MarkerKind::BaseLayerBlock, txn.get_base_layer_block_marker()?),
+        (MarkerKind::Pruned, txn.get_pruning_marker()?),
+    ]))
+}
+
+// Reports every pair of markers where the first must not exceed the second but does. The blocks
+// from the second marker onwards are affected.
+fn check_markers_order(markers: &BTreeMap<MarkerKind, BlockNumber>) -> Vec<Inconsistency> {
+    [
+        (MarkerKind::CompiledClass, MarkerKind::Class),
+        (MarkerKind::Class, MarkerKind::State),
+        (MarkerKind::State, MarkerKind::Header),
+        (MarkerKind::Body, MarkerKind::Header),
+        (MarkerKind::BaseLayerBlock, MarkerKind::Header),
+        (MarkerKind::Pruned, MarkerKind::CompiledClass),
+        (MarkerKind::Pruned, MarkerKind::Body),
+    ]
+    .into_iter()
+    .filter(|(lower, upper)| markers[lower] > markers[upper])
+    .map(|(lower, upper)| Inconsistency {
+        kind: InconsistencyKind::MarkersOrder,
+        block_number: Some(markers[&upper]),
+        msg: format!(
+            "The {lower:?} marker {} is greater than the {upper:?} marker {}.",
+            markers[&lower], markers[&upper]
+        ),
+    })
+    .collect()
+}
+
+fn check_locations(
+    txn: &StorageTxn<'_, RO>,
+    inconsistencies: &mut Vec<Inconsistency>,
+) -> StorageResult<()> {
+    let declared_classes_block_table = txn.open_table(&txn.tables.declared_classes_block)?;
+    let class_block = |class_hash: &ClassHash| -> StorageResult<Option<BlockNumber>> {
+        Ok(declared_classes_block_table.get(&txn.txn, class_hash)?)
+    };
+
+    check_table_locations(
+        txn,
+        &txn.tables.state_diffs,
+        OffsetKind::ThinStateDiff,
+        |location| *location,
+        |location| txn.file_handlers.get_thin_state_diff_unchecked(location).is_ok(),
+        |block_number, _| Ok(Some(*block_number)),
+        inconsistencies,
+    )?;
+    check_table_locations(
+        txn,
+        &txn.tables.declared_classes,
+        OffsetKind::ContractClass,
+        |location| *location,
+        |location| txn.file_handlers.get_contract_class_unchecked(location).is_ok(),
+        |class_hash, _| class_block(class_hash),
+        inconsistencies,
+    )?;
+    check_table_locations(
+        txn,
+        &txn.tables.casms,
+        OffsetKind::Casm,
+        |location| *location,
+        |location| txn.file_handlers.get_casm_unchecked(location).is_ok(),
+        |class_hash, _| class_block(class_hash),
+        inconsistencies,
+    )?;
+    check_table_locations(
+        txn,
+        &txn.tables.deprecated_declared_classes,
+        OffsetKind::DeprecatedContractClass,
+        |indexed_class| indexed_class.location_in_file,
+        |location| txn.file_handlers.get_deprecated_contract_class_unchecked(location).is_ok(),
+        |_, indexed_class| Ok(Some(indexed_class.block_number)),
+        inconsistencies,
+    )
+}
+
+// Reports the entries of the table whose location exceeds the offset of the file or doesn't
+// deserialize.
+fn check_table_locations<K: Key + Debug, V: ValueSerde + Debug>(
+    txn: &StorageTxn<'_, RO>,
+    table_id: &TableIdentifier<K, V, SimpleTable>,
+    offset_kind: OffsetKind,
+    get_location: impl Fn(&V::Value) -> LocationInFile,
+    deserializes: impl Fn(LocationInFile) -> bool,
+    get_block_number: impl Fn(&K, &V::Value) -> StorageResult<Option<BlockNumber>>,
+    inconsistencies: &mut Vec<Inconsistency>,
+) -> StorageResult<()> {
+    debug!("Checking the locations in table {}.", table_id.name);
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets)?;
+    let offset = file_offsets_table.get(&txn.txn, &offset_kind)?.unwrap_or_default();
+    let table = txn.open_table(table_id)?;
+    let mut cursor = table.cursor(&txn.txn)?;
+    for entry in DbIter::new(&mut cursor) {
+        let (key, value) = entry?;
+        let location = get_location(&value);
+        let problem = if location.next_offset() > offset {
+            format!("exceeds the {offset_kind:?} file offset {offset}")
+        } else if !deserializes(location) {
+            "doesn't deserialize".to_owned()
+        } else {
+            continue;
+        };
+        inconsistencies.push(Inconsistency {
+            kind: InconsistencyKind::InvalidLocation,
+            block_number: get_block_number(&key, &value)?,
+            msg: format!("The location {location:?} of {key:?} in {} {problem}.", table_id.name),
+        });
+    }
+    Ok(())
+}
+
+fn check_transaction_indices(
+    txn: &StorageTxn<'_, RO>,
+    inconsistencies: &mut Vec<Inconsistency>,
+) -> StorageResult<()> {
+    debug!("Checking the transaction indices.");
+    let transaction_hash_to_idx_table = txn.open_table(&txn.tables.transaction_hash_to_idx)?;
+    let transaction_idx_to_hash_table = txn.open_table(&txn.tables.transaction_idx_to_hash)?;
+
+    let mut cursor = transaction_hash_to_idx_table.cursor(&txn.txn)?;
+    for entry in DbIter::new(&mut cursor) {
+        let (tx_hash, tx_index) = entry?;
+        let inverse_tx_hash = transaction_idx_to_hash_table.get(&txn.txn, &tx_index)?;
+        if inverse_tx_hash != Some(tx_hash) {
+            inconsistencies.push(Inconsistency {
+                kind: InconsistencyKind::TransactionIndexMismatch,
+                block_number: Some(tx_index.0),
+                msg: format!(
+                    "The transaction hash {tx_hash} is mapped to {tx_index:?}, which is mapped to \
+                     {inverse_tx_hash:?}."
+                ),
+            });
+        }
+    }
+
+    let mut cursor = transaction_idx_to_hash_table.cursor(&txn.txn)?;
+    for entry in DbIter::new(&mut cursor) {
+        let (tx_index, tx_hash) = entry?;
+        let inverse_tx_index = transaction_hash_to_idx_table.get(&txn.txn, &tx_hash)?;
+        if inverse_tx_index != Some(tx_index) {
+            inconsistencies.push(Inconsistency {
+                kind: InconsistencyKind::TransactionIndexMismatch,
+                block_number: Some(tx_index.0),
+                msg: format!(
+                    "The transaction index {tx_index:?} is mapped to {tx_hash}, which is mapped \
+                     to {inverse_tx_index:?}."
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_header_chain(
+    txn: &StorageTxn<'_, RO>,
+    header_marker: BlockNumber,
+    inconsistencies: &mut Vec<Inconsistency>,
+) -> StorageResult<()> {
+    debug!("Checking the header chain.");
+    let mut parent_hash = None;
+    for block_number in (0..header_marker.0).map(BlockNumber) {
+        let Some(header) = txn.get_block_header(block_number)? else {
+            inconsistencies.push(Inconsistency {
+                kind: InconsistencyKind::BrokenHeaderChain,
+                block_number: Some(block_number),
+                msg: format!("The header of block {block_number} is missing."),
+            });
+            parent_hash = None;
+            continue;
+        };
+        if let Some(parent_hash) = parent_hash.filter(|hash| *hash != header.parent_hash) {
+            inconsistencies.push(Inconsistency {
+                kind: InconsistencyKind::BrokenHeaderChain,
+                block_number: Some(block_number),
+                msg: format!(
+                    "The parent hash {} of block {block_number} isn't the hash {parent_hash} of \
+                     the previous block.",
+                    header.parent_hash
+                ),
+            });
+        }
+        parent_hash = Some(header.block_hash);
+    }
+    Ok(())
+}
+
+// Reverts the blocks from the given block onwards, from the last block backwards, in
+// transactions of `batch_size` blocks, like the revert of the storage. Blocks whose data is missing
+// are truncated by lowering their markers. The markers are lowered before each commit, so if the
+// truncation is interrupted, the remaining inconsistent blocks are found and truncated by running
+// the check again.
+fn truncate_storage(
+    writer: &mut StorageWriter,
+    markers: &BTreeMap<MarkerKind, BlockNumber>,
+    truncate_to: BlockNumber,
+    batch_size: u64,
+) -> StorageResult<()> {
+    let pruning_marker = markers[&MarkerKind::Pruned];
+    if truncate_to < pruning_marker {
+        return Err(StorageError::DBInconsistency {
+            msg: format!(
+                "Can't truncate the storage to block {truncate_to}, the blocks before \
+                 {pruning_marker} were pruned."
+            ),
+        });
+    }
+    info!("Truncating the storage to block {truncate_to}.");
+
+    let last_marker = markers.values().max().copied().unwrap_or_default();
+    let mut txn = writer.begin_rw_txn()?;
+    let mut reverted_blocks = 0;
+    for block_number in (truncate_to.0..last_marker.0).rev().map(BlockNumber) {
+        // Markers that exceed the block are lowered to it, so the block can be reverted.
+        lower_markers(&txn, block_number.unchecked_next())?;
+        lower_markers_of_missing_data(&txn, block_number)?;
+        txn = revert_block(txn, block_number)?;
+        reverted_blocks += 1;
+        if reverted_blocks % batch_size == 0 {
+            debug!("Committing the truncation of the blocks from block {block_number}.");
+            lower_markers(&txn, block_number)?;
+            txn.commit()?;
+            txn = writer.begin_rw_txn()?;
+        }
+    }
+    lower_markers(&txn, truncate_to)?;
+    if txn.scope != StorageScope::StateOnly {
+        delete_transaction_indices(&txn, truncate_to)?;
+    }
+    txn.commit()
+}
+
+// Lowers the markers that exceed the given block to it. The pruning marker is never lowered.
+fn lower_markers(txn: &StorageTxn<'_, RW>, block_number: BlockNumber) -> StorageResult<()> {
+    let markers_table = txn.open_table(&txn.tables.markers)?;
+    for marker_kind in TRUNCATED_MARKERS {
+        if markers_table.get(&txn.txn, &marker_kind)?.unwrap_or_default() > block_number {
+            markers_table.upsert(&txn.txn, &marker_kind, &block_number)?;
+        }
+    }
+    Ok(())
+}
+
+// Lowers the markers of the data of the block that is missing to the block, so the revert of the
+// block skips this data.
+fn lower_markers_of_missing_data(
+    txn: &StorageTxn<'_, RW>,
+    block_number: BlockNumber,
+) -> StorageResult<()> {
+    let markers_table = txn.open_table(&txn.tables.markers)?;
+    let missing_data = [
+        (MarkerKind::Header, txn.get_block_header(block_number)?.is_none()),
+        // There is no body data under the StateOnly scope.
+        (MarkerKind::Body, txn.scope == StorageScope::StateOnly || !has_body(txn, block_number)?),
+        (MarkerKind::State, txn.get_state_diff(block_number)?.is_none()),
+    ];
+    for (marker_kind, is_missing) in missing_data {
+        if is_missing
+            && markers_table.get(&txn.txn, &marker_kind)?.unwrap_or_default() > block_number
+        {
+            markers_table.upsert(&txn.txn, &marker_kind, &block_number)?;
+        }
+    }
+    Ok(())
+}
+
+fn has_body(txn: &StorageTxn<'_, RW>, block_number: BlockNumber) -> StorageResult<bool> {
+    Ok(txn.get_block_transactions(block_number)?.is_some()
+        && txn.get_block_transaction_outputs(block_number)?.is_some()
+        && txn.get_block_transaction_hashes(block_number)?.is_some())
+}
+
+// Deletes the entries of the transaction hash and transaction index tables that were left for the
+// truncated blocks because the tables weren't the inverse of each other.
+fn delete_transaction_indices(
+    txn: &StorageTxn<'_, RW>,
+    truncate_to: BlockNumber,
+) -> StorageResult<()> {
+    let transaction_hash_to_idx_table = txn.open_table(&txn.tables.transaction_hash_to_idx)?;
+    let transaction_idx_to_hash_table = txn.open_table(&txn.tables.transaction_idx_to_hash)?;
+
+    let mut cursor = transaction_hash_to_idx_table.cursor(&txn.txn)?;
+    let truncated_tx_hashes = DbIter::new(&mut cursor)
+        .filter_map(|entry| match entry {
+            Ok((_, tx_index)) if tx_index.0 < truncate_to => None,
+            Ok((tx_hash, _)) => Some(Ok(tx_hash)),
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut cursor = transaction_idx_to_hash_table.cursor(&txn.txn)?;
+    let truncated_tx_indices = DbIter::new(&mut cursor)
+        .filter_map(|entry| match entry {
+            Ok((tx_index, _)) if tx_index.0 < truncate_to => None,
+            Ok((tx_index, _)) => Some(Ok(tx_index)),
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for tx_hash in truncated_tx_hashes {
+        transaction_hash_to_idx_table.delete(&txn.txn, &tx_hash)?;
+    }
+    for tx_index in truncated_tx_indices {
+        transaction_idx_to_hash_table.delete(&txn.txn, &tx_index)?;
+    }
+    Ok(())
+}
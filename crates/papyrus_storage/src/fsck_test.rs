@@ -0,0 +1,167 @@
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{TransactionHash, TransactionOffsetInBlock};
+
+use crate::body::{BodyStorageReader, TransactionIndex};
+use crate::db::table_types::Table;
+use crate::db::DbError;
+use crate::fsck::{check_storage, get_markers, truncate_storage, InconsistencyKind};
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::test_utils::{
+    append_test_blocks,
+    get_test_block_hash,
+    get_test_storage_with_config_by_scope,
+};
+use crate::{MarkerKind, OffsetKind, StorageConfig, StorageError, StorageScope};
+
+const N_BLOCKS: u64 = 3;
+
+fn get_test_storage_with_blocks() -> (StorageConfig, tempfile::TempDir) {
+    let ((_, mut writer), config, temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+    (config, temp_dir)
+}
+
+#[test]
+fn check_consistent_storage() {
+    let (config, _temp_dir) = get_test_storage_with_blocks();
+
+    let report = check_storage(config, true).unwrap();
+    assert_eq!(report.inconsistencies, vec![]);
+    assert_eq!(report.first_inconsistent_block, None);
+    assert_eq!(report.truncated_to, None);
+    for marker_kind in ["Header", "Body", "State", "Class", "CompiledClass"] {
+        assert_eq!(report.markers[marker_kind], BlockNumber(N_BLOCKS), "{marker_kind}");
+    }
+}
+
+#[test]
+fn check_opened_storage() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+
+    // The storage is checked read-only while it's opened, but can't be truncated.
+    let report = check_storage(config.clone(), false).unwrap();
+    assert_eq!(report.inconsistencies, vec![]);
+    assert_matches!(
+        check_storage(config, true),
+        Err(StorageError::InnerError(DbError::EnvironmentInUse(_)))
+    );
+    drop((reader, writer));
+}
+
+#[test]
+fn check_and_truncate_inconsistent_storage() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+
+    // Break the header chain at block 2.
+    let header = reader.begin_ro_txn().unwrap().get_block_header(BlockNumber(2)).unwrap().unwrap();
+    let (txn, _, _) = writer.begin_rw_txn().unwrap().revert_header(BlockNumber(2)).unwrap();
+    let parent_hash = get_test_block_hash(BlockNumber(N_BLOCKS));
+    txn.append_header(BlockNumber(2), &BlockHeader { parent_hash, ..header })
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Map a transaction index of block 1 to an unknown transaction hash and advance the state
+    // marker beyond the header marker.
+    let txn = writer.begin_rw_txn().unwrap();
+    let transaction_idx_to_hash_table =
+        txn.open_table(&txn.tables.transaction_idx_to_hash).unwrap();
+    transaction_idx_to_hash_table
+        .upsert(
+            &txn.txn,
+            &TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(0)),
+            &TransactionHash(StarkFelt::from(u128::MAX)),
+        )
+        .unwrap();
+    let markers_table = txn.open_table(&txn.tables.markers).unwrap();
+    markers_table.upsert(&txn.txn, &MarkerKind::State, &BlockNumber(N_BLOCKS + 1)).unwrap();
+    txn.commit().unwrap();
+    drop((reader, writer));
+
+    let report = check_storage(config.clone(), false).unwrap();
+    let mut inconsistencies = report
+        .inconsistencies
+        .iter()
+        .map(|inconsistency| (inconsistency.kind, inconsistency.block_number))
+        .collect::<Vec<_>>();
+    inconsistencies.sort_by_key(|(_, block_number)| *block_number);
+    assert_eq!(
+        inconsistencies,
+        vec![
+            (InconsistencyKind::TransactionIndexMismatch, Some(BlockNumber(1))),
+            (InconsistencyKind::TransactionIndexMismatch, Some(BlockNumber(1))),
+            (InconsistencyKind::BrokenHeaderChain, Some(BlockNumber(2))),
+            (InconsistencyKind::MarkersOrder, Some(BlockNumber(N_BLOCKS))),
+        ]
+    );
+    assert_eq!(report.first_inconsistent_block, Some(BlockNumber(1)));
+    assert_eq!(report.truncated_to, None);
+
+    let report = check_storage(config.clone(), true).unwrap();
+    assert_eq!(report.truncated_to, Some(BlockNumber(1)));
+
+    let report = check_storage(config, false).unwrap();
+    assert_eq!(report.inconsistencies, vec![]);
+    for marker_kind in ["Header", "Body", "State", "Class", "CompiledClass"] {
+        assert_eq!(report.markers[marker_kind], BlockNumber(1), "{marker_kind}");
+    }
+}
+
+#[test]
+fn truncate_storage_in_batches() {
+    let ((reader, mut writer), _config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+
+    let markers = get_markers(&reader.begin_ro_txn().unwrap()).unwrap();
+    truncate_storage(&mut writer, &markers, BlockNumber(1), 1).unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(get_markers(&txn).unwrap()[&MarkerKind::Header], BlockNumber(1));
+    let markers_table = txn.open_table(&txn.tables.markers).unwrap();
+    assert_eq!(markers_table.get(&txn.txn, &MarkerKind::Event).unwrap(), Some(BlockNumber(1)));
+    assert!(txn.get_block_header(BlockNumber(0)).unwrap().is_some());
+    for block_number in 1..N_BLOCKS {
+        assert!(txn.get_block_header(BlockNumber(block_number)).unwrap().is_none());
+        assert!(txn.get_block_transactions(BlockNumber(block_number)).unwrap().is_none());
+    }
+}
+
+#[test]
+fn check_invalid_locations() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+
+    // Truncate the state diffs file to the state diff of block 0.
+    let txn = writer.begin_rw_txn().unwrap();
+    let state_diffs_table = txn.open_table(&txn.tables.state_diffs).unwrap();
+    let offset = state_diffs_table.get(&txn.txn, &BlockNumber(0)).unwrap().unwrap().next_offset();
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets).unwrap();
+    file_offsets_table.upsert(&txn.txn, &OffsetKind::ThinStateDiff, &offset).unwrap();
+    txn.commit().unwrap();
+    drop((reader, writer));
+
+    let report = check_storage(config, false).unwrap();
+    let inconsistencies = report
+        .inconsistencies
+        .iter()
+        .map(|inconsistency| (inconsistency.kind, inconsistency.block_number))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        inconsistencies,
+        (1..N_BLOCKS)
+            .map(|block_number| {
+                (InconsistencyKind::InvalidLocation, Some(BlockNumber(block_number)))
+            })
+            .collect::<Vec<_>>()
+    );
+}
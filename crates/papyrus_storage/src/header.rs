@@ -62,6 +62,8 @@ use tracing::debug;
 use crate::db::serialization::NoVersionValueWrapper;
 use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+#[cfg(feature = "document_calls")]
+use crate::document_calls::{DocumentedCall, StorageQuery};
 use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageTxn};
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
@@ -156,6 +158,9 @@ impl<'env, Mode: TransactionKind> HeaderStorageReader for StorageTxn<'env, Mode>
     }
 
     fn get_block_header(&self, block_number: BlockNumber) -> StorageResult<Option<BlockHeader>> {
+        #[cfg(feature = "document_calls")]
+        let _documented_call = DocumentedCall::new(StorageQuery::GetBlockHeader(block_number));
+
         let headers_table = self.open_table(&self.tables.headers)?;
         let Some(block_header) = headers_table.get(&self.txn, &block_number)? else {
             return Ok(None);
@@ -53,6 +53,11 @@
 pub mod base_layer;
 pub mod body;
 pub mod class;
+#[cfg(feature = "clap")]
+pub mod cli;
+#[cfg(feature = "columnar_export")]
+pub mod columnar_export;
+pub mod compaction;
 pub mod compiled_class;
 #[cfg(feature = "document_calls")]
 pub mod document_calls;
@@ -61,11 +66,20 @@ pub mod utils;
 #[doc(hidden)]
 pub mod compression_utils;
 pub mod db;
+pub mod encryption;
+pub mod fsck;
 pub mod header;
+mod migration;
 pub mod mmap_file;
+pub mod pruning;
+pub mod read_only;
+pub mod revert;
 mod serialization;
+pub mod snapshot;
 pub mod state;
+pub mod table_dump;
 mod version;
+mod write_batch;
 
 mod deprecated;
 
@@ -77,7 +91,10 @@ pub mod test_utils;
 
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use body::events::EventIndex;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
@@ -85,6 +102,7 @@ use db::db_stats::{DbTableStats, DbWholeStats};
 use db::serialization::{
     Key,
     NoVersionValueWrapper,
+    SerdeContext,
     ValueSerde,
     VersionWrapper,
     VersionZeroWrapper,
@@ -99,23 +117,31 @@ use mmap_file::{
     Reader,
     Writer,
 };
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_proc_macros::latency_histogram;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockNumber, BlockSignature, StarknetVersion};
 use starknet_api::core::{ClassHash, ContractAddress, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::hash::StarkFelt;
+use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::{ContractClass, StateNumber, StorageKey, ThinStateDiff};
-use starknet_api::transaction::{EventContent, Transaction, TransactionHash};
-use tracing::{debug, warn};
+use starknet_api::transaction::{EventContent, EventKey, Transaction, TransactionHash};
+use tracing::{debug, error, warn};
 use validator::Validate;
 use version::{StorageVersionError, Version};
 
 use crate::body::events::ThinTransactionOutput;
 use crate::body::TransactionIndex;
-use crate::db::table_types::SimpleTable;
+use crate::compaction::finish_compaction;
+use crate::compression_utils::{CompressionCodec, CompressionContext};
+use crate::db::table_types::{DbCursorTrait, SimpleTable};
 use crate::db::{
     open_env,
     DbConfig,
@@ -129,11 +155,20 @@ use crate::db::{
     RO,
     RW,
 };
+use crate::encryption::{EncryptionContext, EncryptionKey};
 use crate::header::StorageBlockHeader;
+use crate::migration::{has_pending_migrations, migrate_storage, MigrationProgress};
 use crate::mmap_file::MMapFileStats;
+use crate::pruning::{PendingReclaims, PruningStorageReader};
+pub use crate::read_only::open_storage_read_only;
+use crate::read_only::ReadOnlyState;
+use crate::snapshot::import_snapshot;
+use crate::state::cache::StateCache;
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state::trie::{StateRoots, TrieNode};
 pub use crate::utils::update_storage_metrics;
 use crate::version::{VersionStorageReader, VersionStorageWriter};
+use crate::write_batch::WriteBatch;
 
 // TODO(dvir): add detailed explanation with examples about the storage versions, especially the
 // major and minor differences.
@@ -141,43 +176,97 @@ use crate::version::{VersionStorageReader, VersionStorageWriter};
 /// The current version of the storage state code.
 /// Major change requires a re-sync, minor change means a versioned value changed an re-sync is not
 /// required.
-pub const STORAGE_VERSION_STATE: Version = Version { major: 1, minor: 1 };
+pub const STORAGE_VERSION_STATE: Version = Version { major: 1, minor: 2 };
 /// The current version of the storage blocks code.
 /// Major change requires a re-sync, minor change means a versioned value changed an re-sync is not
 /// required.
 /// This version is only checked for storages that store transactions (StorageScope::FullArchive).
-pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 2, minor: 0 };
+pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 2, minor: 2 };
 
 /// Opens a storage and returns a [`StorageReader`] and a [`StorageWriter`].
+/// Runs the pending migrations of the storage before returning, which may take a long time. Only
+/// the node should migrate the storage, other executables should use
+/// [`open_storage_without_migrations`].
 pub fn open_storage(
     storage_config: StorageConfig,
 ) -> StorageResult<(StorageReader, StorageWriter)> {
-    let (db_reader, mut db_writer) = open_env(&storage_config.db_config)?;
+    open_storage_with_options(storage_config, false, true)
+}
+
+/// Opens a storage like [`open_storage`], but without running its pending migrations.
+///
+/// # Errors
+/// Returns [`StorageError::PendingMigrations`] if the storage has pending migrations.
+pub fn open_storage_without_migrations(
+    storage_config: StorageConfig,
+) -> StorageResult<(StorageReader, StorageWriter)> {
+    open_storage_with_options(storage_config, false, false)
+}
+
+// Opens a storage like [`open_storage_without_migrations`], but fails if the storage is opened by
+// another process, and prevents other processes from opening it until the returned reader and
+// writer are dropped.
+pub(crate) fn open_storage_exclusively(
+    storage_config: StorageConfig,
+) -> StorageResult<(StorageReader, StorageWriter)> {
+    open_storage_with_options(storage_config, true, false)
+}
+
+fn open_storage_with_options(
+    storage_config: StorageConfig,
+    exclusive: bool,
+    run_migrations: bool,
+) -> StorageResult<(StorageReader, StorageWriter)> {
+    let serde_context = SerdeContext {
+        compression: Arc::new(CompressionContext::new(storage_config.compression_codec)),
+        encryption: Arc::new(EncryptionContext::new(storage_config.encryption_key.as_ref())),
+    };
+    let (db_reader, mut db_writer) = open_env(
+        &storage_config.db_config,
+        storage_config.write_batch_size == 1,
+        exclusive,
+        serde_context,
+    )?;
     let tables = Arc::new(Tables {
         block_hash_to_number: db_writer.create_simple_table("block_hash_to_number")?,
         block_signatures: db_writer.create_simple_table("block_signatures")?,
         casms: db_writer.create_simple_table("casms")?,
+        compacted_files: db_writer.create_simple_table("compacted_files")?,
         contract_storage: db_writer.create_simple_table("contract_storage")?,
+        contract_storage_roots: db_writer.create_simple_table("contract_storage_roots")?,
         declared_classes: db_writer.create_simple_table("declared_classes")?,
         declared_classes_block: db_writer.create_simple_table("declared_classes_block")?,
         deprecated_declared_classes: db_writer
             .create_simple_table("deprecated_declared_classes")?,
         deployed_contracts: db_writer.create_simple_table("deployed_contracts")?,
+        event_keys: db_writer.create_simple_table("event_keys")?,
         events: db_writer.create_simple_table("events")?,
         headers: db_writer.create_simple_table("headers")?,
         markers: db_writer.create_simple_table("markers")?,
         nonces: db_writer.create_simple_table("nonces")?,
         file_offsets: db_writer.create_simple_table("file_offsets")?,
+        sender_nonce_to_transaction_idx: db_writer
+            .create_simple_table("sender_nonce_to_transaction_idx")?,
         state_diffs: db_writer.create_simple_table("state_diffs")?,
+        state_roots: db_writer.create_simple_table("state_roots")?,
         transaction_hash_to_idx: db_writer.create_simple_table("transaction_hash_to_idx")?,
         transaction_idx_to_hash: db_writer.create_simple_table("transaction_idx_to_hash")?,
         transaction_outputs: db_writer.create_simple_table("transaction_outputs")?,
         transactions: db_writer.create_simple_table("transactions")?,
+        trie_nodes: db_writer.create_simple_table("trie_nodes")?,
+        trie_nodes_by_block: db_writer.create_simple_table("trie_nodes_by_block")?,
+        zstd_dictionaries: db_writer.create_simple_table("zstd_dictionaries")?,
 
         // Version tables
+        migration_progress: db_writer.create_simple_table("migration_progress")?,
         starknet_version: db_writer.create_simple_table("starknet_version")?,
         storage_version: db_writer.create_simple_table("storage_version")?,
     });
+    if let Some(snapshot_path) = &storage_config.snapshot_to_import {
+        import_snapshot(snapshot_path, &storage_config, &db_reader, &mut db_writer)?;
+    }
+    register_zstd_dictionaries(&db_reader.begin_ro_txn()?, &tables.zstd_dictionaries, 0)?;
+    finish_compaction(&mut db_writer, &tables.compacted_files, storage_config.db_config.path())?;
     let (file_writers, file_readers) = open_storage_files(
         &storage_config.db_config,
         storage_config.mmap_file_config,
@@ -185,15 +274,34 @@ pub fn open_storage(
         &tables.file_offsets,
     )?;
 
+    let state_cache = StateCache::new(storage_config.state_cache_size).map(Arc::new);
+
     let reader = StorageReader {
         db_reader,
         tables: tables.clone(),
         scope: storage_config.scope,
+        retained_blocks: storage_config.retained_blocks,
         file_readers,
+        read_only_state: None,
+        state_cache: state_cache.clone(),
+    };
+    let write_batch = (storage_config.write_batch_size > 1).then(|| {
+        Arc::new(Mutex::new(WriteBatch::new(
+            storage_config.write_batch_size,
+            storage_config.write_batch_max_duration,
+        )))
+    });
+    let writer = StorageWriter {
+        db_writer,
+        tables,
+        scope: storage_config.scope,
+        file_writers,
+        state_cache,
+        write_batch,
+        pending_reclaims: Arc::new(Mutex::new(PendingReclaims::default())),
     };
-    let writer = StorageWriter { db_writer, tables, scope: storage_config.scope, file_writers };
 
-    let writer = set_version_if_needed(reader.clone(), writer)?;
+    let writer = set_version_if_needed(reader.clone(), writer, run_migrations)?;
     verify_storage_version(reader.clone())?;
     Ok((reader, writer))
 }
@@ -201,15 +309,18 @@ pub fn open_storage(
 // In case storage version does not exist, set it to the crate version.
 // Expected to happen once - when the node is launched for the first time.
 // If the storage scope has changed, update accordingly.
+// The pending migrations are run only if `run_migrations` is true, otherwise an error is returned
+// if there are any.
 fn set_version_if_needed(
     reader: StorageReader,
     mut writer: StorageWriter,
+    run_migrations: bool,
 ) -> StorageResult<StorageWriter> {
-    let Some(existing_storage_version) = get_storage_version(reader)? else {
+    let Some(existing_storage_version) = get_storage_version(reader.clone())? else {
         // Initialize the storage version.
         writer.begin_rw_txn()?.set_state_version(&STORAGE_VERSION_STATE)?.commit()?;
-        // If in full-archive mode, also set the block version.
-        if writer.scope == StorageScope::FullArchive {
+        // If the blocks are stored, also set the block version.
+        if writer.scope != StorageScope::StateOnly {
             writer.begin_rw_txn()?.set_blocks_version(&STORAGE_VERSION_BLOCKS)?.commit()?;
         }
         debug!(
@@ -231,16 +342,32 @@ fn set_version_if_needed(
                 debug!("Changing the storage scope from FullArchive to StateOnly.");
                 writer.begin_rw_txn()?.delete_blocks_version()?.commit()?;
             }
+            // The history of a pruned storage cannot be restored.
+            if writer.scope == StorageScope::FullArchive
+                && reader.begin_ro_txn()?.get_pruning_marker()? > BlockNumber(0)
+            {
+                return Err(StorageError::StorageVersionInconsistency(
+                    StorageVersionError::InconsistentStorageScope,
+                ));
+            }
         }
         StorageVersion::StateOnly(StateOnlyVersion { state_version: _ }) => {
-            // The storage cannot change from state-only to full-archive mode.
-            if writer.scope == StorageScope::FullArchive {
+            // The storage cannot change from state-only to a scope that stores the blocks.
+            if writer.scope != StorageScope::StateOnly {
                 return Err(StorageError::StorageVersionInconsistency(
                     StorageVersionError::InconsistentStorageScope,
                 ));
             }
         }
     }
+    // Run the migrations from the existing versions of the storage.
+    let existing_storage_version = if run_migrations {
+        migrate_storage(&mut writer, existing_storage_version)?
+    } else if has_pending_migrations(&existing_storage_version, writer.scope) {
+        return Err(StorageError::PendingMigrations);
+    } else {
+        existing_storage_version
+    };
     // Update the version if it's lower than the crate version.
     let mut wtxn = writer.begin_rw_txn()?;
     match existing_storage_version {
@@ -388,6 +515,24 @@ pub enum StorageScope {
     /// Stores the data describing the current state. In this mode the transaction, events and
     /// state-diffs are not stored.
     StateOnly,
+    /// Stores all types of data, but the transactions, transaction outputs, events and
+    /// state-diffs are kept only for the most recent blocks (see [`StorageConfig`]).
+    Pruned,
+}
+
+impl FromStr for StorageScope {
+    type Err = String;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        match scope {
+            "FullArchive" => Ok(StorageScope::FullArchive),
+            "StateOnly" => Ok(StorageScope::StateOnly),
+            "Pruned" => Ok(StorageScope::Pruned),
+            _ => Err(format!(
+                "Unknown storage scope {scope}, expected FullArchive, StateOnly or Pruned."
+            )),
+        }
+    }
 }
 
 /// A struct for starting RO transactions ([`StorageTxn`]) to the storage.
@@ -397,20 +542,43 @@ pub struct StorageReader {
     file_readers: FileHandlers<RO>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    retained_blocks: u64,
+    // Set if the storage was opened by [`open_storage_read_only`].
+    read_only_state: Option<Arc<ReadOnlyState>>,
+    state_cache: Option<Arc<StateCache>>,
 }
 
 impl StorageReader {
     /// Takes a snapshot of the current state of the storage and returns a [`StorageTxn`] for
     /// reading data from the storage.
     pub fn begin_ro_txn(&self) -> StorageResult<StorageTxn<'_, RO>> {
+        // The generation is taken before the transaction starts, so values that are read in a
+        // transaction that started before a revert was committed aren't cached.
+        let state_cache_generation =
+            self.state_cache.as_ref().map(|state_cache| state_cache.generation());
+        let txn = self.db_reader.begin_ro_txn()?;
+        if let Some(read_only_state) = &self.read_only_state {
+            read_only_state.update(&txn, &self.tables, &self.file_readers)?;
+        }
         Ok(StorageTxn {
-            txn: self.db_reader.begin_ro_txn()?,
+            txn,
             file_handlers: self.file_readers.clone(),
             tables: self.tables.clone(),
             scope: self.scope,
+            state_cache: self.state_cache.clone(),
+            state_cache_generation,
+            reverted_state_from: None,
+            pruned_state_diffs: Vec::new(),
+            write_batch: None,
+            pending_reclaims: None,
         })
     }
 
+    /// Returns the compression state of the storage.
+    pub fn compression_context(&self) -> &CompressionContext {
+        &self.db_reader.serde_context().compression
+    }
+
     /// Returns metadata about the tables in the storage.
     pub fn db_tables_stats(&self) -> StorageResult<DbStats> {
         let mut tables_stats = BTreeMap::new();
@@ -429,6 +597,12 @@ impl StorageReader {
     pub fn get_scope(&self) -> StorageScope {
         self.scope
     }
+
+    /// Returns the number of most recent blocks whose history is kept under the
+    /// [`StorageScope::Pruned`] scope.
+    pub fn get_retained_blocks(&self) -> u64 {
+        self.retained_blocks
+    }
 }
 
 /// A struct for starting RW transactions ([`StorageTxn`]) to the storage.
@@ -439,6 +613,9 @@ pub struct StorageWriter {
     file_writers: FileHandlers<RW>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    state_cache: Option<Arc<StateCache>>,
+    write_batch: Option<Arc<Mutex<WriteBatch>>>,
+    pending_reclaims: Arc<Mutex<PendingReclaims>>,
 }
 
 impl StorageWriter {
@@ -450,8 +627,64 @@ impl StorageWriter {
             file_handlers: self.file_writers.clone(),
             tables: self.tables.clone(),
             scope: self.scope,
+            state_cache: self.state_cache.clone(),
+            // Values that are read in a write transaction might not be committed, so they aren't
+            // cached.
+            state_cache_generation: None,
+            reverted_state_from: None,
+            pruned_state_diffs: Vec::new(),
+            write_batch: self.write_batch.clone(),
+            pending_reclaims: Some(self.pending_reclaims.clone()),
+        })
+    }
+
+    /// Returns the maximal number of blocks to write in one commit, which is the write_batch_size
+    /// in the [`StorageConfig`].
+    pub fn write_batch_size(&self) -> usize {
+        self.write_batch.as_ref().map_or(1, |write_batch| {
+            write_batch.lock().expect("Lock should not be poisoned").max_size()
+        })
+    }
+
+    /// Returns the time after which a write batch that isn't full is made durable by
+    /// [`flush_expired_write_batch`](Self::flush_expired_write_batch), or None if the commits
+    /// aren't batched.
+    pub fn write_batch_max_duration(&self) -> Option<Duration> {
+        self.write_batch.as_ref().map(|write_batch| {
+            write_batch.lock().expect("Lock should not be poisoned").max_duration()
         })
     }
+
+    /// Makes the commits of the current write batch durable if the batch is older than the
+    /// write_batch_max_duration in the [`StorageConfig`]. A writer that batches its commits calls
+    /// it periodically, so the last batch is made durable even if no more commits are made.
+    pub fn flush_expired_write_batch(&mut self) -> StorageResult<()> {
+        let Some(write_batch) = &self.write_batch else {
+            return Ok(());
+        };
+        let mut write_batch = write_batch.lock().expect("Lock should not be poisoned");
+        if write_batch.is_expired() {
+            debug!("Making the commits of the expired write batch durable.");
+            // The files are flushed before the database is synced, like in the commits.
+            self.file_writers.flush();
+            self.db_writer.sync()?;
+            write_batch.clear();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StorageWriter {
+    // Makes the commits of the current write batch durable.
+    fn drop(&mut self) {
+        if self.write_batch.is_none() {
+            return;
+        }
+        self.file_writers.flush();
+        if let Err(err) = self.db_writer.sync() {
+            error!("Failed syncing the storage to the disk: {err}.");
+        }
+    }
 }
 
 /// A struct for interacting with the storage.
@@ -461,13 +694,64 @@ pub struct StorageTxn<'env, Mode: TransactionKind> {
     file_handlers: FileHandlers<Mode>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    state_cache: Option<Arc<StateCache>>,
+    // Set in read transactions, to the generation of the state cache when the transaction started.
+    state_cache_generation: Option<u64>,
+    // The first block whose state diff was reverted in this transaction.
+    reverted_state_from: Option<BlockNumber>,
+    // The locations of the state diffs that were pruned in this transaction. Their space in the
+    // file is reclaimed once the transaction is durably committed and no reader sees them anymore.
+    pruned_state_diffs: Vec<LocationInFile>,
+    // Set in write transactions when the commits are batched, see the write_batch module.
+    write_batch: Option<Arc<Mutex<WriteBatch>>>,
+    // Set in write transactions, to the pruned locations that weren't reclaimed yet.
+    pending_reclaims: Option<Arc<Mutex<PendingReclaims>>>,
 }
 
 impl<'env> StorageTxn<'env, RW> {
-    /// Commits the changes made in the transaction to the storage.
+    /// Commits the changes made in the transaction to the storage. When the commits are batched,
+    /// the commit is durable only once its batch is full.
     pub fn commit(self) -> StorageResult<()> {
-        self.file_handlers.flush();
-        Ok(self.txn.commit()?)
+        let txn_id = self.txn.id();
+        let db_readers = self.txn.readers();
+        // A commit that prunes state diffs is made durable, so the database can't roll back to a
+        // state that refers to the reclaimed space.
+        let end_of_batch = self.write_batch.as_ref().map(|write_batch| {
+            let mut write_batch = write_batch.lock().expect("Lock should not be poisoned");
+            let end_of_batch = write_batch.add_commit() || !self.pruned_state_diffs.is_empty();
+            if end_of_batch {
+                write_batch.clear();
+            }
+            end_of_batch
+        });
+        match end_of_batch {
+            None => {
+                self.file_handlers.flush();
+                self.txn.commit()?;
+            }
+            // The files are flushed before the database is synced, so the data of a durable commit
+            // is always in the files.
+            Some(true) => {
+                self.file_handlers.flush();
+                self.txn.commit_and_sync()?;
+            }
+            Some(false) => self.txn.commit()?,
+        }
+        if let (Some(state_cache), Some(block_number)) =
+            (&self.state_cache, self.reverted_state_from)
+        {
+            state_cache.invalidate_after(block_number);
+        }
+        if let Some(pending_reclaims) = &self.pending_reclaims {
+            let mut pending_reclaims =
+                pending_reclaims.lock().expect("Lock should not be poisoned");
+            pending_reclaims.push(txn_id, self.pruned_state_diffs);
+            if !pending_reclaims.is_empty() {
+                let reclaimable = pending_reclaims.pop_reclaimable(db_readers.oldest_txn_id()?);
+                self.file_handlers.thin_state_diff.reclaim(&reclaimable)?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -478,7 +762,9 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
     ) -> StorageResult<TableHandle<'_, K, V, SimpleTable>> {
         if self.scope == StorageScope::StateOnly {
             let unused_tables = [
+                self.tables.event_keys.name,
                 self.tables.events.name,
+                self.tables.sender_nonce_to_transaction_idx.name,
                 self.tables.transaction_hash_to_idx.name,
                 self.tables.transaction_idx_to_hash.name,
                 self.tables.transaction_outputs.name,
@@ -505,23 +791,32 @@ struct_field_names! {
         block_hash_to_number: TableIdentifier<BlockHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>,
         block_signatures: TableIdentifier<BlockNumber, VersionZeroWrapper<BlockSignature>, SimpleTable>,
         casms: TableIdentifier<ClassHash, VersionZeroWrapper<LocationInFile>, SimpleTable>,
+        compacted_files: TableIdentifier<OffsetKind, NoVersionValueWrapper<()>, SimpleTable>,
         contract_storage: TableIdentifier<(ContractAddress, StorageKey, BlockNumber), NoVersionValueWrapper<StarkFelt>, SimpleTable>,
+        contract_storage_roots: TableIdentifier<(ContractAddress, BlockNumber), NoVersionValueWrapper<StarkHash>, SimpleTable>,
         declared_classes: TableIdentifier<ClassHash, VersionZeroWrapper<LocationInFile>, SimpleTable>,
         declared_classes_block: TableIdentifier<ClassHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>,
         deprecated_declared_classes: TableIdentifier<ClassHash, VersionWrapper<IndexedDeprecatedContractClass, 1>, SimpleTable>,
         deployed_contracts: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<ClassHash>, SimpleTable>,
+        event_keys: TableIdentifier<(usize, EventKey, EventIndex), NoVersionValueWrapper<ContractAddress>, SimpleTable>,
         events: TableIdentifier<(ContractAddress, EventIndex), NoVersionValueWrapper<EventContent>, SimpleTable>,
         headers: TableIdentifier<BlockNumber, VersionWrapper<StorageBlockHeader, 2>, SimpleTable>,
         markers: TableIdentifier<MarkerKind, VersionZeroWrapper<BlockNumber>, SimpleTable>,
         nonces: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<Nonce>, SimpleTable>,
         file_offsets: TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
+        sender_nonce_to_transaction_idx: TableIdentifier<(ContractAddress, Nonce), NoVersionValueWrapper<TransactionIndex>, SimpleTable>,
         state_diffs: TableIdentifier<BlockNumber, VersionZeroWrapper<LocationInFile>, SimpleTable>,
+        state_roots: TableIdentifier<BlockNumber, VersionZeroWrapper<StateRoots>, SimpleTable>,
         transaction_hash_to_idx: TableIdentifier<TransactionHash, NoVersionValueWrapper<TransactionIndex>, SimpleTable>,
         transaction_idx_to_hash: TableIdentifier<TransactionIndex, NoVersionValueWrapper<TransactionHash>, SimpleTable>,
         transaction_outputs: TableIdentifier<TransactionIndex, VersionZeroWrapper<ThinTransactionOutput>, SimpleTable>,
         transactions: TableIdentifier<TransactionIndex, VersionZeroWrapper<Transaction>, SimpleTable>,
+        trie_nodes: TableIdentifier<StarkHash, VersionZeroWrapper<TrieNode>, SimpleTable>,
+        trie_nodes_by_block: TableIdentifier<(BlockNumber, StarkHash), NoVersionValueWrapper<()>, SimpleTable>,
+        zstd_dictionaries: TableIdentifier<u32, NoVersionValueWrapper<Vec<u8>>, SimpleTable>,
 
         // Version tables
+        migration_progress: TableIdentifier<String, NoVersionValueWrapper<MigrationProgress>, SimpleTable>,
         starknet_version: TableIdentifier<BlockNumber, VersionZeroWrapper<StarknetVersion>, SimpleTable>,
         storage_version: TableIdentifier<String, NoVersionValueWrapper<Version>, SimpleTable>
     }
@@ -538,6 +833,28 @@ macro_rules! struct_field_names {
                 static NAMES: &'static [&'static str] = &[$(stringify!($fname)),*];
                 NAMES
             }
+
+            // Returns the identifiers of the existing tables, without creating them.
+            fn open(db_reader: &DbReader) -> StorageResult<Self> {
+                Ok(Self { $($fname : db_reader.open_simple_table(stringify!($fname))?),* })
+            }
+        }
+
+        // A table of the storage, so a match on the tables fails to compile when a table is added.
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+        pub(crate) enum TableName {
+            $($fname),*
+        }
+
+        impl TableName {
+            pub(crate) const ALL: &'static [TableName] = &[$(TableName::$fname),*];
+
+            pub(crate) fn as_str(&self) -> &'static str {
+                match self {
+                    $(TableName::$fname => stringify!($fname)),*
+                }
+            }
         }
     }
 }
@@ -572,6 +889,12 @@ pub enum StorageError {
     StorageVersionInconsistency(#[from] StorageVersionError),
     #[error("The table {table_name} is unused under the {storage_scope:?} storage scope.")]
     ScopeError { table_name: String, storage_scope: StorageScope },
+    #[error("Pruning is not supported under the {storage_scope:?} storage scope.")]
+    PruningNotSupported { storage_scope: StorageScope },
+    #[error(
+        "Cannot prune blocks until {until}, only blocks before {prunable_until} can be pruned."
+    )]
+    InvalidPruningTarget { until: BlockNumber, prunable_until: BlockNumber },
     #[error(transparent)]
     IOError(#[from] std::io::Error),
     #[error(transparent)]
@@ -586,6 +909,21 @@ pub enum StorageError {
          {block_number}."
     )]
     BlockSignatureForNonExistingBlock { block_number: BlockNumber, block_signature: BlockSignature },
+    #[error("Invalid storage snapshot: {msg}.")]
+    InvalidSnapshot { msg: String },
+    #[error("The storage snapshot doesn't match the storage: {msg}.")]
+    IncompatibleSnapshot { msg: String },
+    #[error("Invalid table dump: {msg}.")]
+    InvalidTableDump { msg: String },
+    #[error(
+        "Cannot revert the storage to block {target_block}, the blocks before {pruning_marker} \
+         were pruned."
+    )]
+    InvalidRevertTarget { target_block: BlockNumber, pruning_marker: BlockNumber },
+    #[error("Failed to compact the storage: {msg}.")]
+    CompactionError { msg: String },
+    #[error("The storage has pending migrations, run the node to migrate it.")]
+    PendingMigrations,
 }
 
 /// A type alias that maps to std::result::Result<T, StorageError>.
@@ -593,23 +931,107 @@ pub type StorageResult<V> = std::result::Result<V, StorageError>;
 
 /// A struct for the configuration of the storage.
 #[allow(missing_docs)]
-#[derive(Serialize, Debug, Default, Deserialize, Clone, PartialEq, Validate)]
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Validate)]
 pub struct StorageConfig {
     #[validate]
     pub db_config: DbConfig,
     #[validate]
     pub mmap_file_config: MmapFileConfig,
     pub scope: StorageScope,
+    #[validate(range(min = 1))]
+    pub retained_blocks: u64,
+    pub snapshot_to_import: Option<PathBuf>,
+    pub compression_codec: CompressionCodec,
+    pub state_cache_size: usize,
+    pub encryption_key: Option<EncryptionKey>,
+    #[validate(range(min = 1))]
+    pub write_batch_size: usize,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub write_batch_max_duration: Duration,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            db_config: DbConfig::default(),
+            mmap_file_config: MmapFileConfig::default(),
+            scope: StorageScope::default(),
+            retained_blocks: 10000,
+            snapshot_to_import: None,
+            compression_codec: CompressionCodec::default(),
+            state_cache_size: 0,
+            encryption_key: None,
+            write_batch_size: 1,
+            write_batch_max_duration: Duration::from_secs(10),
+        }
+    }
 }
 
 impl SerializeConfig for StorageConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        let mut dumped_config = BTreeMap::from_iter([ser_param(
-            "scope",
-            &self.scope,
-            "The categories of data saved in storage.",
+        let mut dumped_config = BTreeMap::from_iter([
+            ser_param(
+                "scope",
+                &self.scope,
+                "The categories of data saved in storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "retained_blocks",
+                &self.retained_blocks,
+                "The number of most recent blocks whose transactions, events and state diffs are \
+                 kept when the storage scope is Pruned.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "compression_codec",
+                &self.compression_codec,
+                "The codec for compressing new values: Gzip, Zstd or Lz4. Existing values stay \
+                 readable after the codec changes.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "state_cache_size",
+                &self.state_cache_size,
+                "The maximal number of storage values and the maximal number of nonces that are \
+                 cached by the state reader. Each unit takes about 300 bytes of memory, e.g. a \
+                 size of 100000 takes about 30MB. 0 disables the cache.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "write_batch_size",
+                &self.write_batch_size,
+                "The maximal number of blocks that the sync writes in one commit, and of commits \
+                 that are made durable together, by one flush of the files and one sync of the \
+                 database. 1 makes every commit durable. A system crash loses the commits of the \
+                 last batch, which are then synced again.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "write_batch_max_duration",
+                &self.write_batch_max_duration.as_secs(),
+                "Time in seconds from the first commit of a batch after which the batch is made \
+                 durable even if it isn't full.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        dumped_config.extend(ser_optional_param(
+            &self.snapshot_to_import,
+            PathBuf::from("./snapshot"),
+            "snapshot_to_import",
+            "A storage snapshot to restore when the storage is empty (see the \
+             export_storage_snapshot binary).",
             ParamPrivacyInput::Public,
-        )]);
+        ));
+        dumped_config.extend(ser_optional_param(
+            &self.encryption_key,
+            EncryptionKey::default(),
+            "encryption_key",
+            "A 32 bytes hex key for encrypting the values of the storage at rest. The key can \
+             only be set when the storage is created, the values of a storage that was created \
+             without a key are never encrypted.",
+            ParamPrivacyInput::Private,
+        ));
         dumped_config
             .extend(append_sub_config_name(self.mmap_file_config.dump(), "mmap_file_config"));
         dumped_config.extend(append_sub_config_name(self.db_config.dump(), "db_config"));
@@ -632,7 +1054,9 @@ pub struct DbStats {
 // - CompiledClass <= Class <= State <= Header
 // - Body <= Header
 // - BaseLayerBlock <= Header
+// - Pruned <= CompiledClass, Pruned <= Body
 // Event is currently unsupported.
+// The Pruned marker is the first block number whose data wasn't pruned.
 pub(crate) enum MarkerKind {
     Header,
     Body,
@@ -641,6 +1065,7 @@ pub(crate) enum MarkerKind {
     Class,
     CompiledClass,
     BaseLayerBlock,
+    Pruned,
 }
 
 pub(crate) type MarkersTable<'env> =
@@ -738,6 +1163,27 @@ impl<Mode: TransactionKind> FileHandlers<Mode> {
     }
 }
 
+// Registers the zstd dictionaries of the storage, starting from the given index, in the order they
+// were trained, so the last one is used for new writes. Returns the index of the next dictionary.
+fn register_zstd_dictionaries(
+    db_transaction: &DbTransaction<'_, RO>,
+    zstd_dictionaries_table: &TableIdentifier<u32, NoVersionValueWrapper<Vec<u8>>, SimpleTable>,
+    first_index: u32,
+) -> StorageResult<u32> {
+    let table = db_transaction.open_table(zstd_dictionaries_table)?;
+    let mut cursor = table.cursor(db_transaction)?;
+    let mut next_index = first_index;
+    let mut entry = cursor.lower_bound(&first_index)?;
+    while let Some((index, dictionary)) = entry {
+        let dictionary_id =
+            db_transaction.serde_context().compression.register_zstd_dictionary(&dictionary);
+        debug!("Registered zstd dictionary {dictionary_id}.");
+        next_index = index + 1;
+        entry = cursor.next()?;
+    }
+    Ok(next_index)
+}
+
 fn open_storage_files(
     db_config: &DbConfig,
     mmap_file_config: MmapFileConfig,
@@ -753,6 +1199,7 @@ fn open_storage_files(
         mmap_file_config.clone(),
         db_config.path().join("thin_state_diff.dat"),
         thin_state_diff_offset,
+        db_reader.serde_context().clone(),
     )?;
 
     let contract_class_offset =
@@ -761,11 +1208,16 @@ fn open_storage_files(
         mmap_file_config.clone(),
         db_config.path().join("contract_class.dat"),
         contract_class_offset,
+        db_reader.serde_context().clone(),
     )?;
 
     let casm_offset = table.get(&db_transaction, &OffsetKind::Casm)?.unwrap_or_default();
-    let (casm_writer, casm_reader) =
-        open_file(mmap_file_config.clone(), db_config.path().join("casm.dat"), casm_offset)?;
+    let (casm_writer, casm_reader) = open_file(
+        mmap_file_config.clone(),
+        db_config.path().join("casm.dat"),
+        casm_offset,
+        db_reader.serde_context().clone(),
+    )?;
 
     let deprecated_contract_class_offset =
         table.get(&db_transaction, &OffsetKind::DeprecatedContractClass)?.unwrap_or_default();
@@ -773,6 +1225,7 @@ fn open_storage_files(
         mmap_file_config,
         db_config.path().join("deprecated_contract_class.dat"),
         deprecated_contract_class_offset,
+        db_reader.serde_context().clone(),
     )?;
 
     Ok((
@@ -804,6 +1257,18 @@ pub enum OffsetKind {
     DeprecatedContractClass,
 }
 
+impl OffsetKind {
+    /// The name of the file, without its extension.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            OffsetKind::ThinStateDiff => "thin_state_diff",
+            OffsetKind::ContractClass => "contract_class",
+            OffsetKind::Casm => "casm",
+            OffsetKind::DeprecatedContractClass => "deprecated_contract_class",
+        }
+    }
+}
+
 /// A storage query. Used for benchmarking in the storage_benchmark binary.
 // TODO(dvir): add more queries (especially get casm).
 // TODO(dvir): consider move this, maybe to test_utils.
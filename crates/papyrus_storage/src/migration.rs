@@ -0,0 +1,410 @@
+//! Migrations of the storage between versions.
+//!
+//! A migration upgrades the state version or the blocks version of the storage from one
+//! [`Version`] to another, and may change the major version. When the storage is opened by
+//! [`open_storage`](crate::open_storage), the migrations registered for its existing versions run
+//! one after the other until there's no migration from the version the storage reached. The other
+//! ways of opening the storage fail if there are pending migrations. A storage whose major version
+//! is different than the crate version can therefore be used without a re-sync, as long as there
+//! are migrations to the crate version.
+//!
+//! A migration runs in batches, each in its own RW transaction. The transaction of a batch also
+//! stores the position to continue the migration from, so if the node stops in the middle of a
+//! migration, the migration resumes from the last committed batch when the storage is opened
+//! again.
+//!
+//! The progress of each migration is reported in the `storage_migration_progress` and
+//! `storage_migration_total` gauges, labeled by the name of the migration, as the number of
+//! migrated units out of the number of units the migration migrates.
+
+#[cfg(test)]
+#[path = "migration_test.rs"]
+mod migration_test;
+
+use metrics::{gauge, increment_counter};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use tracing::{debug, info};
+
+use crate::body::{EventsTableKey, TransactionIndex};
+use crate::db::serialization::{StorageSerde, StorageSerdeEx};
+use crate::db::table_types::Table;
+use crate::db::{DbError, RW};
+use crate::state::StateStorageReader;
+use crate::version::{Version, VersionKind};
+use crate::{
+    FullArchiveVersion,
+    StateOnlyVersion,
+    StorageError,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    StorageVersion,
+    StorageWriter,
+};
+
+// The maximal number of entries each batch of a migration migrates.
+const MIGRATION_BATCH_SIZE: usize = 10000;
+
+// The migrations of the state version.
+const STATE_MIGRATIONS: &[&dyn Migration] = &[&UpgradeStateVersionOne, &BackfillStateTries];
+
+// The migrations of the blocks version.
+const BLOCKS_MIGRATIONS: &[&dyn Migration] = &[&IndexEventKeys, &IndexTransactionSenders];
+
+/// A migration of the storage from one version to another.
+pub(crate) trait Migration: Sync {
+    /// A unique name of the migration. The progress of the migration is stored under this name.
+    fn name(&self) -> &'static str;
+
+    /// The version the migration upgrades from.
+    fn source_version(&self) -> Version;
+
+    /// The version of the storage once the migration is done.
+    fn target_version(&self) -> Version;
+
+    /// The number of units (e.g. entries or blocks) the migration migrates. Used for reporting the
+    /// progress of the migration.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error.
+    fn total_units(&self, txn: &StorageTxn<'_, RW>) -> StorageResult<u64>;
+
+    /// Migrates at most `batch_size` entries, starting from the given position, or from the
+    /// beginning if there's no position. Returns the position to continue from, or None if the
+    /// migration is done, and the number of units that were migrated.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error.
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        position: Option<Vec<u8>>,
+        batch_size: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, Option<Vec<u8>>, u64)>;
+}
+
+/// The progress of a migration that didn't finish.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct MigrationProgress {
+    /// The position to continue the migration from.
+    pub position: Vec<u8>,
+    /// The number of units that were migrated.
+    pub n_migrated: u64,
+}
+
+// Runs the registered migrations from the existing versions of the storage. Returns the versions of
+// the storage after the migrations.
+pub(crate) fn migrate_storage(
+    writer: &mut StorageWriter,
+    storage_version: StorageVersion,
+) -> StorageResult<StorageVersion> {
+    match storage_version {
+        StorageVersion::FullArchive(FullArchiveVersion { state_version, blocks_version }) => {
+            let state_version = run_migrations(
+                writer,
+                VersionKind::State,
+                state_version,
+                STATE_MIGRATIONS,
+                MIGRATION_BATCH_SIZE,
+            )?;
+            // The blocks version is deleted if the scope changed to state-only.
+            let blocks_version = if writer.scope == StorageScope::StateOnly {
+                blocks_version
+            } else {
+                run_migrations(
+                    writer,
+                    VersionKind::Blocks,
+                    blocks_version,
+                    BLOCKS_MIGRATIONS,
+                    MIGRATION_BATCH_SIZE,
+                )?
+            };
+            Ok(StorageVersion::FullArchive(FullArchiveVersion { state_version, blocks_version }))
+        }
+        StorageVersion::StateOnly(StateOnlyVersion { state_version }) => {
+            let state_version = run_migrations(
+                writer,
+                VersionKind::State,
+                state_version,
+                STATE_MIGRATIONS,
+                MIGRATION_BATCH_SIZE,
+            )?;
+            Ok(StorageVersion::StateOnly(StateOnlyVersion { state_version }))
+        }
+    }
+}
+
+// Returns whether there are migrations from the existing versions of the storage.
+pub(crate) fn has_pending_migrations(
+    storage_version: &StorageVersion,
+    scope: StorageScope,
+) -> bool {
+    let has_migration_from = |migrations: &[&dyn Migration], version: &Version| {
+        migrations.iter().any(|migration| migration.source_version() == *version)
+    };
+    match storage_version {
+        StorageVersion::FullArchive(FullArchiveVersion { state_version, blocks_version }) => {
+            has_migration_from(STATE_MIGRATIONS, state_version)
+                // The blocks version is deleted if the scope changed to state-only.
+                || (scope != StorageScope::StateOnly
+                    && has_migration_from(BLOCKS_MIGRATIONS, blocks_version))
+        }
+        StorageVersion::StateOnly(StateOnlyVersion { state_version }) => {
+            has_migration_from(STATE_MIGRATIONS, state_version)
+        }
+    }
+}
+
+// Runs the migrations one after the other, starting from the given version, as long as there's a
+// migration from the version the storage reached. Returns the version after the migrations.
+pub(crate) fn run_migrations(
+    writer: &mut StorageWriter,
+    kind: VersionKind,
+    mut version: Version,
+    migrations: &[&dyn Migration],
+    batch_size: usize,
+) -> StorageResult<Version> {
+    while let Some(migration) =
+        migrations.iter().find(|migration| migration.source_version() == version)
+    {
+        run_migration(writer, kind, *migration, batch_size)?;
+        version = migration.target_version();
+    }
+    Ok(version)
+}
+
+fn run_migration(
+    writer: &mut StorageWriter,
+    kind: VersionKind,
+    migration: &dyn Migration,
+    batch_size: usize,
+) -> StorageResult<()> {
+    let name = migration.name();
+    let (progress, total) = {
+        let txn = writer.begin_rw_txn()?;
+        (txn.get_migration_progress(name)?, migration.total_units(&txn)?)
+    };
+    let (mut position, mut n_migrated) = match progress {
+        Some(MigrationProgress { position, n_migrated }) => (Some(position), n_migrated),
+        None => (None, 0),
+    };
+    gauge!("storage_migration_total", total as f64, "migration" => name);
+    gauge!("storage_migration_progress", n_migrated as f64, "migration" => name);
+    if position.is_some() {
+        info!(
+            "Resuming the storage migration {name} from {} to {}.",
+            migration.source_version(),
+            migration.target_version()
+        );
+    } else {
+        info!(
+            "Starting the storage migration {name} from {} to {}.",
+            migration.source_version(),
+            migration.target_version()
+        );
+    }
+
+    let mut batches = 0_usize;
+    loop {
+        let (txn, next_position, n_batch_migrated) =
+            migration.migrate_batch(writer.begin_rw_txn()?, position, batch_size)?;
+        n_migrated += n_batch_migrated;
+        let txn = match next_position.clone() {
+            Some(next_position) => txn.set_migration_progress(
+                name,
+                &MigrationProgress { position: next_position, n_migrated },
+            )?,
+            // The migration and the version update are committed together.
+            None => txn
+                .delete_migration_progress(name)?
+                .set_migrated_version(kind, &migration.target_version())?,
+        };
+        txn.commit()?;
+        batches += 1;
+        increment_counter!("storage_migration_batches", "migration" => name);
+        gauge!("storage_migration_progress", n_migrated as f64, "migration" => name);
+        debug!("Migrated batch {batches} of the storage migration {name} ({n_migrated}/{total}).");
+
+        position = next_position;
+        if position.is_none() {
+            break;
+        }
+    }
+    info!("Finished the storage migration {name} after {batches} batches.");
+    Ok(())
+}
+
+impl<'env> StorageTxn<'env, RW> {
+    fn get_migration_progress(&self, name: &str) -> StorageResult<Option<MigrationProgress>> {
+        let migration_progress_table = self.open_table(&self.tables.migration_progress)?;
+        Ok(migration_progress_table.get(&self.txn, &name.to_string())?)
+    }
+
+    fn set_migration_progress(
+        self,
+        name: &str,
+        progress: &MigrationProgress,
+    ) -> StorageResult<Self> {
+        let migration_progress_table = self.open_table(&self.tables.migration_progress)?;
+        migration_progress_table.upsert(&self.txn, &name.to_string(), progress)?;
+        Ok(self)
+    }
+
+    fn delete_migration_progress(self, name: &str) -> StorageResult<Self> {
+        let migration_progress_table = self.open_table(&self.tables.migration_progress)?;
+        migration_progress_table.delete(&self.txn, &name.to_string())?;
+        Ok(self)
+    }
+}
+
+// Positions of migrations that iterate a table are the serialized key of the next entry.
+fn decode_position<K: StorageSerde>(position: Option<Vec<u8>>) -> StorageResult<Option<K>> {
+    position
+        .map(|bytes| {
+            K::deserialize_from(&mut bytes.as_slice())
+                .ok_or(StorageError::InnerError(DbError::InnerDeserialization))
+        })
+        .transpose()
+}
+
+fn encode_position<K: StorageSerde>(position: Option<K>) -> StorageResult<Option<Vec<u8>>> {
+    Ok(position.map(|key| key.serialize()).transpose()?)
+}
+
+// Adds the event keys index of blocks version 2.1.
+struct IndexEventKeys;
+
+impl Migration for IndexEventKeys {
+    fn name(&self) -> &'static str {
+        "index_event_keys"
+    }
+
+    fn source_version(&self) -> Version {
+        Version { major: 2, minor: 0 }
+    }
+
+    fn target_version(&self) -> Version {
+        Version { major: 2, minor: 1 }
+    }
+
+    // The number of events.
+    fn total_units(&self, txn: &StorageTxn<'_, RW>) -> StorageResult<u64> {
+        let events_table = txn.open_table(&txn.tables.events)?;
+        Ok(txn.txn.get_entries_count(&events_table)? as u64)
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        position: Option<Vec<u8>>,
+        batch_size: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, Option<Vec<u8>>, u64)> {
+        let start = decode_position::<EventsTableKey>(position)?;
+        let (txn, next, n_indexed) = txn.index_event_keys(start, batch_size)?;
+        Ok((txn, encode_position(next)?, n_indexed as u64))
+    }
+}
+
+// Adds the sender and nonce index of the transactions of blocks version 2.2.
+struct IndexTransactionSenders;
+
+impl Migration for IndexTransactionSenders {
+    fn name(&self) -> &'static str {
+        "index_transaction_senders"
+    }
+
+    fn source_version(&self) -> Version {
+        Version { major: 2, minor: 1 }
+    }
+
+    fn target_version(&self) -> Version {
+        Version { major: 2, minor: 2 }
+    }
+
+    // The number of transactions.
+    fn total_units(&self, txn: &StorageTxn<'_, RW>) -> StorageResult<u64> {
+        let transactions_table = txn.open_table(&txn.tables.transactions)?;
+        Ok(txn.txn.get_entries_count(&transactions_table)? as u64)
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        position: Option<Vec<u8>>,
+        batch_size: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, Option<Vec<u8>>, u64)> {
+        let start = decode_position::<TransactionIndex>(position)?;
+        let (txn, next, n_indexed) = txn.index_transaction_senders(start, batch_size)?;
+        Ok((txn, encode_position(next)?, n_indexed as u64))
+    }
+}
+
+// State version 1.1 only changed versioned values, which are migrated when they're read. The
+// migration ensures storages of state version 1.0 reach the state tries migration.
+struct UpgradeStateVersionOne;
+
+impl Migration for UpgradeStateVersionOne {
+    fn name(&self) -> &'static str {
+        "upgrade_state_version_one"
+    }
+
+    fn source_version(&self) -> Version {
+        Version { major: 1, minor: 0 }
+    }
+
+    fn target_version(&self) -> Version {
+        Version { major: 1, minor: 1 }
+    }
+
+    fn total_units(&self, _txn: &StorageTxn<'_, RW>) -> StorageResult<u64> {
+        Ok(0)
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        _position: Option<Vec<u8>>,
+        _batch_size: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, Option<Vec<u8>>, u64)> {
+        Ok((txn, None, 0))
+    }
+}
+
+// Builds the state tries of state version 1.2 from the stored state diffs.
+struct BackfillStateTries;
+
+impl Migration for BackfillStateTries {
+    fn name(&self) -> &'static str {
+        "backfill_state_tries"
+    }
+
+    fn source_version(&self) -> Version {
+        Version { major: 1, minor: 1 }
+    }
+
+    fn target_version(&self) -> Version {
+        Version { major: 1, minor: 2 }
+    }
+
+    // The number of blocks with a state diff.
+    fn total_units(&self, txn: &StorageTxn<'_, RW>) -> StorageResult<u64> {
+        Ok(txn.get_state_marker()?.0)
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        position: Option<Vec<u8>>,
+        batch_size: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, Option<Vec<u8>>, u64)> {
+        let start = decode_position::<BlockNumber>(position)?.unwrap_or_default();
+        let (txn, next) = txn.backfill_state_tries(Some(start), batch_size)?;
+        let end = match next {
+            Some(next) => next,
+            None => txn.get_state_marker()?,
+        };
+        let n_blocks = end.0.saturating_sub(start.0);
+        Ok((txn, encode_position(next)?, n_blocks))
+    }
+}
@@ -0,0 +1,317 @@
+use std::fmt::Debug;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::core::{ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::patricia_key;
+use starknet_api::state::{StorageKey, ThinStateDiff};
+use test_utils::get_test_block;
+
+use crate::body::BodyStorageWriter;
+use crate::db::serialization::{
+    Key,
+    NoVersionValueWrapper,
+    StorageSerde,
+    StorageSerdeEx,
+    ValueSerde,
+};
+use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
+use crate::db::{TableIdentifier, RW};
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::migration::{
+    decode_position,
+    encode_position,
+    run_migrations,
+    Migration,
+    MigrationProgress,
+};
+use crate::state::trie::{StateRoots, StateTrieStorageReader};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{
+    get_test_block_hash,
+    get_test_storage,
+    get_test_storage_with_config_by_scope,
+};
+use crate::version::{Version, VersionKind, VersionStorageReader};
+use crate::{
+    open_storage,
+    open_storage_without_migrations,
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    STORAGE_VERSION_BLOCKS,
+    STORAGE_VERSION_STATE,
+};
+
+const N_HEADERS: u64 = 7;
+
+// A migration that appends headers, and fails when it reaches the given block.
+struct AppendHeaders {
+    fail_at: Option<BlockNumber>,
+}
+
+impl Migration for AppendHeaders {
+    fn name(&self) -> &'static str {
+        "append_headers"
+    }
+
+    fn source_version(&self) -> Version {
+        STORAGE_VERSION_BLOCKS
+    }
+
+    fn target_version(&self) -> Version {
+        Version { major: STORAGE_VERSION_BLOCKS.major + 1, minor: 0 }
+    }
+
+    fn total_units(&self, _txn: &StorageTxn<'_, RW>) -> StorageResult<u64> {
+        Ok(N_HEADERS)
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        mut txn: StorageTxn<'env, RW>,
+        position: Option<Vec<u8>>,
+        batch_size: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, Option<Vec<u8>>, u64)> {
+        let start = decode_position::<BlockNumber>(position)?.unwrap_or_default();
+        let end = std::cmp::min(start.0 + batch_size as u64, N_HEADERS);
+        for block_number in (start.0..end).map(BlockNumber) {
+            if self.fail_at == Some(block_number) {
+                return Err(StorageError::DBInconsistency { msg: "Migration failed.".to_owned() });
+            }
+            let header = BlockHeader {
+                block_number,
+                block_hash: get_test_block_hash(block_number),
+                ..Default::default()
+            };
+            txn = txn.append_header(block_number, &header)?;
+        }
+        let next = if end < N_HEADERS { Some(BlockNumber(end)) } else { None };
+        Ok((txn, encode_position(next)?, end - start.0))
+    }
+}
+
+// A migration that doesn't change anything.
+struct Noop;
+
+impl Migration for Noop {
+    fn name(&self) -> &'static str {
+        "noop"
+    }
+
+    fn source_version(&self) -> Version {
+        Version { major: STORAGE_VERSION_BLOCKS.major + 1, minor: 0 }
+    }
+
+    fn target_version(&self) -> Version {
+        Version { major: STORAGE_VERSION_BLOCKS.major + 1, minor: 1 }
+    }
+
+    fn total_units(&self, _txn: &StorageTxn<'_, RW>) -> StorageResult<u64> {
+        Ok(0)
+    }
+
+    fn migrate_batch<'env>(
+        &self,
+        txn: StorageTxn<'env, RW>,
+        _position: Option<Vec<u8>>,
+        _batch_size: usize,
+    ) -> StorageResult<(StorageTxn<'env, RW>, Option<Vec<u8>>, u64)> {
+        Ok((txn, None, 0))
+    }
+}
+
+#[test]
+fn run_migrations_resumes_from_last_batch() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+
+    // The migration fails in the third batch.
+    let failing_migration = AppendHeaders { fail_at: Some(BlockNumber(5)) };
+    let res = run_migrations(
+        &mut writer,
+        VersionKind::Blocks,
+        STORAGE_VERSION_BLOCKS,
+        &[&failing_migration, &Noop],
+        2,
+    );
+    assert_matches!(res, Err(StorageError::DBInconsistency { .. }));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(4));
+    assert_eq!(txn.get_blocks_version().unwrap(), Some(STORAGE_VERSION_BLOCKS));
+    // The progress counts the headers of the committed batches.
+    assert_eq!(
+        get_migration_progress(&reader),
+        vec![(
+            "append_headers".to_owned(),
+            MigrationProgress { position: BlockNumber(4).serialize().unwrap(), n_migrated: 4 }
+        )]
+    );
+    drop(txn);
+
+    // The migration continues from the last batch. Appending a header that was already appended
+    // would fail.
+    let version = run_migrations(
+        &mut writer,
+        VersionKind::Blocks,
+        STORAGE_VERSION_BLOCKS,
+        &[&AppendHeaders { fail_at: None }, &Noop],
+        2,
+    )
+    .unwrap();
+    let expected_version = Version { major: STORAGE_VERSION_BLOCKS.major + 1, minor: 1 };
+    assert_eq!(version, expected_version);
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(N_HEADERS));
+    assert_eq!(txn.get_blocks_version().unwrap(), Some(expected_version));
+    assert!(get_migration_progress(&reader).is_empty());
+}
+
+#[test]
+fn open_storage_migrates_blocks_version() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let block = get_test_block(10, Some(3), None, None);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block.header.block_number, &block.header)
+        .unwrap()
+        .append_body(block.header.block_number, block.body)
+        .unwrap()
+        .commit()
+        .unwrap();
+    let event_keys = get_table_entries(&reader, &reader.tables.event_keys);
+    assert!(!event_keys.is_empty());
+    let sender_nonce_to_transaction_idx =
+        get_table_entries(&reader, &reader.tables.sender_nonce_to_transaction_idx);
+
+    // Delete the indices that were added in blocks version 2.1 and 2.2 and set the version to 2.0,
+    // as in storages that were created before them.
+    let txn = writer.begin_rw_txn().unwrap();
+    let event_keys_table = txn.open_table(&txn.tables.event_keys).unwrap();
+    for (key, _) in &event_keys {
+        event_keys_table.delete(&txn.txn, key).unwrap();
+    }
+    let sender_nonce_to_transaction_idx_table =
+        txn.open_table(&txn.tables.sender_nonce_to_transaction_idx).unwrap();
+    for (key, _) in &sender_nonce_to_transaction_idx {
+        sender_nonce_to_transaction_idx_table.delete(&txn.txn, key).unwrap();
+    }
+    txn.set_migrated_version(VersionKind::Blocks, &Version { major: 2, minor: 0 })
+        .unwrap()
+        .commit()
+        .unwrap();
+    drop(reader);
+    drop(writer);
+
+    // The storage can't be opened without running the migrations.
+    assert_matches!(
+        open_storage_without_migrations(config.clone()),
+        Err(StorageError::PendingMigrations)
+    );
+
+    // Reopening the storage rebuilds the indices.
+    let (reader, _writer) = open_storage(config).unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_blocks_version().unwrap(),
+        Some(STORAGE_VERSION_BLOCKS)
+    );
+    assert_eq!(get_table_entries(&reader, &reader.tables.event_keys), event_keys);
+    assert_eq!(
+        get_table_entries(&reader, &reader.tables.sender_nonce_to_transaction_idx),
+        sender_nonce_to_transaction_idx
+    );
+    assert!(get_migration_progress(&reader).is_empty());
+}
+
+#[test]
+fn open_storage_migrates_state_version() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let address = ContractAddress(patricia_key!("0x100"));
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for i in 0..3_u64 {
+        let state_diff = ThinStateDiff {
+            storage_diffs: indexmap! {
+                address => indexmap! { StorageKey(patricia_key!(i + 1)) => StarkFelt::from(i + 7) },
+            },
+            nonces: indexmap! { address => Nonce(StarkFelt::from(i + 1)) },
+            ..Default::default()
+        };
+        txn = txn.append_state_diff(BlockNumber(i), state_diff).unwrap();
+    }
+    txn.commit().unwrap();
+    let state_roots = get_all_state_roots(&reader);
+    let trie_nodes_by_block = get_table_entries(&reader, &reader.tables.trie_nodes_by_block);
+    assert!(!trie_nodes_by_block.is_empty());
+
+    // Delete the tries that were added in state version 1.2 and set the version to 1.1, as in
+    // storages that were created before them.
+    let txn = writer.begin_rw_txn().unwrap();
+    clear_table(&txn, &txn.tables.state_roots);
+    clear_table(&txn, &txn.tables.contract_storage_roots);
+    clear_table(&txn, &txn.tables.trie_nodes);
+    clear_table(&txn, &txn.tables.trie_nodes_by_block);
+    txn.set_migrated_version(VersionKind::State, &Version { major: 1, minor: 1 })
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert!(get_all_state_roots(&reader).iter().all(Option::is_none));
+    drop(reader);
+    drop(writer);
+
+    // Reopening the storage rebuilds the tries.
+    let (reader, _writer) = open_storage(config).unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_state_version().unwrap(),
+        Some(STORAGE_VERSION_STATE)
+    );
+    assert_eq!(get_all_state_roots(&reader), state_roots);
+    assert_eq!(get_table_entries(&reader, &reader.tables.trie_nodes_by_block), trie_nodes_by_block);
+    assert!(get_migration_progress(&reader).is_empty());
+}
+
+fn get_all_state_roots(reader: &StorageReader) -> Vec<Option<StateRoots>> {
+    let txn = reader.begin_ro_txn().unwrap();
+    (0..txn.get_state_marker().unwrap().0)
+        .map(|block_number| txn.get_state_roots(BlockNumber(block_number)).unwrap())
+        .collect()
+}
+
+fn clear_table<K: Key + Debug, V: ValueSerde + Debug>(
+    txn: &StorageTxn<'_, RW>,
+    table_id: &TableIdentifier<K, V, SimpleTable>,
+) {
+    let table = txn.open_table(table_id).unwrap();
+    let mut cursor = table.cursor(&txn.txn).unwrap();
+    let mut keys = vec![];
+    while let Some((key, _)) = cursor.next().unwrap() {
+        keys.push(key);
+    }
+    for key in keys {
+        table.delete(&txn.txn, &key).unwrap();
+    }
+}
+
+fn get_migration_progress(reader: &StorageReader) -> Vec<(String, MigrationProgress)> {
+    get_table_entries(reader, &reader.tables.migration_progress)
+}
+
+fn get_table_entries<K: Key + Debug, V: StorageSerde + Debug>(
+    reader: &StorageReader,
+    table_id: &TableIdentifier<K, NoVersionValueWrapper<V>, SimpleTable>,
+) -> Vec<(K, V)> {
+    let txn = reader.begin_ro_txn().unwrap();
+    let table = txn.open_table(table_id).unwrap();
+    let mut cursor = table.cursor(&txn.txn).unwrap();
+    let mut entries = vec![];
+    while let Some(entry) = cursor.next().unwrap() {
+        entries.push(entry);
+    }
+    entries
+}
@@ -31,6 +31,7 @@ fn write_read() {
         get_mmap_file_test_config(),
         dir.path().to_path_buf().join("test_write_read"),
         offset,
+        SerdeContext::default(),
     )
     .unwrap();
     let data = vec![1, 2, 3];
@@ -53,6 +54,7 @@ fn concurrent_reads() {
         get_mmap_file_test_config(),
         dir.path().to_path_buf().join("test_concurrent_reads"),
         offset,
+        SerdeContext::default(),
     )
     .unwrap();
     let data = vec![1, 2, 3];
@@ -84,6 +86,7 @@ fn concurrent_reads_single_write() {
         get_mmap_file_test_config(),
         dir.path().to_path_buf().join("test_concurrent_reads_single_write"),
         offset,
+        SerdeContext::default(),
     )
     .unwrap();
     let first_data = vec![1, 2, 3];
@@ -125,7 +128,8 @@ fn concurrent_reads_single_write() {
 #[test]
 fn grow_file() {
     let data = vec![1, 2];
-    let serialization_size = NoVersionValueWrapper::serialize(&data).unwrap().len();
+    let serialization_size =
+        NoVersionValueWrapper::serialize(&data, &SerdeContext::default()).unwrap().len();
     let dir = tempdir().unwrap();
     let config = MmapFileConfig {
         max_size: 10 * serialization_size,
@@ -146,9 +150,13 @@ fn grow_file() {
         // file_size = 0, offset = 0
         assert_eq!(file.metadata().unwrap().len(), 0);
 
-        let (mut writer, _) =
-            open_file::<NoVersionValueWrapper<Vec<u8>>>(config.clone(), file_path.clone(), offset)
-                .unwrap();
+        let (mut writer, _) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
+            config.clone(),
+            file_path.clone(),
+            offset,
+            SerdeContext::default(),
+        )
+        .unwrap();
         // file_size = 4 (growth_step), offset = 0
         let mut file_size = file.metadata().unwrap().len();
         assert_eq!(file_size, config.growth_step as u64);
@@ -187,7 +195,13 @@ fn grow_file() {
         .open(file_path.clone())
         .unwrap();
     assert_eq!(file.metadata().unwrap().len(), 4 * config.growth_step as u64);
-    let _ = open_file::<NoVersionValueWrapper<Vec<u8>>>(config.clone(), file_path, offset).unwrap();
+    let _ = open_file::<NoVersionValueWrapper<Vec<u8>>>(
+        config.clone(),
+        file_path,
+        offset,
+        SerdeContext::default(),
+    )
+    .unwrap();
     assert_eq!(file.metadata().unwrap().len(), 4 * config.growth_step as u64);
 
     dir.close().unwrap();
@@ -201,6 +215,7 @@ async fn write_read_different_locations() {
         get_mmap_file_test_config(),
         dir.path().to_path_buf().join("test_write_read_different_locations"),
         offset,
+        SerdeContext::default(),
     )
     .unwrap();
     let mut data = vec![0, 1];
@@ -258,6 +273,7 @@ fn reader_when_writer_is_out_of_scope() {
         get_mmap_file_test_config(),
         dir.path().to_path_buf().join("test_reader_when_writer_is_out_of_scope"),
         offset,
+        SerdeContext::default(),
     )
     .unwrap();
     let data = vec![1, 2, 3];
@@ -272,3 +288,92 @@ fn reader_when_writer_is_out_of_scope() {
 
     dir.close().unwrap();
 }
+
+#[test]
+fn compact_file() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().to_path_buf().join("test_compact_file");
+    let data = [vec![1], vec![2, 3], vec![4, 5, 6]];
+    let locations = {
+        let (mut writer, _) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
+            get_mmap_file_test_config(),
+            file_path.clone(),
+            0,
+            SerdeContext::default(),
+        )
+        .unwrap();
+        let locations = data.iter().map(|value| writer.append(value)).collect::<Vec<_>>();
+        writer.flush();
+        locations
+    };
+    let offset = locations[2].next_offset();
+
+    // Nothing to reclaim.
+    assert!(write_compacted_file(&file_path, offset, locations.clone()).unwrap().is_none());
+    assert!(!compacted_file_path(&file_path).exists());
+
+    // Drop the second object.
+    let kept_locations = [locations[0], locations[2]];
+    let compacted_locations =
+        write_compacted_file(&file_path, offset, kept_locations).unwrap().unwrap();
+    assert_eq!(compacted_locations[&locations[0]].offset, 0);
+    assert_eq!(compacted_locations[&locations[2]].offset, locations[0].len);
+    let compacted_offset = compacted_locations[&locations[2]].next_offset();
+
+    // Discarding the compacted file keeps the original file.
+    discard_compacted_file(&file_path).unwrap();
+    assert!(!compacted_file_path(&file_path).exists());
+    assert_eq!(
+        fs::metadata(&file_path).unwrap().len(),
+        get_mmap_file_test_config().growth_step as u64
+    );
+
+    // The file is replaced with the compacted file, and is opened with the compacted offset.
+    write_compacted_file(&file_path, offset, kept_locations).unwrap().unwrap();
+    replace_with_compacted_file(&file_path).unwrap();
+    let (mut writer, reader) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
+        get_mmap_file_test_config(),
+        file_path.clone(),
+        compacted_offset,
+        SerdeContext::default(),
+    )
+    .unwrap();
+    assert!(!compacted_file_path(&file_path).exists());
+    assert_eq!(reader.get(compacted_locations[&locations[0]]).unwrap().unwrap(), data[0]);
+    assert_eq!(reader.get(compacted_locations[&locations[2]]).unwrap().unwrap(), data[2]);
+    assert_eq!(reader.stats().offset, compacted_offset);
+
+    // New data is appended after the compacted data.
+    let location = writer.append(&data[1]);
+    assert_eq!(location.offset, compacted_offset);
+    assert_eq!(reader.get(location).unwrap().unwrap(), data[1]);
+
+    dir.close().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn reclaim() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().to_path_buf().join("test_reclaim");
+    let (mut writer, reader) = open_file::<NoVersionValueWrapper<Vec<u8>>>(
+        get_mmap_file_test_config(),
+        file_path,
+        0,
+        SerdeContext::default(),
+    )
+    .unwrap();
+    let data = [vec![1; 10], vec![2; 20], vec![3; 30]];
+    let locations = data.iter().map(|value| writer.append(value)).collect::<Vec<_>>();
+    writer.flush();
+
+    writer.reclaim(&[locations[1]]).unwrap();
+    assert_eq!(reader.get(locations[0]).unwrap().unwrap(), data[0]);
+    assert_eq!(reader.get(locations[2]).unwrap().unwrap(), data[2]);
+    // The released object is read as zeros.
+    let released_bytes = unsafe {
+        std::slice::from_raw_parts(reader.memory_ptr.add(locations[1].offset), locations[1].len)
+    };
+    assert!(released_bytes.iter().all(|byte| *byte == 0));
+    assert_eq!(reader.stats().offset, locations[2].next_offset());
+}
@@ -3,19 +3,30 @@
 //! The caller **must** ensure that:
 //! * The serialized data is not larger than the maximum object size.
 //! * New data is appended to the file (i.e, at the offset returned by the previous write).
+//!
+//! A file can also be opened for reading only (see `open_file_read_only`), in a process other than
+//! the one that appends to it.
+//!
+//! Data that is no longer needed is not removed from the file. Instead, a compacted version of the
+//! file can be written (see `write_compacted_file`), and it replaces the file once its locations
+//! are committed (see `replace_with_compacted_file`). On Linux, the disk space of data that is
+//! no longer needed can also be released in place (see `FileHandler::reclaim`), without changing
+//! the locations of the other objects.
 
 #[cfg(test)]
 mod mmap_file_test;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsString;
 use std::fmt::Debug;
-use std::fs::{File, OpenOptions};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
 use std::marker::PhantomData;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::result;
 use std::sync::{Arc, Mutex};
 
-use memmap2::{MmapMut, MmapOptions};
+use memmap2::{MmapOptions, MmapRaw};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 #[cfg(test)]
@@ -27,7 +38,7 @@ use thiserror::Error;
 use tracing::{debug, instrument, trace};
 use validator::{Validate, ValidationError};
 
-use crate::db::serialization::{StorageSerde, ValueSerde};
+use crate::db::serialization::{SerdeContext, StorageSerde, ValueSerde};
 use crate::db::{TransactionKind, RO, RW};
 
 type MmapFileResult<V> = result::Result<V, MMapFileError>;
@@ -118,7 +129,7 @@ pub(crate) trait Reader<V: ValueSerde> {
 }
 
 /// Represents a location in the file.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct LocationInFile {
     /// Offset in the file.
     offset: usize,
@@ -139,7 +150,7 @@ struct MMapFile<V: ValueSerde> {
     config: MmapFileConfig,
     file: File,
     size: usize,
-    mmap: MmapMut,
+    mmap: MmapRaw,
     offset: usize,
     should_flush: bool,
     _value_type: PhantomData<V>,
@@ -163,16 +174,18 @@ impl<V: ValueSerde> MMapFile<V> {
     }
 }
 
-/// Open a memory mapped file, create it if it doesn't exist.
-#[instrument(level = "debug", err)]
+/// Open a memory mapped file, create it if it doesn't exist. The values are (de)serialized with the
+/// given context.
+#[instrument(level = "debug", skip(serde_context), err)]
 pub(crate) fn open_file<V: ValueSerde>(
     config: MmapFileConfig,
     path: PathBuf,
     offset: usize,
+    serde_context: SerdeContext,
 ) -> MmapFileResult<(FileHandler<V, RW>, FileHandler<V, RO>)> {
     let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
     let size = file.metadata()?.len();
-    let mmap = unsafe { MmapOptions::new().len(config.max_size).map_mut(&file)? };
+    let mmap = MmapOptions::new().len(config.max_size).map_raw(&file)?;
     let mmap_ptr = mmap.as_ptr();
     let mmap_file = MMapFile {
         config,
@@ -188,21 +201,125 @@ pub(crate) fn open_file<V: ValueSerde>(
     let mut write_file_handler: FileHandler<V, RW> = FileHandler {
         memory_ptr: mmap_ptr,
         mmap_file: shared_mmap_file.clone(),
+        serde_context: serde_context.clone(),
         _mode: PhantomData,
     };
-    write_file_handler.grow_file_if_needed(0);
+    write_file_handler.grow_file_if_needed(offset);
 
-    let read_file_handler: FileHandler<V, RO> =
-        FileHandler { memory_ptr: mmap_ptr, mmap_file: shared_mmap_file, _mode: PhantomData };
+    let read_file_handler: FileHandler<V, RO> = FileHandler {
+        memory_ptr: mmap_ptr,
+        mmap_file: shared_mmap_file,
+        serde_context,
+        _mode: PhantomData,
+    };
 
     Ok((write_file_handler, read_file_handler))
 }
 
+/// Open an existing memory mapped file for reading only. The file can be appended to by another
+/// process, which stores the offsets of the appended data, and the offset of the returned handler
+/// should be updated accordingly (see [`FileHandler::update_offset`]).
+/// Note that a file that was replaced by its compacted version while the file is open is not
+/// visible to the returned handler, so the file should be opened again after a compaction.
+#[instrument(level = "debug", skip(serde_context), err)]
+pub(crate) fn open_file_read_only<V: ValueSerde>(
+    config: MmapFileConfig,
+    path: PathBuf,
+    offset: usize,
+    serde_context: SerdeContext,
+) -> MmapFileResult<FileHandler<V, RO>> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    // The mapping covers the maximal size of the file, so data that is appended to the file after
+    // it was opened is visible through the mapping.
+    let mmap = MmapOptions::new().len(config.max_size).map_raw_read_only(&file)?;
+    let mmap_ptr = mmap.as_ptr();
+    let mmap_file = MMapFile {
+        config,
+        file,
+        mmap,
+        size: size.try_into().expect("size should fit in usize"),
+        offset,
+        should_flush: false,
+        _value_type: PhantomData {},
+    };
+    Ok(FileHandler {
+        memory_ptr: mmap_ptr,
+        mmap_file: Arc::new(Mutex::new(mmap_file)),
+        serde_context,
+        _mode: PhantomData,
+    })
+}
+
+/// Returns the path of the compacted version of the file at the given path.
+pub(crate) fn compacted_file_path(path: &Path) -> PathBuf {
+    let mut compacted_path = OsString::from(path.as_os_str());
+    compacted_path.push(".compacted");
+    PathBuf::from(compacted_path)
+}
+
+/// Writes the objects at the given locations of the file into a compacted version of the file,
+/// ordered by their offset. Returns the location of each object in the compacted file, or None if
+/// the objects already occupy all the data of the file (up to the given offset).
+/// The compacted file should replace the file (see [`replace_with_compacted_file`]) only after the
+/// locations in the compacted file were committed, and be discarded (see
+/// [`discard_compacted_file`]) otherwise.
+#[instrument(skip(locations), level = "debug", err)]
+pub(crate) fn write_compacted_file(
+    path: &Path,
+    offset: usize,
+    locations: impl IntoIterator<Item = LocationInFile>,
+) -> MmapFileResult<Option<HashMap<LocationInFile, LocationInFile>>> {
+    let locations = locations.into_iter().collect::<BTreeSet<_>>();
+    if locations.iter().map(|location| location.len).sum::<usize>() == offset {
+        return Ok(None);
+    }
+
+    let mut file = File::open(path)?;
+    let mut compacted_file = BufWriter::new(File::create(compacted_file_path(path))?);
+    let mut compacted_locations = HashMap::new();
+    let mut compacted_offset = 0;
+    let mut buffer = Vec::new();
+    for location in locations {
+        buffer.resize(location.len, 0);
+        file.seek(SeekFrom::Start(location.offset.try_into()?))?;
+        file.read_exact(&mut buffer)?;
+        compacted_file.write_all(&buffer)?;
+        compacted_locations
+            .insert(location, LocationInFile { offset: compacted_offset, len: location.len });
+        compacted_offset += location.len;
+    }
+    compacted_file.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    debug!("Compacted {path:?} from {offset} to {compacted_offset} bytes.");
+    Ok(Some(compacted_locations))
+}
+
+/// Replaces the file with its compacted version, if it exists. The file must not be open.
+pub(crate) fn replace_with_compacted_file(path: &Path) -> MmapFileResult<()> {
+    let compacted_path = compacted_file_path(path);
+    if compacted_path.exists() {
+        debug!("Replacing {path:?} with its compacted version.");
+        fs::rename(compacted_path, path)?;
+    }
+    Ok(())
+}
+
+/// Removes the compacted version of the file, if it exists.
+pub(crate) fn discard_compacted_file(path: &Path) -> MmapFileResult<()> {
+    let compacted_path = compacted_file_path(path);
+    if compacted_path.exists() {
+        debug!("Removing the uncommitted compacted version of {path:?}.");
+        fs::remove_file(compacted_path)?;
+    }
+    Ok(())
+}
+
 /// A wrapper around `MMapFile` that provides both write and read interfaces.
 #[derive(Clone, Debug)]
 pub(crate) struct FileHandler<V: ValueSerde, Mode: TransactionKind> {
     memory_ptr: *const u8,
     mmap_file: Arc<Mutex<MMapFile<V>>>,
+    serde_context: SerdeContext,
     _mode: PhantomData<Mode>,
 }
 
@@ -222,18 +339,64 @@ impl<V: ValueSerde> FileHandler<V, RW> {
     }
 }
 
+impl<V: ValueSerde> FileHandler<V, RW> {
+    /// Releases the disk space of the objects at the given locations by punching holes in the
+    /// file. The size of the file and the locations of the other objects don't change, and reading
+    /// the released objects returns zeros. The caller must ensure that the objects are no longer
+    /// referenced, also after a crash. Does nothing on systems other than Linux.
+    pub(crate) fn reclaim(&self, locations: &[LocationInFile]) -> MmapFileResult<()> {
+        let mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
+        for location in locations {
+            punch_hole(&mmap_file.file, location)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn punch_hole(file: &File, location: &LocationInFile) -> MmapFileResult<()> {
+    use std::os::fd::AsRawFd;
+
+    // Safety: the range is inside the file, and the space it occupies is only deallocated.
+    let res = unsafe {
+        libc::fallocate(
+            file.as_raw_fd(),
+            libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+            location.offset.try_into()?,
+            location.len.try_into()?,
+        )
+    };
+    if res != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn punch_hole(_file: &File, _location: &LocationInFile) -> MmapFileResult<()> {
+    Ok(())
+}
+
 impl<V: ValueSerde + Debug> Writer<V> for FileHandler<V, RW> {
     fn append(&mut self, val: &V::Value) -> LocationInFile {
         trace!("Inserting object: {:?}", val);
-        let serialized = V::serialize(val).expect("Should be able to serialize");
+        let serialized = V::serialize(val, &self.serde_context).expect("Should be able to serialize");
         let len = serialized.len();
         let offset;
         {
             let mut mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
             offset = mmap_file.offset;
             debug!("Inserting object at offset: {}", offset);
-            let mmap_slice = &mut mmap_file.mmap[offset..];
-            mmap_slice[..len].copy_from_slice(&serialized);
+            assert!(
+                offset + len <= mmap_file.mmap.len(),
+                "The object at offset {offset} with length {len} exceeds the mapped file."
+            );
+            // The file was mapped for writing by `open_file`. The slice covers only the written
+            // range, so it doesn't alias the data that the readers may read at the same time.
+            let mmap_slice = unsafe {
+                std::slice::from_raw_parts_mut(mmap_file.mmap.as_mut_ptr().add(offset), len)
+            };
+            mmap_slice.copy_from_slice(&serialized);
             mmap_file
                 .mmap
                 .flush_async_range(offset, len)
@@ -254,6 +417,20 @@ impl<V: ValueSerde + Debug> Writer<V> for FileHandler<V, RW> {
     }
 }
 
+impl<V: ValueSerde> FileHandler<V, RO> {
+    /// Updates the offset of a file that was opened by [`open_file_read_only`] to the offset of the
+    /// data that was appended to the file by the process that writes to it.
+    pub(crate) fn update_offset(&self, offset: usize) -> MmapFileResult<()> {
+        let mut mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
+        if mmap_file.offset != offset {
+            trace!("Updating the offset from {} to {}", mmap_file.offset, offset);
+            mmap_file.size = mmap_file.file.metadata()?.len().try_into()?;
+            mmap_file.offset = offset;
+        }
+        Ok(())
+    }
+}
+
 impl<V: ValueSerde, Mode: TransactionKind> Reader<V> for FileHandler<V, Mode> {
     /// Returns an object from the file.
     fn get(&self, location: LocationInFile) -> MmapFileResult<Option<V::Value>> {
@@ -265,7 +442,7 @@ impl<V: ValueSerde, Mode: TransactionKind> Reader<V> for FileHandler<V, Mode> {
             )
         };
         trace!("Deserializing object: {:?}", bytes);
-        Ok(V::deserialize(&mut bytes))
+        Ok(V::deserialize(&mut bytes, &self.serde_context))
     }
 }
 
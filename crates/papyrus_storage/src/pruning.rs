@@ -0,0 +1,181 @@
+//! Interface for pruning the history of the storage.
+//!
+//! Under the [`StorageScope::Pruned`] scope the storage keeps the transactions, transaction
+//! outputs, events and state diffs only for the most recent blocks. The data of older blocks is
+//! deleted by calling [`PruningStorageWriter::prune_blocks`], and the pruning marker is advanced
+//! accordingly. The headers, the transaction hashes and the state itself are never pruned.
+//!
+//! The space that the pruned state diffs occupy in the memory mapped file is released on Linux,
+//! without changing the size of the file, in the first commit after the pruning that no reader can
+//! see the pruned state diffs in anymore, including the readers of other processes. The space of
+//! state diffs that are still pending when the writer is dropped is left for the compaction of the
+//! storage (see [`crate::compaction`]), which also shrinks the file.
+//!
+//! Import [`PruningStorageReader`] and [`PruningStorageWriter`] to read and write data related to
+//! the pruning using a [`StorageTxn`].
+
+#[cfg(test)]
+#[path = "pruning_test.rs"]
+mod pruning_test;
+
+use std::collections::VecDeque;
+
+use starknet_api::block::BlockNumber;
+use starknet_api::transaction::{EventIndexInTransactionOutput, TransactionOffsetInBlock};
+use tracing::debug;
+
+use crate::body::events::EventIndex;
+use crate::body::{delete_event_keys, delete_sender_nonce, BodyStorageReader, TransactionIndex};
+use crate::compiled_class::CasmStorageReader;
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{TransactionKind, RW};
+use crate::mmap_file::LocationInFile;
+use crate::state::StateStorageReader;
+use crate::{MarkerKind, StorageError, StorageResult, StorageScope, StorageTxn};
+
+/// Interface for reading data related to the pruning.
+pub trait PruningStorageReader {
+    /// The pruning marker is the first block number whose data wasn't pruned.
+    fn get_pruning_marker(&self) -> StorageResult<BlockNumber>;
+
+    /// Returns the first block number whose data should be kept, given the number of most recent
+    /// blocks to retain.
+    fn get_first_retained_block(&self, retained_blocks: u64) -> StorageResult<BlockNumber>;
+}
+
+/// Interface for pruning the history of the storage.
+pub trait PruningStorageWriter
+where
+    Self: Sized,
+{
+    /// Deletes the transactions, transaction outputs, events and state diffs of the blocks from
+    /// the pruning marker up to the given block number (exclusive).
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn prune_blocks(self, until: BlockNumber) -> StorageResult<Self>;
+}
+
+impl<'env, Mode: TransactionKind> PruningStorageReader for StorageTxn<'env, Mode> {
+    fn get_pruning_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::Pruned)?.unwrap_or_default())
+    }
+
+    fn get_first_retained_block(&self, retained_blocks: u64) -> StorageResult<BlockNumber> {
+        // The compiled class marker advances by reading the state diffs, so its state diff and
+        // the ones after it are always kept.
+        let synced_marker = self.get_body_marker()?.min(self.get_state_marker()?);
+        Ok(BlockNumber(synced_marker.0.saturating_sub(retained_blocks))
+            .min(self.get_compiled_class_marker()?))
+    }
+}
+
+impl<'env> PruningStorageWriter for StorageTxn<'env, RW> {
+    fn prune_blocks(mut self, until: BlockNumber) -> StorageResult<Self> {
+        if self.scope != StorageScope::Pruned {
+            return Err(StorageError::PruningNotSupported { storage_scope: self.scope });
+        }
+        let prunable_until = self.get_first_retained_block(0)?;
+        if until > prunable_until {
+            return Err(StorageError::InvalidPruningTarget { until, prunable_until });
+        }
+
+        let pruning_marker = self.get_pruning_marker()?;
+        if until <= pruning_marker {
+            debug!("Blocks until {until} are already pruned. Returning without an action.");
+            return Ok(self);
+        }
+
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let transactions_table = self.open_table(&self.tables.transactions)?;
+        let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
+        let events_table = self.open_table(&self.tables.events)?;
+        let event_keys_table = self.open_table(&self.tables.event_keys)?;
+        let sender_nonce_to_transaction_idx_table =
+            self.open_table(&self.tables.sender_nonce_to_transaction_idx)?;
+        let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
+
+        // Collect the pruned transaction outputs before deleting them to avoid modifying the table
+        // while iterating it.
+        let mut pruned_transaction_outputs = Vec::new();
+        let mut cursor = transaction_outputs_table.cursor(&self.txn)?;
+        let mut current =
+            cursor.lower_bound(&TransactionIndex(pruning_marker, TransactionOffsetInBlock(0)))?;
+        while let Some((tx_index, tx_output)) = current {
+            if tx_index.0 >= until {
+                break;
+            }
+            pruned_transaction_outputs.push((tx_index, tx_output));
+            current = cursor.next()?;
+        }
+
+        for (tx_index, tx_output) in pruned_transaction_outputs {
+            for (index, from_address) in
+                tx_output.events_contract_addresses_as_ref().iter().enumerate()
+            {
+                let event_index = EventIndex(tx_index, EventIndexInTransactionOutput(index));
+                let key = (*from_address, event_index);
+                if let Some(content) = events_table.get(&self.txn, &key)? {
+                    delete_event_keys(&self.txn, &event_keys_table, &content, event_index)?;
+                }
+                events_table.delete(&self.txn, &key)?;
+            }
+            if let Some(tx) = transactions_table.get(&self.txn, &tx_index)? {
+                delete_sender_nonce(
+                    &self.txn,
+                    &sender_nonce_to_transaction_idx_table,
+                    &tx,
+                    &tx_output,
+                    tx_index,
+                )?;
+            }
+            transactions_table.delete(&self.txn, &tx_index)?;
+            transaction_outputs_table.delete(&self.txn, &tx_index)?;
+        }
+
+        let mut pruned_state_diffs = Vec::new();
+        let mut block_number = pruning_marker;
+        while block_number < until {
+            if let Some(location) = state_diffs_table.get(&self.txn, &block_number)? {
+                pruned_state_diffs.push(location);
+            }
+            state_diffs_table.delete(&self.txn, &block_number)?;
+            block_number = block_number.unchecked_next();
+        }
+
+        markers_table.upsert(&self.txn, &MarkerKind::Pruned, &until)?;
+        self.pruned_state_diffs.extend(pruned_state_diffs);
+        Ok(self)
+    }
+}
+
+// The locations of pruned state diffs whose space wasn't reclaimed yet, together with the id of
+// the transaction that pruned them, from the oldest prune.
+#[derive(Debug, Default)]
+pub(crate) struct PendingReclaims(VecDeque<(u64, Vec<LocationInFile>)>);
+
+impl PendingReclaims {
+    pub(crate) fn push(&mut self, txn_id: u64, locations: Vec<LocationInFile>) {
+        if !locations.is_empty() {
+            self.0.push_back((txn_id, locations));
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Removes and returns the locations that were pruned by a transaction that all the readers
+    // see, given the id of the transaction that the oldest reader sees.
+    pub(crate) fn pop_reclaimable(&mut self, oldest_reader_txn_id: u64) -> Vec<LocationInFile> {
+        let mut reclaimable = Vec::new();
+        while let Some((txn_id, _)) = self.0.front() {
+            if *txn_id > oldest_reader_txn_id {
+                break;
+            }
+            if let Some((_, locations)) = self.0.pop_front() {
+                reclaimable.extend(locations);
+            }
+        }
+        reclaimable
+    }
+}
@@ -0,0 +1,107 @@
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::transaction::TransactionOffsetInBlock;
+
+use crate::body::{BodyStorageReader, TransactionIndex};
+use crate::pruning::{PruningStorageReader, PruningStorageWriter};
+use crate::state::StateStorageReader;
+use crate::test_utils::{
+    append_test_blocks,
+    get_test_storage_by_scope,
+    TEST_EVENTS_PER_TX,
+    TEST_TXS_PER_BLOCK,
+};
+use crate::{StorageError, StorageScope, StorageWriter};
+
+const N_BLOCKS: u64 = 3;
+
+#[test]
+fn prune_blocks() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::Pruned);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_pruning_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_first_retained_block(1).unwrap(), BlockNumber(N_BLOCKS - 1));
+    drop(txn);
+
+    writer.begin_rw_txn().unwrap().prune_blocks(BlockNumber(2)).unwrap().commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_pruning_marker().unwrap(), BlockNumber(2));
+    for block_number in 0..N_BLOCKS {
+        let block_number = BlockNumber(block_number);
+        let is_pruned = block_number < BlockNumber(2);
+        for offset in 0..TEST_TXS_PER_BLOCK {
+            let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            assert_eq!(txn.get_transaction(tx_index).unwrap().is_none(), is_pruned);
+            assert_eq!(txn.get_transaction_output(tx_index).unwrap().is_none(), is_pruned);
+            assert_eq!(txn.get_transaction_events(tx_index).unwrap().is_none(), is_pruned);
+            // The transaction hashes are kept.
+            assert!(txn.get_transaction_hash_by_idx(&tx_index).unwrap().is_some());
+        }
+        assert_eq!(txn.get_state_diff(block_number).unwrap().is_none(), is_pruned);
+    }
+    drop(txn);
+
+    // Only the events of the last block are left.
+    let events_stats = &reader.db_tables_stats().unwrap().tables_stats["events"];
+    assert_eq!(events_stats.entries, TEST_TXS_PER_BLOCK * TEST_EVENTS_PER_TX);
+
+    // Pruning already pruned blocks does nothing.
+    writer.begin_rw_txn().unwrap().prune_blocks(BlockNumber(1)).unwrap().commit().unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_pruning_marker().unwrap(), BlockNumber(2));
+}
+
+#[test]
+fn reclaim_pruned_state_diffs_once_not_read() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::Pruned);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+    let is_pending = |writer: &StorageWriter| !writer.pending_reclaims.lock().unwrap().is_empty();
+
+    // Without readers, the state diff is reclaimed right away.
+    writer.begin_rw_txn().unwrap().prune_blocks(BlockNumber(1)).unwrap().commit().unwrap();
+    assert!(!is_pending(&writer));
+
+    // A reader that started before the pruning still reads the state diff.
+    let txn = reader.begin_ro_txn().unwrap();
+    writer.begin_rw_txn().unwrap().prune_blocks(BlockNumber(2)).unwrap().commit().unwrap();
+    assert!(is_pending(&writer));
+    assert!(txn.get_state_diff(BlockNumber(1)).unwrap().is_some());
+
+    // The state diff is reclaimed in the first commit after the reader is done.
+    drop(txn);
+    writer.begin_rw_txn().unwrap().commit().unwrap();
+    assert!(!is_pending(&writer));
+}
+
+#[test]
+fn prune_blocks_beyond_synced_blocks() {
+    let ((_reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::Pruned);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+
+    let until = BlockNumber(N_BLOCKS + 1);
+    let Err(err) = writer.begin_rw_txn().unwrap().prune_blocks(until) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::InvalidPruningTarget { until: err_until, prunable_until }
+        if err_until == until && prunable_until == BlockNumber(N_BLOCKS)
+    );
+}
+
+#[test]
+fn prune_blocks_unsupported_scope() {
+    let ((_reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+
+    let Err(err) = writer.begin_rw_txn().unwrap().prune_blocks(BlockNumber(1)) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::PruningNotSupported { storage_scope: StorageScope::FullArchive }
+    );
+}
@@ -0,0 +1,169 @@
+//! Read-only access to a storage that is written by another process.
+//!
+//! The node that syncs the chain opens the storage with [`open_storage`](crate::open_storage).
+//! Other processes, e.g. RPC replicas or analytics jobs, can open the same storage with
+//! [`open_storage_read_only`] and read the data the node writes, without being able to write to
+//! the storage themselves.
+//!
+//! The memory mapped files are mapped read-only. Every read transaction of a read-only storage
+//! updates the offsets of the files from the `file_offsets` table, and registers the zstd
+//! dictionaries that were added since the previous transaction, so the transaction sees all the
+//! data that was committed by the writing process before it started.
+//!
+//! Note that a compaction of the storage (see [`crate::compaction`]) replaces the memory mapped
+//! files, so the storage should be opened again after a compaction.
+
+#[cfg(test)]
+#[path = "read_only_test.rs"]
+mod read_only_test;
+
+use std::sync::{Arc, Mutex};
+
+use crate::compression_utils::CompressionContext;
+use crate::db::serialization::{NoVersionValueWrapper, SerdeContext};
+use crate::db::table_types::{SimpleTable, Table};
+use crate::db::{open_env_read_only, DbConfig, DbTransaction, TableIdentifier, RO};
+use crate::encryption::EncryptionContext;
+use crate::mmap_file::{open_file_read_only, MmapFileConfig};
+use crate::{
+    register_zstd_dictionaries,
+    verify_storage_version,
+    FileHandlers,
+    OffsetKind,
+    StorageConfig,
+    StorageReader,
+    StorageResult,
+    Tables,
+};
+
+/// Opens an existing storage for reading only and returns a [`StorageReader`] to it.
+/// The storage can be written at the same time by another process that opened it with
+/// [`open_storage`](crate::open_storage), and each transaction of the returned reader sees the
+/// data that was committed by that process before the transaction started.
+///
+/// # Errors
+/// Returns [`StorageError`](crate::StorageError) if the storage doesn't exist, wasn't migrated to
+/// the current storage version or there was an error opening it.
+pub fn open_storage_read_only(storage_config: StorageConfig) -> StorageResult<StorageReader> {
+    let serde_context = SerdeContext {
+        compression: Arc::new(CompressionContext::new(storage_config.compression_codec)),
+        encryption: Arc::new(EncryptionContext::new(storage_config.encryption_key.as_ref())),
+    };
+    let db_reader = open_env_read_only(&storage_config.db_config, serde_context)?;
+    let tables = Arc::new(Tables::open(&db_reader)?);
+    let db_transaction = db_reader.begin_ro_txn()?;
+    let next_zstd_dictionary =
+        register_zstd_dictionaries(&db_transaction, &tables.zstd_dictionaries, 0)?;
+    let file_readers = open_storage_files_read_only(
+        &storage_config.db_config,
+        storage_config.mmap_file_config,
+        &db_transaction,
+        &tables.file_offsets,
+    )?;
+    drop(db_transaction);
+
+    let reader = StorageReader {
+        db_reader,
+        file_readers,
+        tables,
+        scope: storage_config.scope,
+        retained_blocks: storage_config.retained_blocks,
+        read_only_state: Some(Arc::new(ReadOnlyState {
+            next_zstd_dictionary: Mutex::new(next_zstd_dictionary),
+        })),
+        // The reverts of the writing process can't be tracked, so the state isn't cached.
+        state_cache: None,
+    };
+    verify_storage_version(reader.clone())?;
+    Ok(reader)
+}
+
+// The state of a storage that was opened by [`open_storage_read_only`], which follows the data
+// that is committed by the writing process.
+#[derive(Debug)]
+pub(crate) struct ReadOnlyState {
+    // The index of the next zstd dictionary to register. The lock is held while registering, so
+    // concurrent transactions don't register the same dictionaries, and a transaction that started
+    // before another one doesn't register an older dictionary after it.
+    next_zstd_dictionary: Mutex<u32>,
+}
+
+impl ReadOnlyState {
+    // Updates the memory mapped files and the zstd dictionaries with the data that was committed
+    // before the given transaction started.
+    pub(crate) fn update(
+        &self,
+        db_transaction: &DbTransaction<'_, RO>,
+        tables: &Tables,
+        file_readers: &FileHandlers<RO>,
+    ) -> StorageResult<()> {
+        file_readers.update_offsets(db_transaction, &tables.file_offsets)?;
+        let mut next_zstd_dictionary =
+            self.next_zstd_dictionary.lock().expect("Lock should not be poisoned");
+        *next_zstd_dictionary = register_zstd_dictionaries(
+            db_transaction,
+            &tables.zstd_dictionaries,
+            *next_zstd_dictionary,
+        )?;
+        Ok(())
+    }
+}
+
+impl FileHandlers<RO> {
+    // Updates the offsets of the files to the offsets that were committed before the given
+    // transaction started.
+    fn update_offsets(
+        &self,
+        db_transaction: &DbTransaction<'_, RO>,
+        file_offsets_table: &TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
+    ) -> StorageResult<()> {
+        let table = db_transaction.open_table(file_offsets_table)?;
+        let get_offset = |offset_kind| -> StorageResult<usize> {
+            Ok(table.get(db_transaction, &offset_kind)?.unwrap_or_default())
+        };
+        self.thin_state_diff.update_offset(get_offset(OffsetKind::ThinStateDiff)?)?;
+        self.contract_class.update_offset(get_offset(OffsetKind::ContractClass)?)?;
+        self.casm.update_offset(get_offset(OffsetKind::Casm)?)?;
+        self.deprecated_contract_class
+            .update_offset(get_offset(OffsetKind::DeprecatedContractClass)?)?;
+        Ok(())
+    }
+}
+
+fn open_storage_files_read_only(
+    db_config: &DbConfig,
+    mmap_file_config: MmapFileConfig,
+    db_transaction: &DbTransaction<'_, RO>,
+    file_offsets_table: &TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
+) -> StorageResult<FileHandlers<RO>> {
+    let table = db_transaction.open_table(file_offsets_table)?;
+    let get_offset = |offset_kind| -> StorageResult<usize> {
+        Ok(table.get(db_transaction, &offset_kind)?.unwrap_or_default())
+    };
+    Ok(FileHandlers {
+        thin_state_diff: open_file_read_only(
+            mmap_file_config.clone(),
+            db_config.path().join("thin_state_diff.dat"),
+            get_offset(OffsetKind::ThinStateDiff)?,
+            db_transaction.serde_context().clone(),
+        )?,
+        contract_class: open_file_read_only(
+            mmap_file_config.clone(),
+            db_config.path().join("contract_class.dat"),
+            get_offset(OffsetKind::ContractClass)?,
+            db_transaction.serde_context().clone(),
+        )?,
+        casm: open_file_read_only(
+            mmap_file_config.clone(),
+            db_config.path().join("casm.dat"),
+            get_offset(OffsetKind::Casm)?,
+            db_transaction.serde_context().clone(),
+        )?,
+        deprecated_contract_class: open_file_read_only(
+            mmap_file_config,
+            db_config.path().join("deprecated_contract_class.dat"),
+            get_offset(OffsetKind::DeprecatedContractClass)?,
+            db_transaction.serde_context().clone(),
+        )?,
+    })
+}
@@ -0,0 +1,98 @@
+use std::process::Command;
+
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ClassHash;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{ContractClass, ThinStateDiff};
+use starknet_api::stark_felt;
+use test_utils::get_test_state_diff;
+
+use crate::class::ClassStorageReader;
+use crate::db::DbError;
+use crate::header::HeaderStorageReader;
+use crate::read_only::open_storage_read_only;
+use crate::state::StateStorageReader;
+use crate::test_utils::{
+    append_test_blocks_with_states,
+    get_test_config,
+    get_test_storage_with_config_by_scope,
+    TestBlockState,
+};
+use crate::{open_storage, StorageError, StorageScope};
+
+// The environment variable that holds the path prefix of the storage that
+// `write_block_in_another_process` writes to.
+const STORAGE_PATH_PREFIX_ENV_VAR: &str = "READ_ONLY_TEST_STORAGE_PATH_PREFIX";
+
+fn second_block() -> TestBlockState {
+    let class_hash = ClassHash(stark_felt!("0x11"));
+    TestBlockState {
+        state_diff: ThinStateDiff {
+            declared_classes: [(class_hash, Default::default())].into(),
+            ..Default::default()
+        },
+        classes: vec![(class_hash, ContractClass { abi: "abi".to_owned(), ..Default::default() })],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn read_only_storage_reads_data_of_writer() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let block0 = TestBlockState::from(get_test_state_diff());
+    append_test_blocks_with_states(&mut writer, BlockNumber(0), vec![block0.clone()]);
+    let mmap_files_stats = reader.mmap_files_stats();
+    drop((reader, writer));
+
+    let read_only_reader = open_storage_read_only(config.clone()).unwrap();
+    let txn = read_only_reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(block0.state_diff));
+    assert_eq!(read_only_reader.mmap_files_stats(), mmap_files_stats);
+
+    // A storage can't be opened twice in the same process, so the writer runs in another process.
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "read_only::read_only_test::write_block_in_another_process"])
+        .arg("--ignored")
+        .env(STORAGE_PATH_PREFIX_ENV_VAR, &config.db_config.path_prefix)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    // Data that was written after the storage was opened is visible to new transactions.
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    drop(txn);
+    let txn = read_only_reader.begin_ro_txn().unwrap();
+    let block1 = second_block();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert_eq!(txn.get_state_diff(BlockNumber(1)).unwrap(), Some(block1.state_diff));
+    let (class_hash, class) = &block1.classes[0];
+    assert_eq!(txn.get_class(class_hash).unwrap().as_ref(), Some(class));
+    let mmap_files_stats = read_only_reader.mmap_files_stats();
+    drop(txn);
+    drop(read_only_reader);
+
+    let (reader, _writer) = open_storage(config).unwrap();
+    assert_eq!(reader.mmap_files_stats(), mmap_files_stats);
+}
+
+#[test]
+#[ignore = "Runs in another process by read_only_storage_reads_data_of_writer."]
+fn write_block_in_another_process() {
+    let (mut config, _temp_dir) = get_test_config(Some(StorageScope::FullArchive));
+    config.db_config.path_prefix = std::env::var(STORAGE_PATH_PREFIX_ENV_VAR).unwrap().into();
+    let (_reader, mut writer) = open_storage(config).unwrap();
+    append_test_blocks_with_states(&mut writer, BlockNumber(1), vec![second_block()]);
+}
+
+#[test]
+fn open_storage_read_only_without_storage() {
+    let (config, _temp_dir) = get_test_config(None);
+    assert_matches!(
+        open_storage_read_only(config).err(),
+        Some(StorageError::InnerError(DbError::FileDoesNotExist(_)))
+    );
+}
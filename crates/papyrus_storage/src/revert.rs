@@ -0,0 +1,99 @@
+//! Reverting the storage back to a block.
+//!
+//! [`revert_to_block`] reverts the blocks of the storage from a given block onwards, so the node
+//! resyncs them when it's started again. It's used by operators when the node ingested bad data.
+//!
+//! The blocks are reverted from the last block backwards, each in its own transaction, in the same
+//! order the sync reverts a block: the base layer marker, the header, the body and the state diff.
+//! So the storage is consistent after each block is reverted, and an interrupted revert can be run
+//! again. The class and compiled class markers are lowered with the state marker when the state
+//! diff of the block they point to is reverted.
+//!
+//! The revert must not run while the storage is opened by another process.
+
+#[cfg(test)]
+#[path = "revert_test.rs"]
+mod revert_test;
+
+use starknet_api::block::BlockNumber;
+use tracing::{debug, info, instrument};
+
+use crate::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::class::ClassStorageReader;
+use crate::compiled_class::CasmStorageReader;
+use crate::db::{TransactionKind, RW};
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::pruning::PruningStorageReader;
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::{StorageError, StorageResult, StorageTxn, StorageWriter};
+
+/// Reverts the blocks from `target_block` onwards and returns the header marker before the revert,
+/// so the blocks in [target_block, returned marker) were reverted. After the revert, all the
+/// markers are at most `target_block`.
+///
+/// # Errors
+/// Returns [`StorageError::InvalidRevertTarget`] if the blocks before `target_block` were pruned.
+#[instrument(skip(writer), level = "debug", err)]
+pub fn revert_to_block(
+    writer: &mut StorageWriter,
+    target_block: BlockNumber,
+) -> StorageResult<BlockNumber> {
+    let txn = writer.begin_rw_txn()?;
+    verify_revert_target(&txn, target_block)?;
+    // The header marker is the greatest marker.
+    let header_marker = txn.get_header_marker()?;
+    drop(txn);
+
+    for block_number in (target_block.0..header_marker.0).rev().map(BlockNumber) {
+        revert_block(writer.begin_rw_txn()?, block_number)?.commit()?;
+    }
+
+    let txn = writer.begin_rw_txn()?;
+    let markers = [
+        ("header", txn.get_header_marker()?),
+        ("body", txn.get_body_marker()?),
+        ("state", txn.get_state_marker()?),
+        ("class", txn.get_class_marker()?),
+        ("compiled class", txn.get_compiled_class_marker()?),
+        ("base layer", txn.get_base_layer_block_marker()?),
+    ];
+    for (marker_name, marker) in markers {
+        if marker > target_block {
+            return Err(StorageError::DBInconsistency {
+                msg: format!(
+                    "The {marker_name} marker is {marker} after reverting to block \
+                     {target_block}, the storage should be checked by storage_fsck."
+                ),
+            });
+        }
+    }
+    info!("Reverted the storage to block {target_block} from block {header_marker}.");
+    Ok(header_marker)
+}
+
+// Returns an error if the blocks before the target block were pruned.
+pub(crate) fn verify_revert_target<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    target_block: BlockNumber,
+) -> StorageResult<()> {
+    let pruning_marker = txn.get_pruning_marker()?;
+    if target_block < pruning_marker {
+        return Err(StorageError::InvalidRevertTarget { target_block, pruning_marker });
+    }
+    Ok(())
+}
+
+// Reverts the data of the given block, which must be the last block of each marker it's stored in.
+pub(crate) fn revert_block(
+    txn: StorageTxn<'_, RW>,
+    block_number: BlockNumber,
+) -> StorageResult<StorageTxn<'_, RW>> {
+    debug!("Reverting block {block_number}.");
+    // Each revert is a no-op if the data of the block wasn't stored, e.g. if the state diffs were
+    // synced up to an earlier block than the headers.
+    let txn = txn.try_revert_base_layer_marker(block_number)?;
+    let txn = txn.revert_header(block_number)?.0;
+    let txn = txn.revert_body(block_number)?.0;
+    Ok(txn.revert_state_diff(block_number)?.0)
+}
@@ -0,0 +1,97 @@
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHeader, BlockNumber};
+
+use crate::base_layer::BaseLayerStorageReader;
+use crate::body::BodyStorageReader;
+use crate::class::ClassStorageReader;
+use crate::compiled_class::CasmStorageReader;
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::pruning::PruningStorageWriter;
+use crate::revert::revert_to_block;
+use crate::state::StateStorageReader;
+use crate::test_utils::{
+    append_test_blocks,
+    get_test_block_hash,
+    get_test_storage_with_config_by_scope,
+};
+use crate::{StorageError, StorageReader, StorageScope};
+
+const N_BLOCKS: u64 = 3;
+
+fn get_markers(reader: &StorageReader) -> [BlockNumber; 6] {
+    let txn = reader.begin_ro_txn().unwrap();
+    [
+        txn.get_header_marker().unwrap(),
+        txn.get_body_marker().unwrap(),
+        txn.get_state_marker().unwrap(),
+        txn.get_class_marker().unwrap(),
+        txn.get_compiled_class_marker().unwrap(),
+        txn.get_base_layer_block_marker().unwrap(),
+    ]
+}
+
+#[test]
+fn revert_and_resync() {
+    let ((reader, mut writer), _config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+    assert_eq!(get_markers(&reader), [BlockNumber(N_BLOCKS); 6]);
+
+    assert_eq!(revert_to_block(&mut writer, BlockNumber(1)).unwrap(), BlockNumber(N_BLOCKS));
+    assert_eq!(get_markers(&reader), [BlockNumber(1); 6]);
+    let txn = reader.begin_ro_txn().unwrap();
+    assert!(txn.get_block_header(BlockNumber(0)).unwrap().is_some());
+    assert!(txn.get_block_transactions(BlockNumber(0)).unwrap().is_some());
+    for block_number in 1..N_BLOCKS {
+        assert_eq!(txn.get_block_header(BlockNumber(block_number)).unwrap(), None);
+        assert_eq!(txn.get_block_transactions(BlockNumber(block_number)).unwrap(), None);
+        assert_eq!(txn.get_state_diff(BlockNumber(block_number)).unwrap(), None);
+    }
+    drop(txn);
+
+    // Reverting to a block that wasn't stored yet does nothing.
+    assert_eq!(revert_to_block(&mut writer, BlockNumber(2)).unwrap(), BlockNumber(1));
+    assert_eq!(get_markers(&reader), [BlockNumber(1); 6]);
+
+    // The reverted blocks can be synced again.
+    append_test_blocks(&mut writer, 1..N_BLOCKS);
+    assert_eq!(get_markers(&reader), [BlockNumber(N_BLOCKS); 6]);
+}
+
+#[test]
+fn revert_partially_synced_blocks() {
+    let ((reader, mut writer), _config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..1);
+    // The header of a block whose body and state diff weren't synced yet.
+    let header = BlockHeader {
+        block_number: BlockNumber(1),
+        block_hash: get_test_block_hash(BlockNumber(1)),
+        parent_hash: get_test_block_hash(BlockNumber(0)),
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(1), &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    revert_to_block(&mut writer, BlockNumber(0)).unwrap();
+    assert_eq!(get_markers(&reader), [BlockNumber(0); 6]);
+}
+
+#[test]
+fn revert_pruned_blocks() {
+    let ((_, mut writer), _config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::Pruned);
+    append_test_blocks(&mut writer, 0..N_BLOCKS);
+    writer.begin_rw_txn().unwrap().prune_blocks(BlockNumber(2)).unwrap().commit().unwrap();
+
+    assert_matches!(
+        revert_to_block(&mut writer, BlockNumber(1)),
+        Err(StorageError::InvalidRevertTarget { pruning_marker: BlockNumber(2), .. })
+    );
+}
@@ -127,17 +127,20 @@ use crate::body::events::{
 use crate::body::TransactionIndex;
 use crate::compression_utils::{
     compress,
+    compress_class,
     decompress,
     decompress_from_reader,
-    serialize_and_compress,
+    serialize_and_compress_class,
     IsCompressed,
 };
 use crate::db::serialization::{StorageSerde, StorageSerdeError};
 use crate::header::StorageBlockHeader;
+use crate::migration::MigrationProgress;
 use crate::mmap_file::LocationInFile;
 #[cfg(test)]
 use crate::serialization::serializers_test::{create_storage_serde_test, StorageSerdeTest};
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state::trie::{BinaryNode, EdgeNode, StateRoots, TrieNode};
 use crate::version::Version;
 use crate::{MarkerKind, OffsetKind};
 
@@ -146,6 +149,10 @@ const COMPRESSION_THRESHOLD_BYTES: usize = 384;
 
 auto_storage_serde! {
     pub struct AccountDeploymentData(pub Vec<StarkFelt>);
+    pub struct BinaryNode {
+        pub left: StarkHash,
+        pub right: StarkHash,
+    }
     pub struct BlockHash(pub StarkHash);
     pub struct StorageBlockHeader {
         pub block_hash: BlockHash,
@@ -236,6 +243,11 @@ auto_storage_serde! {
         External = 1,
         L1Handler = 2,
     }
+    pub struct EdgeNode {
+        pub path: StarkFelt,
+        pub length: u8,
+        pub child: StarkHash,
+    }
     pub struct EntryPoint {
         pub function_idx: FunctionIndex,
         pub selector: EntryPointSelector,
@@ -306,6 +318,11 @@ auto_storage_serde! {
         Class = 4,
         CompiledClass = 5,
         BaseLayerBlock = 6,
+        Pruned = 7,
+    }
+    pub struct MigrationProgress {
+        pub position: Vec<u8>,
+        pub n_migrated: u64,
     }
     pub struct MessageToL1 {
         pub to_address: EthAddress,
@@ -372,6 +389,11 @@ auto_storage_serde! {
     }
     pub struct StarknetVersion(pub String);
     pub struct StateDiffCommitment(pub PoseidonHash);
+    pub struct StateRoots {
+        pub contracts_trie_root: StarkHash,
+        pub classes_trie_root: StarkHash,
+        pub global_root: GlobalRoot,
+    }
     pub struct Tip(pub u64);
     pub struct ThinDeclareTransactionOutput {
         pub actual_fee: Fee,
@@ -441,6 +463,10 @@ auto_storage_serde! {
     pub struct TransactionOffsetInBlock(pub usize);
     pub struct TransactionSignature(pub Vec<StarkFelt>);
     pub struct TransactionVersion(pub StarkFelt);
+    pub enum TrieNode {
+        Binary(BinaryNode) = 0,
+        Edge(EdgeNode) = 1,
+    }
     pub struct Version{
         pub major: u32,
         pub minor: u32,
@@ -485,6 +511,7 @@ auto_storage_serde! {
     binary(u128, read_u128, write_u128);
 
 
+    (BlockNumber, StarkHash);
     (BlockNumber, TransactionOffsetInBlock);
     (BlockHash, ClassHash);
     (ContractAddress, BlockHash);
@@ -493,6 +520,7 @@ auto_storage_serde! {
     (ContractAddress, EventIndex);
     (ContractAddress, StorageKey, BlockHash);
     (ContractAddress, StorageKey, BlockNumber);
+    (usize, EventKey, EventIndex);
     (usize, Vec<Hint>);
     (usize, Vec<String>);
 }
@@ -693,6 +721,17 @@ impl StorageSerde for StorageKey {
 ////////////////////////////////////////////////////////////////////////
 //  Primitive types.
 ////////////////////////////////////////////////////////////////////////
+// Values of tables that are used as sets.
+impl StorageSerde for () {
+    fn serialize_into(&self, _res: &mut impl std::io::Write) -> Result<(), StorageSerdeError> {
+        Ok(())
+    }
+
+    fn deserialize_from(_bytes: &mut impl std::io::Read) -> Option<Self> {
+        Some(())
+    }
+}
+
 impl StorageSerde for bool {
     fn serialize_into(&self, res: &mut impl std::io::Write) -> Result<(), StorageSerdeError> {
         u8::from(*self).serialize_into(res)
@@ -911,9 +950,9 @@ impl StorageSerde for BigUint {
 ////////////////////////////////////////////////////////////////////////
 impl StorageSerde for ContractClass {
     fn serialize_into(&self, res: &mut impl std::io::Write) -> Result<(), StorageSerdeError> {
-        serialize_and_compress(&self.sierra_program)?.serialize_into(res)?;
+        serialize_and_compress_class(&self.sierra_program)?.serialize_into(res)?;
         self.entry_points_by_type.serialize_into(res)?;
-        serialize_and_compress(&self.abi)?.serialize_into(res)?;
+        serialize_and_compress_class(&self.abi)?.serialize_into(res)?;
         Ok(())
     }
 
@@ -938,7 +977,7 @@ impl StorageSerde for DeprecatedContractClass {
         let mut to_compress: Vec<u8> = Vec::new();
         self.abi.serialize_into(&mut to_compress)?;
         self.program.serialize_into(&mut to_compress)?;
-        let compressed = compress(to_compress.as_slice())?;
+        let compressed = compress_class(to_compress.as_slice())?;
         compressed.serialize_into(res)?;
         self.entry_points_by_type.serialize_into(res)?;
         Ok(())
@@ -0,0 +1,357 @@
+//! Export and import of storage snapshots.
+//!
+//! A snapshot holds the content of all the tables of the storage and the written data of the
+//! memory mapped files, as of a single transaction. It is used to bootstrap a node without
+//! syncing the whole chain: [`export_snapshot`] writes a snapshot of an existing storage, and
+//! [`open_storage`](crate::open_storage) restores it into an empty storage when
+//! [`StorageConfig::snapshot_to_import`] is set.
+//!
+//! A snapshot can be taken at an earlier block than the markers of the storage. In that case, the
+//! later blocks are reverted in a write transaction that is exported and then aborted, so the
+//! storage itself is unchanged.
+//!
+//! The checksum of a snapshot is verified before it's imported. The tables are imported in batches
+//! of [`IMPORT_BATCH_SIZE`] entries, except for the `storage_version` table that is imported
+//! together with the memory mapped files in the last transaction. So a storage whose import was
+//! interrupted is detected by having entries without a storage version, and is rejected.
+//!
+//! A snapshot is a gzip compressed file, which holds:
+//! * A magic prefix and the version of the snapshot format.
+//! * A [`SnapshotManifest`] that records the storage versions, the chain id and the storage scope.
+//!   Snapshots that don't match the current storage versions or the storage configuration are
+//!   rejected.
+//! * The entries of each table and the content of each memory mapped file.
+//! * A CRC32 checksum of all the above.
+
+#[cfg(test)]
+#[path = "snapshot_test.rs"]
+mod snapshot_test;
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::{Compression, Crc, CrcReader, CrcWriter};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ChainId;
+use tracing::{debug, info, instrument};
+
+use crate::db::table_types::Table;
+use crate::db::{DbReader, DbWriter, TransactionKind};
+use crate::header::HeaderStorageReader;
+use crate::read_only::open_storage_read_only;
+use crate::revert::{revert_block, verify_revert_target};
+use crate::state::StateStorageReader;
+use crate::version::Version;
+use crate::{
+    open_storage_exclusively,
+    OffsetKind,
+    StorageConfig,
+    StorageError,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    Tables,
+    STORAGE_VERSION_BLOCKS,
+    STORAGE_VERSION_STATE,
+};
+
+const SNAPSHOT_MAGIC: &[u8] = b"PAPYRUS_STORAGE_SNAPSHOT";
+const SNAPSHOT_FORMAT_VERSION: u32 = 0;
+
+// The memory mapped files of the storage and the kind of their offset.
+const MMAP_FILES: [(&str, OffsetKind); 4] = [
+    ("thin_state_diff.dat", OffsetKind::ThinStateDiff),
+    ("contract_class.dat", OffsetKind::ContractClass),
+    ("casm.dat", OffsetKind::Casm),
+    ("deprecated_contract_class.dat", OffsetKind::DeprecatedContractClass),
+];
+
+// Each table entry is preceded by ENTRY, and the entries of each table are followed by
+// END_OF_TABLE.
+const ENTRY: u8 = 1;
+const END_OF_TABLE: u8 = 0;
+
+/// The number of table entries that are imported in each transaction.
+pub const IMPORT_BATCH_SIZE: usize = 100000;
+
+// The table that is imported in the last transaction of the import.
+const STORAGE_VERSION_TABLE: &str = "storage_version";
+
+/// Metadata about the storage a snapshot was taken from.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotManifest {
+    /// The state version of the storage.
+    pub state_version: Version,
+    /// The blocks version of the storage, if the blocks are stored.
+    pub blocks_version: Option<Version>,
+    /// The chain id of the storage.
+    pub chain_id: ChainId,
+    /// The scope of the storage.
+    pub scope: StorageScope,
+    /// The header marker of the storage when the snapshot was taken.
+    pub header_marker: BlockNumber,
+    /// The state marker of the storage when the snapshot was taken.
+    pub state_marker: BlockNumber,
+}
+
+impl SnapshotManifest {
+    // Returns an error if a storage opened with the given config can't be restored from the
+    // snapshot.
+    fn verify_compatibility(&self, storage_config: &StorageConfig) -> StorageResult<()> {
+        let incompatibility = if self.chain_id != storage_config.db_config.chain_id {
+            format!("the snapshot is of chain {}", self.chain_id)
+        } else if self.scope != storage_config.scope {
+            format!("the snapshot is of a {:?} storage", self.scope)
+        } else if self.state_version != STORAGE_VERSION_STATE
+            || self.blocks_version != expected_blocks_version(self.scope)
+        {
+            format!(
+                "the snapshot is of state version {} and blocks version {:?}",
+                self.state_version, self.blocks_version
+            )
+        } else {
+            return Ok(());
+        };
+        Err(StorageError::IncompatibleSnapshot { msg: incompatibility })
+    }
+}
+
+fn expected_blocks_version(scope: StorageScope) -> Option<Version> {
+    (scope != StorageScope::StateOnly).then_some(STORAGE_VERSION_BLOCKS)
+}
+
+/// Writes a snapshot of the storage to the given path and returns its manifest.
+/// If `block_number` isn't given, the snapshot is taken at the markers of the storage, which is
+/// opened read-only, so the export can run while the node is running.
+/// If `block_number` is given, the snapshot is taken as if the blocks from `block_number` onwards
+/// were reverted. The blocks are reverted in a write transaction that is aborted, so the storage is
+/// opened exclusively in this case, and the node must be stopped until the export is done.
+/// The snapshot is written to a temporary file first, so the given path holds either a complete
+/// snapshot or nothing.
+///
+/// # Errors
+/// Returns [`StorageError::InvalidRevertTarget`] if the blocks before `block_number` were pruned,
+/// and [`DbError::EnvironmentInUse`](crate::db::DbError::EnvironmentInUse) if `block_number` is
+/// given and the storage is opened by another process.
+#[instrument(skip(storage_config), level = "debug", err)]
+pub fn export_snapshot(
+    storage_config: StorageConfig,
+    snapshot_path: &Path,
+    block_number: Option<BlockNumber>,
+) -> StorageResult<SnapshotManifest> {
+    let Some(block_number) = block_number else {
+        let reader = open_storage_read_only(storage_config.clone())?;
+        return write_snapshot(&reader.begin_ro_txn()?, &storage_config, snapshot_path);
+    };
+    let (_, mut writer) = open_storage_exclusively(storage_config.clone())?;
+    let mut txn = writer.begin_rw_txn()?;
+    verify_revert_target(&txn, block_number)?;
+    // The header marker is the greatest marker.
+    for reverted_block in (block_number.0..txn.get_header_marker()?.0).rev().map(BlockNumber) {
+        txn = revert_block(txn, reverted_block)?;
+    }
+    // The transaction is aborted when it's dropped.
+    write_snapshot(&txn, &storage_config, snapshot_path)
+}
+
+fn write_snapshot<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    storage_config: &StorageConfig,
+    snapshot_path: &Path,
+) -> StorageResult<SnapshotManifest> {
+    let manifest = SnapshotManifest {
+        state_version: STORAGE_VERSION_STATE,
+        blocks_version: expected_blocks_version(storage_config.scope),
+        chain_id: storage_config.db_config.chain_id.clone(),
+        scope: storage_config.scope,
+        header_marker: txn.get_header_marker()?,
+        state_marker: txn.get_state_marker()?,
+    };
+
+    let temp_path = snapshot_path.with_extension("tmp");
+    let mut writer = CrcWriter::new(GzEncoder::new(
+        BufWriter::new(File::create(&temp_path)?),
+        Compression::fast(),
+    ));
+    writer.write_all(SNAPSHOT_MAGIC)?;
+    writer.write_u32::<BigEndian>(SNAPSHOT_FORMAT_VERSION)?;
+    write_bytes(&mut writer, &serde_json::to_vec(&manifest)?)?;
+
+    for table_name in Tables::field_names() {
+        debug!("Exporting table {table_name}.");
+        write_bytes(&mut writer, table_name.as_bytes())?;
+        txn.txn.for_each_serialized_entry(table_name, |key, value| {
+            writer.write_u8(ENTRY)?;
+            write_bytes(&mut writer, key)?;
+            write_bytes(&mut writer, value)?;
+            Ok::<_, StorageError>(())
+        })?;
+        writer.write_u8(END_OF_TABLE)?;
+    }
+
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets)?;
+    let db_path = storage_config.db_config.path();
+    for (file_name, offset_kind) in MMAP_FILES {
+        debug!("Exporting file {file_name}.");
+        // The files are append only, so their content up to the offsets of this transaction is
+        // consistent with the tables.
+        let offset = file_offsets_table.get(&txn.txn, &offset_kind)?.unwrap_or_default();
+        let len = u64::try_from(offset).expect("usize should fit in u64");
+        writer.write_u64::<BigEndian>(len)?;
+        let copied = io::copy(&mut File::open(db_path.join(file_name))?.take(len), &mut writer)?;
+        if copied != len {
+            return Err(StorageError::DBInconsistency {
+                msg: format!("The file {file_name} is shorter than its offset {offset}."),
+            });
+        }
+    }
+
+    let checksum = writer.crc().sum();
+    let mut writer = writer.into_inner();
+    writer.write_u32::<BigEndian>(checksum)?;
+    let file = writer.finish()?.into_inner().map_err(|err| err.into_error())?;
+    file.sync_all()?;
+    fs::rename(temp_path, snapshot_path)?;
+    info!("Exported a snapshot of the storage to {snapshot_path:?}: {manifest:?}.");
+    Ok(manifest)
+}
+
+// Restores the snapshot at the given path into the storage if the storage is empty. The tables
+// must already be created.
+#[instrument(skip(storage_config, db_reader, db_writer), level = "debug", err)]
+pub(crate) fn import_snapshot(
+    snapshot_path: &Path,
+    storage_config: &StorageConfig,
+    db_reader: &DbReader,
+    db_writer: &mut DbWriter,
+) -> StorageResult<()> {
+    if db_reader.get_table_stats(STORAGE_VERSION_TABLE)?.entries > 0 {
+        debug!("The storage is not empty, the snapshot is not imported.");
+        return Ok(());
+    }
+    for table_name in Tables::field_names() {
+        if db_reader.get_table_stats(table_name)?.entries > 0 {
+            return Err(invalid_snapshot(format!(
+                "the table {table_name} of the storage isn't empty but the storage has no \
+                 version, a previous import was probably interrupted and the storage should be \
+                 removed"
+            )));
+        }
+    }
+    verify_checksum(snapshot_path)?;
+
+    let mut reader = CrcReader::new(GzDecoder::new(BufReader::new(File::open(snapshot_path)?)));
+    let mut magic = vec![0; SNAPSHOT_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != SNAPSHOT_MAGIC {
+        return Err(invalid_snapshot("the file is not a storage snapshot"));
+    }
+    let format_version = reader.read_u32::<BigEndian>()?;
+    if format_version != SNAPSHOT_FORMAT_VERSION {
+        return Err(invalid_snapshot(format!("unsupported format version {format_version}")));
+    }
+    let manifest: SnapshotManifest = serde_json::from_slice(&read_bytes(&mut reader)?)?;
+    manifest.verify_compatibility(storage_config)?;
+
+    let mut txn = db_writer.begin_rw_txn()?;
+    let mut batch_entries = 0;
+    let mut storage_version_entries = Vec::new();
+    for expected_table_name in Tables::field_names() {
+        let table_name = read_bytes(&mut reader)?;
+        if table_name != expected_table_name.as_bytes() {
+            return Err(invalid_snapshot(format!("expected table {expected_table_name}")));
+        }
+        debug!("Importing table {expected_table_name}.");
+        while reader.read_u8()? == ENTRY {
+            let key = read_bytes(&mut reader)?;
+            let value = read_bytes(&mut reader)?;
+            if *expected_table_name == STORAGE_VERSION_TABLE {
+                storage_version_entries.push((key, value));
+                continue;
+            }
+            txn.append_serialized_entry(expected_table_name, &key, &value)?;
+            batch_entries += 1;
+            if batch_entries == IMPORT_BATCH_SIZE {
+                txn.commit()?;
+                txn = db_writer.begin_rw_txn()?;
+                batch_entries = 0;
+            }
+        }
+    }
+
+    let db_path = storage_config.db_config.path();
+    for (file_name, _offset_kind) in MMAP_FILES {
+        debug!("Importing file {file_name}.");
+        let len = reader.read_u64::<BigEndian>()?;
+        let mut file = File::create(db_path.join(file_name))?;
+        if io::copy(&mut reader.by_ref().take(len), &mut file)? != len {
+            return Err(invalid_snapshot(format!("the file {file_name} is truncated")));
+        }
+        file.sync_all()?;
+    }
+
+    // The checksum was already verified, this only guards against a snapshot that was modified
+    // during the import.
+    let checksum = reader.crc().sum();
+    let expected_checksum = reader.into_inner().read_u32::<BigEndian>()?;
+    if checksum != expected_checksum {
+        return Err(invalid_snapshot("checksum mismatch"));
+    }
+    for (key, value) in storage_version_entries {
+        txn.append_serialized_entry(STORAGE_VERSION_TABLE, &key, &value)?;
+    }
+    txn.commit_and_sync()?;
+    info!("Imported the storage snapshot {snapshot_path:?}: {manifest:?}.");
+    Ok(())
+}
+
+// Verifies the checksum at the end of the snapshot, without writing anything to the storage.
+fn verify_checksum(snapshot_path: &Path) -> StorageResult<()> {
+    const CHECKSUM_LEN: usize = 4;
+    let mut reader = GzDecoder::new(BufReader::new(File::open(snapshot_path)?));
+    let mut crc = Crc::new();
+    let mut buffer = vec![0; 1 << 16];
+    // The bytes that were read but weren't added to the checksum yet, since they may be the
+    // checksum itself.
+    let mut tail = Vec::new();
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        tail.extend_from_slice(&buffer[..read]);
+        let checked = tail.len().saturating_sub(CHECKSUM_LEN);
+        crc.update(&tail[..checked]);
+        tail.drain(..checked);
+    }
+    let expected_checksum = <[u8; CHECKSUM_LEN]>::try_from(tail)
+        .map_err(|_| invalid_snapshot("unexpected end of file"))?;
+    if crc.sum() != u32::from_be_bytes(expected_checksum) {
+        return Err(invalid_snapshot("checksum mismatch"));
+    }
+    Ok(())
+}
+
+fn invalid_snapshot(msg: impl Into<String>) -> StorageError {
+    StorageError::InvalidSnapshot { msg: msg.into() }
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_u64::<BigEndian>(u64::try_from(bytes.len()).expect("usize should fit in u64"))?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes(reader: &mut impl Read) -> StorageResult<Vec<u8>> {
+    let len = reader.read_u64::<BigEndian>()?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(invalid_snapshot("unexpected end of file"));
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,184 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use assert_matches::assert_matches;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use tempfile::TempDir;
+use test_utils::get_test_state_diff;
+
+use crate::body::BodyStorageReader;
+use crate::class::ClassStorageReader;
+use crate::db::DbError;
+use crate::header::HeaderStorageReader;
+use crate::snapshot::export_snapshot;
+use crate::state::StateStorageReader;
+use crate::test_utils::{
+    append_test_blocks,
+    append_test_blocks_with_states,
+    get_test_config,
+    get_test_storage_with_config_by_scope,
+    TestBlockState,
+};
+use crate::{
+    open_storage,
+    StorageConfig,
+    StorageError,
+    StorageReader,
+    StorageScope,
+    STORAGE_VERSION_BLOCKS,
+    STORAGE_VERSION_STATE,
+};
+
+// Exports a snapshot of a storage with a single block and returns its path, the reader of the
+// exported storage and the directory that holds both.
+fn export_test_snapshot() -> (PathBuf, StorageReader, TempDir) {
+    let (config, temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    append_test_blocks_with_states(
+        &mut writer,
+        BlockNumber(0),
+        vec![TestBlockState::from(get_test_state_diff())],
+    );
+
+    // A snapshot at the markers is exported while the storage is opened.
+    let snapshot_path = temp_dir.path().join("snapshot");
+    let manifest = export_snapshot(config.clone(), &snapshot_path, None).unwrap();
+    assert_eq!(manifest.state_version, STORAGE_VERSION_STATE);
+    assert_eq!(manifest.blocks_version, Some(STORAGE_VERSION_BLOCKS));
+    assert_eq!(manifest.scope, StorageScope::FullArchive);
+    assert_eq!(manifest.header_marker, BlockNumber(1));
+    assert_eq!(manifest.state_marker, BlockNumber(1));
+    (snapshot_path, reader, temp_dir)
+}
+
+fn import_config(snapshot_path: PathBuf, scope: StorageScope) -> (StorageConfig, TempDir) {
+    let (mut config, temp_dir) = get_test_config(Some(scope));
+    config.snapshot_to_import = Some(snapshot_path);
+    (config, temp_dir)
+}
+
+#[test]
+fn export_and_import_snapshot() {
+    let (snapshot_path, exported_reader, _exported_dir) = export_test_snapshot();
+    let (config, _temp_dir) = import_config(snapshot_path, StorageScope::FullArchive);
+
+    let (reader, _) = open_storage(config.clone()).unwrap();
+    let exported_tables_stats = exported_reader.db_tables_stats().unwrap().tables_stats;
+    for (table_name, table_stats) in reader.db_tables_stats().unwrap().tables_stats {
+        assert_eq!(table_stats.entries, exported_tables_stats[&table_name].entries, "{table_name}");
+    }
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let exported_txn = exported_reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.get_block_header(BlockNumber(0)).unwrap(),
+        exported_txn.get_block_header(BlockNumber(0)).unwrap()
+    );
+    assert_eq!(
+        txn.get_state_diff(BlockNumber(0)).unwrap(),
+        exported_txn.get_state_diff(BlockNumber(0)).unwrap()
+    );
+    let state_diff = txn.get_state_diff(BlockNumber(0)).unwrap().unwrap();
+    for class_hash in state_diff.declared_classes.keys() {
+        assert_eq!(txn.get_class(class_hash).unwrap(), exported_txn.get_class(class_hash).unwrap());
+    }
+    for class_hash in &state_diff.deprecated_declared_classes {
+        assert_eq!(
+            txn.get_deprecated_class(class_hash).unwrap(),
+            exported_txn.get_deprecated_class(class_hash).unwrap()
+        );
+    }
+    drop(txn);
+    drop(reader);
+
+    // A storage that isn't empty is opened without importing the snapshot again.
+    let (reader, _) = open_storage(config).unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), BlockNumber(1));
+}
+
+#[test]
+fn import_incompatible_snapshot() {
+    let (snapshot_path, _, _exported_dir) = export_test_snapshot();
+    let (config, _temp_dir) = import_config(snapshot_path, StorageScope::StateOnly);
+
+    let Err(err) = open_storage(config) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(err, StorageError::IncompatibleSnapshot { .. });
+}
+
+#[test]
+fn import_invalid_snapshot() {
+    let (snapshot_path, _, _exported_dir) = export_test_snapshot();
+    let mut snapshot = GzEncoder::new(File::create(&snapshot_path).unwrap(), Compression::fast());
+    snapshot.write_all(b"NOT_A_PAPYRUS_STORAGE_SNAPSHOT").unwrap();
+    snapshot.finish().unwrap();
+    let (mut config, _temp_dir) = import_config(snapshot_path, StorageScope::FullArchive);
+
+    let Err(err) = open_storage(config.clone()) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(err, StorageError::InvalidSnapshot { .. });
+
+    // The storage is left empty.
+    config.snapshot_to_import = None;
+    let (reader, _) = open_storage(config).unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), BlockNumber(0));
+}
+
+#[test]
+fn import_snapshot_with_checksum_mismatch() {
+    let (snapshot_path, _, _exported_dir) = export_test_snapshot();
+    let mut content = Vec::new();
+    GzDecoder::new(File::open(&snapshot_path).unwrap()).read_to_end(&mut content).unwrap();
+    *content.last_mut().unwrap() ^= 1;
+    let mut snapshot = GzEncoder::new(File::create(&snapshot_path).unwrap(), Compression::fast());
+    snapshot.write_all(&content).unwrap();
+    snapshot.finish().unwrap();
+    let (config, _temp_dir) = import_config(snapshot_path, StorageScope::FullArchive);
+
+    let Err(err) = open_storage(config.clone()) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(err, StorageError::InvalidSnapshot { .. });
+    // Nothing was written before the checksum was verified.
+    assert!(!config.db_config.path().join("thin_state_diff.dat").exists());
+}
+
+#[test]
+fn export_snapshot_at_block() {
+    let ((reader, mut writer), config, temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    append_test_blocks(&mut writer, 0..3);
+
+    // Reverting the blocks requires that the storage isn't opened by another process.
+    let snapshot_path = temp_dir.path().join("snapshot");
+    assert_matches!(
+        export_snapshot(config.clone(), &snapshot_path, Some(BlockNumber(1))),
+        Err(StorageError::InnerError(DbError::EnvironmentInUse(_)))
+    );
+    drop(writer);
+    drop(reader);
+
+    let manifest = export_snapshot(config.clone(), &snapshot_path, Some(BlockNumber(1))).unwrap();
+    assert_eq!(manifest.header_marker, BlockNumber(1));
+    assert_eq!(manifest.state_marker, BlockNumber(1));
+
+    // The exported storage is unchanged.
+    let (reader, _) = open_storage(config).unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), BlockNumber(3));
+
+    let (config, _import_dir) = import_config(snapshot_path, StorageScope::FullArchive);
+    let (reader, _) = open_storage(config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(1));
+    assert!(txn.get_block_header(BlockNumber(0)).unwrap().is_some());
+    assert!(txn.get_block_header(BlockNumber(1)).unwrap().is_none());
+}
@@ -0,0 +1,173 @@
+//! A read-through cache of the values that [`StateReader`](super::StateReader) reads from the
+//! storage and nonces tables.
+//!
+//! Each value is cached by the state number it was read at, so the cache holds historical values
+//! and is shared by all the transactions of a [`StorageReader`](crate::StorageReader), e.g. by all
+//! the calls of a batch of simulated transactions.
+//!
+//! Only values of state numbers up to the state marker are cached, since those don't change when
+//! blocks are appended. When a state diff is reverted, the values of the state numbers after the
+//! reverted block are removed from the cache once the revert is committed.
+//!
+//! The cache is split into shards by the contract address and the storage key, each with its own
+//! lock, so concurrent readers of different keys don't wait for each other.
+
+#[cfg(test)]
+#[path = "cache_test.rs"]
+mod cache_test;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use lru::LruCache;
+use metrics::increment_counter;
+use papyrus_common::metrics::{PAPYRUS_STATE_CACHE_HITS, PAPYRUS_STATE_CACHE_MISSES};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{StateNumber, StorageKey};
+
+// The number of shards of the cache.
+const N_SHARDS: usize = 16;
+
+type StorageCacheKey = (StateNumber, ContractAddress, StorageKey);
+type NonceCacheKey = (StateNumber, ContractAddress);
+
+pub(crate) struct StateCache {
+    // Incremented on every invalidation. Values that were read by a transaction that started
+    // before an invalidation might be stale, so they aren't inserted to the cache.
+    generation: AtomicU64,
+    shards: Vec<Mutex<StateCacheShard>>,
+}
+
+struct StateCacheShard {
+    // The generation of the last invalidation that removed the stale values of the shard.
+    generation: u64,
+    storage: LruCache<StorageCacheKey, StarkFelt>,
+    nonces: LruCache<NonceCacheKey, Option<Nonce>>,
+}
+
+impl StateCache {
+    // Returns None if the size is 0, which means the cache is disabled. The size is divided between
+    // the shards, rounded up.
+    pub(crate) fn new(size: usize) -> Option<Self> {
+        let shard_size = NonZeroUsize::new(size.div_ceil(N_SHARDS))?;
+        Some(Self {
+            generation: AtomicU64::new(0),
+            shards: (0..N_SHARDS)
+                .map(|_| {
+                    Mutex::new(StateCacheShard {
+                        generation: 0,
+                        storage: LruCache::new(shard_size),
+                        nonces: LruCache::new(shard_size),
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    // The generation of the cache. Should be taken before a transaction starts and passed when
+    // inserting values that were read in that transaction.
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn get_storage(
+        &self,
+        state_number: StateNumber,
+        address: ContractAddress,
+        key: StorageKey,
+    ) -> Option<StarkFelt> {
+        let value =
+            self.lock_shard(&(address, key)).storage.get(&(state_number, address, key)).copied();
+        record_lookup("storage", value.is_some());
+        value
+    }
+
+    pub(crate) fn insert_storage(
+        &self,
+        generation: u64,
+        state_number: StateNumber,
+        address: ContractAddress,
+        key: StorageKey,
+        value: StarkFelt,
+    ) {
+        let mut shard = self.lock_shard(&(address, key));
+        if shard.generation == generation {
+            shard.storage.put((state_number, address, key), value);
+        }
+    }
+
+    pub(crate) fn get_nonce(
+        &self,
+        state_number: StateNumber,
+        address: ContractAddress,
+    ) -> Option<Option<Nonce>> {
+        let value = self.lock_shard(&address).nonces.get(&(state_number, address)).copied();
+        record_lookup("nonce", value.is_some());
+        value
+    }
+
+    pub(crate) fn insert_nonce(
+        &self,
+        generation: u64,
+        state_number: StateNumber,
+        address: ContractAddress,
+        nonce: Option<Nonce>,
+    ) {
+        let mut shard = self.lock_shard(&address);
+        if shard.generation == generation {
+            shard.nonces.put((state_number, address), nonce);
+        }
+    }
+
+    // Removes the values of the states after the given block, which are no longer valid after
+    // the state diff of the block was reverted.
+    pub(crate) fn invalidate_after(&self, block_number: BlockNumber) {
+        // Transactions that start from now on take the new generation. A shard accepts values of
+        // the new generation only after its stale values were removed, and values that are
+        // inserted to a shard before that are removed with them.
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        for shard in &self.shards {
+            let mut shard = shard.lock().expect("Failed to lock state cache.");
+            shard.generation = generation;
+            let stale_storage_keys: Vec<_> = shard
+                .storage
+                .iter()
+                .filter(|((state_number, _, _), _)| state_number.0 > block_number)
+                .map(|(key, _)| *key)
+                .collect();
+            for key in stale_storage_keys {
+                shard.storage.pop(&key);
+            }
+            let stale_nonce_keys: Vec<_> = shard
+                .nonces
+                .iter()
+                .filter(|((state_number, _), _)| state_number.0 > block_number)
+                .map(|(key, _)| *key)
+                .collect();
+            for key in stale_nonce_keys {
+                shard.nonces.pop(&key);
+            }
+        }
+    }
+
+    // Locks the shard of the given key. All the state numbers of a key are in the same shard.
+    fn lock_shard<K: Hash>(&self, key: &K) -> MutexGuard<'_, StateCacheShard> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let index = (hasher.finish() % N_SHARDS as u64) as usize;
+        self.shards[index].lock().expect("Failed to lock state cache.")
+    }
+}
+
+fn record_lookup(kind: &'static str, hit: bool) {
+    if hit {
+        increment_counter!(PAPYRUS_STATE_CACHE_HITS, "kind" => kind);
+    } else {
+        increment_counter!(PAPYRUS_STATE_CACHE_MISSES, "kind" => kind);
+    }
+}
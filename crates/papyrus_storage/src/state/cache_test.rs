@@ -0,0 +1,169 @@
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StateNumber, StorageKey, ThinStateDiff};
+use starknet_api::{patricia_key, stark_felt};
+
+use crate::state::cache::StateCache;
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::get_test_storage;
+use crate::{StorageReader, StorageWriter};
+
+fn address() -> ContractAddress {
+    ContractAddress(patricia_key!("0x11"))
+}
+
+fn key() -> StorageKey {
+    StorageKey(patricia_key!("0x12"))
+}
+
+fn append_state_diff(writer: &mut StorageWriter, block_number: BlockNumber, value: StarkFelt) {
+    let state_diff = ThinStateDiff {
+        storage_diffs: indexmap! { address() => indexmap! { key() => value } },
+        nonces: indexmap! { address() => Nonce(value) },
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(block_number, state_diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+fn read_storage_and_nonce(
+    reader: &StorageReader,
+    state_number: StateNumber,
+) -> (StarkFelt, Option<Nonce>) {
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    (
+        state_reader.get_storage_at(state_number, &address(), &key()).unwrap(),
+        state_reader.get_nonce_at(state_number, &address()).unwrap(),
+    )
+}
+
+fn cached_storage_and_nonce(
+    reader: &StorageReader,
+    state_number: StateNumber,
+) -> (Option<StarkFelt>, Option<Option<Nonce>>) {
+    let state_cache = reader.state_cache.as_ref().unwrap();
+    (
+        state_cache.get_storage(state_number, address(), key()),
+        state_cache.get_nonce(state_number, address()),
+    )
+}
+
+#[test]
+fn caches_values_up_to_state_marker() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_state_diff(&mut writer, BlockNumber(0), stark_felt!("0x1"));
+    let state0 = StateNumber::right_before_block(BlockNumber(0));
+    let state1 = StateNumber::right_before_block(BlockNumber(1));
+    let state2 = StateNumber::right_before_block(BlockNumber(2));
+
+    assert_eq!(read_storage_and_nonce(&reader, state0), (StarkFelt::default(), None));
+    assert_eq!(
+        read_storage_and_nonce(&reader, state1),
+        (stark_felt!("0x1"), Some(Nonce(stark_felt!("0x1"))))
+    );
+    assert_eq!(
+        read_storage_and_nonce(&reader, state2),
+        (stark_felt!("0x1"), Some(Nonce(stark_felt!("0x1"))))
+    );
+
+    assert_eq!(cached_storage_and_nonce(&reader, state0), (Some(StarkFelt::default()), Some(None)));
+    assert_eq!(
+        cached_storage_and_nonce(&reader, state1),
+        (Some(stark_felt!("0x1")), Some(Some(Nonce(stark_felt!("0x1")))))
+    );
+    // The state after the state marker changes when the next block is appended.
+    assert_eq!(cached_storage_and_nonce(&reader, state2), (None, None));
+    append_state_diff(&mut writer, BlockNumber(1), stark_felt!("0x2"));
+    assert_eq!(
+        read_storage_and_nonce(&reader, state2),
+        (stark_felt!("0x2"), Some(Nonce(stark_felt!("0x2"))))
+    );
+}
+
+#[test]
+fn revert_invalidates_cached_values() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_state_diff(&mut writer, BlockNumber(0), stark_felt!("0x1"));
+    append_state_diff(&mut writer, BlockNumber(1), stark_felt!("0x2"));
+    let state1 = StateNumber::right_before_block(BlockNumber(1));
+    let state2 = StateNumber::right_before_block(BlockNumber(2));
+    read_storage_and_nonce(&reader, state1);
+    read_storage_and_nonce(&reader, state2);
+
+    // A transaction that started before the revert sees the reverted values.
+    let old_txn = reader.begin_ro_txn().unwrap();
+
+    let (txn, _) = writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(1)).unwrap();
+    // The cache is invalidated only when the revert is committed.
+    assert_eq!(cached_storage_and_nonce(&reader, state2).0, Some(stark_felt!("0x2")));
+    txn.commit().unwrap();
+    assert_eq!(cached_storage_and_nonce(&reader, state1).0, Some(stark_felt!("0x1")));
+    assert_eq!(cached_storage_and_nonce(&reader, state2), (None, None));
+
+    // The values that the old transaction reads aren't cached.
+    let old_state_reader = old_txn.get_state_reader().unwrap();
+    assert_eq!(
+        old_state_reader.get_storage_at(state2, &address(), &key()).unwrap(),
+        stark_felt!("0x2")
+    );
+    assert_eq!(cached_storage_and_nonce(&reader, state2), (None, None));
+
+    append_state_diff(&mut writer, BlockNumber(1), stark_felt!("0x3"));
+    assert_eq!(
+        read_storage_and_nonce(&reader, state2),
+        (stark_felt!("0x3"), Some(Nonce(stark_felt!("0x3"))))
+    );
+}
+
+#[test]
+fn cache_is_bounded() {
+    let state_cache = StateCache::new(1).unwrap();
+    let state0 = StateNumber::right_before_block(BlockNumber(0));
+    let state1 = StateNumber::right_before_block(BlockNumber(1));
+    state_cache.insert_storage(0, state0, address(), key(), stark_felt!("0x1"));
+    state_cache.insert_storage(0, state1, address(), key(), stark_felt!("0x2"));
+    assert_eq!(state_cache.get_storage(state0, address(), key()), None);
+    assert_eq!(state_cache.get_storage(state1, address(), key()), Some(stark_felt!("0x2")));
+}
+
+#[test]
+fn zero_size_disables_cache() {
+    assert!(StateCache::new(0).is_none());
+}
+
+#[test]
+fn concurrent_readers_of_different_keys() {
+    let state_cache = StateCache::new(1000).unwrap();
+    let state0 = StateNumber::right_before_block(BlockNumber(0));
+    let key = |i: u64| StorageKey(patricia_key!(i));
+    std::thread::scope(|scope| {
+        for thread in 0..4_u64 {
+            let state_cache = &state_cache;
+            scope.spawn(move || {
+                for i in (thread * 10)..(thread * 10 + 10) {
+                    state_cache.insert_storage(0, state0, address(), key(i), StarkFelt::from(i));
+                    assert_eq!(
+                        state_cache.get_storage(state0, address(), key(i)),
+                        Some(StarkFelt::from(i))
+                    );
+                }
+            });
+        }
+    });
+
+    state_cache.invalidate_after(BlockNumber(0));
+    // Values of the previous generation aren't inserted after the invalidation.
+    state_cache.insert_storage(0, state0, address(), key(100), stark_felt!("0x1"));
+    assert_eq!(state_cache.get_storage(state0, address(), key(100)), None);
+    // The values of the states up to the block are kept.
+    assert_eq!(state_cache.get_storage(state0, address(), key(1)), Some(StarkFelt::from(1_u64)));
+}
@@ -49,10 +49,12 @@
 //! # Ok::<(), papyrus_storage::StorageError>(())
 //! ```
 
+pub(crate) mod cache;
 #[doc(hidden)]
 pub mod data;
 #[cfg(test)]
 mod state_test;
+pub mod trie;
 
 use std::collections::HashSet;
 
@@ -67,11 +69,12 @@ use starknet_api::state::{ContractClass, StateNumber, StorageKey, ThinStateDiff}
 use tracing::debug;
 
 use crate::db::serialization::{NoVersionValueWrapper, VersionWrapper, VersionZeroWrapper};
-use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
+use crate::db::table_types::{DbCursor, DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbError, DbTransaction, TableHandle, TransactionKind, RW};
 #[cfg(feature = "document_calls")]
-use crate::document_calls::{add_query, StorageQuery};
+use crate::document_calls::{DocumentedCall, StorageQuery};
 use crate::mmap_file::LocationInFile;
+use crate::state::cache::StateCache;
 use crate::state::data::IndexedDeprecatedContractClass;
 use crate::{
     FileHandlers,
@@ -166,6 +169,9 @@ impl<'env, Mode: TransactionKind> StateStorageReader<Mode> for StorageTxn<'env,
         Ok(markers_table.get(&self.txn, &MarkerKind::State)?.unwrap_or_default())
     }
     fn get_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<ThinStateDiff>> {
+        #[cfg(feature = "document_calls")]
+        let _documented_call = DocumentedCall::new(StorageQuery::GetStateDiff(block_number));
+
         let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
         let state_diff_location = state_diffs_table.get(&self.txn, &block_number)?;
         match state_diff_location {
@@ -194,6 +200,9 @@ pub struct StateReader<'env, Mode: TransactionKind> {
     storage_table: ContractStorageTable<'env>,
     markers_table: MarkersTable<'env>,
     file_handlers: &'env FileHandlers<Mode>,
+    // The state cache, the generation it had when the transaction started and the state marker.
+    // Set if the values that are read can be cached.
+    state_cache: Option<(&'env StateCache, u64, BlockNumber)>,
 }
 
 impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
@@ -216,6 +225,14 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         let nonces_table = txn.txn.open_table(&txn.tables.nonces)?;
         let storage_table = txn.txn.open_table(&txn.tables.contract_storage)?;
         let markers_table = txn.txn.open_table(&txn.tables.markers)?;
+        let state_cache = match (&txn.state_cache, txn.state_cache_generation) {
+            (Some(state_cache), Some(generation)) => {
+                let state_marker =
+                    markers_table.get(&txn.txn, &MarkerKind::State)?.unwrap_or_default();
+                Some((state_cache.as_ref(), generation, state_marker))
+            }
+            _ => None,
+        };
         Ok(StateReader {
             txn: &txn.txn,
             declared_classes_table,
@@ -226,9 +243,19 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
             storage_table,
             markers_table,
             file_handlers: &txn.file_handlers,
+            state_cache,
         })
     }
 
+    // Returns the state cache and its generation if the values of the given state number can be
+    // cached. The values of the states after the state marker change when blocks are appended, so
+    // they aren't cached.
+    fn state_cache_at(&self, state_number: StateNumber) -> Option<(&'env StateCache, u64)> {
+        self.state_cache
+            .filter(|(_, _, state_marker)| state_number.is_before(*state_marker))
+            .map(|(state_cache, generation, _)| (state_cache, generation))
+    }
+
     /// Returns the class hash at a given state number.
     /// If class hash is not found, returns `None`.
     ///
@@ -245,7 +272,8 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ) -> StorageResult<Option<ClassHash>> {
         // TODO(dvir): create an attribute instead of this.
         #[cfg(feature = "document_calls")]
-        add_query(StorageQuery::GetClassHashAt(state_number, *address));
+        let _documented_call =
+            DocumentedCall::new(StorageQuery::GetClassHashAt(state_number, *address));
 
         let first_irrelevant_block: BlockNumber = state_number.block_after();
         let db_key = (*address, first_irrelevant_block);
@@ -275,8 +303,27 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         address: &ContractAddress,
     ) -> StorageResult<Option<Nonce>> {
         #[cfg(feature = "document_calls")]
-        add_query(StorageQuery::GetNonceAt(state_number, *address));
+        let _documented_call =
+            DocumentedCall::new(StorageQuery::GetNonceAt(state_number, *address));
+
+        let state_cache = self.state_cache_at(state_number);
+        if let Some((state_cache, _)) = state_cache {
+            if let Some(nonce) = state_cache.get_nonce(state_number, *address) {
+                return Ok(nonce);
+            }
+        }
+        let nonce = self.read_nonce_at(state_number, address)?;
+        if let Some((state_cache, generation)) = state_cache {
+            state_cache.insert_nonce(generation, state_number, *address, nonce);
+        }
+        Ok(nonce)
+    }
 
+    fn read_nonce_at(
+        &self,
+        state_number: StateNumber,
+        address: &ContractAddress,
+    ) -> StorageResult<Option<Nonce>> {
         // State diff updates are indexed by the block_number at which they occurred.
         let first_irrelevant_block: BlockNumber = state_number.block_after();
         // The relevant update is the last update strictly before `first_irrelevant_block`.
@@ -316,8 +363,28 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         key: &StorageKey,
     ) -> StorageResult<StarkFelt> {
         #[cfg(feature = "document_calls")]
-        add_query(StorageQuery::GetStorageAt(state_number, *address, *key));
+        let _documented_call =
+            DocumentedCall::new(StorageQuery::GetStorageAt(state_number, *address, *key));
+
+        let state_cache = self.state_cache_at(state_number);
+        if let Some((state_cache, _)) = state_cache {
+            if let Some(value) = state_cache.get_storage(state_number, *address, *key) {
+                return Ok(value);
+            }
+        }
+        let value = self.read_storage_at(state_number, address, key)?;
+        if let Some((state_cache, generation)) = state_cache {
+            state_cache.insert_storage(generation, state_number, *address, *key, value);
+        }
+        Ok(value)
+    }
 
+    fn read_storage_at(
+        &self,
+        state_number: StateNumber,
+        address: &ContractAddress,
+        key: &StorageKey,
+    ) -> StorageResult<StarkFelt> {
         // The updates to the storage key are indexed by the block_number at which they occurred.
         let first_irrelevant_block: BlockNumber = state_number.block_after();
         // The relevant update is the last update strictly before `first_irrelevant_block`.
@@ -340,6 +407,58 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         }
     }
 
+    /// Returns an iterator over the changes to the storage of a contract, ordered by the storage
+    /// key and then by the block number. Only changes from blocks in the range
+    /// [`from_block`, `to_block`] are returned.
+    ///
+    /// # Arguments
+    /// * address - contract address to iterate its storage changes.
+    /// * start - the storage key and block number to start iterating from. Changes of keys smaller
+    ///   than the start key and changes of the start key before the start block are skipped.
+    /// * from_block - the first block to return changes from.
+    /// * to_block - the last block to return changes from.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    pub fn iter_storage_diffs(
+        &self,
+        address: ContractAddress,
+        start: (StorageKey, BlockNumber),
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    ) -> StorageResult<StorageDiffsIter<'env, Mode>> {
+        let mut cursor = self.storage_table.cursor(self.txn)?;
+        let first = cursor.lower_bound(&(address, start.0, start.1))?;
+        let mut it = StorageDiffsIter { cursor, current: None, address, from_block, to_block };
+        it.current = it.skip_to_blocks_range(first)?;
+        Ok(it)
+    }
+
+    /// Returns an iterator over the changes to a single storage key of a contract in the blocks
+    /// range [`from_block`, `to_block`], ordered by the block number. Each item is the block in
+    /// which the value was changed and the new value.
+    ///
+    /// # Arguments
+    /// * address - contract address to iterate its storage changes.
+    /// * key - storage key to iterate its changes.
+    /// * from_block - the first block to return changes from.
+    /// * to_block - the last block to return changes from.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    pub fn iter_storage_key_diffs(
+        &self,
+        address: ContractAddress,
+        key: StorageKey,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    ) -> StorageResult<impl Iterator<Item = StorageResult<(BlockNumber, StarkFelt)>> + 'env> {
+        Ok(self
+            .iter_storage_diffs(address, (key, from_block), from_block, to_block)?
+            .take_while(move |diff| diff.as_ref().map_or(true, |((got_key, _), _)| *got_key == key))
+            .map(|diff| diff.map(|((_, block_number), value)| (block_number, value))))
+    }
+
     /// Returns the class definition at a given state number.
     ///
     /// If class_hash is not found, returns `None`.
@@ -359,6 +478,10 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         state_number: StateNumber,
         class_hash: &ClassHash,
     ) -> StorageResult<Option<ContractClass>> {
+        #[cfg(feature = "document_calls")]
+        let _documented_call =
+            DocumentedCall::new(StorageQuery::GetClassDefinitionAt(state_number, *class_hash));
+
         let Some(block_number) = self.declared_classes_block_table.get(self.txn, class_hash)?
         else {
             return Ok(None);
@@ -412,6 +535,12 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         state_number: StateNumber,
         class_hash: &ClassHash,
     ) -> StorageResult<Option<DeprecatedContractClass>> {
+        #[cfg(feature = "document_calls")]
+        let _documented_call = DocumentedCall::new(StorageQuery::GetDeprecatedClassDefinitionAt(
+            state_number,
+            *class_hash,
+        ));
+
         let Some(value) = self.deprecated_declared_classes_table.get(self.txn, class_hash)? else {
             return Ok(None);
         };
@@ -425,6 +554,82 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     }
 }
 
+type ContractStorageTableKeyValue = ((ContractAddress, StorageKey, BlockNumber), StarkFelt);
+
+type ContractStorageTableCursor<'txn, Mode> = DbCursor<
+    'txn,
+    Mode,
+    (ContractAddress, StorageKey, BlockNumber),
+    NoVersionValueWrapper<StarkFelt>,
+    SimpleTable,
+>;
+
+/// A change to the storage of a contract: the storage key and the block in which it was changed,
+/// and the new value.
+pub type StorageDiffsIterItem = ((StorageKey, BlockNumber), StarkFelt);
+
+/// An iterator over the changes to the storage of a single contract in a blocks range.
+/// The changes are iterated by the storage key and then by the block number. An error reading the
+/// table is returned as an item, after which the iteration ends.
+pub struct StorageDiffsIter<'txn, Mode: TransactionKind> {
+    cursor: ContractStorageTableCursor<'txn, Mode>,
+    current: Option<StorageDiffsIterItem>,
+    address: ContractAddress,
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+}
+
+impl<Mode: TransactionKind> Iterator for StorageDiffsIter<'_, Mode> {
+    type Item = StorageResult<StorageDiffsIterItem>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_storage_diff().transpose()
+    }
+}
+
+impl<Mode: TransactionKind> StorageDiffsIter<'_, Mode> {
+    /// Returns the next storage change. If there are no more changes, returns None.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error.
+    fn next_storage_diff(&mut self) -> StorageResult<Option<StorageDiffsIterItem>> {
+        let res = self.current.take();
+        if res.is_some() {
+            let next = self.cursor.next()?;
+            self.current = self.skip_to_blocks_range(next)?;
+        }
+        Ok(res)
+    }
+
+    // Returns the first change, starting from the given table entry, that belongs to the contract
+    // and was made in a block in the blocks range. Instead of scanning the changes of each key
+    // outside the range, the cursor jumps to the start of the range or to the next key.
+    fn skip_to_blocks_range(
+        &mut self,
+        mut entry: Option<ContractStorageTableKeyValue>,
+    ) -> StorageResult<Option<StorageDiffsIterItem>> {
+        loop {
+            let Some(((address, key, block_number), value)) = entry else {
+                return Ok(None);
+            };
+            if address != self.address {
+                return Ok(None);
+            }
+            if block_number < self.from_block {
+                entry = self.cursor.lower_bound(&(address, key, self.from_block))?;
+            } else if block_number > self.to_block {
+                // Skip the rest of the changes of this key.
+                entry = self.cursor.lower_bound(&(address, key, BlockNumber(u64::MAX)))?;
+                if matches!(entry, Some(((_, got_key, _), _)) if got_key == key) {
+                    entry = self.cursor.next()?;
+                }
+            } else {
+                return Ok(Some(((key, block_number), value)));
+            }
+        }
+    }
+}
+
 impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
     #[latency_histogram("storage_append_thin_state_diff_latency_seconds", false)]
     fn append_state_diff(
@@ -474,6 +679,8 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
 
         update_marker_to_next_block(&self.txn, &markers_table, MarkerKind::State, block_number)?;
 
+        self.update_state_tries(block_number, &thin_state_diff)?;
+
         advance_compiled_class_marker_over_blocks_without_classes(
             &self.txn,
             &markers_table,
@@ -485,7 +692,7 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
     }
 
     fn revert_state_diff(
-        self,
+        mut self,
         block_number: BlockNumber,
     ) -> StorageResult<(Self, Option<RevertedStateDiff>)> {
         let markers_table = self.open_table(&self.tables.markers)?;
@@ -556,6 +763,7 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             &nonces_table,
         )?;
         delete_storage_diffs(&self.txn, block_number, &thin_state_diff, &storage_table)?;
+        self.revert_state_tries(block_number, &thin_state_diff)?;
         delete_nonces(&self.txn, block_number, &thin_state_diff, &nonces_table)?;
         state_diffs_table.delete(&self.txn, &block_number)?;
         delete_replaced_classes(
@@ -564,6 +772,9 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             &thin_state_diff,
             &deployed_contracts_table,
         )?;
+        // The cached values of the states after the block are removed once the revert is
+        // committed.
+        self.reverted_state_from = Some(block_number);
 
         Ok((
             self,
@@ -14,7 +14,7 @@ use crate::class::{ClassStorageReader, ClassStorageWriter};
 use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
 use crate::state::{StateStorageReader, StateStorageWriter};
 use crate::test_utils::get_test_storage;
-use crate::StorageWriter;
+use crate::{StorageResult, StorageWriter};
 
 #[test]
 fn get_class_definition_at() {
@@ -770,3 +770,127 @@ fn declare_revert_declare_scenario() {
             .is_some()
     );
 }
+
+#[test]
+fn iter_storage_diffs() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let contract_address = ContractAddress(patricia_key!("0x11"));
+    let other_contract_address = ContractAddress(patricia_key!("0x12"));
+    let key0 = StorageKey(patricia_key!("0x0"));
+    let key1 = StorageKey(patricia_key!("0x1"));
+    let key2 = StorageKey(patricia_key!("0x2"));
+
+    let storage_diffs = vec![
+        indexmap! {
+            contract_address => indexmap! {key1 => stark_felt!("0x1"), key2 => stark_felt!("0x10")},
+            other_contract_address => indexmap! {key1 => stark_felt!("0x100")},
+        },
+        indexmap! {contract_address => indexmap! {key1 => stark_felt!("0x2")}},
+        indexmap! {contract_address => indexmap! {key2 => stark_felt!("0x20")}},
+        indexmap! {
+            contract_address => indexmap! {key1 => stark_felt!("0x3"), key2 => stark_felt!("0x30")},
+        },
+    ];
+    for (block_number, storage_diffs) in storage_diffs.into_iter().enumerate() {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(
+                BlockNumber(block_number as u64),
+                ThinStateDiff { storage_diffs, ..Default::default() },
+            )
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+
+    // All the changes of the contract.
+    let all_diffs = state_reader
+        .iter_storage_diffs(
+            contract_address,
+            (key0, BlockNumber(0)),
+            BlockNumber(0),
+            BlockNumber(3),
+        )
+        .unwrap()
+        .collect::<StorageResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        all_diffs,
+        vec![
+            ((key1, BlockNumber(0)), stark_felt!("0x1")),
+            ((key1, BlockNumber(1)), stark_felt!("0x2")),
+            ((key1, BlockNumber(3)), stark_felt!("0x3")),
+            ((key2, BlockNumber(0)), stark_felt!("0x10")),
+            ((key2, BlockNumber(2)), stark_felt!("0x20")),
+            ((key2, BlockNumber(3)), stark_felt!("0x30")),
+        ]
+    );
+
+    // Changes outside the blocks range are skipped.
+    let diffs_in_range = state_reader
+        .iter_storage_diffs(
+            contract_address,
+            (key0, BlockNumber(1)),
+            BlockNumber(1),
+            BlockNumber(2),
+        )
+        .unwrap()
+        .collect::<StorageResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        diffs_in_range,
+        vec![
+            ((key1, BlockNumber(1)), stark_felt!("0x2")),
+            ((key2, BlockNumber(2)), stark_felt!("0x20")),
+        ]
+    );
+
+    // Start from the middle of the changes of a key.
+    let diffs_from_start = state_reader
+        .iter_storage_diffs(
+            contract_address,
+            (key1, BlockNumber(2)),
+            BlockNumber(1),
+            BlockNumber(3),
+        )
+        .unwrap()
+        .collect::<StorageResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        diffs_from_start,
+        vec![
+            ((key1, BlockNumber(3)), stark_felt!("0x3")),
+            ((key2, BlockNumber(2)), stark_felt!("0x20")),
+            ((key2, BlockNumber(3)), stark_felt!("0x30")),
+        ]
+    );
+
+    // The changes of a single key.
+    let key_diffs = state_reader
+        .iter_storage_key_diffs(contract_address, key1, BlockNumber(1), BlockNumber(3))
+        .unwrap()
+        .collect::<StorageResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        key_diffs,
+        vec![(BlockNumber(1), stark_felt!("0x2")), (BlockNumber(3), stark_felt!("0x3"))]
+    );
+
+    // The other contract has no changes in the range.
+    assert!(
+        state_reader
+            .iter_storage_diffs(
+                other_contract_address,
+                (key0, BlockNumber(1)),
+                BlockNumber(1),
+                BlockNumber(3)
+            )
+            .unwrap()
+            .next()
+            .is_none()
+    );
+}
@@ -0,0 +1,587 @@
+//! Interface for handling the global state commitment.
+//!
+//! The state is committed to by Patricia-Merkle tries of height 251:
+//! - A storage trie per contract, mapping storage keys to values.
+//! - The contracts trie, mapping contract addresses to a hash of the contract's class hash, nonce
+//!   and storage trie root.
+//! - The classes trie, mapping class hashes of Cairo 1 classes to a hash of their compiled class
+//!   hash.
+//!
+//! The global root combines the roots of the contracts trie and the classes trie. See
+//! [`papyrus_common::state_commitment`] for the exact hash definitions.
+//!
+//! The tries are updated incrementally when a state diff is appended and the roots after each
+//! block are recorded. The trie nodes are stored by their hash and are shared between the tries of
+//! all the blocks, so the tries of every stored block can be traversed. Each node is also recorded
+//! under the block that created it, and reverting a block deletes the nodes it created, since no
+//! trie of an earlier block refers to them.
+//!
+//! Import [`StateTrieStorageReader`] to read the roots of the tries and proofs of their leaves
+//! using a [`StorageTxn`].
+
+#[cfg(test)]
+#[path = "trie_test.rs"]
+mod trie_test;
+
+use std::collections::HashSet;
+
+use bitvec::prelude::{BitArray, BitSlice, BitVec, Msb0};
+use papyrus_common::patricia_hash_tree::{
+    calculate_binary_node_hash,
+    calculate_edge_node_hash,
+    TreeHashFunction,
+};
+use papyrus_common::state_commitment::{
+    calculate_class_leaf_hash,
+    calculate_contract_state_hash,
+    calculate_global_root,
+    CLASSES_TRIE_HASH_FUNCTION,
+    CONTRACTS_TRIE_HASH_FUNCTION,
+    STATE_TRIE_HEIGHT,
+};
+use papyrus_proc_macros::latency_histogram;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ContractAddress, GlobalRoot};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StorageKey, ThinStateDiff};
+
+use crate::db::serialization::{NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
+use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
+use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+use crate::state::StateStorageReader;
+use crate::{StorageError, StorageResult, StorageTxn};
+
+pub(crate) type TrieNodesTable<'env> =
+    TableHandle<'env, StarkHash, VersionZeroWrapper<TrieNode>, SimpleTable>;
+pub(crate) type TrieNodesByBlockTable<'env> =
+    TableHandle<'env, (BlockNumber, StarkHash), NoVersionValueWrapper<()>, SimpleTable>;
+
+// The number of bits in a felt's byte representation that precede the bits of a trie key.
+const KEY_BITS_OFFSET: usize = 256 - STATE_TRIE_HEIGHT as usize;
+
+/// An inner node of a state trie. The leaves are not stored as nodes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum TrieNode {
+    /// A node with two children.
+    Binary(BinaryNode),
+    /// A node with a single edge.
+    Edge(EdgeNode),
+}
+
+/// A trie node with two children.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BinaryNode {
+    /// The hash of the child whose key continues with a '0' bit.
+    pub left: StarkHash,
+    /// The hash of the child whose key continues with a '1' bit.
+    pub right: StarkHash,
+}
+
+/// A trie node with a single edge.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct EdgeNode {
+    /// The bits of the edge as a big-endian number.
+    pub path: StarkFelt,
+    /// The number of bits in the edge.
+    pub length: u8,
+    /// The hash of the node at the end of the edge.
+    pub child: StarkHash,
+}
+
+/// The roots of the state tries after a block.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct StateRoots {
+    /// The root of the contracts trie.
+    pub contracts_trie_root: StarkHash,
+    /// The root of the classes trie.
+    pub classes_trie_root: StarkHash,
+    /// The global state root, as committed to in the block header.
+    pub global_root: GlobalRoot,
+}
+
+/// Interface for reading data related to the state commitment.
+pub trait StateTrieStorageReader {
+    /// Returns the roots of the state tries after the given block, or `None` if the state diff of
+    /// the block wasn't stored yet.
+    fn get_state_roots(&self, block_number: BlockNumber) -> StorageResult<Option<StateRoots>>;
+    /// Returns the root of the storage trie of the contract after the given block. The root of an
+    /// empty storage trie is zero.
+    fn get_contract_storage_root(
+        &self,
+        block_number: BlockNumber,
+        address: &ContractAddress,
+    ) -> StorageResult<StarkHash>;
+    /// Returns the nodes on the path from the given root towards the leaf of the given key,
+    /// starting from the root, together with their hashes. If the key has no leaf in the trie, the
+    /// nodes prove its absence.
+    fn get_trie_proof(
+        &self,
+        root: &StarkHash,
+        key: &StarkFelt,
+    ) -> StorageResult<Vec<(StarkHash, TrieNode)>>;
+}
+
+impl<'env, Mode: TransactionKind> StateTrieStorageReader for StorageTxn<'env, Mode> {
+    fn get_state_roots(&self, block_number: BlockNumber) -> StorageResult<Option<StateRoots>> {
+        let state_roots_table = self.open_table(&self.tables.state_roots)?;
+        Ok(state_roots_table.get(&self.txn, &block_number)?)
+    }
+
+    fn get_contract_storage_root(
+        &self,
+        block_number: BlockNumber,
+        address: &ContractAddress,
+    ) -> StorageResult<StarkHash> {
+        let contract_storage_roots_table = self.open_table(&self.tables.contract_storage_roots)?;
+        Ok(get_last_value_until(
+            &self.txn,
+            &contract_storage_roots_table,
+            address,
+            block_number.unchecked_next(),
+        )?
+        .unwrap_or_default())
+    }
+
+    fn get_trie_proof(
+        &self,
+        root: &StarkHash,
+        key: &StarkFelt,
+    ) -> StorageResult<Vec<(StarkHash, TrieNode)>> {
+        let trie_nodes_table = self.open_table(&self.tables.trie_nodes)?;
+        let key = felt_to_key(key);
+        let mut proof = vec![];
+        let mut hash = *root;
+        let mut height = 0;
+        while hash != StarkHash::ZERO && height < STATE_TRIE_HEIGHT {
+            let node = get_trie_node(&self.txn, &trie_nodes_table, &hash)?;
+            proof.push((hash, node));
+            let key_bits = &key[KEY_BITS_OFFSET + usize::from(height)..];
+            match node {
+                TrieNode::Binary(BinaryNode { left, right }) => {
+                    hash = if key_bits[0] { right } else { left };
+                    height += 1;
+                }
+                TrieNode::Edge(EdgeNode { path, length, child }) => {
+                    if key_bits[..usize::from(length)] != felt_to_path(&path, length) {
+                        break;
+                    }
+                    hash = child;
+                    height += length;
+                }
+            }
+        }
+        Ok(proof)
+    }
+}
+
+impl<'env> StorageTxn<'env, RW> {
+    // Updates the state tries with the given state diff and records the roots after the block.
+    // Assumes the state diff was already written to the state tables and that the roots of the
+    // previous block are stored.
+    #[latency_histogram("storage_update_state_tries_latency_seconds", true)]
+    pub(crate) fn update_state_tries(
+        &self,
+        block_number: BlockNumber,
+        thin_state_diff: &ThinStateDiff,
+    ) -> StorageResult<()> {
+        let trie_nodes_table = self.open_table(&self.tables.trie_nodes)?;
+        let trie_nodes_by_block_table = self.open_table(&self.tables.trie_nodes_by_block)?;
+        let contract_storage_roots_table = self.open_table(&self.tables.contract_storage_roots)?;
+        let state_roots_table = self.open_table(&self.tables.state_roots)?;
+        let deployed_contracts_table = self.open_table(&self.tables.deployed_contracts)?;
+        let nonces_table = self.open_table(&self.tables.nonces)?;
+
+        let previous_roots = match block_number.prev() {
+            None => StateRoots::default(),
+            Some(previous_block_number) => state_roots_table
+                .get(&self.txn, &previous_block_number)?
+                .ok_or(StorageError::DBInconsistency {
+                    msg: format!(
+                        "Missing state roots of block {previous_block_number} (for updating the \
+                         state tries of block {block_number})."
+                    ),
+                })?,
+        };
+        let new_trie_updater = |hash_function| TrieUpdater {
+            txn: &self.txn,
+            trie_nodes_table: &trie_nodes_table,
+            trie_nodes_by_block_table: &trie_nodes_by_block_table,
+            block_number,
+            hash_function,
+        };
+        // The storage tries use the hash function of the contracts trie.
+        let storage_tries = new_trie_updater(CONTRACTS_TRIE_HASH_FUNCTION);
+        let contracts_trie = new_trie_updater(CONTRACTS_TRIE_HASH_FUNCTION);
+        let classes_trie = new_trie_updater(CLASSES_TRIE_HASH_FUNCTION);
+
+        // Update the storage tries of the contracts whose storage changed.
+        for (address, storage_entries) in &thin_state_diff.storage_diffs {
+            let storage_root = get_last_value_until(
+                &self.txn,
+                &contract_storage_roots_table,
+                address,
+                block_number,
+            )?
+            .unwrap_or_default();
+            let new_storage_root = storage_tries.update(
+                storage_root,
+                storage_entries.iter().map(|(StorageKey(key), value)| (*key.key(), *value)),
+            )?;
+            contract_storage_roots_table.upsert(
+                &self.txn,
+                &(*address, block_number),
+                &new_storage_root,
+            )?;
+        }
+
+        // Update the leaves of all the contracts that changed.
+        let changed_contracts: HashSet<&ContractAddress> = thin_state_diff
+            .deployed_contracts
+            .keys()
+            .chain(thin_state_diff.replaced_classes.keys())
+            .chain(thin_state_diff.nonces.keys())
+            .chain(thin_state_diff.storage_diffs.keys())
+            .collect();
+        let mut contract_leaves = Vec::with_capacity(changed_contracts.len());
+        for address in changed_contracts {
+            let class_hash = get_last_value_until(
+                &self.txn,
+                &deployed_contracts_table,
+                address,
+                block_number.unchecked_next(),
+            )?
+            .unwrap_or_default();
+            let nonce = get_last_value_until(
+                &self.txn,
+                &nonces_table,
+                address,
+                block_number.unchecked_next(),
+            )?
+            .unwrap_or_default();
+            let storage_root = get_last_value_until(
+                &self.txn,
+                &contract_storage_roots_table,
+                address,
+                block_number.unchecked_next(),
+            )?
+            .unwrap_or_default();
+            contract_leaves.push((
+                *address.0.key(),
+                calculate_contract_state_hash(&class_hash, &storage_root, &nonce),
+            ));
+        }
+        let contracts_trie_root =
+            contracts_trie.update(previous_roots.contracts_trie_root, contract_leaves)?;
+
+        // Deprecated classes are not part of the classes trie.
+        let classes_trie_root = classes_trie.update(
+            previous_roots.classes_trie_root,
+            thin_state_diff.declared_classes.iter().map(|(class_hash, compiled_class_hash)| {
+                (class_hash.0, calculate_class_leaf_hash(compiled_class_hash))
+            }),
+        )?;
+
+        state_roots_table.insert(
+            &self.txn,
+            &block_number,
+            &StateRoots {
+                contracts_trie_root,
+                classes_trie_root,
+                global_root: calculate_global_root(&contracts_trie_root, &classes_trie_root),
+            },
+        )?;
+        Ok(())
+    }
+
+    // Removes the roots that were recorded for the block and the trie nodes the block created.
+    // Assumes the block is the last block whose state diff is stored, so only the tries of earlier
+    // blocks remain, and these don't refer to the nodes the block created.
+    pub(crate) fn revert_state_tries(
+        &self,
+        block_number: BlockNumber,
+        thin_state_diff: &ThinStateDiff,
+    ) -> StorageResult<()> {
+        let trie_nodes_table = self.open_table(&self.tables.trie_nodes)?;
+        let trie_nodes_by_block_table = self.open_table(&self.tables.trie_nodes_by_block)?;
+        let contract_storage_roots_table = self.open_table(&self.tables.contract_storage_roots)?;
+        let state_roots_table = self.open_table(&self.tables.state_roots)?;
+
+        for address in thin_state_diff.storage_diffs.keys() {
+            contract_storage_roots_table.delete(&self.txn, &(*address, block_number))?;
+        }
+        state_roots_table.delete(&self.txn, &block_number)?;
+
+        let mut created_nodes = vec![];
+        let mut cursor = trie_nodes_by_block_table.cursor(&self.txn)?;
+        let mut current = cursor.lower_bound(&(block_number, StarkHash::ZERO))?;
+        while let Some(((current_block_number, hash), _)) = current {
+            if current_block_number != block_number {
+                break;
+            }
+            created_nodes.push(hash);
+            current = cursor.next()?;
+        }
+        for hash in created_nodes {
+            trie_nodes_table.delete(&self.txn, &hash)?;
+            trie_nodes_by_block_table.delete(&self.txn, &(block_number, hash))?;
+        }
+        Ok(())
+    }
+
+    // Builds the state tries from the stored state diffs, starting from the given block (or from
+    // the first block if it's None), until the state diffs of about `batch_size` state entries
+    // were applied. Returns the next block to apply, or None if the tries are built up to the
+    // state marker. Used for storages that were created before the tries were added.
+    pub(crate) fn backfill_state_tries(
+        self,
+        start: Option<BlockNumber>,
+        batch_size: usize,
+    ) -> StorageResult<(Self, Option<BlockNumber>)> {
+        let state_marker = self.get_state_marker()?;
+        let mut block_number = start.unwrap_or_default();
+        let mut n_entries = 0;
+        while block_number < state_marker && n_entries < batch_size {
+            let thin_state_diff =
+                self.get_state_diff(block_number)?.ok_or(StorageError::DBInconsistency {
+                    msg: format!(
+                        "Missing state diff of block {block_number} (for building the state \
+                         tries)."
+                    ),
+                })?;
+            self.update_state_tries(block_number, &thin_state_diff)?;
+            n_entries += count_state_diff_entries(&thin_state_diff);
+            block_number = block_number.unchecked_next();
+        }
+        Ok((self, (block_number < state_marker).then_some(block_number)))
+    }
+}
+
+// Returns the number of leaves the state diff sets in the state tries. Empty state diffs count as a
+// single entry, so a batch of them still ends.
+fn count_state_diff_entries(thin_state_diff: &ThinStateDiff) -> usize {
+    let n_entries =
+        thin_state_diff.storage_diffs.values().map(|entries| entries.len()).sum::<usize>()
+            + thin_state_diff.deployed_contracts.len()
+            + thin_state_diff.replaced_classes.len()
+            + thin_state_diff.nonces.len()
+            + thin_state_diff.declared_classes.len();
+    n_entries.max(1)
+}
+
+// Returns the last value that was written for the contract strictly before the given block.
+fn get_last_value_until<'env, Mode: TransactionKind, V: ValueSerde + std::fmt::Debug>(
+    txn: &'env DbTransaction<'env, Mode>,
+    table: &'env TableHandle<'env, (ContractAddress, BlockNumber), V, SimpleTable>,
+    address: &ContractAddress,
+    first_irrelevant_block: BlockNumber,
+) -> StorageResult<Option<V::Value>> {
+    let mut cursor = table.cursor(txn)?;
+    cursor.lower_bound(&(*address, first_irrelevant_block))?;
+    match cursor.prev()? {
+        Some(((got_address, _), value)) if got_address == *address => Ok(Some(value)),
+        _ => Ok(None),
+    }
+}
+
+type Path = BitVec<u8, Msb0>;
+type KeyBits = BitArray<[u8; 32], Msb0>;
+
+// A sub-trie during an update.
+enum SubTrie {
+    Empty,
+    // A sub-trie that wasn't modified. Its root node wasn't necessarily loaded.
+    Unmodified(StarkHash),
+    // A sub-trie whose root is a binary node or a leaf.
+    Node(StarkHash),
+    // A sub-trie whose root is an edge. The edge node is stored only when it's final, since edges
+    // are merged while going up the trie.
+    Edge { path: Path, child: StarkHash },
+}
+
+// Applies the modifications of a block to a trie whose nodes are stored in the trie nodes table.
+struct TrieUpdater<'env> {
+    txn: &'env DbTransaction<'env, RW>,
+    trie_nodes_table: &'env TrieNodesTable<'env>,
+    trie_nodes_by_block_table: &'env TrieNodesByBlockTable<'env>,
+    block_number: BlockNumber,
+    hash_function: TreeHashFunction,
+}
+
+impl<'env> TrieUpdater<'env> {
+    // Sets the given leaves in the trie with the given root and returns the new root. A zero value
+    // removes the leaf. The keys are expected to be unique.
+    fn update(
+        &self,
+        root: StarkHash,
+        leaves: impl IntoIterator<Item = (StarkFelt, StarkFelt)>,
+    ) -> StorageResult<StarkHash> {
+        let mut leaves: Vec<(KeyBits, StarkFelt)> =
+            leaves.into_iter().map(|(key, value)| (felt_to_key(&key), value)).collect();
+        if leaves.is_empty() {
+            return Ok(root);
+        }
+        leaves.sort_unstable_by(|(first, _), (second, _)| first.cmp(second));
+        let trie = if root == StarkHash::ZERO { SubTrie::Empty } else { SubTrie::Unmodified(root) };
+        let updated_trie = self.update_sub_trie(trie, 0, &leaves)?;
+        self.commit(updated_trie)
+    }
+
+    // Sets the leaves in a sub-trie whose root is at the given height. All the leaves belong to
+    // this sub-trie.
+    fn update_sub_trie(
+        &self,
+        sub_trie: SubTrie,
+        height: u8,
+        leaves: &[(KeyBits, StarkFelt)],
+    ) -> StorageResult<SubTrie> {
+        if leaves.is_empty() {
+            return Ok(sub_trie);
+        }
+        if height == STATE_TRIE_HEIGHT {
+            let (_, value) = leaves.last().expect("Leaves should not be empty.");
+            return Ok(if *value == StarkFelt::ZERO {
+                SubTrie::Empty
+            } else {
+                SubTrie::Node(*value)
+            });
+        }
+        let (left, right) = self.split(sub_trie)?;
+        let bit_index = KEY_BITS_OFFSET + usize::from(height);
+        let partition_point = leaves.partition_point(|(key, _)| !key[bit_index]);
+        let left = self.update_sub_trie(left, height + 1, &leaves[..partition_point])?;
+        let right = self.update_sub_trie(right, height + 1, &leaves[partition_point..])?;
+        self.join(left, right, height + 1)
+    }
+
+    // Returns the two sub-tries under the root of a sub-trie that is not a leaf.
+    fn split(&self, sub_trie: SubTrie) -> StorageResult<(SubTrie, SubTrie)> {
+        match sub_trie {
+            SubTrie::Empty => Ok((SubTrie::Empty, SubTrie::Empty)),
+            SubTrie::Unmodified(hash) | SubTrie::Node(hash) => match self.get_node(&hash)? {
+                TrieNode::Binary(BinaryNode { left, right }) => {
+                    Ok((SubTrie::Unmodified(left), SubTrie::Unmodified(right)))
+                }
+                TrieNode::Edge(EdgeNode { path, length, child }) => {
+                    self.split(SubTrie::Edge { path: felt_to_path(&path, length), child })
+                }
+            },
+            SubTrie::Edge { path, child } => {
+                let rest = if path.len() == 1 {
+                    SubTrie::Unmodified(child)
+                } else {
+                    SubTrie::Edge { path: path[1..].to_bitvec(), child }
+                };
+                if path[0] { Ok((SubTrie::Empty, rest)) } else { Ok((rest, SubTrie::Empty)) }
+            }
+        }
+    }
+
+    // Returns the sub-trie whose children are the given sub-tries, whose roots are at the given
+    // height.
+    fn join(&self, left: SubTrie, right: SubTrie, children_height: u8) -> StorageResult<SubTrie> {
+        match (left, right) {
+            (SubTrie::Empty, SubTrie::Empty) => Ok(SubTrie::Empty),
+            (child, SubTrie::Empty) => self.prepend_to_edge(false, child, children_height),
+            (SubTrie::Empty, child) => self.prepend_to_edge(true, child, children_height),
+            (left, right) => {
+                let left = self.commit(left)?;
+                let right = self.commit(right)?;
+                let hash = calculate_binary_node_hash(self.hash_function, &left, &right);
+                self.store_node(&hash, &TrieNode::Binary(BinaryNode { left, right }))?;
+                Ok(SubTrie::Node(hash))
+            }
+        }
+    }
+
+    // Returns an edge with the given bit followed by the path to the root of the given non-empty
+    // sub-trie.
+    fn prepend_to_edge(
+        &self,
+        bit: bool,
+        child: SubTrie,
+        child_height: u8,
+    ) -> StorageResult<SubTrie> {
+        let (mut path, child) = match child {
+            SubTrie::Empty => {
+                return Err(StorageError::DBInconsistency {
+                    msg: "A trie edge can't lead to an empty sub-trie.".to_owned(),
+                });
+            }
+            SubTrie::Edge { path, child } => (path, child),
+            SubTrie::Node(hash) => (Path::new(), hash),
+            SubTrie::Unmodified(hash) if child_height == STATE_TRIE_HEIGHT => (Path::new(), hash),
+            SubTrie::Unmodified(hash) => match self.get_node(&hash)? {
+                TrieNode::Edge(EdgeNode { path, length, child }) => {
+                    (felt_to_path(&path, length), child)
+                }
+                TrieNode::Binary(_) => (Path::new(), hash),
+            },
+        };
+        path.insert(0, bit);
+        Ok(SubTrie::Edge { path, child })
+    }
+
+    // Stores the root of the sub-trie if it wasn't stored yet and returns its hash.
+    fn commit(&self, sub_trie: SubTrie) -> StorageResult<StarkHash> {
+        match sub_trie {
+            SubTrie::Empty => Ok(StarkHash::ZERO),
+            SubTrie::Unmodified(hash) | SubTrie::Node(hash) => Ok(hash),
+            SubTrie::Edge { path, child } => {
+                let edge_node = EdgeNode {
+                    path: path_to_felt(&path),
+                    length: u8::try_from(path.len()).expect("Edge length should fit in u8."),
+                    child,
+                };
+                let hash = calculate_edge_node_hash(
+                    self.hash_function,
+                    &edge_node.child,
+                    &edge_node.path,
+                    edge_node.length,
+                );
+                self.store_node(&hash, &TrieNode::Edge(edge_node))?;
+                Ok(hash)
+            }
+        }
+    }
+
+    // Stores the node if it doesn't exist yet, and records that the block created it.
+    fn store_node(&self, hash: &StarkHash, node: &TrieNode) -> StorageResult<()> {
+        if self.trie_nodes_table.get(self.txn, hash)?.is_none() {
+            self.trie_nodes_table.insert(self.txn, hash, node)?;
+            self.trie_nodes_by_block_table.insert(self.txn, &(self.block_number, *hash), &())?;
+        }
+        Ok(())
+    }
+
+    fn get_node(&self, hash: &StarkHash) -> StorageResult<TrieNode> {
+        get_trie_node(self.txn, self.trie_nodes_table, hash)
+    }
+}
+
+fn get_trie_node<'env, Mode: TransactionKind>(
+    txn: &'env DbTransaction<'env, Mode>,
+    trie_nodes_table: &'env TrieNodesTable<'env>,
+    hash: &StarkHash,
+) -> StorageResult<TrieNode> {
+    trie_nodes_table
+        .get(txn, hash)?
+        .ok_or(StorageError::DBInconsistency { msg: format!("Missing state trie node {hash}.") })
+}
+
+fn path_to_felt(path: &BitSlice<u8, Msb0>) -> StarkFelt {
+    let mut bits = KeyBits::ZERO;
+    bits[256 - path.len()..].copy_from_bitslice(path);
+    StarkFelt::new(bits.into_inner()).expect("A trie path should fit in a felt.")
+}
+
+// Only the lower bits of the felt are used as the key, as a felt may exceed the trie height.
+fn felt_to_key(felt: &StarkFelt) -> KeyBits {
+    let mut key = KeyBits::new(*felt.bytes());
+    key[..KEY_BITS_OFFSET].fill(false);
+    key
+}
+
+fn felt_to_path(felt: &StarkFelt, length: u8) -> Path {
+    KeyBits::new(*felt.bytes())[256 - usize::from(length)..].to_bitvec()
+}
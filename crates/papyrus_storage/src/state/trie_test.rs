@@ -0,0 +1,501 @@
+use std::collections::BTreeMap;
+
+use indexmap::{indexmap, IndexMap};
+use papyrus_common::patricia_hash_tree::{
+    calculate_binary_node_hash,
+    calculate_edge_node_hash,
+    TreeHashFunction,
+};
+use papyrus_common::state_commitment::{
+    calculate_class_leaf_hash,
+    calculate_contract_state_hash,
+    calculate_global_root,
+    CLASSES_TRIE_HASH_FUNCTION,
+    CONTRACTS_TRIE_HASH_FUNCTION,
+    STATE_TRIE_HEIGHT,
+};
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StorageKey, ThinStateDiff};
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::state::trie::{BinaryNode, EdgeNode, StateRoots, StateTrieStorageReader, TrieNode};
+use crate::state::StateStorageWriter;
+use crate::test_utils::get_test_storage;
+use crate::StorageReader;
+
+// Calculates the root of a trie from all of its leaves, without using the storage.
+fn calculate_root_from_scratch(
+    hash_function: TreeHashFunction,
+    leaves: &BTreeMap<StarkFelt, StarkFelt>,
+) -> StarkHash {
+    let leaves: Vec<(Vec<bool>, StarkFelt)> = leaves
+        .iter()
+        .filter(|(_, value)| **value != StarkFelt::ZERO)
+        .map(|(key, value)| (felt_to_bits(key), *value))
+        .collect();
+    match calculate_sub_root(hash_function, &leaves, 0) {
+        None => StarkHash::ZERO,
+        Some((path, child)) => finalize(hash_function, &path, child),
+    }
+}
+
+// Returns the lower bits of the felt that make up a trie key, most significant first.
+fn felt_to_bits(felt: &StarkFelt) -> Vec<bool> {
+    felt.bytes()
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| byte & (1 << i) != 0))
+        .skip(256 - usize::from(STATE_TRIE_HEIGHT))
+        .collect()
+}
+
+// Returns the path of the edge from the sub-trie's root and the hash of the node it leads to.
+fn calculate_sub_root(
+    hash_function: TreeHashFunction,
+    leaves: &[(Vec<bool>, StarkFelt)],
+    height: usize,
+) -> Option<(Vec<bool>, StarkHash)> {
+    if leaves.is_empty() {
+        return None;
+    }
+    if height == usize::from(STATE_TRIE_HEIGHT) {
+        return Some((vec![], leaves[0].1));
+    }
+    let (left, right): (Vec<_>, Vec<_>) =
+        leaves.iter().cloned().partition(|(bits, _)| !bits[height]);
+    match (
+        calculate_sub_root(hash_function, &left, height + 1),
+        calculate_sub_root(hash_function, &right, height + 1),
+    ) {
+        (None, None) => unreachable!(),
+        (Some((path, child)), None) => Some(([vec![false], path].concat(), child)),
+        (None, Some((path, child))) => Some(([vec![true], path].concat(), child)),
+        (Some((left_path, left)), Some((right_path, right))) => {
+            let left = finalize(hash_function, &left_path, left);
+            let right = finalize(hash_function, &right_path, right);
+            Some((vec![], calculate_binary_node_hash(hash_function, &left, &right)))
+        }
+    }
+}
+
+fn finalize(hash_function: TreeHashFunction, path: &[bool], child: StarkHash) -> StarkHash {
+    if path.is_empty() {
+        return child;
+    }
+    let mut bytes = [0u8; 32];
+    for (i, bit) in path.iter().rev().enumerate() {
+        if *bit {
+            bytes[31 - i / 8] |= 1 << (i % 8);
+        }
+    }
+    calculate_edge_node_hash(
+        hash_function,
+        &child,
+        &StarkFelt::new(bytes).unwrap(),
+        u8::try_from(path.len()).unwrap(),
+    )
+}
+
+// Checks that the proof is valid for the given root and returns the value of the key's leaf, or
+// None if the proof shows that the key has no leaf.
+fn verify_proof(
+    hash_function: TreeHashFunction,
+    root: StarkHash,
+    key: &StarkFelt,
+    proof: &[(StarkHash, TrieNode)],
+) -> Option<StarkFelt> {
+    let key_bits = felt_to_bits(key);
+    let mut expected_hash = root;
+    let mut height = 0;
+    for (hash, node) in proof {
+        assert_eq!(*hash, expected_hash);
+        match *node {
+            TrieNode::Binary(BinaryNode { left, right }) => {
+                assert_eq!(calculate_binary_node_hash(hash_function, &left, &right), *hash);
+                expected_hash = if key_bits[height] { right } else { left };
+                height += 1;
+            }
+            TrieNode::Edge(EdgeNode { path, length, child }) => {
+                assert_eq!(calculate_edge_node_hash(hash_function, &child, &path, length), *hash);
+                let length = usize::from(length);
+                let path_bits = &felt_to_bits(&path)[usize::from(STATE_TRIE_HEIGHT) - length..];
+                if key_bits[height..height + length] != *path_bits {
+                    return None;
+                }
+                expected_hash = child;
+                height += length;
+            }
+        }
+    }
+    if expected_hash == StarkHash::ZERO {
+        return None;
+    }
+    assert_eq!(height, usize::from(STATE_TRIE_HEIGHT), "The proof doesn't reach a leaf.");
+    Some(expected_hash)
+}
+
+// The full state, used to calculate the expected roots.
+#[derive(Clone, Default)]
+struct TestState {
+    storage: BTreeMap<ContractAddress, BTreeMap<StarkFelt, StarkFelt>>,
+    class_hashes: BTreeMap<ContractAddress, ClassHash>,
+    nonces: BTreeMap<ContractAddress, Nonce>,
+    classes: BTreeMap<StarkFelt, StarkFelt>,
+}
+
+impl TestState {
+    fn apply(&mut self, state_diff: &ThinStateDiff) {
+        for (address, class_hash) in
+            state_diff.deployed_contracts.iter().chain(state_diff.replaced_classes.iter())
+        {
+            self.class_hashes.insert(*address, *class_hash);
+        }
+        for (address, nonce) in &state_diff.nonces {
+            self.nonces.insert(*address, *nonce);
+        }
+        for (address, storage_entries) in &state_diff.storage_diffs {
+            let storage = self.storage.entry(*address).or_default();
+            for (key, value) in storage_entries {
+                storage.insert(*key.0.key(), *value);
+            }
+        }
+        for (class_hash, compiled_class_hash) in &state_diff.declared_classes {
+            self.classes.insert(class_hash.0, calculate_class_leaf_hash(compiled_class_hash));
+        }
+    }
+
+    fn roots(&self) -> StateRoots {
+        let mut contracts = BTreeMap::new();
+        let addresses = self
+            .class_hashes
+            .keys()
+            .chain(self.nonces.keys())
+            .chain(self.storage.keys())
+            .collect::<Vec<_>>();
+        for address in addresses {
+            let storage_root = calculate_root_from_scratch(
+                CONTRACTS_TRIE_HASH_FUNCTION,
+                &self.storage.get(address).cloned().unwrap_or_default(),
+            );
+            contracts.insert(
+                *address.0.key(),
+                calculate_contract_state_hash(
+                    &self.class_hashes.get(address).cloned().unwrap_or_default(),
+                    &storage_root,
+                    &self.nonces.get(address).cloned().unwrap_or_default(),
+                ),
+            );
+        }
+        let contracts_trie_root =
+            calculate_root_from_scratch(CONTRACTS_TRIE_HASH_FUNCTION, &contracts);
+        let classes_trie_root =
+            calculate_root_from_scratch(CLASSES_TRIE_HASH_FUNCTION, &self.classes);
+        StateRoots {
+            contracts_trie_root,
+            classes_trie_root,
+            global_root: calculate_global_root(&contracts_trie_root, &classes_trie_root),
+        }
+    }
+}
+
+fn storage_diff(
+    address: ContractAddress,
+    entries: &[(&str, &str)],
+) -> IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>> {
+    indexmap! {
+        address => entries
+            .iter()
+            .map(|(key, value)| (StorageKey(patricia_key!(*key)), stark_felt!(*value)))
+            .collect(),
+    }
+}
+
+fn test_state_diffs() -> Vec<ThinStateDiff> {
+    let address0 = contract_address!("0x100");
+    let address1 = contract_address!("0x101");
+    let address2 =
+        contract_address!("0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+    vec![
+        ThinStateDiff {
+            deployed_contracts: indexmap! {
+                address0 => class_hash!("0x10"),
+                address1 => class_hash!("0x11"),
+            },
+            storage_diffs: storage_diff(
+                address0,
+                &[("0x0", "0x1"), ("0x1", "0x2"), ("0x5", "0x3")],
+            ),
+            ..Default::default()
+        },
+        ThinStateDiff {
+            deployed_contracts: indexmap! { address2 => class_hash!("0x12") },
+            storage_diffs: storage_diff(address1, &[("0x7", "0x8")]),
+            declared_classes: indexmap! {
+                class_hash!("0x20") => CompiledClassHash(stark_felt!("0x200")),
+                class_hash!("0x21") => CompiledClassHash(stark_felt!("0x201")),
+            },
+            nonces: indexmap! { address0 => Nonce(stark_felt!("0x1")) },
+            ..Default::default()
+        },
+        ThinStateDiff {
+            // Deletes a leaf and modifies another one.
+            storage_diffs: storage_diff(address0, &[("0x1", "0x0"), ("0x5", "0x9")]),
+            replaced_classes: indexmap! { address1 => class_hash!("0x13") },
+            declared_classes: indexmap! {
+                class_hash!("0x22") => CompiledClassHash(stark_felt!("0x202")),
+            },
+            ..Default::default()
+        },
+        ThinStateDiff {
+            // Deletes all the leaves of the storage trie.
+            storage_diffs: storage_diff(address1, &[("0x7", "0x0")]),
+            nonces: indexmap! { address2 => Nonce(stark_felt!("0x1")) },
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn single_leaf_root() {
+    let key = stark_felt!("0x5");
+    let value = stark_felt!("0x1234");
+    let expected_root =
+        calculate_edge_node_hash(CONTRACTS_TRIE_HASH_FUNCTION, &value, &key, STATE_TRIE_HEIGHT);
+    assert_eq!(
+        calculate_root_from_scratch(CONTRACTS_TRIE_HASH_FUNCTION, &BTreeMap::from([(key, value)])),
+        expected_root
+    );
+
+    let address = contract_address!("0x100");
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            ThinStateDiff {
+                storage_diffs: storage_diff(address, &[("0x5", "0x1234")]),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+    let roots = reader.begin_ro_txn().unwrap().get_state_roots(BlockNumber(0)).unwrap().unwrap();
+    let contract_leaf =
+        calculate_contract_state_hash(&ClassHash::default(), &expected_root, &Nonce::default());
+    assert_eq!(
+        roots.contracts_trie_root,
+        calculate_edge_node_hash(
+            CONTRACTS_TRIE_HASH_FUNCTION,
+            &contract_leaf,
+            address.0.key(),
+            STATE_TRIE_HEIGHT,
+        )
+    );
+    assert_eq!(roots.classes_trie_root, StarkHash::ZERO);
+    assert_eq!(roots.global_root.0, roots.contracts_trie_root);
+}
+
+// The storage trie of the "simple" test case of cairo-lang's patricia_tree_test.py, with the
+// Pedersen root that pathfinder checked against the python implementation of Starknet.
+#[test]
+fn known_storage_root() {
+    let address = contract_address!("0x100");
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            ThinStateDiff {
+                storage_diffs: storage_diff(
+                    address,
+                    &[("0x1", "0x0"), ("0x86", "0x1"), ("0x87", "0x2")],
+                ),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_contract_storage_root(BlockNumber(0), &address).unwrap(),
+        stark_felt!("0x05458b9f8491e7c845bffa4cd36cdb3a7c29dcdf75f2809bd6f4ce65386facfc")
+    );
+}
+
+#[test]
+fn append_state_diffs_updates_roots() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut state = TestState::default();
+    for (i, state_diff) in test_state_diffs().into_iter().enumerate() {
+        let block_number = BlockNumber(i as u64);
+        state.apply(&state_diff);
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(block_number, state_diff)
+            .unwrap()
+            .commit()
+            .unwrap();
+        let roots = reader.begin_ro_txn().unwrap().get_state_roots(block_number).unwrap();
+        assert_eq!(roots, Some(state.roots()), "Wrong roots at block {block_number}.");
+    }
+}
+
+#[test]
+fn revert_state_diff_removes_roots() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let state_diffs = test_state_diffs();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (i, state_diff) in state_diffs.iter().enumerate() {
+        txn = txn.append_state_diff(BlockNumber(i as u64), state_diff.clone()).unwrap();
+    }
+    txn.commit().unwrap();
+    let last_block_number = BlockNumber(state_diffs.len() as u64 - 1);
+    let expected_roots = reader.begin_ro_txn().unwrap().get_state_roots(last_block_number).unwrap();
+    assert!(expected_roots.is_some());
+
+    let (txn, _) = writer.begin_rw_txn().unwrap().revert_state_diff(last_block_number).unwrap();
+    txn.commit().unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_state_roots(last_block_number).unwrap(), None);
+
+    // Appending the state diff again recalculates the same roots.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(last_block_number, state_diffs.last().unwrap().clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_state_roots(last_block_number).unwrap(),
+        expected_roots
+    );
+}
+
+#[test]
+fn revert_state_diff_deletes_created_trie_nodes() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut state_diffs = test_state_diffs();
+    let last_state_diff = state_diffs.pop().unwrap();
+    let last_block_number = BlockNumber(state_diffs.len() as u64);
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (i, state_diff) in state_diffs.into_iter().enumerate() {
+        txn = txn.append_state_diff(BlockNumber(i as u64), state_diff).unwrap();
+    }
+    txn.commit().unwrap();
+    let trie_nodes = get_trie_nodes(&reader);
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(last_block_number, last_state_diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert!(get_trie_nodes(&reader).len() > trie_nodes.len());
+
+    let (txn, _) = writer.begin_rw_txn().unwrap().revert_state_diff(last_block_number).unwrap();
+    txn.commit().unwrap();
+    assert_eq!(get_trie_nodes(&reader), trie_nodes);
+}
+
+fn get_trie_nodes(reader: &StorageReader) -> Vec<(StarkHash, TrieNode)> {
+    let txn = reader.begin_ro_txn().unwrap();
+    let trie_nodes_table = txn.open_table(&txn.tables.trie_nodes).unwrap();
+    let mut cursor = trie_nodes_table.cursor(&txn.txn).unwrap();
+    let mut trie_nodes = vec![];
+    while let Some(entry) = cursor.next().unwrap() {
+        trie_nodes.push(entry);
+    }
+    trie_nodes
+}
+
+#[test]
+fn historical_roots_are_kept() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut state = TestState::default();
+    let mut expected_roots = vec![];
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (i, state_diff) in test_state_diffs().into_iter().enumerate() {
+        state.apply(&state_diff);
+        expected_roots.push(Some(state.roots()));
+        txn = txn.append_state_diff(BlockNumber(i as u64), state_diff).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    for (i, expected_roots) in expected_roots.into_iter().enumerate() {
+        assert_eq!(txn.get_state_roots(BlockNumber(i as u64)).unwrap(), expected_roots);
+    }
+}
+
+#[test]
+fn trie_proofs() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let address0 = contract_address!("0x100");
+    let absent_address = contract_address!("0x555");
+    let mut states = vec![];
+    let mut state = TestState::default();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (i, state_diff) in test_state_diffs().into_iter().enumerate() {
+        state.apply(&state_diff);
+        states.push(state.clone());
+        txn = txn.append_state_diff(BlockNumber(i as u64), state_diff).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    for (i, state) in states.iter().enumerate() {
+        let block_number = BlockNumber(i as u64);
+        let roots = txn.get_state_roots(block_number).unwrap().unwrap();
+
+        // Contracts trie.
+        for address in state.class_hashes.keys().chain([&absent_address]) {
+            let proof = txn.get_trie_proof(&roots.contracts_trie_root, address.0.key()).unwrap();
+            let leaf = verify_proof(
+                CONTRACTS_TRIE_HASH_FUNCTION,
+                roots.contracts_trie_root,
+                address.0.key(),
+                &proof,
+            );
+            assert_eq!(leaf.is_some(), *address != absent_address);
+        }
+
+        // Storage trie of a contract whose storage keys are deleted over time.
+        let storage = state.storage.get(&address0).cloned().unwrap_or_default();
+        let storage_root = txn.get_contract_storage_root(block_number, &address0).unwrap();
+        assert_eq!(
+            storage_root,
+            calculate_root_from_scratch(CONTRACTS_TRIE_HASH_FUNCTION, &storage)
+        );
+        for key in ["0x0", "0x1", "0x5", "0x6"].map(|key| stark_felt!(key)) {
+            let proof = txn.get_trie_proof(&storage_root, &key).unwrap();
+            let expected_value = storage.get(&key).filter(|value| **value != StarkFelt::ZERO);
+            assert_eq!(
+                verify_proof(CONTRACTS_TRIE_HASH_FUNCTION, storage_root, &key, &proof).as_ref(),
+                expected_value
+            );
+        }
+
+        // Classes trie.
+        for class_hash in [stark_felt!("0x20"), stark_felt!("0x22")] {
+            let proof = txn.get_trie_proof(&roots.classes_trie_root, &class_hash).unwrap();
+            assert_eq!(
+                verify_proof(
+                    CLASSES_TRIE_HASH_FUNCTION,
+                    roots.classes_trie_root,
+                    &class_hash,
+                    &proof
+                )
+                .as_ref(),
+                state.classes.get(&class_hash)
+            );
+        }
+    }
+}
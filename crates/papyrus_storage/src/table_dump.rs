@@ -0,0 +1,576 @@
+//! Dump and load of storage tables as JSON lines.
+//!
+//! [`dump_tables`] writes the entries of the given tables, for the blocks up to an end block, to
+//! `<table_name>.jsonl` files, with one `{"key": ..., "value": ...}` JSON object per line.
+//! [`load_table_dump`] loads such files back into an empty storage. This is used for reproducing
+//! bugs with a slice of the data of a real network. A dump always starts at the first block, since
+//! a storage can't hold blocks without the blocks before them.
+//!
+//! The markers are clamped to the end block, and the entries of the other tables that aren't
+//! indexed by a block (e.g. the trie nodes) are dumped regardless of the end block. Each table is
+//! dumped in its own transaction, so a long dump doesn't keep a single read transaction open. The
+//! blocks before the end block don't change between the transactions, unless they are reverted.
+//!
+//! The dump is loaded in batches of [`LOAD_BATCH_SIZE`] entries, one transaction each, and the
+//! markers are loaded in the last transaction. So until the load is done, the markers of the
+//! storage are those of an empty storage, and its readers don't see the loaded blocks. A storage
+//! whose load was interrupted isn't empty, so the dump can't be loaded into it again.
+//!
+//! The tables that point to objects in the memory mapped files are dumped with the objects
+//! themselves, which are appended to the files of the storage when the dump is loaded. So a memory
+//! mapped file is dumped by dumping its table, and the names of the files can be given to
+//! [`dump_tables`] instead of the names of their tables: `thin_state_diff` for `state_diffs`,
+//! `contract_class` for `declared_classes`, `casm` for `casms` and `deprecated_contract_class`
+//! for `deprecated_declared_classes`. The `file_offsets` table is set by the loaded objects, and
+//! the `compacted_files` table is cleared when the storage is opened, so they aren't dumped.
+
+#[cfg(test)]
+#[path = "table_dump_test.rs"]
+mod table_dump_test;
+
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use tracing::{debug, info};
+
+use crate::db::serialization::{Key, ValueSerde};
+use crate::db::table_types::{SimpleTable, Table};
+use crate::db::{DbIter, TableIdentifier, RO, RW};
+use crate::state::data::IndexedDeprecatedContractClass;
+use crate::{
+    open_storage_without_migrations,
+    OffsetKind,
+    StorageConfig,
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageTxn,
+    TableName,
+};
+
+/// The number of entries that are loaded in each transaction.
+pub const LOAD_BATCH_SIZE: usize = 10000;
+
+// The memory mapped files and the tables that point to them.
+const MMAP_FILE_TABLES: [(&str, TableName); 4] = [
+    ("thin_state_diff", TableName::state_diffs),
+    ("contract_class", TableName::declared_classes),
+    ("casm", TableName::casms),
+    ("deprecated_contract_class", TableName::deprecated_declared_classes),
+];
+
+// The tables that aren't empty in a new storage.
+const INITIALIZED_TABLES: [TableName; 1] = [TableName::storage_version];
+
+#[derive(Serialize, Deserialize)]
+struct DumpEntry<K, V> {
+    key: K,
+    value: V,
+}
+
+// The dumped value of the deprecated_declared_classes table.
+#[derive(Serialize, Deserialize)]
+struct DumpedDeprecatedClass {
+    block_number: BlockNumber,
+    class: DeprecatedContractClass,
+}
+
+/// Dumps the entries of the given tables (or memory mapped files) for the blocks before
+/// `end_block` to `<table_name>.jsonl` files in `output_dir`.
+///
+/// The dump always starts at the first block, so it can be loaded into an empty storage.
+pub fn dump_tables(
+    reader: &StorageReader,
+    table_names: &[String],
+    end_block: BlockNumber,
+    output_dir: &Path,
+) -> StorageResult<()> {
+    std::fs::create_dir_all(output_dir)?;
+    let block_range = BlockNumber(0)..end_block;
+    for name in table_names {
+        let table = resolve_table_name(name)?;
+        let file_path = output_dir.join(format!("{}.jsonl", table.as_str()));
+        let mut writer = BufWriter::new(File::create(&file_path)?);
+        let dumped_entries = dump_table(&reader.begin_ro_txn()?, table, &block_range, &mut writer)?;
+        writer.flush()?;
+        info!("Dumped {dumped_entries} entries of the {table:?} table to {file_path:?}.");
+    }
+    Ok(())
+}
+
+/// Loads the `<table_name>.jsonl` files in `dump_dir` into the storage. The storage must be empty.
+pub fn load_table_dump(storage_config: StorageConfig, dump_dir: &Path) -> StorageResult<()> {
+    load_table_dump_in_batches(storage_config, dump_dir, LOAD_BATCH_SIZE)
+}
+
+fn load_table_dump_in_batches(
+    storage_config: StorageConfig,
+    dump_dir: &Path,
+    batch_size: usize,
+) -> StorageResult<()> {
+    let (reader, mut writer) = open_storage_without_migrations(storage_config)?;
+    for (table_name, stats) in reader.db_tables_stats()?.tables_stats {
+        if stats.entries > 0
+            && !INITIALIZED_TABLES.iter().any(|table| table.as_str() == table_name.as_str())
+        {
+            return Err(StorageError::InvalidTableDump {
+                msg: format!(
+                    "the storage is not empty, the {table_name} table has entries. If a previous \
+                     load was interrupted, the storage should be removed"
+                ),
+            });
+        }
+    }
+
+    // The markers are loaded last, so the storage is empty for its readers until the load is done.
+    let tables = TableName::ALL.iter().filter(|table| **table != TableName::markers);
+    for table in tables.chain([&TableName::markers]) {
+        let file_path = dump_dir.join(format!("{}.jsonl", table.as_str()));
+        if !file_path.exists() {
+            continue;
+        }
+        let mut lines = BufReader::new(File::open(&file_path)?).lines().peekable();
+        let mut loaded_entries = 0;
+        while lines.peek().is_some() {
+            let txn = writer.begin_rw_txn()?;
+            loaded_entries += load_table(&txn, *table, lines.by_ref().take(batch_size))?;
+            txn.commit()?;
+        }
+        info!("Loaded {loaded_entries} entries of the {table:?} table from {file_path:?}.");
+    }
+    Ok(())
+}
+
+fn resolve_table_name(name: &str) -> StorageResult<TableName> {
+    if let Some((_, table)) = MMAP_FILE_TABLES.iter().find(|(file_name, _)| *file_name == name) {
+        return Ok(*table);
+    }
+    TableName::ALL.iter().find(|table| table.as_str() == name).copied().ok_or_else(|| {
+        StorageError::InvalidTableDump { msg: format!("unknown table or file {name}") }
+    })
+}
+
+fn no_block_number<K, V>(_key: &K, _value: &V) -> StorageResult<Option<BlockNumber>> {
+    Ok(None)
+}
+
+fn dump_table(
+    txn: &StorageTxn<'_, RO>,
+    table: TableName,
+    block_range: &Range<BlockNumber>,
+    writer: &mut impl Write,
+) -> StorageResult<usize> {
+    let tables = &txn.tables;
+    // The classes are dumped by the block they were declared in.
+    let declared_classes_block = txn.open_table(&tables.declared_classes_block)?;
+    let declared_class_block_number =
+        |class_hash: &_, _: &_| Ok(declared_classes_block.get(&txn.txn, class_hash)?);
+    match table {
+        TableName::block_hash_to_number => dump_entries(
+            txn,
+            &tables.block_hash_to_number,
+            block_range,
+            writer,
+            |_, block_number| Ok(Some(*block_number)),
+            Ok,
+        ),
+        TableName::block_signatures => dump_block_entries(
+            txn,
+            &tables.block_signatures,
+            block_range,
+            writer,
+            |block_number| *block_number,
+            Ok,
+        ),
+        TableName::casms => dump_entries(
+            txn,
+            &tables.casms,
+            block_range,
+            writer,
+            declared_class_block_number,
+            |location| txn.file_handlers.get_casm_unchecked(location),
+        ),
+        TableName::compacted_files => {
+            debug!("The compacted_files table is cleared on opening, it isn't dumped.");
+            Ok(0)
+        }
+        TableName::contract_storage => dump_entries(
+            txn,
+            &tables.contract_storage,
+            block_range,
+            writer,
+            |(_, _, block_number), _| Ok(Some(*block_number)),
+            Ok,
+        ),
+        TableName::contract_storage_roots => dump_entries(
+            txn,
+            &tables.contract_storage_roots,
+            block_range,
+            writer,
+            |(_, block_number), _| Ok(Some(*block_number)),
+            Ok,
+        ),
+        TableName::declared_classes => dump_entries(
+            txn,
+            &tables.declared_classes,
+            block_range,
+            writer,
+            declared_class_block_number,
+            |location| txn.file_handlers.get_contract_class_unchecked(location),
+        ),
+        TableName::declared_classes_block => dump_entries(
+            txn,
+            &tables.declared_classes_block,
+            block_range,
+            writer,
+            |_, block_number| Ok(Some(*block_number)),
+            Ok,
+        ),
+        TableName::deprecated_declared_classes => dump_entries(
+            txn,
+            &tables.deprecated_declared_classes,
+            block_range,
+            writer,
+            |_, indexed_class| Ok(Some(indexed_class.block_number)),
+            |indexed_class| {
+                Ok(DumpedDeprecatedClass {
+                    block_number: indexed_class.block_number,
+                    class: txn
+                        .file_handlers
+                        .get_deprecated_contract_class_unchecked(indexed_class.location_in_file)?,
+                })
+            },
+        ),
+        TableName::deployed_contracts => dump_entries(
+            txn,
+            &tables.deployed_contracts,
+            block_range,
+            writer,
+            |(_, block_number), _| Ok(Some(*block_number)),
+            Ok,
+        ),
+        TableName::event_keys => dump_entries(
+            txn,
+            &tables.event_keys,
+            block_range,
+            writer,
+            |(_, _, event_index), _| Ok(Some(event_index.0.0)),
+            Ok,
+        ),
+        TableName::events => dump_entries(
+            txn,
+            &tables.events,
+            block_range,
+            writer,
+            |(_, event_index), _| Ok(Some(event_index.0.0)),
+            Ok,
+        ),
+        TableName::headers => dump_block_entries(
+            txn,
+            &tables.headers,
+            block_range,
+            writer,
+            |block_number| *block_number,
+            Ok,
+        ),
+        TableName::markers => {
+            dump_entries(txn, &tables.markers, block_range, writer, no_block_number, |marker| {
+                Ok(marker.min(block_range.end))
+            })
+        }
+        TableName::nonces => dump_entries(
+            txn,
+            &tables.nonces,
+            block_range,
+            writer,
+            |(_, block_number), _| Ok(Some(*block_number)),
+            Ok,
+        ),
+        TableName::file_offsets => {
+            debug!("The file_offsets table is set by the loaded objects, it isn't dumped.");
+            Ok(0)
+        }
+        TableName::sender_nonce_to_transaction_idx => dump_entries(
+            txn,
+            &tables.sender_nonce_to_transaction_idx,
+            block_range,
+            writer,
+            |_, transaction_index| Ok(Some(transaction_index.0)),
+            Ok,
+        ),
+        TableName::state_diffs => dump_block_entries(
+            txn,
+            &tables.state_diffs,
+            block_range,
+            writer,
+            |block_number| *block_number,
+            |location| txn.file_handlers.get_thin_state_diff_unchecked(location),
+        ),
+        TableName::state_roots => dump_block_entries(
+            txn,
+            &tables.state_roots,
+            block_range,
+            writer,
+            |block_number| *block_number,
+            Ok,
+        ),
+        TableName::transaction_hash_to_idx => dump_entries(
+            txn,
+            &tables.transaction_hash_to_idx,
+            block_range,
+            writer,
+            |_, transaction_index| Ok(Some(transaction_index.0)),
+            Ok,
+        ),
+        TableName::transaction_idx_to_hash => dump_block_entries(
+            txn,
+            &tables.transaction_idx_to_hash,
+            block_range,
+            writer,
+            |transaction_index| transaction_index.0,
+            Ok,
+        ),
+        TableName::transaction_outputs => dump_block_entries(
+            txn,
+            &tables.transaction_outputs,
+            block_range,
+            writer,
+            |transaction_index| transaction_index.0,
+            Ok,
+        ),
+        TableName::transactions => dump_block_entries(
+            txn,
+            &tables.transactions,
+            block_range,
+            writer,
+            |transaction_index| transaction_index.0,
+            Ok,
+        ),
+        TableName::trie_nodes => {
+            dump_entries(txn, &tables.trie_nodes, block_range, writer, no_block_number, Ok)
+        }
+        TableName::trie_nodes_by_block => dump_block_entries(
+            txn,
+            &tables.trie_nodes_by_block,
+            block_range,
+            writer,
+            |(block_number, _)| *block_number,
+            Ok,
+        ),
+        TableName::zstd_dictionaries => {
+            dump_entries(txn, &tables.zstd_dictionaries, block_range, writer, no_block_number, Ok)
+        }
+        TableName::migration_progress => {
+            dump_entries(txn, &tables.migration_progress, block_range, writer, no_block_number, Ok)
+        }
+        // The version of each block is the version of the last block before it that is in the
+        // table, so all the entries before the end block are dumped.
+        TableName::starknet_version => dump_block_entries(
+            txn,
+            &tables.starknet_version,
+            block_range,
+            writer,
+            |block_number| *block_number,
+            Ok,
+        ),
+        TableName::storage_version => {
+            dump_entries(txn, &tables.storage_version, block_range, writer, no_block_number, Ok)
+        }
+    }
+}
+
+// Writes the entries of a table whose keys start with the block number, up to the end of the
+// given range, and returns their number. The range starts at the first block, so the iteration
+// stops at the first entry of a block that isn't in the range.
+fn dump_block_entries<K, V, D>(
+    txn: &StorageTxn<'_, RO>,
+    table_id: &TableIdentifier<K, V, SimpleTable>,
+    block_range: &Range<BlockNumber>,
+    writer: &mut impl Write,
+    block_number: impl Fn(&K) -> BlockNumber,
+    dumped_value: impl Fn(V::Value) -> StorageResult<D>,
+) -> StorageResult<usize>
+where
+    K: Key + Debug + Serialize,
+    V: ValueSerde + Debug,
+    D: Serialize,
+{
+    let table = txn.open_table(table_id)?;
+    let mut cursor = table.cursor(&txn.txn)?;
+    let mut dumped_entries = 0;
+    for entry in DbIter::new(&mut cursor) {
+        let (key, value) = entry?;
+        if block_number(&key) >= block_range.end {
+            break;
+        }
+        write_entry(writer, key, dumped_value(value)?)?;
+        dumped_entries += 1;
+    }
+    Ok(dumped_entries)
+}
+
+// Writes the entries of the table whose block number is in the given range, or that have no block
+// number, and returns their number.
+fn dump_entries<K, V, D>(
+    txn: &StorageTxn<'_, RO>,
+    table_id: &TableIdentifier<K, V, SimpleTable>,
+    block_range: &Range<BlockNumber>,
+    writer: &mut impl Write,
+    block_number: impl Fn(&K, &V::Value) -> StorageResult<Option<BlockNumber>>,
+    dumped_value: impl Fn(V::Value) -> StorageResult<D>,
+) -> StorageResult<usize>
+where
+    K: Key + Debug + Serialize,
+    V: ValueSerde + Debug,
+    D: Serialize,
+{
+    let table = txn.open_table(table_id)?;
+    let mut cursor = table.cursor(&txn.txn)?;
+    let mut dumped_entries = 0;
+    for entry in DbIter::new(&mut cursor) {
+        let (key, value) = entry?;
+        if let Some(block_number) = block_number(&key, &value)? {
+            if !block_range.contains(&block_number) {
+                continue;
+            }
+        }
+        write_entry(writer, key, dumped_value(value)?)?;
+        dumped_entries += 1;
+    }
+    Ok(dumped_entries)
+}
+
+fn write_entry<K: Serialize, D: Serialize>(
+    writer: &mut impl Write,
+    key: K,
+    value: D,
+) -> StorageResult<()> {
+    serde_json::to_writer(&mut *writer, &DumpEntry { key, value })?;
+    Ok(writer.write_all(b"\n")?)
+}
+
+// Loads the entries of the given lines of a dump file into the table and returns their number.
+fn load_table(
+    txn: &StorageTxn<'_, RW>,
+    table: TableName,
+    lines: impl Iterator<Item = io::Result<String>>,
+) -> StorageResult<usize> {
+    let tables = &txn.tables;
+    let file_offsets = txn.txn.open_table(&tables.file_offsets)?;
+    let append_to_file = |offset_kind, location: crate::mmap_file::LocationInFile| {
+        file_offsets.upsert(&txn.txn, &offset_kind, &location.next_offset())?;
+        Ok(location)
+    };
+    match table {
+        TableName::block_hash_to_number => {
+            load_entries(txn, &tables.block_hash_to_number, lines, Ok)
+        }
+        TableName::block_signatures => load_entries(txn, &tables.block_signatures, lines, Ok),
+        TableName::casms => load_entries(txn, &tables.casms, lines, |casm| {
+            append_to_file(OffsetKind::Casm, txn.file_handlers.append_casm(&casm))
+        }),
+        TableName::compacted_files => {
+            debug!("The compacted_files table is cleared on opening, its dump is ignored.");
+            Ok(0)
+        }
+        TableName::contract_storage => load_entries(txn, &tables.contract_storage, lines, Ok),
+        TableName::contract_storage_roots => {
+            load_entries(txn, &tables.contract_storage_roots, lines, Ok)
+        }
+        TableName::declared_classes => {
+            load_entries(txn, &tables.declared_classes, lines, |class| {
+                append_to_file(
+                    OffsetKind::ContractClass,
+                    txn.file_handlers.append_contract_class(&class),
+                )
+            })
+        }
+        TableName::declared_classes_block => {
+            load_entries(txn, &tables.declared_classes_block, lines, Ok)
+        }
+        TableName::deprecated_declared_classes => load_entries(
+            txn,
+            &tables.deprecated_declared_classes,
+            lines,
+            |dumped_class: DumpedDeprecatedClass| {
+                let location =
+                    txn.file_handlers.append_deprecated_contract_class(&dumped_class.class);
+                Ok(IndexedDeprecatedContractClass {
+                    block_number: dumped_class.block_number,
+                    location_in_file: append_to_file(
+                        OffsetKind::DeprecatedContractClass,
+                        location,
+                    )?,
+                })
+            },
+        ),
+        TableName::deployed_contracts => load_entries(txn, &tables.deployed_contracts, lines, Ok),
+        TableName::event_keys => load_entries(txn, &tables.event_keys, lines, Ok),
+        TableName::events => load_entries(txn, &tables.events, lines, Ok),
+        TableName::headers => load_entries(txn, &tables.headers, lines, Ok),
+        TableName::markers => load_entries(txn, &tables.markers, lines, Ok),
+        TableName::nonces => load_entries(txn, &tables.nonces, lines, Ok),
+        TableName::file_offsets => {
+            debug!("The file_offsets table is set by the loaded objects, its dump is ignored.");
+            Ok(0)
+        }
+        TableName::sender_nonce_to_transaction_idx => {
+            load_entries(txn, &tables.sender_nonce_to_transaction_idx, lines, Ok)
+        }
+        TableName::state_diffs => load_entries(txn, &tables.state_diffs, lines, |state_diff| {
+            append_to_file(
+                OffsetKind::ThinStateDiff,
+                txn.file_handlers.append_state_diff(&state_diff),
+            )
+        }),
+        TableName::state_roots => load_entries(txn, &tables.state_roots, lines, Ok),
+        TableName::transaction_hash_to_idx => {
+            load_entries(txn, &tables.transaction_hash_to_idx, lines, Ok)
+        }
+        TableName::transaction_idx_to_hash => {
+            load_entries(txn, &tables.transaction_idx_to_hash, lines, Ok)
+        }
+        TableName::transaction_outputs => load_entries(txn, &tables.transaction_outputs, lines, Ok),
+        TableName::transactions => load_entries(txn, &tables.transactions, lines, Ok),
+        TableName::trie_nodes => load_entries(txn, &tables.trie_nodes, lines, Ok),
+        TableName::trie_nodes_by_block => load_entries(txn, &tables.trie_nodes_by_block, lines, Ok),
+        TableName::zstd_dictionaries => load_entries(txn, &tables.zstd_dictionaries, lines, Ok),
+        TableName::migration_progress => load_entries(txn, &tables.migration_progress, lines, Ok),
+        TableName::starknet_version => load_entries(txn, &tables.starknet_version, lines, Ok),
+        TableName::storage_version => load_entries(txn, &tables.storage_version, lines, Ok),
+    }
+}
+
+// Writes the entries of the lines of a dump file to the table and returns their number.
+fn load_entries<K, V, D>(
+    txn: &StorageTxn<'_, RW>,
+    table_id: &TableIdentifier<K, V, SimpleTable>,
+    lines: impl Iterator<Item = io::Result<String>>,
+    stored_value: impl Fn(D) -> StorageResult<V::Value>,
+) -> StorageResult<usize>
+where
+    K: Key + Debug + DeserializeOwned,
+    V: ValueSerde + Debug,
+    D: DeserializeOwned,
+{
+    let table = txn.open_table(table_id)?;
+    let mut loaded_entries = 0;
+    for line in lines {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let entry: DumpEntry<K, D> = serde_json::from_str(&line)?;
+        // The storage version entries of the new storage are overridden.
+        table.upsert(&txn.txn, &entry.key, &stored_value(entry.value)?)?;
+        loaded_entries += 1;
+    }
+    Ok(loaded_entries)
+}
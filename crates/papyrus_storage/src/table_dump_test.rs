@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::Path;
+
+use assert_matches::assert_matches;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use test_utils::get_test_state_diff;
+
+use crate::body::BodyStorageReader;
+use crate::class::ClassStorageReader;
+use crate::compiled_class::CasmStorageReader;
+use crate::header::HeaderStorageReader;
+use crate::state::StateStorageReader;
+use crate::table_dump::{dump_tables, load_table_dump, load_table_dump_in_batches};
+use crate::test_utils::{
+    append_test_blocks_with_states,
+    get_test_config,
+    get_test_storage,
+    TestBlockState,
+};
+use crate::{open_storage, table_names, StorageError, StorageWriter};
+
+// Appends two blocks, where the classes are declared in the first one.
+fn append_blocks(writer: &mut StorageWriter) {
+    let mut block0 = TestBlockState::from(get_test_state_diff());
+    let (class_hash, _) = block0.state_diff.declared_classes.first().unwrap();
+    block0.casms = vec![(*class_hash, CasmContractClass::default())];
+    append_test_blocks_with_states(writer, BlockNumber(0), vec![block0, TestBlockState::default()]);
+}
+
+fn dumped_lines(dump_dir: &Path, table_name: &str) -> usize {
+    fs::read_to_string(dump_dir.join(format!("{table_name}.jsonl"))).unwrap().lines().count()
+}
+
+#[test]
+fn dump_and_load_block_range() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+    let dump_dir = tempfile::tempdir().unwrap();
+    let all_tables =
+        table_names().iter().map(|table_name| table_name.to_string()).collect::<Vec<_>>();
+    dump_tables(&reader, &all_tables, BlockNumber(1), dump_dir.path()).unwrap();
+    assert_eq!(dumped_lines(dump_dir.path(), "headers"), 1);
+    assert_eq!(dumped_lines(dump_dir.path(), "transactions"), 2);
+    assert_eq!(dumped_lines(dump_dir.path(), "casms"), 1);
+    // The markers are dumped clamped to the end block.
+    assert_eq!(
+        dumped_lines(dump_dir.path(), "markers"),
+        reader.db_tables_stats().unwrap().tables_stats["markers"].entries
+    );
+    assert_eq!(dumped_lines(dump_dir.path(), "file_offsets"), 0);
+
+    let (config, _loaded_dir) = get_test_config(None);
+    load_table_dump(config.clone(), dump_dir.path()).unwrap();
+    let (loaded_reader, _) = open_storage(config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    let loaded_txn = loaded_reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        loaded_txn.get_block_header(BlockNumber(0)).unwrap(),
+        txn.get_block_header(BlockNumber(0)).unwrap()
+    );
+    // The loaded storage ends at the end block of the dump.
+    assert_eq!(loaded_txn.get_header_marker().unwrap(), BlockNumber(1));
+    assert_eq!(loaded_txn.get_body_marker().unwrap(), BlockNumber(1));
+    assert_eq!(loaded_txn.get_state_marker().unwrap(), BlockNumber(1));
+    assert_eq!(loaded_txn.get_block_header(BlockNumber(1)).unwrap(), None);
+    assert_eq!(
+        loaded_txn.get_starknet_version(BlockNumber(0)).unwrap(),
+        txn.get_starknet_version(BlockNumber(0)).unwrap()
+    );
+    assert_eq!(
+        loaded_txn.get_block_transactions(BlockNumber(0)).unwrap(),
+        txn.get_block_transactions(BlockNumber(0)).unwrap()
+    );
+    let state_diff = txn.get_state_diff(BlockNumber(0)).unwrap().unwrap();
+    assert_eq!(loaded_txn.get_state_diff(BlockNumber(0)).unwrap(), Some(state_diff.clone()));
+    for class_hash in state_diff.declared_classes.keys() {
+        assert_eq!(loaded_txn.get_class(class_hash).unwrap(), txn.get_class(class_hash).unwrap());
+        assert_eq!(loaded_txn.get_casm(class_hash).unwrap(), txn.get_casm(class_hash).unwrap());
+    }
+    for class_hash in &state_diff.deprecated_declared_classes {
+        assert_eq!(
+            loaded_txn.get_deprecated_class(class_hash).unwrap(),
+            txn.get_deprecated_class(class_hash).unwrap()
+        );
+    }
+}
+
+#[test]
+fn load_in_batches() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+    let dump_dir = tempfile::tempdir().unwrap();
+    let all_tables =
+        table_names().iter().map(|table_name| table_name.to_string()).collect::<Vec<_>>();
+    dump_tables(&reader, &all_tables, BlockNumber(2), dump_dir.path()).unwrap();
+
+    let (config, _loaded_dir) = get_test_config(None);
+    load_table_dump_in_batches(config.clone(), dump_dir.path(), 1).unwrap();
+    let (loaded_reader, _) = open_storage(config).unwrap();
+    let loaded_tables_stats = loaded_reader.db_tables_stats().unwrap().tables_stats;
+    for (table_name, table_stats) in reader.db_tables_stats().unwrap().tables_stats {
+        assert_eq!(loaded_tables_stats[&table_name].entries, table_stats.entries, "{table_name}");
+    }
+    let loaded_txn = loaded_reader.begin_ro_txn().unwrap();
+    assert_eq!(loaded_txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert_eq!(
+        loaded_txn.get_block_transactions(BlockNumber(1)).unwrap(),
+        reader.begin_ro_txn().unwrap().get_block_transactions(BlockNumber(1)).unwrap()
+    );
+}
+
+#[test]
+fn dump_mmap_file_by_name() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+    let dump_dir = tempfile::tempdir().unwrap();
+    dump_tables(
+        &reader,
+        &["thin_state_diff".to_string(), "casm".to_string()],
+        BlockNumber(2),
+        dump_dir.path(),
+    )
+    .unwrap();
+    assert_eq!(dumped_lines(dump_dir.path(), "state_diffs"), 2);
+    assert_eq!(dumped_lines(dump_dir.path(), "casms"), 1);
+
+    assert_matches!(
+        dump_tables(&reader, &["no_such_table".to_string()], BlockNumber(2), dump_dir.path()),
+        Err(StorageError::InvalidTableDump { .. })
+    );
+}
+
+#[test]
+fn load_into_non_empty_storage() {
+    let (config, _temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    append_blocks(&mut writer);
+    let dump_dir = tempfile::tempdir().unwrap();
+    dump_tables(&reader, &["headers".to_string()], BlockNumber(2), dump_dir.path()).unwrap();
+    drop((reader, writer));
+
+    assert_matches!(
+        load_table_dump(config, dump_dir.path()),
+        Err(StorageError::InvalidTableDump { .. })
+    );
+}
@@ -9,6 +9,7 @@ use starknet_api::core::{
     TransactionCommitment,
 };
 use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::transaction::{
     EventIndexInTransactionOutput,
     ExecutionResources,
@@ -30,12 +31,23 @@ use crate::body::events::{
 use crate::body::TransactionIndex;
 use crate::compression_utils::IsCompressed;
 use crate::header::StorageBlockHeader;
+use crate::migration::MigrationProgress;
 use crate::mmap_file::LocationInFile;
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state::trie::{BinaryNode, EdgeNode, StateRoots, TrieNode};
 use crate::version::Version;
 use crate::{EventIndex, MarkerKind, OffsetKind};
 
 auto_impl_get_test_instance! {
+    pub struct BinaryNode {
+        pub left: StarkHash,
+        pub right: StarkHash,
+    }
+    pub struct EdgeNode {
+        pub path: StarkFelt,
+        pub length: u8,
+        pub child: StarkHash,
+    }
     pub struct StorageBlockHeader {
         pub block_hash: BlockHash,
         pub parent_hash: BlockHash,
@@ -72,6 +84,11 @@ auto_impl_get_test_instance! {
         Class = 4,
         CompiledClass = 5,
         BaseLayerBlock = 6,
+        Pruned = 7,
+    }
+    pub struct MigrationProgress {
+        pub position: Vec<u8>,
+        pub n_migrated: u64,
     }
     pub enum OffsetKind {
         ThinStateDiff = 0,
@@ -79,6 +96,11 @@ auto_impl_get_test_instance! {
         Casm = 2,
         DeprecatedContractClass = 3,
     }
+    pub struct StateRoots {
+        pub contracts_trie_root: StarkHash,
+        pub classes_trie_root: StarkHash,
+        pub global_root: GlobalRoot,
+    }
     pub struct ThinDeclareTransactionOutput {
         pub actual_fee: Fee,
         pub messages_sent: Vec<MessageToL1>,
@@ -124,6 +146,10 @@ auto_impl_get_test_instance! {
         L1Handler(ThinL1HandlerTransactionOutput) = 4,
     }
     struct TransactionIndex(pub BlockNumber, pub TransactionOffsetInBlock);
+    pub enum TrieNode {
+        Binary(BinaryNode) = 0,
+        Edge(EdgeNode) = 1,
+    }
     pub struct Version{
         pub major: u32,
         pub minor: u32,
@@ -1,11 +1,40 @@
 #![allow(clippy::unwrap_used)]
 //! Test utilities for the storage crate users.
 
+#[cfg(test)]
+use std::ops::Range;
+
+#[cfg(test)]
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+#[cfg(test)]
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
 use starknet_api::core::ChainId;
+#[cfg(test)]
+use starknet_api::core::ClassHash;
+#[cfg(test)]
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+#[cfg(test)]
+use starknet_api::hash::StarkHash;
+#[cfg(test)]
+use starknet_api::state::{ContractClass, StateDiff, ThinStateDiff};
 use tempfile::{tempdir, TempDir};
+#[cfg(test)]
+use test_utils::get_test_block;
 
+#[cfg(test)]
+use crate::base_layer::BaseLayerStorageWriter;
+#[cfg(test)]
+use crate::body::BodyStorageWriter;
+#[cfg(test)]
+use crate::class::ClassStorageWriter;
+#[cfg(test)]
+use crate::compiled_class::CasmStorageWriter;
 use crate::db::DbConfig;
+#[cfg(test)]
+use crate::header::HeaderStorageWriter;
 use crate::mmap_file::MmapFileConfig;
+#[cfg(test)]
+use crate::state::StateStorageWriter;
 use crate::{open_storage, StorageConfig, StorageReader, StorageScope, StorageWriter};
 
 /// Returns a db config and the temporary directory that holds this db.
@@ -29,6 +58,8 @@ pub fn get_test_config(storage_scope: Option<StorageScope>) -> (StorageConfig, T
             },
             scope: storage_scope,
             mmap_file_config: get_mmap_file_test_config(),
+            state_cache_size: 1000,
+            ..Default::default()
         },
         dir,
     )
@@ -73,3 +104,110 @@ pub fn get_test_storage_with_config_by_scope(
 
     ((reader, writer), config, temp_dir)
 }
+
+/// The number of transactions in each block that is appended by [`append_test_blocks`].
+#[cfg(test)]
+pub(crate) const TEST_TXS_PER_BLOCK: usize = 2;
+/// The number of events of each transaction that is appended by [`append_test_blocks`].
+#[cfg(test)]
+pub(crate) const TEST_EVENTS_PER_TX: usize = 2;
+
+/// Returns the hash of the test block with the given number.
+#[cfg(test)]
+pub(crate) fn get_test_block_hash(block_number: BlockNumber) -> BlockHash {
+    BlockHash(StarkHash::from(block_number.0 + 1))
+}
+
+/// The state of a block that is appended by [`append_test_blocks_with_states`]: its state diff, the
+/// classes that it declares and their compiled classes.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct TestBlockState {
+    pub(crate) state_diff: ThinStateDiff,
+    pub(crate) classes: Vec<(ClassHash, ContractClass)>,
+    pub(crate) deprecated_classes: Vec<(ClassHash, DeprecatedContractClass)>,
+    pub(crate) casms: Vec<(ClassHash, CasmContractClass)>,
+}
+
+#[cfg(test)]
+impl From<StateDiff> for TestBlockState {
+    fn from(state_diff: StateDiff) -> Self {
+        let (state_diff, classes, deprecated_classes) = ThinStateDiff::from_state_diff(state_diff);
+        TestBlockState {
+            state_diff,
+            classes: classes.into_iter().collect(),
+            deprecated_classes: deprecated_classes.into_iter().collect(),
+            casms: Vec::new(),
+        }
+    }
+}
+
+/// Appends the given range of test blocks, each with a header, a body of [`TEST_TXS_PER_BLOCK`]
+/// transactions, an empty state diff and no classes, and advances the base layer block marker.
+/// The transactions of a block don't depend on the range, so reverted blocks can be appended again.
+#[cfg(test)]
+pub(crate) fn append_test_blocks(writer: &mut StorageWriter, blocks: Range<u64>) {
+    let first_block = BlockNumber(blocks.start);
+    append_test_blocks_with_states(
+        writer,
+        first_block,
+        blocks.map(|_| TestBlockState::default()).collect(),
+    );
+}
+
+/// Appends test blocks like [`append_test_blocks`], starting at the given block, with the given
+/// states instead of empty ones.
+#[cfg(test)]
+pub(crate) fn append_test_blocks_with_states(
+    writer: &mut StorageWriter,
+    first_block: BlockNumber,
+    states: Vec<TestBlockState>,
+) {
+    let end = first_block.0 as usize + states.len();
+    let body = get_test_block(TEST_TXS_PER_BLOCK * end, Some(TEST_EVENTS_PER_TX), None, None).body;
+    for (block_number, state) in (first_block.0..).map(BlockNumber).zip(states) {
+        let header = BlockHeader {
+            block_number,
+            block_hash: get_test_block_hash(block_number),
+            parent_hash: block_number.prev().map(get_test_block_hash).unwrap_or_default(),
+            ..Default::default()
+        };
+        let txs_range = TEST_TXS_PER_BLOCK * block_number.0 as usize
+            ..TEST_TXS_PER_BLOCK * (block_number.0 as usize + 1);
+        let block_body = BlockBody {
+            transactions: body.transactions[txs_range.clone()].to_vec(),
+            transaction_outputs: body.transaction_outputs[txs_range.clone()].to_vec(),
+            transaction_hashes: body.transaction_hashes[txs_range].to_vec(),
+        };
+        let mut txn = writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, block_body)
+            .unwrap()
+            .append_state_diff(block_number, state.state_diff)
+            .unwrap()
+            .append_classes(
+                block_number,
+                &state
+                    .classes
+                    .iter()
+                    .map(|(class_hash, class)| (*class_hash, class))
+                    .collect::<Vec<_>>(),
+                &state
+                    .deprecated_classes
+                    .iter()
+                    .map(|(class_hash, deprecated_class)| (*class_hash, deprecated_class))
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap();
+        for (class_hash, casm) in &state.casms {
+            txn = txn.append_casm(class_hash, casm).unwrap();
+        }
+        txn.update_base_layer_block_marker(&block_number.unchecked_next())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
@@ -16,10 +16,18 @@ use starknet_api::state::{EntryPoint, EntryPointType};
 use tracing::debug;
 
 use crate::compiled_class::CasmStorageReader;
-use crate::db::table_types::Table;
-use crate::db::RO;
+use crate::db::serialization::StorageSerdeEx;
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{DbIter, RO};
 use crate::state::StateStorageReader;
-use crate::{open_storage, StorageConfig, StorageError, StorageReader, StorageResult, StorageTxn};
+use crate::{
+    open_storage_without_migrations,
+    StorageConfig,
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageTxn,
+};
 
 #[derive(Serialize)]
 struct DumpDeclaredClass {
@@ -38,7 +46,7 @@ pub fn dump_declared_classes_table_by_block_range(
 ) -> StorageResult<()> {
     let mut storage_config = StorageConfig::default();
     storage_config.db_config.chain_id = ChainId(chain_id.to_string());
-    let (storage_reader, _) = open_storage(storage_config)?;
+    let (storage_reader, _) = open_storage_without_migrations(storage_config)?;
     let txn = storage_reader.begin_ro_txn()?;
     let compiled_class_marker = txn.get_compiled_class_marker()?;
     if end_block > compiled_class_marker.0 {
@@ -88,6 +96,77 @@ fn dump_declared_classes_table_by_block_range_internal(
     Ok(())
 }
 
+/// Returns up to `max_samples` uncompressed contract classes and deprecated contract classes of the
+/// storage, serialized as they are before their compression. The samples are used for training
+/// and benchmarking the compression of contract classes.
+pub fn get_contract_class_compression_samples(
+    txn: &StorageTxn<'_, RO>,
+    max_samples: usize,
+) -> StorageResult<Vec<Vec<u8>>> {
+    let mut samples = Vec::new();
+
+    // Half of the samples are of contract classes, whose program and abi are compressed separately.
+    let declared_classes_table = txn.open_table(&txn.tables.declared_classes)?;
+    let mut cursor = declared_classes_table.cursor(&txn.txn)?;
+    for entry in DbIter::new(&mut cursor) {
+        if samples.len() >= max_samples / 2 {
+            break;
+        }
+        let (_, location) = entry?;
+        let contract_class = txn.file_handlers.get_contract_class_unchecked(location)?;
+        samples.extend([
+            StorageSerdeEx::serialize(&contract_class.sierra_program)?,
+            StorageSerdeEx::serialize(&contract_class.abi)?,
+        ]);
+    }
+
+    let deprecated_declared_classes_table =
+        txn.open_table(&txn.tables.deprecated_declared_classes)?;
+    let mut cursor = deprecated_declared_classes_table.cursor(&txn.txn)?;
+    for entry in DbIter::new(&mut cursor) {
+        if samples.len() >= max_samples {
+            break;
+        }
+        let (_, indexed_class) = entry?;
+        let deprecated_class = txn
+            .file_handlers
+            .get_deprecated_contract_class_unchecked(indexed_class.location_in_file)?;
+        samples.push(
+            [
+                StorageSerdeEx::serialize(&deprecated_class.abi)?,
+                StorageSerdeEx::serialize(&deprecated_class.program)?,
+            ]
+            .concat(),
+        );
+    }
+    samples.truncate(max_samples);
+    Ok(samples)
+}
+
+/// Trains a zstd dictionary of the given size on up to `max_samples` contract classes of the
+/// storage and stores it. New contract classes are compressed with the dictionary when the
+/// compression codec of the storage is Zstd. Returns the id of the dictionary.
+pub fn train_zstd_dictionary(
+    storage_config: StorageConfig,
+    max_samples: usize,
+    dictionary_size: usize,
+) -> StorageResult<u32> {
+    let (reader, mut writer) = open_storage_without_migrations(storage_config)?;
+    let samples = get_contract_class_compression_samples(&reader.begin_ro_txn()?, max_samples)?;
+    debug!("Training a zstd dictionary on {} samples.", samples.len());
+    let dictionary = zstd::dict::from_samples(&samples, dictionary_size)?;
+
+    let txn = writer.begin_rw_txn()?;
+    let zstd_dictionaries_table = txn.open_table(&txn.tables.zstd_dictionaries)?;
+    let next_index = match zstd_dictionaries_table.cursor(&txn.txn)?.prev()? {
+        Some((last_index, _)) => last_index + 1,
+        None => 0,
+    };
+    zstd_dictionaries_table.insert(&txn.txn, &next_index, &dictionary)?;
+    txn.commit()?;
+    Ok(reader.compression_context().register_zstd_dictionary(&dictionary))
+}
+
 // TODO(dvir): consider adding storage size metrics.
 // TODO(dvir): relocate all the storage metrics in one module and export them (also in other
 // crates).
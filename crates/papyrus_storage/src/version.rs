@@ -4,6 +4,8 @@ mod version_test;
 
 use std::fmt::Display;
 
+use serde::{Deserialize, Serialize};
+
 use crate::db::table_types::Table;
 use crate::db::{TransactionKind, RW};
 use crate::{StorageError, StorageResult, StorageTxn};
@@ -11,12 +13,19 @@ use crate::{StorageError, StorageResult, StorageTxn};
 const VERSION_STATE_KEY: &str = "storage_version_state";
 const VERSION_BLOCKS_KEY: &str = "storage_version_blocks";
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Version {
     pub major: u32,
     pub minor: u32,
 }
 
+// The kinds of the versions of the storage.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum VersionKind {
+    State,
+    Blocks,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum StorageVersionError {
     #[error(
@@ -126,6 +135,24 @@ impl<'env> VersionStorageWriter for StorageTxn<'env, RW> {
     }
 }
 
+impl<'env> StorageTxn<'env, RW> {
+    // Sets the version of the given kind once the storage was migrated to it. Unlike the functions
+    // of VersionStorageWriter, the major version may change.
+    pub(crate) fn set_migrated_version(
+        self,
+        kind: VersionKind,
+        version: &Version,
+    ) -> StorageResult<Self> {
+        let version_table = self.open_table(&self.tables.storage_version)?;
+        let key = match kind {
+            VersionKind::State => VERSION_STATE_KEY,
+            VersionKind::Blocks => VERSION_BLOCKS_KEY,
+        };
+        version_table.upsert(&self.txn, &key.to_string(), version)?;
+        Ok(self)
+    }
+}
+
 impl Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let major = self.major.to_string();
@@ -1,8 +1,13 @@
 use assert_matches::assert_matches;
 use pretty_assertions::assert_eq;
 use rand::Rng;
+use starknet_api::block::{BlockBody, BlockNumber};
+use starknet_api::state::ThinStateDiff;
 
+use crate::body::BodyStorageWriter;
 use crate::db::table_types::Table;
+use crate::pruning::PruningStorageWriter;
+use crate::state::StateStorageWriter;
 use crate::test_utils::{
     get_test_storage,
     get_test_storage_by_scope,
@@ -287,3 +292,28 @@ fn test_set_version_if_needed() {
         "Should fail, because storage scope cannot shift from state-only to full-archive."
     );
 }
+
+#[test]
+fn set_version_if_needed_pruned_to_full_archive() {
+    let ((mut reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::Pruned);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), ThinStateDiff::default())
+        .unwrap()
+        .prune_blocks(BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+    reader.scope = StorageScope::FullArchive;
+    writer.scope = StorageScope::FullArchive;
+    let Err(err) = set_version_if_needed(reader, writer) else {
+        panic!("Should fail, because the history of a pruned storage cannot be restored.");
+    };
+    assert_matches!(
+        err,
+        StorageError::StorageVersionInconsistency(StorageVersionError::InconsistentStorageScope)
+    );
+}
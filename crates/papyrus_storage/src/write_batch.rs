@@ -0,0 +1,72 @@
+//! Batching the durable commits of the storage.
+//!
+//! Making a commit durable requires flushing the memory mapped files and syncing the database to
+//! the disk, which dominates the time of writing a block during the initial sync. The writes are
+//! batched in two ways when the write_batch_size in the [`StorageConfig`](crate::StorageConfig) is
+//! greater than 1:
+//! - The sync writes the blocks that were already downloaded in one commit, up to
+//!   [`StorageWriter::write_batch_size`](crate::StorageWriter::write_batch_size) blocks.
+//! - The database is opened in a mode where commits aren't synced to the disk, and only the last
+//!   commit of each batch flushes the files and syncs the database, making all the commits of the
+//!   batch durable together. A batch that isn't full is made durable by
+//!   [`StorageWriter::flush_expired_write_batch`](crate::StorageWriter::flush_expired_write_batch)
+//!   once it's older than write_batch_max_duration, which the writer calls periodically, so the
+//!   batch doesn't stay non-durable when no blocks are written, e.g. at the tip of the chain.
+//!
+//! The commits of a batch are visible to the readers as soon as they are committed. If the process
+//! crashes, the operating system still writes them to the disk. On a system crash, the database
+//! rolls back to the last durable commit, whose data in the files was flushed before it was
+//! synced, so the markers stay consistent with the stored data and the lost blocks are synced
+//! again.
+
+#[cfg(test)]
+#[path = "write_batch_test.rs"]
+mod write_batch_test;
+
+use std::time::{Duration, Instant};
+
+// The commits that weren't made durable yet.
+#[derive(Debug)]
+pub(crate) struct WriteBatch {
+    max_size: usize,
+    max_duration: Duration,
+    size: usize,
+    start: Option<Instant>,
+}
+
+impl WriteBatch {
+    pub(crate) fn new(max_size: usize, max_duration: Duration) -> Self {
+        Self { max_size, max_duration, size: 0, start: None }
+    }
+
+    pub(crate) fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub(crate) fn max_duration(&self) -> Duration {
+        self.max_duration
+    }
+
+    // Adds a commit to the batch. Returns whether the batch is full, in which case the commit
+    // should be made durable and a new batch is started.
+    pub(crate) fn add_commit(&mut self) -> bool {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        self.size += 1;
+        if self.size < self.max_size && start.elapsed() < self.max_duration {
+            return false;
+        }
+        self.clear();
+        true
+    }
+
+    // Returns whether the batch has commits and started at least max_duration ago.
+    pub(crate) fn is_expired(&self) -> bool {
+        self.start.map_or(false, |start| start.elapsed() >= self.max_duration)
+    }
+
+    // Starts a new batch, after the commits of the batch were made durable.
+    pub(crate) fn clear(&mut self) {
+        self.size = 0;
+        self.start = None;
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::ThinStateDiff;
+
+use crate::body::BodyStorageReader;
+use crate::fsck::check_storage;
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{append_test_blocks, get_test_config};
+use crate::write_batch::WriteBatch;
+use crate::{open_storage, StorageConfig};
+
+#[test]
+fn batch_is_full_after_max_size_commits() {
+    let mut write_batch = WriteBatch::new(3, Duration::MAX);
+    for _ in 0..2 {
+        assert_eq!(write_batch.add_commit(), false);
+        assert_eq!(write_batch.add_commit(), false);
+        assert_eq!(write_batch.add_commit(), true);
+    }
+}
+
+#[test]
+fn batch_is_full_after_max_duration() {
+    let mut write_batch = WriteBatch::new(usize::MAX, Duration::ZERO);
+    assert_eq!(write_batch.add_commit(), true);
+    assert_eq!(write_batch.add_commit(), true);
+}
+
+#[test]
+fn batch_expires_after_max_duration() {
+    let mut write_batch = WriteBatch::new(usize::MAX, Duration::from_millis(10));
+    // An empty batch doesn't expire.
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(write_batch.is_expired(), false);
+
+    assert_eq!(write_batch.add_commit(), false);
+    assert_eq!(write_batch.is_expired(), false);
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(write_batch.is_expired(), true);
+
+    write_batch.clear();
+    assert_eq!(write_batch.is_expired(), false);
+}
+
+#[test]
+fn batched_commits_are_visible_and_kept_after_reopening() {
+    let (config, _temp_dir) = get_test_config(None);
+    let config = StorageConfig { write_batch_size: 10, ..config };
+    let n_blocks = 4;
+    {
+        let (reader, mut writer) = open_storage(config.clone()).unwrap();
+        for block_number in 0..n_blocks {
+            let header = BlockHeader {
+                block_number: BlockNumber(block_number),
+                block_hash: BlockHash(StarkFelt::from(block_number)),
+                ..Default::default()
+            };
+            writer
+                .begin_rw_txn()
+                .unwrap()
+                .append_header(BlockNumber(block_number), &header)
+                .unwrap()
+                .commit()
+                .unwrap();
+            // The commits are visible before the batch is made durable.
+            assert_eq!(
+                reader.begin_ro_txn().unwrap().get_header_marker().unwrap(),
+                BlockNumber(block_number + 1)
+            );
+        }
+    }
+
+    // Dropping the writer made the batch durable.
+    let (reader, _writer) = open_storage(config).unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), BlockNumber(n_blocks));
+}
+
+#[test]
+fn dropping_the_writer_mid_batch_keeps_the_markers_consistent() {
+    let (config, _temp_dir) = get_test_config(None);
+    let config = StorageConfig { write_batch_size: 10, ..config };
+    {
+        let (_reader, mut writer) = open_storage(config.clone()).unwrap();
+        append_test_blocks(&mut writer, 0..3);
+        // A commit of several blocks that is interrupted before it's committed, after it wrote
+        // the state diffs to the file.
+        let mut txn = writer.begin_rw_txn().unwrap();
+        for block_number in (3..5).map(BlockNumber) {
+            txn = txn
+                .append_header(block_number, &BlockHeader { block_number, ..Default::default() })
+                .unwrap()
+                .append_state_diff(block_number, ThinStateDiff::default())
+                .unwrap();
+        }
+        drop(txn);
+        // The batch isn't full when the writer is dropped.
+    }
+
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(3));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(3));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(3));
+    drop(txn);
+
+    // The blocks of the interrupted commit are written again.
+    append_test_blocks(&mut writer, 3..5);
+    drop((reader, writer));
+    let report = check_storage(config, false).unwrap();
+    assert_eq!(report.inconsistencies, vec![]);
+    assert_eq!(report.first_inconsistent_block, None);
+}
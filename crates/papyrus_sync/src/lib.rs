@@ -16,10 +16,13 @@ use std::time::Duration;
 use async_stream::try_stream;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use chrono::{TimeZone, Utc};
-use futures_util::{pin_mut, select, Stream, StreamExt};
-use indexmap::IndexMap;
+use futures_util::{future, pin_mut, select, Stream, StreamExt};
+use papyrus_common::block_hash::{validate_body, validate_header, BlockHashError};
+use papyrus_common::class_hash::calculate_class_hash;
 use papyrus_common::pending_classes::PendingClasses;
-use papyrus_common::{metrics as papyrus_metrics, BlockHashAndNumber};
+use papyrus_common::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+use papyrus_common::transaction_hash::validate_transaction_hash;
+use papyrus_common::{metrics as papyrus_metrics, BlockHashAndNumber, TransactionOptions};
 use papyrus_config::converters::deserialize_seconds_to_duration;
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
@@ -28,23 +31,39 @@ use papyrus_storage::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter
 use papyrus_storage::body::BodyStorageWriter;
 use papyrus_storage::class::ClassStorageWriter;
 use papyrus_storage::compiled_class::{CasmStorageReader, CasmStorageWriter};
-use papyrus_storage::db::DbError;
+use papyrus_storage::db::{DbError, RW};
 use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::pruning::{PruningStorageReader, PruningStorageWriter};
+use papyrus_storage::state::trie::StateTrieStorageReader;
 use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
-use papyrus_storage::{StorageError, StorageReader, StorageWriter};
+use papyrus_storage::{StorageError, StorageReader, StorageScope, StorageTxn, StorageWriter};
 use serde::{Deserialize, Serialize};
 use sources::base_layer::BaseLayerSourceError;
-use starknet_api::block::{Block, BlockHash, BlockNumber, BlockSignature};
-use starknet_api::core::{ClassHash, CompiledClassHash, SequencerPublicKey};
-use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::state::{StateDiff, ThinStateDiff};
-use starknet_client::reader::PendingData;
+use starknet_api::block::{
+    verify_block_signature,
+    Block,
+    BlockHash,
+    BlockNumber,
+    BlockSignature,
+    BlockVerificationError,
+};
+use starknet_api::core::{ChainId, ClassHash, CompiledClassHash, GlobalRoot, SequencerPublicKey};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{DeclaredClasses, StateDiff, ThinStateDiff};
+use starknet_api::transaction::TransactionHash;
+use starknet_api::StarknetApiError;
+use starknet_client::reader::{PendingData, ReaderClientError};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, instrument, trace, warn};
 
 use crate::pending_sync::sync_pending_data;
 use crate::sources::base_layer::{BaseLayerSourceTrait, EthereumBaseLayerSource};
-use crate::sources::central::{CentralError, CentralSource, CentralSourceTrait};
+use crate::sources::central::{
+    CentralError,
+    CentralSource,
+    CentralSourceTrait,
+    CentralStateUpdate,
+};
 use crate::sources::pending::{PendingError, PendingSource, PendingSourceTrait};
 
 // TODO(dvir): add to config.
@@ -54,6 +73,9 @@ const PENDING_SLEEP_DURATION: Duration = Duration::from_millis(500);
 // Sleep duration, in seconds, between sync progress checks.
 const SLEEP_TIME_SYNC_PROGRESS: Duration = Duration::from_secs(300);
 
+// Max amount of blocks to prune in a single storage transaction.
+const MAX_BLOCKS_TO_PRUNE_IN_TXN: u64 = 100;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SyncConfig {
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
@@ -62,6 +84,8 @@ pub struct SyncConfig {
     pub base_layer_propagation_sleep_duration: Duration,
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub recoverable_error_sleep_duration: Duration,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub pruning_sleep_duration: Duration,
     pub blocks_max_stream_size: u32,
     pub state_updates_max_stream_size: u32,
     pub verify_blocks: bool,
@@ -89,6 +113,13 @@ impl SerializeConfig for SyncConfig {
                  error.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "pruning_sleep_duration",
+                &self.pruning_sleep_duration.as_secs(),
+                "Time in seconds between checks for blocks to prune when the storage scope is \
+                 pruned.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "blocks_max_stream_size",
                 &self.blocks_max_stream_size,
@@ -117,6 +148,7 @@ impl Default for SyncConfig {
             block_propagation_sleep_duration: Duration::from_secs(2),
             base_layer_propagation_sleep_duration: Duration::from_secs(10),
             recoverable_error_sleep_duration: Duration::from_secs(3),
+            pruning_sleep_duration: Duration::from_secs(60),
             blocks_max_stream_size: 1000,
             state_updates_max_stream_size: 1000,
             verify_blocks: true,
@@ -141,6 +173,7 @@ pub struct GenericStateSync<
     reader: StorageReader,
     writer: StorageWriter,
     sequencer_pub_key: Option<SequencerPublicKey>,
+    chain_id: ChainId,
 }
 
 pub type StateSyncResult = Result<(), StateSyncError>;
@@ -180,36 +213,92 @@ pub enum StateSyncError {
     },
     #[error("Sequencer public key changed from {old:?} to {new:?}.")]
     SequencerPubKeyChanged { old: SequencerPublicKey, new: SequencerPublicKey },
+    #[error(
+        "State root of block {block_number} is not consistent with the stored state. Expected \
+         {expected_state_root}, calculated {calculated_state_root}."
+    )]
+    StateRootMismatch {
+        block_number: BlockNumber,
+        expected_state_root: GlobalRoot,
+        calculated_state_root: GlobalRoot,
+    },
+    #[error("Block hash {block_hash} of block {block_number} doesn't match its header.")]
+    BlockHashMismatch { block_number: BlockNumber, block_hash: BlockHash },
+    #[error(
+        "Transaction or event commitment of block {block_number} doesn't match its transactions \
+         and events."
+    )]
+    BodyCommitmentsMismatch { block_number: BlockNumber },
+    #[error(
+        "Transaction hash {transaction_hash} of block {block_number} doesn't match the \
+         transaction."
+    )]
+    TransactionHashMismatch { block_number: BlockNumber, transaction_hash: TransactionHash },
+    #[error("Signature of block {block_number} with hash {block_hash} is not valid.")]
+    BlockSignatureMismatch { block_number: BlockNumber, block_hash: BlockHash },
+    #[error(
+        "Block {block_number} has no signature and can't be verified. Sources without block \
+         signatures, such as JSON-RPC nodes, require verify_blocks to be false."
+    )]
+    UnsignedBlock { block_number: BlockNumber },
+    #[error(
+        "State diff of block {block_number} doesn't match the state diff commitment or length in \
+         its header."
+    )]
+    StateDiffMismatch { block_number: BlockNumber },
+    #[error("Class hash {class_hash} doesn't match the class, calculated {calculated_class_hash}.")]
+    ClassHashMismatch { class_hash: ClassHash, calculated_class_hash: ClassHash },
+    #[error(
+        "Compiled class hash of class {class_hash} doesn't match the compiled class. Expected \
+         {compiled_class_hash}, calculated {calculated_compiled_class_hash}."
+    )]
+    CompiledClassHashMismatch {
+        class_hash: ClassHash,
+        compiled_class_hash: CompiledClassHash,
+        calculated_compiled_class_hash: CompiledClassHash,
+    },
+    #[error(transparent)]
+    BlockHashError(#[from] BlockHashError),
+    #[error(transparent)]
+    StarknetApiError(#[from] StarknetApiError),
+    #[error(transparent)]
+    BlockVerificationError(#[from] BlockVerificationError),
 }
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum SyncEvent {
     NoProgress,
-    BlockAvailable {
-        block_number: BlockNumber,
-        block: Block,
-        signature: BlockSignature,
+    // Consecutive blocks that were downloaded together, which are stored in one commit. The
+    // signature of a block is None if the source doesn't have block signatures.
+    BlocksAvailable {
+        blocks: Vec<(BlockNumber, Block, Option<BlockSignature>)>,
     },
-    StateDiffAvailable {
-        block_number: BlockNumber,
-        block_hash: BlockHash,
-        state_diff: StateDiff,
-        // TODO(anatg): Remove once there are no more deployed contracts with undeclared classes.
-        // Class definitions of deployed contracts with classes that were not declared in this
-        // state diff.
-        // Note: Since 0.11 new classes can not be implicitly declared.
-        deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
+    // TODO(anatg): Remove the class definitions once there are no more deployed contracts with
+    // undeclared classes.
+    // The state diffs of consecutive blocks that were downloaded together, which are stored in
+    // one commit. Each state diff comes with its block number and block hash, and with the class
+    // definitions of deployed contracts with classes that were not declared in the state diff.
+    // Note: Since 0.11 new classes can not be implicitly declared.
+    StateDiffsAvailable {
+        state_diffs: Vec<CentralStateUpdate>,
     },
+    // A compiled class that was compiled locally can differ from the one that was declared, so
+    // its compiled class hash isn't verified.
     CompiledClassAvailable {
         class_hash: ClassHash,
         compiled_class_hash: CompiledClassHash,
         compiled_class: CasmContractClass,
+        is_compiled_locally: bool,
     },
     NewBaseLayerBlock {
         block_number: BlockNumber,
         block_hash: BlockHash,
     },
+    PruneBlocks {
+        until: BlockNumber,
+    },
+    WriteBatchExpired,
 }
 
 impl<
@@ -238,29 +327,21 @@ impl<
                 }
             }
         }
-
-        // Whitelisting of errors from which we might be able to recover.
-        fn is_recoverable(err: &StateSyncError) -> bool {
-            // We don't use here catch-all pattern to enforce conscious decision for each error
-            // kind.
-            match err {
-                StateSyncError::StorageError(error) => {
-                    matches!(error, StorageError::InnerError(_))
-                }
-                StateSyncError::NoProgress
-                | StateSyncError::CentralSourceError(_)
-                | StateSyncError::PendingSourceError(_)
-                | StateSyncError::BaseLayerSourceError(_)
-                | StateSyncError::ParentBlockHashMismatch { .. }
-                | StateSyncError::BaseLayerHashMismatch { .. }
-                | StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. } => true,
-                StateSyncError::SequencerPubKeyChanged { .. } => false,
-            }
-        }
     }
 
     async fn track_sequencer_public_key_changes(&mut self) -> StateSyncResult {
-        let sequencer_pub_key = self.central_source.get_sequencer_pub_key().await?;
+        let sequencer_pub_key = match self.central_source.get_sequencer_pub_key().await {
+            Ok(sequencer_pub_key) => sequencer_pub_key,
+            // Sources without block signatures, such as JSON-RPC nodes, don't have the sequencer
+            // public key either.
+            Err(CentralError::ClientError(err))
+                if matches!(*err, ReaderClientError::UnsupportedMethod { .. }) =>
+            {
+                debug!("The central source has no sequencer public key. Skipping its tracking.");
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
         match self.sequencer_pub_key {
             // First time setting the sequencer public key.
             None => {
@@ -293,6 +374,9 @@ impl<
             self.track_sequencer_public_key_changes().await?;
         }
         self.handle_block_reverts().await?;
+        // The blocks and the state diffs that were already downloaded are stored together, up to
+        // write_batch_size blocks in one commit.
+        let max_blocks_per_commit = self.writer.write_batch_size();
         let block_stream = stream_new_blocks(
             self.reader.clone(),
             self.central_source.clone(),
@@ -303,6 +387,7 @@ impl<
             self.config.block_propagation_sleep_duration,
             PENDING_SLEEP_DURATION,
             self.config.blocks_max_stream_size,
+            max_blocks_per_commit,
         )
         .fuse();
         let state_diff_stream = stream_new_state_diffs(
@@ -310,6 +395,7 @@ impl<
             self.central_source.clone(),
             self.config.block_propagation_sleep_duration,
             self.config.state_updates_max_stream_size,
+            max_blocks_per_commit,
         )
         .fuse();
         let compiled_class_stream = stream_new_compiled_classes(
@@ -326,15 +412,25 @@ impl<
             self.config.base_layer_propagation_sleep_duration,
         )
         .fuse();
+        let prune_blocks_stream = stream_blocks_to_prune(
+            self.reader.clone(),
+            self.config.pruning_sleep_duration,
+            MAX_BLOCKS_TO_PRUNE_IN_TXN,
+        )
+        .fuse();
         // TODO(dvir): try use interval instead of stream.
         // TODO: fix the bug and remove this check.
         let check_sync_progress = check_sync_progress(self.reader.clone()).fuse();
+        let expired_write_batch_stream =
+            stream_expired_write_batches(self.writer.write_batch_max_duration()).fuse();
         pin_mut!(
             block_stream,
             state_diff_stream,
             compiled_class_stream,
             base_layer_block_stream,
-            check_sync_progress
+            prune_blocks_stream,
+            check_sync_progress,
+            expired_write_batch_stream
         );
 
         loop {
@@ -344,7 +440,9 @@ impl<
               res = state_diff_stream.next() => res,
               res = compiled_class_stream.next() => res,
               res = base_layer_block_stream.next() => res,
+              res = prune_blocks_stream.next() => res,
               res = check_sync_progress.next() => res,
+              res = expired_write_batch_stream.next() => res,
               complete => break,
             }
             .expect("Received None as a sync event.")?;
@@ -357,52 +455,66 @@ impl<
     // Tries to store the incoming data.
     async fn process_sync_event(&mut self, sync_event: SyncEvent) -> StateSyncResult {
         match sync_event {
-            SyncEvent::BlockAvailable { block_number, block, signature } => {
-                self.store_block(block_number, block, &signature)
-            }
-            SyncEvent::StateDiffAvailable {
-                block_number,
-                block_hash,
-                state_diff,
-                deployed_contract_class_definitions,
-            } => self.store_state_diff(
-                block_number,
-                block_hash,
-                state_diff,
-                deployed_contract_class_definitions,
-            ),
+            SyncEvent::BlocksAvailable { blocks } => self.store_blocks(blocks),
+            SyncEvent::StateDiffsAvailable { state_diffs } => self.store_state_diffs(state_diffs),
             SyncEvent::CompiledClassAvailable {
                 class_hash,
                 compiled_class_hash,
                 compiled_class,
-            } => self.store_compiled_class(class_hash, compiled_class_hash, compiled_class),
+                is_compiled_locally,
+            } => self.store_compiled_class(
+                class_hash,
+                compiled_class_hash,
+                compiled_class,
+                is_compiled_locally,
+            ),
             SyncEvent::NewBaseLayerBlock { block_number, block_hash } => {
                 self.store_base_layer_block(block_number, block_hash)
             }
+            SyncEvent::PruneBlocks { until } => self.prune_blocks(until),
+            SyncEvent::WriteBatchExpired => Ok(self.writer.flush_expired_write_batch()?),
             SyncEvent::NoProgress => Err(StateSyncError::NoProgress),
         }
     }
 
     #[latency_histogram("sync_store_block_latency_seconds", false)]
-    #[instrument(skip(self, block), level = "debug", fields(block_hash = %block.header.block_hash), err)]
-    fn store_block(
+    #[instrument(skip(self, blocks), level = "debug", fields(n_blocks = blocks.len()), err)]
+    fn store_blocks(
         &mut self,
-        block_number: BlockNumber,
-        block: Block,
-        signature: &BlockSignature,
+        blocks: Vec<(BlockNumber, Block, Option<BlockSignature>)>,
     ) -> StateSyncResult {
-        // Assuming the central source is trusted, detect reverts by comparing the incoming block's
-        // parent hash to the current hash.
-        self.verify_parent_block_hash(block_number, &block)?;
-
-        debug!("Storing block.");
-        trace!("Block data: {block:#?}, signature: {signature:?}");
-        self.writer
-            .begin_rw_txn()?
-            .append_header(block_number, &block.header)?
-            .append_block_signature(block_number, signature)?
-            .append_body(block_number, block.body)?
-            .commit()?;
+        // The blocks are verified before any of them is stored, so a block that fails the
+        // verification is downloaded again with the blocks after it.
+        let mut prev_block_hash = None;
+        for (block_number, block, signature) in &blocks {
+            // Assuming the central source is trusted, detect reverts by comparing the incoming
+            // block's parent hash to the current hash.
+            self.verify_parent_block_hash(*block_number, block, prev_block_hash)?;
+            if self.config.verify_blocks {
+                let Some(signature) = signature else {
+                    return Err(StateSyncError::UnsignedBlock { block_number: *block_number });
+                };
+                self.verify_block(*block_number, block, signature)?;
+            }
+            prev_block_hash = Some(block.header.block_hash);
+        }
+
+        debug!("Storing {} blocks.", blocks.len());
+        let mut txn = self.writer.begin_rw_txn()?;
+        let mut last_block = None;
+        for (block_number, block, signature) in blocks {
+            trace!("Block data: {block:#?}, signature: {signature:?}");
+            txn = txn.append_header(block_number, &block.header)?;
+            if let Some(signature) = &signature {
+                txn = txn.append_block_signature(block_number, signature)?;
+            }
+            txn = txn.append_body(block_number, block.body)?;
+            last_block = Some((block_number, block.header.timestamp));
+        }
+        txn.commit()?;
+        let Some((block_number, timestamp)) = last_block else {
+            return Ok(());
+        };
         metrics::gauge!(
             papyrus_metrics::PAPYRUS_HEADER_MARKER,
             block_number.unchecked_next().0 as f64
@@ -413,7 +525,7 @@ impl<
         );
         let dt = Utc::now()
             - Utc
-                .timestamp_opt(block.header.timestamp.0 as i64, 0)
+                .timestamp_opt(timestamp.0 as i64, 0)
                 .single()
                 .expect("block timestamp should be valid");
         let header_latency = dt.num_seconds();
@@ -425,26 +537,50 @@ impl<
     }
 
     #[latency_histogram("sync_store_state_diff_latency_seconds", false)]
-    #[instrument(skip(self, state_diff, deployed_contract_class_definitions), level = "debug", err)]
-    fn store_state_diff(
-        &mut self,
-        block_number: BlockNumber,
-        block_hash: BlockHash,
-        state_diff: StateDiff,
-        deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
-    ) -> StateSyncResult {
-        // TODO(dan): verifications - verify state diff against stored header.
-        debug!("Storing state diff.");
-        trace!("StateDiff data: {state_diff:#?}");
-
-        // TODO(shahak): split the state diff stream to 2 separate streams for blocks and for
-        // classes.
-        let (thin_state_diff, classes, deprecated_classes) =
-            ThinStateDiff::from_state_diff(state_diff);
-        self.writer
-            .begin_rw_txn()?
-            .append_state_diff(block_number, thin_state_diff)?
-            .append_classes(
+    #[instrument(skip(self, state_diffs), level = "debug", fields(n_blocks = state_diffs.len()), err)]
+    fn store_state_diffs(&mut self, state_diffs: Vec<CentralStateUpdate>) -> StateSyncResult {
+        debug!("Storing {} state diffs.", state_diffs.len());
+        // The state diffs are verified against the stored headers before the transaction is
+        // started, and against the state roots after they are appended to it.
+        let mut thin_state_diffs = Vec::with_capacity(state_diffs.len());
+        for (block_number, block_hash, state_diff, deployed_contract_class_definitions) in
+            state_diffs
+        {
+            trace!("StateDiff data: {state_diff:#?}");
+            // TODO(shahak): split the state diff stream to 2 separate streams for blocks and for
+            // classes.
+            let (thin_state_diff, classes, deprecated_classes) =
+                ThinStateDiff::from_state_diff(state_diff);
+            if self.config.verify_blocks {
+                self.verify_state_diff(block_number, &thin_state_diff)?;
+                verify_class_hashes(&classes)?;
+            }
+            thin_state_diffs.push((
+                block_number,
+                block_hash,
+                thin_state_diff,
+                classes,
+                deprecated_classes,
+                deployed_contract_class_definitions,
+            ));
+        }
+
+        let mut txn = self.writer.begin_rw_txn()?;
+        let mut added_blocks = Vec::with_capacity(thin_state_diffs.len());
+        for (
+            block_number,
+            block_hash,
+            thin_state_diff,
+            classes,
+            deprecated_classes,
+            deployed_contract_class_definitions,
+        ) in thin_state_diffs
+        {
+            txn = txn.append_state_diff(block_number, thin_state_diff)?;
+            if self.config.verify_blocks {
+                verify_state_root(&txn, block_number)?;
+            }
+            txn = txn.append_classes(
                 block_number,
                 &classes.iter().map(|(class_hash, class)| (*class_hash, class)).collect::<Vec<_>>(),
                 &deprecated_classes
@@ -452,21 +588,27 @@ impl<
                     .chain(deployed_contract_class_definitions.iter())
                     .map(|(class_hash, deprecated_class)| (*class_hash, deprecated_class))
                     .collect::<Vec<_>>(),
-            )?
-            .commit()?;
+            )?;
+            added_blocks.push((block_number, block_hash));
+        }
+        txn.commit()?;
 
-        metrics::gauge!(
-            papyrus_metrics::PAPYRUS_STATE_MARKER,
-            block_number.unchecked_next().0 as f64
-        );
+        if let Some((block_number, _)) = added_blocks.last() {
+            metrics::gauge!(
+                papyrus_metrics::PAPYRUS_STATE_MARKER,
+                block_number.unchecked_next().0 as f64
+            );
+        }
         let compiled_class_marker = self.reader.begin_ro_txn()?.get_compiled_class_marker()?;
         metrics::gauge!(
             papyrus_metrics::PAPYRUS_COMPILED_CLASS_MARKER,
             compiled_class_marker.0 as f64
         );
 
-        // Info the user on syncing the block once all the data is stored.
-        info!("Added block {} with hash {}.", block_number, block_hash);
+        // Info the user on syncing the blocks once all the data is stored.
+        for (block_number, block_hash) in added_blocks {
+            info!("Added block {} with hash {}.", block_number, block_hash);
+        }
 
         Ok(())
     }
@@ -478,9 +620,17 @@ impl<
         class_hash: ClassHash,
         compiled_class_hash: CompiledClassHash,
         compiled_class: CasmContractClass,
+        is_compiled_locally: bool,
     ) -> StateSyncResult {
+        if is_compiled_locally {
+            debug!(
+                "Compiled class of {class_hash} was compiled locally, skipping its compiled class \
+                 hash verification."
+            );
+        } else if self.config.verify_blocks {
+            verify_compiled_class_hash(class_hash, compiled_class_hash, &compiled_class)?;
+        }
         let txn = self.writer.begin_rw_txn()?;
-        // TODO: verifications - verify casm corresponds to a class on storage.
         match txn.append_casm(&class_hash, &compiled_class) {
             Ok(txn) => {
                 txn.commit()?;
@@ -538,27 +688,40 @@ impl<
         Ok(())
     }
 
-    // Compares the block's parent hash to the stored block.
+    #[instrument(skip(self), level = "debug", err)]
+    fn prune_blocks(&mut self, until: BlockNumber) -> StateSyncResult {
+        debug!("Pruning the history of the blocks before {until}.");
+        self.writer.begin_rw_txn()?.prune_blocks(until)?.commit()?;
+        Ok(())
+    }
+
+    // Compares the block's parent hash to the stored block, or to the given hash of the previous
+    // block if it's stored in the same commit.
     fn verify_parent_block_hash(
         &self,
         block_number: BlockNumber,
         block: &Block,
+        prev_block_hash: Option<BlockHash>,
     ) -> StateSyncResult {
         let prev_block_number = match block_number.prev() {
             None => return Ok(()),
             Some(bn) => bn,
         };
-        let prev_hash = self
-            .reader
-            .begin_ro_txn()?
-            .get_block_header(prev_block_number)?
-            .ok_or(StorageError::DBInconsistency {
-                msg: format!(
-                    "Missing block {prev_block_number} in the storage (for verifying block \
-                     {block_number}).",
-                ),
-            })?
-            .block_hash;
+        let prev_hash = match prev_block_hash {
+            Some(prev_hash) => prev_hash,
+            None => {
+                self.reader
+                    .begin_ro_txn()?
+                    .get_block_header(prev_block_number)?
+                    .ok_or(StorageError::DBInconsistency {
+                        msg: format!(
+                            "Missing block {prev_block_number} in the storage (for verifying \
+                             block {block_number}).",
+                        ),
+                    })?
+                    .block_hash
+            }
+        };
 
         if prev_hash != block.header.parent_hash {
             // A revert detected, log and restart sync loop.
@@ -577,6 +740,117 @@ impl<
         Ok(())
     }
 
+    // Compares the state diff to the state diff commitment and length in the stored header of the
+    // block. A mismatching state diff is downloaded again.
+    fn verify_state_diff(
+        &self,
+        block_number: BlockNumber,
+        state_diff: &ThinStateDiff,
+    ) -> StateSyncResult {
+        let header = self.reader.begin_ro_txn()?.get_block_header(block_number)?.ok_or(
+            StorageError::DBInconsistency {
+                msg: format!(
+                    "Missing header of block {block_number} (for verifying its state diff)."
+                ),
+            },
+        )?;
+        if let Some(expected_commitment) = header.state_diff_commitment {
+            let calculated_commitment =
+                calculate_state_diff_commitment(state_diff, StateDiffVersion::V0);
+            if calculated_commitment != expected_commitment {
+                debug!(
+                    "State diff commitment of block {block_number} is {calculated_commitment:?}, \
+                     expected {expected_commitment:?}."
+                );
+                count_verification_failure("state_diff_commitment");
+                return Err(StateSyncError::StateDiffMismatch { block_number });
+            }
+        }
+        if let Some(expected_length) = header.state_diff_length {
+            if state_diff.len() != expected_length {
+                debug!(
+                    "State diff length of block {block_number} is {}, expected {expected_length}.",
+                    state_diff.len()
+                );
+                count_verification_failure("state_diff_length");
+                return Err(StateSyncError::StateDiffMismatch { block_number });
+            }
+        }
+        Ok(())
+    }
+
+    // Recomputes the hashes and commitments of the block and verifies its signature, instead of
+    // trusting the central source. Blocks that are older than the commitments in the header are
+    // verified only by their transaction hashes and signature.
+    fn verify_block(
+        &self,
+        block_number: BlockNumber,
+        block: &Block,
+        signature: &BlockSignature,
+    ) -> StateSyncResult {
+        let header = &block.header;
+        match validate_header(header, &self.chain_id) {
+            Ok(true) => {}
+            Ok(false) => {
+                count_verification_failure("block_hash");
+                return Err(StateSyncError::BlockHashMismatch {
+                    block_number,
+                    block_hash: header.block_hash,
+                });
+            }
+            Err(BlockHashError::MissingHeaderData) => {
+                debug!("Block {block_number} has no commitments, skipping its hash verification.");
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        if let (Some(transaction_commitment), Some(event_commitment)) =
+            (&header.transaction_commitment, &header.event_commitment)
+        {
+            if !validate_body(&block.body, transaction_commitment, event_commitment)? {
+                count_verification_failure("body_commitments");
+                return Err(StateSyncError::BodyCommitmentsMismatch { block_number });
+            }
+        }
+
+        for (transaction, transaction_hash) in
+            block.body.transactions.iter().zip(block.body.transaction_hashes.iter())
+        {
+            if !validate_transaction_hash(
+                transaction,
+                &block_number,
+                &self.chain_id,
+                *transaction_hash,
+                &TransactionOptions::default(),
+            )? {
+                count_verification_failure("transaction_hash");
+                return Err(StateSyncError::TransactionHashMismatch {
+                    block_number,
+                    transaction_hash: *transaction_hash,
+                });
+            }
+        }
+
+        if let (Some(sequencer_pub_key), Some(state_diff_commitment)) =
+            (&self.sequencer_pub_key, &header.state_diff_commitment)
+        {
+            if !verify_block_signature(
+                sequencer_pub_key,
+                signature,
+                &GlobalRoot(state_diff_commitment.0.0),
+                &header.block_hash,
+            )? {
+                count_verification_failure("signature");
+                return Err(StateSyncError::BlockSignatureMismatch {
+                    block_number,
+                    block_hash: header.block_hash,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     // Reverts data if needed.
     async fn handle_block_reverts(&mut self) -> Result<(), StateSyncError> {
         debug!("Handling block reverts.");
@@ -641,6 +915,42 @@ impl<
         }
     }
 }
+
+// Whitelisting of errors from which we might be able to recover.
+fn is_recoverable(err: &StateSyncError) -> bool {
+    // We don't use here catch-all pattern to enforce conscious decision for each error kind.
+    match err {
+        StateSyncError::StorageError(error) => matches!(error, StorageError::InnerError(_)),
+        StateSyncError::NoProgress
+        | StateSyncError::CentralSourceError(_)
+        | StateSyncError::PendingSourceError(_)
+        | StateSyncError::BaseLayerSourceError(_)
+        | StateSyncError::ParentBlockHashMismatch { .. }
+        | StateSyncError::BaseLayerHashMismatch { .. }
+        | StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. }
+        | StateSyncError::StateDiffMismatch { .. } => true,
+        StateSyncError::SequencerPubKeyChanged { .. }
+        | StateSyncError::StateRootMismatch { .. }
+        | StateSyncError::BlockHashMismatch { .. }
+        | StateSyncError::BodyCommitmentsMismatch { .. }
+        | StateSyncError::TransactionHashMismatch { .. }
+        | StateSyncError::BlockSignatureMismatch { .. }
+        | StateSyncError::UnsignedBlock { .. }
+        | StateSyncError::ClassHashMismatch { .. }
+        | StateSyncError::CompiledClassHashMismatch { .. }
+        | StateSyncError::BlockHashError(_)
+        | StateSyncError::StarknetApiError(_)
+        | StateSyncError::BlockVerificationError(_) => false,
+    }
+}
+
+fn count_verification_failure(check: &'static str) {
+    metrics::increment_counter!(
+        papyrus_metrics::PAPYRUS_BLOCK_VERIFICATION_FAILURES,
+        "check" => check
+    );
+}
+
 // TODO(dvir): consider gathering in a single pending argument instead.
 #[allow(clippy::too_many_arguments)]
 fn stream_new_blocks<
@@ -656,6 +966,7 @@ fn stream_new_blocks<
     block_propagation_sleep_duration: Duration,
     pending_sleep_duration: Duration,
     max_stream_size: u32,
+    max_blocks_per_commit: usize,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
         loop {
@@ -690,12 +1001,14 @@ fn stream_new_blocks<
             }
             let up_to = min(central_block_marker, BlockNumber(header_marker.0 + max_stream_size as u64));
             debug!("Downloading blocks [{} - {}).", header_marker, up_to);
-            let block_stream =
-                central_source.stream_new_blocks(header_marker, up_to).fuse();
+            let block_stream = central_source
+                .stream_new_blocks(header_marker, up_to)
+                .ready_chunks(max_blocks_per_commit)
+                .fuse();
             pin_mut!(block_stream);
-            while let Some(maybe_block) = block_stream.next().await {
-                let (block_number, block, signature) = maybe_block?;
-                yield SyncEvent::BlockAvailable { block_number, block , signature };
+            while let Some(maybe_blocks) = block_stream.next().await {
+                let blocks = maybe_blocks.into_iter().collect::<Result<Vec<_>, _>>()?;
+                yield SyncEvent::BlocksAvailable { blocks };
             }
         }
     }
@@ -706,6 +1019,7 @@ fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
     central_source: Arc<TCentralSource>,
     block_propagation_sleep_duration: Duration,
     max_stream_size: u32,
+    max_blocks_per_commit: usize,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
         loop {
@@ -720,24 +1034,18 @@ fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
             }
             let up_to = min(last_block_number, BlockNumber(state_marker.0 + max_stream_size as u64));
             debug!("Downloading state diffs [{} - {}).", state_marker, up_to);
-            let state_diff_stream =
-                central_source.stream_state_updates(state_marker, up_to).fuse();
+            let state_diff_stream = central_source
+                .stream_state_updates(state_marker, up_to)
+                .ready_chunks(max_blocks_per_commit)
+                .fuse();
             pin_mut!(state_diff_stream);
 
-            while let Some(maybe_state_diff) = state_diff_stream.next().await {
-                let (
-                    block_number,
-                    block_hash,
-                    mut state_diff,
-                    deployed_contract_class_definitions,
-                ) = maybe_state_diff?;
-                sort_state_diff(&mut state_diff);
-                yield SyncEvent::StateDiffAvailable {
-                    block_number,
-                    block_hash,
-                    state_diff,
-                    deployed_contract_class_definitions,
-                };
+            while let Some(maybe_state_diffs) = state_diff_stream.next().await {
+                let mut state_diffs = maybe_state_diffs.into_iter().collect::<Result<Vec<_>, _>>()?;
+                for (_, _, state_diff, _) in &mut state_diffs {
+                    sort_state_diff(state_diff);
+                }
+                yield SyncEvent::StateDiffsAvailable { state_diffs };
             }
         }
     }
@@ -755,6 +1063,66 @@ pub fn sort_state_diff(diff: &mut StateDiff) {
     }
 }
 
+// Compares the state root that was calculated when storing the state diff to the state root in the
+// stored header of the block.
+fn verify_state_root(txn: &StorageTxn<'_, RW>, block_number: BlockNumber) -> StateSyncResult {
+    let expected_state_root = txn
+        .get_block_header(block_number)?
+        .ok_or(StorageError::DBInconsistency {
+            msg: format!("Missing header of block {block_number} (for verifying its state root)."),
+        })?
+        .state_root;
+    let calculated_state_root = txn
+        .get_state_roots(block_number)?
+        .ok_or(StorageError::DBInconsistency {
+            msg: format!("Missing state roots of block {block_number}."),
+        })?
+        .global_root;
+    if expected_state_root != calculated_state_root {
+        return Err(StateSyncError::StateRootMismatch {
+            block_number,
+            expected_state_root,
+            calculated_state_root,
+        });
+    }
+    Ok(())
+}
+
+// Compares the hashes of the declared classes to their calculated hashes.
+fn verify_class_hashes(classes: &DeclaredClasses) -> StateSyncResult {
+    for (class_hash, class) in classes {
+        let calculated_class_hash = calculate_class_hash(class);
+        if calculated_class_hash != *class_hash {
+            count_verification_failure("class_hash");
+            return Err(StateSyncError::ClassHashMismatch {
+                class_hash: *class_hash,
+                calculated_class_hash,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Compares the compiled class hash that was declared in the state diff to the calculated hash of
+// the compiled class.
+fn verify_compiled_class_hash(
+    class_hash: ClassHash,
+    compiled_class_hash: CompiledClassHash,
+    compiled_class: &CasmContractClass,
+) -> StateSyncResult {
+    let calculated_compiled_class_hash =
+        CompiledClassHash(StarkFelt::new(compiled_class.compiled_class_hash().to_be_bytes())?);
+    if calculated_compiled_class_hash != compiled_class_hash {
+        count_verification_failure("compiled_class_hash");
+        return Err(StateSyncError::CompiledClassHashMismatch {
+            class_hash,
+            compiled_class_hash,
+            calculated_compiled_class_hash,
+        });
+    }
+    Ok(())
+}
+
 pub type StateSync = GenericStateSync<CentralSource, PendingSource, EthereumBaseLayerSource>;
 
 impl StateSync {
@@ -769,6 +1137,7 @@ impl StateSync {
         base_layer_source: EthereumBaseLayerSource,
         reader: StorageReader,
         writer: StorageWriter,
+        chain_id: ChainId,
     ) -> Self {
         Self {
             config,
@@ -781,6 +1150,110 @@ impl StateSync {
             reader,
             writer,
             sequencer_pub_key: None,
+            chain_id,
+        }
+    }
+}
+
+/// Follows the central source without syncing blocks. Keeps the highest block of the central source
+/// and the pending data on top of the last block in the storage up to date, for nodes that serve a
+/// storage which is synced by another process (see
+/// [`open_storage_read_only`](papyrus_storage::open_storage_read_only)).
+pub struct GenericPendingDataFollower<
+    TCentralSource: CentralSourceTrait + Sync + Send,
+    TPendingSource: PendingSourceTrait + Sync + Send,
+> {
+    config: SyncConfig,
+    shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+    pending_data: Arc<RwLock<PendingData>>,
+    pending_classes: Arc<RwLock<PendingClasses>>,
+    central_source: Arc<TCentralSource>,
+    pending_source: Arc<TPendingSource>,
+    reader: StorageReader,
+}
+
+impl<
+    TCentralSource: CentralSourceTrait + Sync + Send + 'static,
+    TPendingSource: PendingSourceTrait + Sync + Send + 'static,
+> GenericPendingDataFollower<TCentralSource, TPendingSource>
+{
+    pub async fn run(&mut self) -> StateSyncResult {
+        info!("Pending data follower started.");
+        loop {
+            match self.follow_while_ok().await {
+                // A recoverable error occurred. Sleep and try following again.
+                Err(err) if is_recoverable(&err) => {
+                    warn!("Recoverable error encountered while following pending data: {}", err);
+                    tokio::time::sleep(self.config.recoverable_error_sleep_duration).await;
+                    continue;
+                }
+                // Unrecoverable errors.
+                Err(err) => {
+                    error!("Fatal error while following pending data: {}", err);
+                    return Err(err);
+                }
+                Ok(_) => {
+                    unreachable!(
+                        "Pending data follower should either return with an error or continue \
+                         forever."
+                    )
+                }
+            }
+        }
+    }
+
+    // Follow until encountering an error. The pending data is followed only while the storage has
+    // the last block and state of the central source.
+    async fn follow_while_ok(&mut self) -> StateSyncResult {
+        loop {
+            let latest_central_block = self.central_source.get_latest_block().await?;
+            *self.shared_highest_block.write().await = latest_central_block;
+            let central_block_marker = latest_central_block
+                .map_or(BlockNumber::default(), |block| block.block_number.unchecked_next());
+
+            let txn = self.reader.begin_ro_txn()?;
+            let is_synced = txn.get_header_marker()? == central_block_marker
+                && txn.get_state_marker()? == central_block_marker;
+            drop(txn);
+            if is_synced {
+                // Returns once the central source has a new block.
+                sync_pending_data(
+                    self.reader.clone(),
+                    self.central_source.clone(),
+                    self.pending_source.clone(),
+                    self.pending_data.clone(),
+                    self.pending_classes.clone(),
+                    PENDING_SLEEP_DURATION,
+                )
+                .await?;
+            } else {
+                debug!("Waiting for the storage to reach the last block of the central source.");
+                tokio::time::sleep(self.config.block_propagation_sleep_duration).await;
+            }
+        }
+    }
+}
+
+pub type PendingDataFollower = GenericPendingDataFollower<CentralSource, PendingSource>;
+
+impl PendingDataFollower {
+    pub fn new(
+        config: SyncConfig,
+        shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+        pending_data: Arc<RwLock<PendingData>>,
+        pending_classes: Arc<RwLock<PendingClasses>>,
+        central_source: CentralSource,
+        pending_source: PendingSource,
+        reader: StorageReader,
+    ) -> Self {
+        Self {
+            config,
+            shared_highest_block,
+            pending_data,
+            pending_classes,
+            central_source: Arc::new(central_source),
+            pending_source: Arc::new(pending_source),
+            reader,
         }
     }
 }
@@ -822,11 +1295,13 @@ fn stream_new_compiled_classes<TCentralSource: CentralSourceTrait + Sync + Send>
             pin_mut!(compiled_classes_stream);
 
             while let Some(maybe_compiled_class) = compiled_classes_stream.next().await {
-                let (class_hash, compiled_class_hash, compiled_class) = maybe_compiled_class?;
+                let (class_hash, compiled_class_hash, compiled_class, is_compiled_locally) =
+                    maybe_compiled_class?;
                 yield SyncEvent::CompiledClassAvailable {
                     class_hash,
                     compiled_class_hash,
                     compiled_class,
+                    is_compiled_locally,
                 };
             }
         }
@@ -866,6 +1341,55 @@ fn stream_new_base_layer_block<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
     }
 }
 
+// Under the pruned storage scope, returns the blocks whose history is no longer retained, at most
+// 'max_blocks_to_prune' blocks at a time.
+fn stream_blocks_to_prune(
+    reader: StorageReader,
+    pruning_sleep_duration: Duration,
+    max_blocks_to_prune: u64,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        if reader.get_scope() != StorageScope::Pruned {
+            // The stream is selected together with the other sync streams, so it should never end.
+            future::pending::<()>().await;
+        }
+        loop {
+            let txn = reader.begin_ro_txn()?;
+            let pruning_marker = txn.get_pruning_marker()?;
+            let first_retained_block =
+                txn.get_first_retained_block(reader.get_retained_blocks())?;
+            drop(txn);
+            if pruning_marker < first_retained_block {
+                let until = min(
+                    first_retained_block,
+                    BlockNumber(pruning_marker.0 + max_blocks_to_prune),
+                );
+                yield SyncEvent::PruneBlocks { until };
+                continue;
+            }
+            debug!("No blocks to prune, waiting for the sync to advance.");
+            tokio::time::sleep(pruning_sleep_duration).await;
+        }
+    }
+}
+
+// Yields an event every write_batch_max_duration, for making the commits of the write batch of the
+// storage durable once it expires, also when no blocks are synced, e.g. at the tip of the chain.
+// Yields nothing if the commits aren't batched.
+fn stream_expired_write_batches(
+    write_batch_max_duration: Option<Duration>,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        loop {
+            match write_batch_max_duration {
+                Some(duration) => tokio::time::sleep(duration).await,
+                None => future::pending::<()>().await,
+            }
+            yield SyncEvent::WriteBatchExpired;
+        }
+    }
+}
+
 // This function is used to check if the sync is stuck.
 // TODO: fix the bug and remove this function.
 // TODO(dvir): add a test for this scenario.
@@ -10,6 +10,7 @@ use std::sync::{Arc, Mutex};
 use async_stream::stream;
 use async_trait::async_trait;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use cairo_lang_starknet_classes::contract_class::ContractClass as CairoLangContractClass;
 use futures::stream::BoxStream;
 use futures_util::StreamExt;
 use indexmap::IndexMap;
@@ -19,31 +20,57 @@ use lru::LruCache;
 use mockall::automock;
 use papyrus_common::pending_classes::ApiContractClass;
 use papyrus_common::BlockHashAndNumber;
-use papyrus_config::converters::{deserialize_optional_map, serialize_optional_map};
+use papyrus_config::converters::{
+    deserialize_optional_map,
+    deserialize_vec,
+    serialize_optional_map,
+    serialize_slice,
+};
 use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use starknet_api::block::{Block, BlockHash, BlockNumber, BlockSignature};
-use starknet_api::core::{ClassHash, CompiledClassHash, SequencerPublicKey};
+use starknet_api::core::{ClassHash, CompiledClassHash, GlobalRoot, SequencerPublicKey};
 use starknet_api::crypto::Signature;
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::state::StateDiff;
+use starknet_api::state::{ContractClass, EntryPointType, StateDiff};
 use starknet_api::StarknetApiError;
-use starknet_client::reader::{ReaderClientError, StarknetFeederGatewayClient, StarknetReader};
+use starknet_client::reader::{
+    BlockOrDeprecated,
+    BlockSignatureData,
+    FailoverStarknetReader,
+    ReaderClientError,
+    StarknetFeederGatewayClient,
+    StarknetJsonRpcClient,
+    StarknetReader,
+};
 use starknet_client::{ClientCreationError, RetryConfig};
 use tracing::{debug, trace};
 
 use self::state_update_stream::{StateUpdateStream, StateUpdateStreamConfig};
 
+// The compiled classes are of classes that were already declared on Starknet, so their size isn't
+// limited again.
+const MAX_COMPILED_CLASS_BYTECODE_SIZE: usize = usize::MAX;
+// The Sierra contract class version of all the Cairo 1 classes.
+const SIERRA_CONTRACT_CLASS_VERSION: &str = "0.1.0";
+
 type CentralResult<T> = Result<T, CentralError>;
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct CentralSourceConfig {
     pub concurrent_requests: usize,
     pub url: String,
+    pub url_is_json_rpc: bool,
     #[serde(deserialize_with = "deserialize_optional_map")]
     pub http_headers: Option<HashMap<String, String>>,
+    #[serde(deserialize_with = "deserialize_vec")]
+    pub fallback_urls: Vec<String>,
+    #[serde(deserialize_with = "deserialize_vec")]
+    pub json_rpc_urls: Vec<String>,
+    pub cross_check_block_hashes: bool,
     pub max_state_updates_to_download: usize,
     pub max_state_updates_to_store_in_memory: usize,
     pub max_classes_to_download: usize,
@@ -57,7 +84,11 @@ impl Default for CentralSourceConfig {
         CentralSourceConfig {
             concurrent_requests: 10,
             url: String::from("https://alpha-mainnet.starknet.io/"),
+            url_is_json_rpc: false,
             http_headers: None,
+            fallback_urls: Vec::new(),
+            json_rpc_urls: Vec::new(),
+            cross_check_block_hashes: false,
             max_state_updates_to_download: 20,
             max_state_updates_to_store_in_memory: 20,
             max_classes_to_download: 20,
@@ -87,12 +118,41 @@ impl SerializeConfig for CentralSourceConfig {
                 "Starknet feeder-gateway URL. It should match chain_id.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "url_is_json_rpc",
+                &self.url_is_json_rpc,
+                "If true, url is of a Starknet JSON-RPC node instead of a feeder-gateway. The \
+                 blocks of a JSON-RPC node have no signatures, so the sync requires verify_blocks \
+                 to be false, and its compiled classes are compiled locally.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "http_headers",
                 &serialize_optional_map(&self.http_headers),
                 "'k1:v1 k2:v2 ...' headers for SN-client.",
                 ParamPrivacyInput::Private,
             ),
+            ser_param(
+                "fallback_urls",
+                &serialize_slice(&self.fallback_urls),
+                "'url1 url2 ...' Starknet feeder-gateway URLs to fail over to when requests to \
+                 url fail. They should match chain_id.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "json_rpc_urls",
+                &serialize_slice(&self.json_rpc_urls),
+                "'url1 url2 ...' Starknet JSON-RPC node URLs to fail over to after the \
+                 feeder-gateways. They should match chain_id.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "cross_check_block_hashes",
+                &self.cross_check_block_hashes,
+                "If true, the hash of every downloaded block is checked against another source \
+                 before the block is stored.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "max_state_updates_to_download",
                 &self.max_state_updates_to_download,
@@ -129,7 +189,8 @@ pub struct GenericCentralSource<TStarknetClient: StarknetReader + Send + Sync> {
     pub storage_reader: StorageReader,
     pub state_update_stream_config: StateUpdateStreamConfig,
     pub(crate) class_cache: Arc<Mutex<LruCache<ClassHash, ApiContractClass>>>,
-    compiled_class_cache: Arc<Mutex<LruCache<ClassHash, CasmContractClass>>>,
+    // The compiled classes and whether they were compiled locally.
+    compiled_class_cache: Arc<Mutex<LruCache<ClassHash, (CasmContractClass, bool)>>>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -152,6 +213,8 @@ pub enum CentralError {
     StorageError(#[from] StorageError),
     #[error("Wrong type of contract class")]
     BadContractClassType,
+    #[error("Failed to compile class {}: {}.", class_hash, msg)]
+    ClassCompilation { class_hash: ClassHash, msg: String },
 }
 
 #[cfg_attr(test, automock)]
@@ -192,12 +255,15 @@ pub trait CentralSourceTrait {
     async fn get_sequencer_pub_key(&self) -> Result<SequencerPublicKey, CentralError>;
 }
 
+// The signature is None if the source doesn't have block signatures.
 pub(crate) type BlocksStream<'a> =
-    BoxStream<'a, Result<(BlockNumber, Block, BlockSignature), CentralError>>;
-type CentralStateUpdate =
+    BoxStream<'a, Result<(BlockNumber, Block, Option<BlockSignature>), CentralError>>;
+pub(crate) type CentralStateUpdate =
     (BlockNumber, BlockHash, StateDiff, IndexMap<ClassHash, DeprecatedContractClass>);
 pub(crate) type StateUpdatesStream<'a> = BoxStream<'a, CentralResult<CentralStateUpdate>>;
-type CentralCompiledClass = (ClassHash, CompiledClassHash, CasmContractClass);
+// The class hash, the compiled class hash that was declared, the compiled class and whether it was
+// compiled locally.
+type CentralCompiledClass = (ClassHash, CompiledClassHash, CasmContractClass, bool);
 pub(crate) type CompiledClassesStream<'a> = BoxStream<'a, CentralResult<CentralCompiledClass>>;
 
 #[async_trait]
@@ -257,7 +323,14 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
                     .map(|bn| async move {
                         let block_and_signature = futures_util::try_join!(
                             self.starknet_client.block(bn),
-                            self.starknet_client.block_signature(bn)
+                            // Sources that don't have block signatures, such as JSON-RPC nodes,
+                            // return None.
+                            async {
+                                match self.starknet_client.block_signature(bn).await {
+                                    Err(ReaderClientError::UnsupportedMethod { .. }) => Ok(None),
+                                    maybe_signature_data => maybe_signature_data.map(Some),
+                                }
+                            }
                         );
                         (bn, block_and_signature)
                     })
@@ -315,8 +388,9 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
                     match maybe_class_hashes {
                         Ok((class_hash, compiled_class_hash)) => {
                             trace!("Downloading compiled class {:?}.", class_hash);
-                            let compiled_class = self.get_compiled_class(class_hash).await?;
-                            Ok((class_hash, compiled_class_hash, compiled_class))
+                            let (compiled_class, is_compiled_locally) =
+                                self.download_compiled_class(class_hash).await?;
+                            Ok((class_hash, compiled_class_hash, compiled_class, is_compiled_locally))
                         },
                         Err(err) => Err(err),
                     }
@@ -325,8 +399,8 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
 
             while let Some(maybe_compiled_class) = compiled_classes.next().await {
                 match maybe_compiled_class {
-                    Ok((class_hash, compiled_class_hash, compiled_class)) => {
-                        yield Ok((class_hash, compiled_class_hash, compiled_class));
+                    Ok(compiled_class) => {
+                        yield Ok(compiled_class);
                     }
                     Err(err) => {
                         yield Err(err);
@@ -365,42 +439,67 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
         &self,
         class_hash: ClassHash,
     ) -> Result<CasmContractClass, CentralError> {
+        Ok(self.download_compiled_class(class_hash).await?.0)
+    }
+
+    async fn get_sequencer_pub_key(&self) -> Result<SequencerPublicKey, CentralError> {
+        Ok(self.starknet_client.sequencer_pub_key().await.map_err(Arc::new)?)
+    }
+}
+
+impl<TStarknetClient: StarknetReader + Send + Sync + 'static>
+    GenericCentralSource<TStarknetClient>
+{
+    // Returns the compiled class of the class and whether it was compiled locally. The classes of
+    // sources that don't serve compiled classes, such as JSON-RPC nodes, are compiled locally, so
+    // their compiled class can differ from the one that was declared.
+    async fn download_compiled_class(
+        &self,
+        class_hash: ClassHash,
+    ) -> CentralResult<(CasmContractClass, bool)> {
         {
             let mut compiled_class_cache =
                 self.compiled_class_cache.lock().expect("Failed to lock class cache.");
-            if let Some(class) = compiled_class_cache.get(&class_hash) {
-                return Ok(class.clone());
+            if let Some(compiled_class) = compiled_class_cache.get(&class_hash) {
+                return Ok(compiled_class.clone());
             }
         }
-        match self.starknet_client.compiled_class_by_hash(class_hash).await {
-            Ok(Some(compiled_class)) => {
-                let mut compiled_class_cache =
-                    self.compiled_class_cache.lock().expect("Failed to lock class cache.");
-                compiled_class_cache.put(class_hash, compiled_class.clone());
-                Ok(compiled_class)
+        let compiled_class = match self.starknet_client.compiled_class_by_hash(class_hash).await {
+            Ok(Some(compiled_class)) => (compiled_class, false),
+            Ok(None) => return Err(CentralError::CompiledClassNotFound { class_hash }),
+            Err(ReaderClientError::UnsupportedMethod { .. }) => {
+                let ApiContractClass::ContractClass(class) = self.get_class(class_hash).await?
+                else {
+                    return Err(CentralError::BadContractClassType);
+                };
+                let compiled_class =
+                    tokio::task::spawn_blocking(move || compile_class(class_hash, class))
+                        .await
+                        .expect("Failed to join the class compilation task.")?;
+                (compiled_class, true)
             }
-            Ok(None) => Err(CentralError::CompiledClassNotFound { class_hash }),
-            Err(err) => Err(CentralError::ClientError(Arc::new(err))),
-        }
-    }
-
-    async fn get_sequencer_pub_key(&self) -> Result<SequencerPublicKey, CentralError> {
-        Ok(self.starknet_client.sequencer_pub_key().await.map_err(Arc::new)?)
+            Err(err) => return Err(CentralError::ClientError(Arc::new(err))),
+        };
+        let mut compiled_class_cache =
+            self.compiled_class_cache.lock().expect("Failed to lock class cache.");
+        compiled_class_cache.put(class_hash, compiled_class.clone());
+        Ok(compiled_class)
     }
 }
 
+// The signature data is None if the source doesn't have block signatures.
 fn client_to_central_block(
     current_block_number: BlockNumber,
     maybe_client_block: Result<
-        (
-            Option<starknet_client::reader::BlockOrDeprecated>,
-            Option<starknet_client::reader::BlockSignatureData>,
-        ),
+        (Option<BlockOrDeprecated>, Option<Option<BlockSignatureData>>),
         ReaderClientError,
     >,
-) -> CentralResult<(Block, BlockSignature)> {
+) -> CentralResult<(Block, Option<BlockSignature>)> {
     match maybe_client_block {
-        Ok((Some(block), Some(signature_data))) => {
+        Ok((maybe_block, None)) => {
+            client_to_unsigned_central_block(current_block_number, maybe_block)
+        }
+        Ok((Some(block), Some(Some(signature_data)))) => {
             debug!("Received new block {current_block_number} with hash {}.", block.block_hash());
             trace!("Block: {block:#?}, signature data: {signature_data:#?}.");
             let block = block
@@ -410,21 +509,21 @@ fn client_to_central_block(
                 .map_err(|err| CentralError::ClientError(Arc::new(err)))?;
             Ok((
                 block,
-                BlockSignature(Signature {
+                Some(BlockSignature(Signature {
                     r: signature_data.signature[0],
                     s: signature_data.signature[1],
-                }),
+                })),
             ))
         }
-        Ok((None, Some(_))) => {
+        Ok((None, Some(Some(_)))) => {
             debug!("Block {current_block_number} not found, but signature was found.");
             Err(CentralError::BlockNotFound { block_number: current_block_number })
         }
-        Ok((Some(_), None)) => {
+        Ok((Some(_), Some(None))) => {
             debug!("Block {current_block_number} found, but signature was not found.");
             Err(CentralError::BlockNotFound { block_number: current_block_number })
         }
-        Ok((None, None)) => {
+        Ok((None, Some(None))) => {
             debug!("Block {current_block_number} not found.");
             Err(CentralError::BlockNotFound { block_number: current_block_number })
         }
@@ -432,7 +531,54 @@ fn client_to_central_block(
     }
 }
 
-pub type CentralSource = GenericCentralSource<StarknetFeederGatewayClient>;
+// Converts a block of a source that doesn't have block signatures, such as a JSON-RPC node. Such a
+// source doesn't have the state diff commitment of the block either, so the block is stored without
+// a state diff commitment and without a signature, and it can't be verified.
+fn client_to_unsigned_central_block(
+    current_block_number: BlockNumber,
+    maybe_client_block: Option<BlockOrDeprecated>,
+) -> CentralResult<(Block, Option<BlockSignature>)> {
+    match maybe_client_block {
+        Some(block) => {
+            debug!(
+                "Received new unsigned block {current_block_number} with hash {}.",
+                block.block_hash()
+            );
+            trace!("Block: {block:#?}.");
+            let mut block = block
+                .to_starknet_api_block_and_version(GlobalRoot::default())
+                .map_err(|err| CentralError::ClientError(Arc::new(err)))?;
+            block.header.state_diff_commitment = None;
+            Ok((block, None))
+        }
+        None => {
+            debug!("Block {current_block_number} not found.");
+            Err(CentralError::BlockNotFound { block_number: current_block_number })
+        }
+    }
+}
+
+// Compiles a Cairo 1 class with the Sierra compiler that this node is built with. Classes of newer
+// Sierra versions than the compiler supports fail to compile.
+fn compile_class(class_hash: ClassHash, class: ContractClass) -> CentralResult<CasmContractClass> {
+    let entry_points = |entry_point_type| {
+        class.entry_points_by_type.get(&entry_point_type).cloned().unwrap_or_default()
+    };
+    let cairo_lang_class: CairoLangContractClass = serde_json::from_value(json!({
+        "sierra_program": class.sierra_program,
+        "contract_class_version": SIERRA_CONTRACT_CLASS_VERSION,
+        "entry_points_by_type": {
+            "CONSTRUCTOR": entry_points(EntryPointType::Constructor),
+            "EXTERNAL": entry_points(EntryPointType::External),
+            "L1_HANDLER": entry_points(EntryPointType::L1Handler),
+        },
+    }))
+    .map_err(|err| CentralError::ClassCompilation { class_hash, msg: err.to_string() })?;
+    CasmContractClass::from_contract_class(cairo_lang_class, true, MAX_COMPILED_CLASS_BYTECODE_SIZE)
+        .map_err(|err| CentralError::ClassCompilation { class_hash, msg: err.to_string() })
+}
+
+pub type CentralSource = GenericCentralSource<FailoverStarknetReader>;
 
 impl CentralSource {
     pub fn new(
@@ -440,14 +586,72 @@ impl CentralSource {
         node_version: &'static str,
         storage_reader: StorageReader,
     ) -> Result<CentralSource, ClientCreationError> {
-        let starknet_client = StarknetFeederGatewayClient::new(
+        let starknet_client = FailoverStarknetReader::new(
+            new_starknet_readers(&config, node_version)?,
+            config.cross_check_block_hashes,
+        );
+
+        Ok(CentralSource::with_starknet_client(&config, starknet_client, storage_reader))
+    }
+}
+
+// Creates the readers of the configured url, of the fallback urls and of the JSON-RPC urls, in the
+// order in which they are failed over to.
+pub(crate) fn new_starknet_readers(
+    config: &CentralSourceConfig,
+    node_version: &'static str,
+) -> Result<Vec<Box<dyn StarknetReader + Send + Sync>>, ClientCreationError> {
+    let mut readers = vec![new_url_reader(config, node_version)?];
+    for url in &config.fallback_urls {
+        readers.push(Box::new(StarknetFeederGatewayClient::new(
+            url,
+            config.http_headers.clone(),
+            node_version,
+            config.retry_config,
+        )?));
+    }
+    // The http headers are meant for the configured url, so they aren't sent to the JSON-RPC
+    // nodes.
+    for url in &config.json_rpc_urls {
+        readers.push(Box::new(StarknetJsonRpcClient::new(
+            url,
+            None,
+            node_version,
+            config.retry_config,
+        )?));
+    }
+    Ok(readers)
+}
+
+// Creates the reader of the configured url, which is either a feeder gateway or a JSON-RPC node.
+fn new_url_reader(
+    config: &CentralSourceConfig,
+    node_version: &'static str,
+) -> Result<Box<dyn StarknetReader + Send + Sync>, ClientCreationError> {
+    if config.url_is_json_rpc {
+        Ok(Box::new(StarknetJsonRpcClient::new(
             &config.url,
-            config.http_headers,
+            config.http_headers.clone(),
             node_version,
             config.retry_config,
-        )?;
+        )?))
+    } else {
+        Ok(Box::new(StarknetFeederGatewayClient::new(
+            &config.url,
+            config.http_headers.clone(),
+            node_version,
+            config.retry_config,
+        )?))
+    }
+}
 
-        Ok(CentralSource {
+impl<TStarknetClient: StarknetReader + Send + Sync> GenericCentralSource<TStarknetClient> {
+    fn with_starknet_client(
+        config: &CentralSourceConfig,
+        starknet_client: TStarknetClient,
+        storage_reader: StorageReader,
+    ) -> Self {
+        GenericCentralSource {
             concurrent_requests: config.concurrent_requests,
             starknet_client: Arc::new(starknet_client),
             storage_reader,
@@ -464,6 +668,6 @@ impl CentralSource {
                 NonZeroUsize::new(config.class_cache_size)
                     .expect("class_cache_size should be a positive integer."),
             ))),
-        })
+        }
     }
 }
@@ -15,7 +15,7 @@ use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
 use starknet_api::block::{Block, BlockBody, BlockHash, BlockHeader, BlockNumber, BlockSignature};
-use starknet_api::core::{ClassHash, SequencerPublicKey};
+use starknet_api::core::{ChainId, ClassHash, SequencerPublicKey};
 use starknet_api::crypto::PublicKey;
 use starknet_api::hash::StarkFelt;
 use starknet_api::stark_felt;
@@ -92,6 +92,7 @@ fn get_test_sync_config(verify_blocks: bool) -> SyncConfig {
         block_propagation_sleep_duration: SYNC_SLEEP_DURATION,
         base_layer_propagation_sleep_duration: BASE_LAYER_SLEEP_DURATION,
         recoverable_error_sleep_duration: SYNC_SLEEP_DURATION,
+        pruning_sleep_duration: SYNC_SLEEP_DURATION,
         blocks_max_stream_size: STREAM_SIZE,
         state_updates_max_stream_size: STREAM_SIZE,
         verify_blocks,
@@ -121,6 +122,7 @@ async fn run_sync(
         reader,
         writer,
         sequencer_pub_key: None,
+        chain_id: ChainId("SN_MAIN".to_owned()),
     };
 
     state_sync.run().await?;
@@ -194,7 +196,7 @@ async fn sync_happy_flow() {
                 yield Ok((
                     block_number,
                     Block { header, body: BlockBody::default() },
-                    BlockSignature::default(),
+                    Some(BlockSignature::default()),
                 ));
             }
         }
@@ -487,7 +489,7 @@ async fn sync_with_revert() {
                         yield Ok((
                             i,
                             Block{ header, body: BlockBody::default() },
-                            BlockSignature::default(),
+                            Some(BlockSignature::default()),
                         ));
                     }
                 }
@@ -506,7 +508,7 @@ async fn sync_with_revert() {
                         yield Ok((
                             i,
                             Block{header, body: BlockBody::default()},
-                            BlockSignature::default(),
+                            Some(BlockSignature::default()),
                         ));
                     }
                 }
@@ -609,7 +611,7 @@ async fn test_unrecoverable_sync_error_flow() {
             yield Ok((
                 BLOCK_NUMBER,
                 Block { header, body: BlockBody::default()},
-                BlockSignature::default(),
+                Some(BlockSignature::default()),
             ));
         }
         .boxed();
@@ -514,13 +514,14 @@ async fn stream_compiled_classes() {
 
     let expected_compiled_class = CasmContractClass::default();
     for felt in felts {
-        let (class_hash, compiled_class_hash, compiled_class) =
+        let (class_hash, compiled_class_hash, compiled_class, is_compiled_locally) =
             stream.next().await.unwrap().unwrap();
         let expected_class_hash = ClassHash(felt);
         let expected_compiled_class_hash = CompiledClassHash(felt);
         assert_eq!(class_hash, expected_class_hash);
         assert_eq!(compiled_class_hash, expected_compiled_class_hash);
         assert_eq!(compiled_class, expected_compiled_class);
+        assert!(!is_compiled_locally);
     }
 }
 
@@ -631,6 +632,6 @@ fn get_test_class_cache() -> Arc<Mutex<LruCache<ClassHash, ApiContractClass>>> {
     Arc::from(Mutex::new(LruCache::new(NonZeroUsize::new(2).unwrap())))
 }
 
-fn get_test_compiled_class_cache() -> Arc<Mutex<LruCache<ClassHash, CasmContractClass>>> {
+fn get_test_compiled_class_cache() -> Arc<Mutex<LruCache<ClassHash, (CasmContractClass, bool)>>> {
     Arc::from(Mutex::new(LruCache::new(NonZeroUsize::new(2).unwrap())))
 }
@@ -0,0 +1,191 @@
+use std::fs::read_to_string;
+
+use assert_matches::assert_matches;
+use futures_util::pin_mut;
+use mockito::{mock, Matcher};
+use papyrus_common::pending_classes::ApiContractClass;
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::{patricia_key, stark_felt};
+use test_utils::get_absolute_path;
+use tokio_stream::StreamExt;
+
+use crate::sources::central::{CentralSource, CentralSourceConfig, CentralSourceTrait};
+use crate::sources::pending::{PendingSource, PendingSourceTrait};
+
+const NODE_VERSION: &str = "NODE VERSION";
+
+// Each test uses a different path since all the tests share the same mock server.
+fn json_rpc_config(path: &str) -> CentralSourceConfig {
+    CentralSourceConfig {
+        url: format!("{}/{path}", mockito::server_url()),
+        url_is_json_rpc: true,
+        ..Default::default()
+    }
+}
+
+fn request_matcher(method: &str, params: Value) -> Matcher {
+    Matcher::PartialJson(json!({"jsonrpc": "2.0", "method": method, "params": params}))
+}
+
+fn result_body(result: Value) -> String {
+    json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string()
+}
+
+fn block_header_json() -> Value {
+    json!({
+        "parent_hash": "0x0",
+        "timestamp": 1700000000,
+        "sequencer_address": "0x1",
+        "l1_gas_price": {"price_in_fri": "0x2", "price_in_wei": "0x3"},
+        "l1_data_gas_price": {"price_in_fri": "0x4", "price_in_wei": "0x5"},
+        "l1_da_mode": "BLOB",
+        "starknet_version": "0.13.1",
+        "transactions": [],
+    })
+}
+
+fn state_diff_json() -> Value {
+    json!({
+        "storage_diffs": [
+            {"address": "0x11", "storage_entries": [{"key": "0x12", "value": "0x13"}]}
+        ],
+        "deprecated_declared_classes": [],
+        "declared_classes": [],
+        "deployed_contracts": [],
+        "replaced_classes": [],
+        "nonces": [{"contract_address": "0x11", "nonce": "0x1"}],
+    })
+}
+
+#[tokio::test]
+async fn stream_new_blocks() {
+    let ((reader, _), _temp_dir) = get_test_storage();
+    let central_source =
+        CentralSource::new(json_rpc_config("stream_new_blocks"), NODE_VERSION, reader).unwrap();
+    let block_hash = BlockHash(stark_felt!("0xabc"));
+    let mut block = block_header_json();
+    block["status"] = json!("ACCEPTED_ON_L2");
+    block["block_hash"] = json!(block_hash);
+    block["block_number"] = json!(0);
+    block["new_root"] = json!("0x6");
+    let mock_block = mock("POST", "/stream_new_blocks")
+        .match_body(request_matcher(
+            "starknet_getBlockWithReceipts",
+            json!({"block_id": {"block_number": 0}}),
+        ))
+        .with_status(200)
+        .with_body(result_body(block))
+        .create();
+
+    let stream = central_source.stream_new_blocks(BlockNumber(0), BlockNumber(1));
+    pin_mut!(stream);
+    let (block_number, block, signature) = stream.next().await.unwrap().unwrap();
+    assert!(stream.next().await.is_none());
+    mock_block.assert();
+
+    assert_eq!(block_number, BlockNumber(0));
+    assert_eq!(block.header.block_hash, block_hash);
+    assert_eq!(block.header.block_number, BlockNumber(0));
+    // JSON-RPC blocks have no signatures and state diff commitments.
+    assert_eq!(block.header.state_diff_commitment, None);
+    assert_eq!(signature, None);
+}
+
+#[tokio::test]
+async fn stream_state_updates() {
+    let ((reader, _), _temp_dir) = get_test_storage();
+    let central_source =
+        CentralSource::new(json_rpc_config("stream_state_updates"), NODE_VERSION, reader).unwrap();
+    let block_hash = BlockHash(stark_felt!("0xabc"));
+    let mock_state_update = mock("POST", "/stream_state_updates")
+        .match_body(request_matcher(
+            "starknet_getStateUpdate",
+            json!({"block_id": {"block_number": 0}}),
+        ))
+        .with_status(200)
+        .with_body(result_body(json!({
+            "block_hash": block_hash,
+            "new_root": "0x6",
+            "old_root": "0x0",
+            "state_diff": state_diff_json(),
+        })))
+        .create();
+
+    let stream = central_source.stream_state_updates(BlockNumber(0), BlockNumber(1));
+    pin_mut!(stream);
+    let (block_number, state_update_block_hash, state_diff, deployed_classes) =
+        stream.next().await.unwrap().unwrap();
+    assert!(stream.next().await.is_none());
+    mock_state_update.assert();
+
+    let address = ContractAddress(patricia_key!("0x11"));
+    assert_eq!(block_number, BlockNumber(0));
+    assert_eq!(state_update_block_hash, block_hash);
+    assert_eq!(state_diff.nonces[&address], Nonce(stark_felt!("0x1")));
+    assert_eq!(state_diff.storage_diffs[&address].len(), 1);
+    assert!(deployed_classes.is_empty());
+}
+
+#[tokio::test]
+async fn get_compiled_class() {
+    let ((reader, _), _temp_dir) = get_test_storage();
+    let central_source =
+        CentralSource::new(json_rpc_config("get_compiled_class"), NODE_VERSION, reader).unwrap();
+    let class_hash = ClassHash(stark_felt!("0x1"));
+    let class: Value = serde_json::from_str(
+        &read_to_string(get_absolute_path("crates/papyrus_common/resources/class.json")).unwrap(),
+    )
+    .unwrap();
+    // JSON-RPC nodes don't serve compiled classes, so the class is compiled locally.
+    let mock_class = mock("POST", "/get_compiled_class")
+        .match_body(request_matcher(
+            "starknet_getClass",
+            json!({"block_id": "pending", "class_hash": class_hash}),
+        ))
+        .with_status(200)
+        .with_body(result_body(class))
+        .expect(1)
+        .create();
+
+    let compiled_class = central_source.get_compiled_class(class_hash).await.unwrap();
+    assert!(!compiled_class.bytecode.is_empty());
+    assert_matches!(
+        central_source.get_class(class_hash).await.unwrap(),
+        ApiContractClass::ContractClass(_)
+    );
+    // The compiled class is cached.
+    assert_eq!(central_source.get_compiled_class(class_hash).await.unwrap(), compiled_class);
+    mock_class.assert();
+}
+
+#[tokio::test]
+async fn get_pending_data() {
+    let pending_source =
+        PendingSource::new(json_rpc_config("get_pending_data"), NODE_VERSION).unwrap();
+    let mock_block = mock("POST", "/get_pending_data")
+        .match_body(request_matcher(
+            "starknet_getBlockWithReceipts",
+            json!({"block_id": "pending"}),
+        ))
+        .with_status(200)
+        .with_body(result_body(block_header_json()))
+        .create();
+    let mock_state_update = mock("POST", "/get_pending_data")
+        .match_body(request_matcher("starknet_getStateUpdate", json!({"block_id": "pending"})))
+        .with_status(200)
+        .with_body(result_body(json!({"old_root": "0x6", "state_diff": state_diff_json()})))
+        .create();
+
+    let pending_data = pending_source.get_pending_data().await.unwrap();
+    mock_block.assert();
+    mock_state_update.assert();
+
+    assert_eq!(pending_data.block.parent_block_hash(), BlockHash::default());
+    assert!(pending_data.block.transactions().is_empty());
+    assert_eq!(pending_data.state_update.state_diff.nonces.len(), 1);
+}
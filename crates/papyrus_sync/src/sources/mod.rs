@@ -2,4 +2,6 @@ pub mod base_layer;
 pub mod central;
 #[cfg(test)]
 mod central_sync_test;
+#[cfg(test)]
+mod json_rpc_test;
 pub mod pending;
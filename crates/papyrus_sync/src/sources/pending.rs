@@ -8,15 +8,15 @@ use async_trait::async_trait;
 #[cfg(test)]
 use mockall::automock;
 use starknet_client::reader::{
+    FailoverStarknetReader,
     PendingData,
     ReaderClientError,
-    StarknetFeederGatewayClient,
     StarknetReader,
 };
 use starknet_client::ClientCreationError;
 
 // TODO(dvir): add pending config.
-use super::central::CentralSourceConfig;
+use super::central::{new_starknet_readers, CentralSourceConfig};
 
 pub struct GenericPendingSource<TStarknetClient: StarknetReader + Send + Sync> {
     pub starknet_client: Arc<TStarknetClient>,
@@ -50,19 +50,15 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> PendingSourceTrait
     }
 }
 
-pub type PendingSource = GenericPendingSource<StarknetFeederGatewayClient>;
+pub type PendingSource = GenericPendingSource<FailoverStarknetReader>;
 
 impl PendingSource {
     pub fn new(
         config: CentralSourceConfig,
         node_version: &'static str,
     ) -> Result<PendingSource, ClientCreationError> {
-        let starknet_client = StarknetFeederGatewayClient::new(
-            &config.url,
-            config.http_headers,
-            node_version,
-            config.retry_config,
-        )?;
+        let starknet_client =
+            FailoverStarknetReader::new(new_starknet_readers(&config, node_version)?, false);
 
         Ok(PendingSource { starknet_client: Arc::new(starknet_client) })
     }
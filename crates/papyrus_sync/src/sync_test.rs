@@ -3,19 +3,37 @@ use std::time::Duration;
 
 use assert_matches::assert_matches;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
-use futures_util::StreamExt;
+use futures_util::{FutureExt, StreamExt};
 use indexmap::IndexMap;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
+use papyrus_common::BlockHashAndNumber;
 use papyrus_storage::base_layer::BaseLayerStorageReader;
-use papyrus_storage::header::HeaderStorageWriter;
-use papyrus_storage::test_utils::get_test_storage;
-use papyrus_storage::{StorageReader, StorageWriter};
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::pruning::PruningStorageWriter;
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::test_utils::{get_test_config, get_test_storage};
+use papyrus_storage::{open_storage, StorageReader, StorageScope, StorageWriter};
 use pretty_assertions::assert_eq;
-use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
-use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::block::{Block, BlockBody, BlockHash, BlockHeader, BlockNumber, BlockSignature};
+use starknet_api::core::{
+    ChainId,
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    EventCommitment,
+    GlobalRoot,
+    Nonce,
+    PatriciaKey,
+    SequencerPublicKey,
+    StateDiffCommitment,
+    TransactionCommitment,
+};
+use starknet_api::crypto::{PublicKey, Signature};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::hash::{StarkFelt, StarkHash, GENESIS_HASH};
-use starknet_api::state::{ContractClass, StateDiff, StorageKey};
+use starknet_api::hash::{PoseidonHash, StarkFelt, StarkHash, GENESIS_HASH};
+use starknet_api::state::{ContractClass, StateDiff, StorageKey, ThinStateDiff};
+use starknet_api::transaction::{L1HandlerTransaction, Transaction, TransactionHash};
 use starknet_api::{patricia_key, stark_felt};
 use starknet_client::reader::objects::pending_data::{
     AcceptedOnL2ExtraData,
@@ -33,9 +51,12 @@ use crate::sources::base_layer::MockBaseLayerSourceTrait;
 use crate::sources::central::MockCentralSourceTrait;
 use crate::sources::pending::MockPendingSourceTrait;
 use crate::{
+    is_recoverable,
     sort_state_diff,
+    stream_blocks_to_prune,
     stream_new_base_layer_block,
     sync_pending_data,
+    GenericPendingDataFollower,
     GenericStateSync,
     StateSyncError,
     SyncConfig,
@@ -170,6 +191,42 @@ async fn stream_new_base_layer_block_no_blocks_on_base_layer() {
     assert_matches!(event, SyncEvent::NewBaseLayerBlock { block_number: BlockNumber(1), .. });
 }
 
+#[tokio::test]
+async fn stream_blocks_to_prune_test() {
+    let (mut config, _temp_dir) = get_test_config(Some(StorageScope::Pruned));
+    config.retained_blocks = 2;
+    let (reader, mut writer) = open_storage(config).unwrap();
+
+    for i in 0..5 {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_body(BlockNumber(i), BlockBody::default())
+            .unwrap()
+            .append_state_diff(BlockNumber(i), ThinStateDiff::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    // Blocks 3 and 4 are retained, and at most 2 blocks are pruned at a time.
+    let mut stream = stream_blocks_to_prune(reader, Duration::from_millis(0), 2).boxed();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::PruneBlocks { until: BlockNumber(2) });
+    writer.begin_rw_txn().unwrap().prune_blocks(BlockNumber(2)).unwrap().commit().unwrap();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::PruneBlocks { until: BlockNumber(3) });
+}
+
+#[tokio::test]
+async fn stream_blocks_to_prune_full_archive() {
+    let (reader, _writer) = get_test_storage().0;
+    let mut stream = stream_blocks_to_prune(reader, Duration::from_millis(0), 2).boxed();
+    assert!(stream.next().now_or_never().is_none());
+}
+
 #[test]
 fn store_base_layer_block_test() {
     let (reader, mut writer) = get_test_storage().0;
@@ -199,6 +256,7 @@ fn store_base_layer_block_test() {
         reader,
         writer,
         sequencer_pub_key: None,
+        chain_id: ChainId("SN_MAIN".to_owned()),
     };
 
     // Trying to store a block without a header in the storage.
@@ -218,6 +276,319 @@ fn store_base_layer_block_test() {
     assert_eq!(base_layer_marker, BlockNumber(1));
 }
 
+#[test]
+fn store_state_diff_verifies_state_root() {
+    let (reader, mut writer) = get_test_storage().0;
+    let header = BlockHeader {
+        block_number: BlockNumber(0),
+        state_root: GlobalRoot(stark_felt!("0x1234")),
+        ..BlockHeader::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        chain_id: ChainId("SN_MAIN".to_owned()),
+    };
+
+    // The state root of an empty state is zero.
+    let res = gen_state_sync.store_state_diffs(vec![(
+        BlockNumber(0),
+        header.block_hash,
+        StateDiff::default(),
+        IndexMap::new(),
+    )]);
+    assert_matches!(
+        res,
+        Err(StateSyncError::StateRootMismatch { block_number: BlockNumber(0), .. })
+    );
+    // The state diff wasn't stored.
+    let state_marker = gen_state_sync.reader.begin_ro_txn().unwrap().get_state_marker().unwrap();
+    assert_eq!(state_marker, BlockNumber(0));
+}
+
+#[test]
+fn store_state_diff_verifies_state_diff() {
+    let (reader, mut writer) = get_test_storage().0;
+    let header = BlockHeader {
+        block_number: BlockNumber(0),
+        state_diff_commitment: Some(StateDiffCommitment(PoseidonHash(stark_felt!("0x1234")))),
+        ..BlockHeader::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        chain_id: ChainId("SN_MAIN".to_owned()),
+    };
+
+    // The state diff doesn't match the commitment in the header.
+    let res = gen_state_sync.store_state_diffs(vec![(
+        BlockNumber(0),
+        header.block_hash,
+        StateDiff::default(),
+        IndexMap::new(),
+    )]);
+    let err = res.unwrap_err();
+    assert_matches!(err, StateSyncError::StateDiffMismatch { block_number: BlockNumber(0) });
+    // The state diff is downloaded again.
+    assert!(is_recoverable(&err));
+    // The state diff wasn't stored.
+    let state_marker = gen_state_sync.reader.begin_ro_txn().unwrap().get_state_marker().unwrap();
+    assert_eq!(state_marker, BlockNumber(0));
+}
+
+#[test]
+fn store_classes_verifies_class_hashes() {
+    let (reader, mut writer) = get_test_storage().0;
+    let header = BlockHeader { block_number: BlockNumber(0), ..BlockHeader::default() };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        chain_id: ChainId("SN_MAIN".to_owned()),
+    };
+
+    // A declared class that doesn't match its hash.
+    let class_hash = ClassHash(stark_felt!("0x1234"));
+    let state_diff = StateDiff {
+        declared_classes: IndexMap::from([(
+            class_hash,
+            (CompiledClassHash::default(), ContractClass::default()),
+        )]),
+        ..StateDiff::default()
+    };
+    let res = gen_state_sync.store_state_diffs(vec![(
+        BlockNumber(0),
+        header.block_hash,
+        state_diff,
+        IndexMap::new(),
+    )]);
+    assert_matches!(
+        res,
+        Err(StateSyncError::ClassHashMismatch { class_hash: hash, .. }) if hash == class_hash
+    );
+    let state_marker = gen_state_sync.reader.begin_ro_txn().unwrap().get_state_marker().unwrap();
+    assert_eq!(state_marker, BlockNumber(0));
+
+    // A compiled class that doesn't match the declared compiled class hash.
+    let mut rng = get_rng();
+    let compiled_class = CasmContractClass {
+        bytecode_segment_lengths: None,
+        ..CasmContractClass::get_test_instance(&mut rng)
+    };
+    let res = gen_state_sync.store_compiled_class(
+        class_hash,
+        CompiledClassHash(stark_felt!("0x1234")),
+        compiled_class.clone(),
+        false,
+    );
+    assert_matches!(res, Err(StateSyncError::CompiledClassHashMismatch { .. }));
+
+    // A compiled class that was compiled locally isn't verified.
+    gen_state_sync
+        .store_compiled_class(
+            ClassHash(stark_felt!("0x5678")),
+            CompiledClassHash(stark_felt!("0x1234")),
+            compiled_class.clone(),
+            true,
+        )
+        .unwrap();
+
+    // Happy flow.
+    let compiled_class_hash = CompiledClassHash(
+        StarkFelt::new(compiled_class.compiled_class_hash().to_be_bytes()).unwrap(),
+    );
+    gen_state_sync
+        .store_compiled_class(class_hash, compiled_class_hash, compiled_class, false)
+        .unwrap();
+}
+
+#[test]
+fn store_block_verifies_block() {
+    let (reader, writer) = get_test_storage().0;
+    // Values taken from Mainnet.
+    let block_hash =
+        BlockHash(stark_felt!("0x7d5db04c5ca2aea828180dc441afb1580e3cee7547a3567ced3aa5bb8b273c0"));
+    let state_diff_commitment = StateDiffCommitment(PoseidonHash(stark_felt!(
+        "0x64689c12248e1110af4b3af0e2b43cd51ad13e8855f10e37669e2a4baf919c6"
+    )));
+    let signature = BlockSignature(Signature {
+        r: stark_felt!("0x1b382bbfd693011c9b7692bc932b23ed9c288deb27c8e75772e172abbe5950c"),
+        s: stark_felt!("0xbe4438085057e1a7c704a0da3b30f7b8340fe3d24c86772abfd24aa597e42"),
+    });
+    let sequencer_pub_key = SequencerPublicKey(PublicKey(stark_felt!(
+        "0x48253ff2c3bed7af18bde0b611b083b39445959102d4947c51c4db6aa4f4e58"
+    )));
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: Some(sequencer_pub_key),
+        chain_id: ChainId("SN_MAIN".to_owned()),
+    };
+
+    // The header has no commitments, so its hash is verified only by the signature.
+    let header = BlockHeader {
+        block_hash,
+        state_diff_commitment: Some(state_diff_commitment),
+        ..BlockHeader::default()
+    };
+
+    // A block hash that doesn't match the header.
+    let block = Block {
+        header: BlockHeader {
+            n_transactions: Some(0),
+            transaction_commitment: Some(TransactionCommitment::default()),
+            n_events: Some(0),
+            event_commitment: Some(EventCommitment::default()),
+            ..header.clone()
+        },
+        body: BlockBody::default(),
+    };
+    let res = gen_state_sync.store_blocks(vec![(BlockNumber(0), block, Some(signature))]);
+    assert_matches!(
+        res,
+        Err(StateSyncError::BlockHashMismatch { block_hash: hash, .. }) if hash == block_hash
+    );
+
+    // A transaction hash that doesn't match the transaction.
+    let block = Block {
+        header: header.clone(),
+        body: BlockBody {
+            transactions: vec![Transaction::L1Handler(L1HandlerTransaction::default())],
+            transaction_hashes: vec![TransactionHash::default()],
+            ..BlockBody::default()
+        },
+    };
+    let res = gen_state_sync.store_blocks(vec![(BlockNumber(0), block, Some(signature))]);
+    assert_matches!(res, Err(StateSyncError::TransactionHashMismatch { .. }));
+
+    // A signature that doesn't match the block.
+    let block = Block { header: header.clone(), body: BlockBody::default() };
+    let wrong_signature = BlockSignature(Signature { r: signature.0.r, s: stark_felt!("0x1") });
+    let res = gen_state_sync.store_blocks(vec![(BlockNumber(0), block, Some(wrong_signature))]);
+    assert_matches!(
+        res,
+        Err(StateSyncError::BlockSignatureMismatch { block_hash: hash, .. }) if hash == block_hash
+    );
+    // The block wasn't stored.
+    let header_marker = gen_state_sync.reader.begin_ro_txn().unwrap().get_header_marker().unwrap();
+    assert_eq!(header_marker, BlockNumber(0));
+
+    // A block without a signature can't be verified.
+    let block = Block { header: header.clone(), body: BlockBody::default() };
+    let res = gen_state_sync.store_blocks(vec![(BlockNumber(0), block, None)]);
+    assert_matches!(
+        res,
+        Err(StateSyncError::UnsignedBlock { block_number }) if block_number == BlockNumber(0)
+    );
+
+    // Happy flow.
+    let block = Block { header, body: BlockBody::default() };
+    gen_state_sync.store_blocks(vec![(BlockNumber(0), block, Some(signature))]).unwrap();
+    let header_marker = gen_state_sync.reader.begin_ro_txn().unwrap().get_header_marker().unwrap();
+    assert_eq!(header_marker, BlockNumber(1));
+}
+
+#[test]
+fn store_blocks_in_one_commit() {
+    let (reader, writer) = get_test_storage().0;
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig { verify_blocks: false, ..SyncConfig::default() },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        chain_id: ChainId("SN_MAIN".to_owned()),
+    };
+    let block = |block_number: u64, parent_hash: u64| Block {
+        header: BlockHeader {
+            block_number: BlockNumber(block_number),
+            block_hash: BlockHash(block_number.into()),
+            parent_hash: BlockHash(parent_hash.into()),
+            ..BlockHeader::default()
+        },
+        body: BlockBody::default(),
+    };
+
+    // The parent hash of a block is compared to the block before it in the same commit.
+    let res = gen_state_sync.store_blocks(vec![
+        (BlockNumber(0), block(0, 0), None),
+        (BlockNumber(1), block(1, 2), None),
+    ]);
+    assert_matches!(res, Err(StateSyncError::ParentBlockHashMismatch { .. }));
+    // None of the blocks was stored.
+    let header_marker = gen_state_sync.reader.begin_ro_txn().unwrap().get_header_marker().unwrap();
+    assert_eq!(header_marker, BlockNumber(0));
+
+    gen_state_sync
+        .store_blocks(vec![
+            (BlockNumber(0), block(0, 0), None),
+            (BlockNumber(1), block(1, 0), None),
+        ])
+        .unwrap();
+    let header_marker = gen_state_sync.reader.begin_ro_txn().unwrap().get_header_marker().unwrap();
+    assert_eq!(header_marker, BlockNumber(2));
+}
+
 // Adds to the storage 'headers_num' headers.
 fn add_headers(headers_num: u64, writer: &mut StorageWriter) {
     for i in 0..headers_num {
@@ -770,3 +1141,86 @@ async fn pending_sync_classes_are_cleaned_on_first_pending_data_from_latest_bloc
     )
     .await
 }
+
+// Appends the first block, with its state diff, to the storage.
+fn append_first_block(writer: &mut StorageWriter, block_hash: BlockHash) {
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(
+            BlockNumber(0),
+            &BlockHeader { block_hash, block_number: BlockNumber(0), ..Default::default() },
+        )
+        .unwrap()
+        .append_state_diff(BlockNumber(0), ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+fn get_pending_data_follower(
+    reader: StorageReader,
+    central_source: MockCentralSourceTrait,
+    pending_source: MockPendingSourceTrait,
+) -> GenericPendingDataFollower<MockCentralSourceTrait, MockPendingSourceTrait> {
+    GenericPendingDataFollower {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        central_source: Arc::new(central_source),
+        pending_source: Arc::new(pending_source),
+        reader,
+    }
+}
+
+#[tokio::test]
+async fn pending_data_follower_updates_pending_data_when_storage_is_synced() {
+    let block_hash = BlockHash(StarkHash::ONE);
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_first_block(&mut writer, block_hash);
+    let latest_block = BlockHashAndNumber { block_hash, block_number: BlockNumber(0) };
+    let pending_data = PendingData {
+        block: PendingBlockOrDeprecated::Deprecated(DeprecatedPendingBlock {
+            parent_block_hash: block_hash,
+            transactions: vec![ClientTransaction::get_test_instance(&mut get_rng())],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let mut mock_central_source = MockCentralSourceTrait::new();
+    mock_central_source.expect_get_latest_block().returning(move || Ok(Some(latest_block)));
+    let mut mock_pending_source = MockPendingSourceTrait::new();
+    let new_pending_data = pending_data.clone();
+    mock_pending_source.expect_get_pending_data().returning(move || Ok(new_pending_data.clone()));
+
+    let mut follower = get_pending_data_follower(reader, mock_central_source, mock_pending_source);
+    // The follower runs until there's an unrecoverable error.
+    tokio::time::timeout(Duration::from_millis(100), follower.run())
+        .await
+        .expect_err("The follower shouldn't stop.");
+    assert_eq!(*follower.shared_highest_block.read().await, Some(latest_block));
+    assert_eq!(*follower.pending_data.read().await, pending_data);
+}
+
+#[tokio::test]
+async fn pending_data_follower_waits_for_storage_to_sync() {
+    let block_hash = BlockHash(StarkHash::ONE);
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_first_block(&mut writer, block_hash);
+    let latest_block =
+        BlockHashAndNumber { block_hash: BlockHash(StarkHash::TWO), block_number: BlockNumber(1) };
+
+    let mut mock_central_source = MockCentralSourceTrait::new();
+    mock_central_source.expect_get_latest_block().returning(move || Ok(Some(latest_block)));
+    // The pending data isn't requested before the storage has the latest block.
+    let mock_pending_source = MockPendingSourceTrait::new();
+
+    let mut follower = get_pending_data_follower(reader, mock_central_source, mock_pending_source);
+    tokio::time::timeout(Duration::from_millis(100), follower.run())
+        .await
+        .expect_err("The follower shouldn't stop.");
+    assert_eq!(*follower.shared_highest_block.read().await, Some(latest_block));
+    assert_eq!(*follower.pending_data.read().await, PendingData::default());
+}
@@ -0,0 +1,251 @@
+#[cfg(test)]
+#[path = "failover_test.rs"]
+mod failover_test;
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, SequencerPublicKey};
+use tracing::{debug, warn};
+
+use crate::reader::{
+    BlockOrDeprecated,
+    BlockSignatureData,
+    GenericContractClass,
+    PendingData,
+    ReaderClientError,
+    ReaderClientResult,
+    StarknetReader,
+    StateUpdate,
+};
+
+/// A [`StarknetReader`] that sends each request to one of several readers, failing over to the
+/// next reader when a request fails (including when it's rate limited after all of its retries).
+///
+/// The reader that served the last successful request is used for the next requests. Readers that
+/// don't support a request (for example, JSON-RPC nodes don't have block signatures) are skipped
+/// for it without failing over.
+///
+/// If `cross_check_block_hashes` is set, every block is also requested from another reader and is
+/// returned only if both readers agree on its hash. If the other readers don't answer
+/// [`MAX_UNANSWERED_CROSS_CHECKS`] cross-checks in a row, the blocks are returned without
+/// cross-checking them until one of the other readers answers again.
+pub struct FailoverStarknetReader {
+    readers: Vec<Box<dyn StarknetReader + Send + Sync>>,
+    active_reader: AtomicUsize,
+    cross_check_block_hashes: bool,
+    unanswered_cross_checks: AtomicUsize,
+}
+
+/// The number of cross-checks in a row that the other readers may fail to answer before blocks are
+/// returned without cross-checking them.
+pub const MAX_UNANSWERED_CROSS_CHECKS: usize = 5;
+
+impl FailoverStarknetReader {
+    pub fn new(
+        readers: Vec<Box<dyn StarknetReader + Send + Sync>>,
+        cross_check_block_hashes: bool,
+    ) -> Self {
+        assert!(!readers.is_empty(), "FailoverStarknetReader requires at least one reader.");
+        if cross_check_block_hashes && readers.len() < 2 {
+            warn!("Cross-checking block hashes requires at least two readers. Skipping it.");
+        }
+        FailoverStarknetReader {
+            readers,
+            active_reader: AtomicUsize::new(0),
+            cross_check_block_hashes,
+            unanswered_cross_checks: AtomicUsize::new(0),
+        }
+    }
+
+    // Sends the request to the readers in the given order until one of them succeeds. Returns the
+    // index of the reader that succeeded and its response.
+    async fn request_from<'a, T, Request, ResponseFuture>(
+        &'a self,
+        reader_indices: impl Iterator<Item = usize> + Send,
+        request: Request,
+        fail_over: bool,
+    ) -> ReaderClientResult<(usize, T)>
+    where
+        Request: Fn(&'a (dyn StarknetReader + Send + Sync)) -> ResponseFuture + Send,
+        ResponseFuture: Future<Output = ReaderClientResult<T>> + Send,
+    {
+        let mut last_error = None;
+        for reader_index in reader_indices {
+            match request(self.readers[reader_index].as_ref()).await {
+                Ok(response) => return Ok((reader_index, response)),
+                Err(err @ ReaderClientError::UnsupportedMethod { .. }) => {
+                    debug!("Starknet reader {reader_index} doesn't support the request: {err}");
+                    last_error.get_or_insert(err);
+                }
+                Err(err) => {
+                    warn!(
+                        "Request to Starknet reader {reader_index} failed: {err}. Trying the next \
+                         reader."
+                    );
+                    if fail_over {
+                        // Don't override a fail over done by a concurrent request.
+                        let _ = self.active_reader.compare_exchange(
+                            reader_index,
+                            (reader_index + 1) % self.readers.len(),
+                            Ordering::Relaxed,
+                            Ordering::Relaxed,
+                        );
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.expect("Requests are sent to at least one reader."))
+    }
+
+    async fn request<'a, T, Request, ResponseFuture>(
+        &'a self,
+        request: Request,
+    ) -> ReaderClientResult<(usize, T)>
+    where
+        Request: Fn(&'a (dyn StarknetReader + Send + Sync)) -> ResponseFuture + Send,
+        ResponseFuture: Future<Output = ReaderClientResult<T>> + Send,
+    {
+        let first_reader = self.active_reader.load(Ordering::Relaxed);
+        self.request_from(self.readers_from(first_reader, 0), request, true).await
+    }
+
+    // Returns the indices of all the readers, cyclically starting from `first_reader` and skipping
+    // the first `n_skipped` of them.
+    fn readers_from(
+        &self,
+        first_reader: usize,
+        n_skipped: usize,
+    ) -> impl Iterator<Item = usize> + Send {
+        let n_readers = self.readers.len();
+        (first_reader + n_skipped..first_reader + n_readers).map(move |i| i % n_readers)
+    }
+
+    // Returns whether another reader than `reader_index` agrees on the hash of `block`. Returns
+    // false if the first reader that answered doesn't have the block yet, or if none of the other
+    // readers answered, so that an unreachable reader delays the block instead of failing it. Once
+    // the other readers didn't answer MAX_UNANSWERED_CROSS_CHECKS cross-checks in a row, returns
+    // true so that the sync doesn't stall while they are down.
+    async fn cross_check_block_hash(
+        &self,
+        reader_index: usize,
+        block: &BlockOrDeprecated,
+    ) -> ReaderClientResult<bool> {
+        let block_number = block.block_number();
+        let response = self
+            .request_from(
+                self.readers_from(reader_index, 1),
+                |reader| reader.block(block_number),
+                // The readers are queried in a different order than the active one.
+                false,
+            )
+            .await;
+        let (other_reader_index, other_block) = match response {
+            Ok(response) => {
+                self.unanswered_cross_checks.store(0, Ordering::Relaxed);
+                response
+            }
+            Err(err) => {
+                let unanswered_cross_checks =
+                    self.unanswered_cross_checks.fetch_add(1, Ordering::Relaxed) + 1;
+                if unanswered_cross_checks >= MAX_UNANSWERED_CROSS_CHECKS {
+                    warn!(
+                        "Accepting block {block_number} without cross-checking its hash, the \
+                         other Starknet readers didn't answer {unanswered_cross_checks} \
+                         cross-checks in a row: {err}"
+                    );
+                    return Ok(true);
+                }
+                warn!(
+                    "Failed cross-checking the hash of block {block_number}, the other Starknet \
+                     readers didn't answer: {err}"
+                );
+                return Ok(false);
+            }
+        };
+        let Some(other_block) = other_block else {
+            debug!(
+                "Block {block_number} was not found in Starknet reader {other_reader_index}. \
+                 Can't cross-check its hash yet."
+            );
+            return Ok(false);
+        };
+        if other_block.block_hash() != block.block_hash() {
+            return Err(ReaderClientError::BlockHashMismatch {
+                block_number,
+                block_hash: block.block_hash(),
+                other_block_hash: other_block.block_hash(),
+            });
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl StarknetReader for FailoverStarknetReader {
+    async fn latest_block(&self) -> ReaderClientResult<Option<BlockOrDeprecated>> {
+        Ok(self.request(|reader| reader.latest_block()).await?.1)
+    }
+
+    async fn block(
+        &self,
+        block_number: BlockNumber,
+    ) -> ReaderClientResult<Option<BlockOrDeprecated>> {
+        let (reader_index, block) = self.request(|reader| reader.block(block_number)).await?;
+        match block {
+            Some(block) if self.cross_check_block_hashes && self.readers.len() > 1 => {
+                Ok(self.cross_check_block_hash(reader_index, &block).await?.then_some(block))
+            }
+            block => Ok(block),
+        }
+    }
+
+    async fn class_by_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> ReaderClientResult<Option<GenericContractClass>> {
+        Ok(self.request(|reader| reader.class_by_hash(class_hash)).await?.1)
+    }
+
+    async fn compiled_class_by_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> ReaderClientResult<Option<CasmContractClass>> {
+        Ok(self.request(|reader| reader.compiled_class_by_hash(class_hash)).await?.1)
+    }
+
+    async fn state_update(
+        &self,
+        block_number: BlockNumber,
+    ) -> ReaderClientResult<Option<StateUpdate>> {
+        Ok(self.request(|reader| reader.state_update(block_number)).await?.1)
+    }
+
+    async fn pending_data(&self) -> ReaderClientResult<Option<PendingData>> {
+        Ok(self.request(|reader| reader.pending_data()).await?.1)
+    }
+
+    async fn is_alive(&self) -> bool {
+        for reader in &self.readers {
+            if reader.is_alive().await {
+                return true;
+            }
+        }
+        false
+    }
+
+    async fn block_signature(
+        &self,
+        block_number: BlockNumber,
+    ) -> ReaderClientResult<Option<BlockSignatureData>> {
+        Ok(self.request(|reader| reader.block_signature(block_number)).await?.1)
+    }
+
+    async fn sequencer_pub_key(&self) -> ReaderClientResult<SequencerPublicKey> {
+        Ok(self.request(|reader| reader.sequencer_pub_key()).await?.1)
+    }
+}
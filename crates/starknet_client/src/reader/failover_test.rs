@@ -0,0 +1,177 @@
+use assert_matches::assert_matches;
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+
+use super::{FailoverStarknetReader, MAX_UNANSWERED_CROSS_CHECKS};
+use crate::reader::objects::block::DeprecatedBlock;
+use crate::reader::{
+    BlockOrDeprecated,
+    BlockSignatureData,
+    MockStarknetReader,
+    ReaderClientError,
+    StarknetReader,
+};
+
+fn block_with_hash(block_hash: BlockHash) -> BlockOrDeprecated {
+    BlockOrDeprecated::Deprecated(DeprecatedBlock { block_hash, ..Default::default() })
+}
+
+fn failed_request() -> ReaderClientError {
+    ReaderClientError::JsonRpcError { code: -32603, message: "Internal error".to_owned() }
+}
+
+fn failover_reader(
+    readers: Vec<MockStarknetReader>,
+    cross_check_block_hashes: bool,
+) -> FailoverStarknetReader {
+    FailoverStarknetReader::new(
+        readers
+            .into_iter()
+            .map(|reader| Box::new(reader) as Box<dyn StarknetReader + Send + Sync>)
+            .collect(),
+        cross_check_block_hashes,
+    )
+}
+
+#[tokio::test]
+async fn fails_over_to_the_next_reader() {
+    let mut first_reader = MockStarknetReader::new();
+    first_reader.expect_block().times(1).returning(|_| Err(failed_request()));
+    let mut second_reader = MockStarknetReader::new();
+    // The second reader serves the requests after the fail over.
+    second_reader.expect_block().times(2).returning(|_| Ok(Some(BlockOrDeprecated::default())));
+    let reader = failover_reader(vec![first_reader, second_reader], false);
+
+    assert!(reader.block(BlockNumber(0)).await.unwrap().is_some());
+    assert!(reader.block(BlockNumber(1)).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn returns_the_last_error_if_all_readers_fail() {
+    let mut first_reader = MockStarknetReader::new();
+    first_reader.expect_block().times(1).returning(|_| Err(failed_request()));
+    let mut second_reader = MockStarknetReader::new();
+    second_reader
+        .expect_block()
+        .times(1)
+        .returning(|_| Err(ReaderClientError::JsonRpcError { code: 1, message: "".to_owned() }));
+    let reader = failover_reader(vec![first_reader, second_reader], false);
+
+    assert_matches!(
+        reader.block(BlockNumber(0)).await,
+        Err(ReaderClientError::JsonRpcError { code: 1, .. })
+    );
+}
+
+#[tokio::test]
+async fn skips_readers_that_dont_support_the_request() {
+    let mut first_reader = MockStarknetReader::new();
+    first_reader
+        .expect_block_signature()
+        .times(1)
+        .returning(|_| Err(ReaderClientError::UnsupportedMethod { method: "block_signature" }));
+    first_reader.expect_block().times(1).returning(|_| Ok(Some(BlockOrDeprecated::default())));
+    let mut second_reader = MockStarknetReader::new();
+    second_reader
+        .expect_block_signature()
+        .times(1)
+        .returning(|_| Ok(Some(BlockSignatureData::default())));
+    let reader = failover_reader(vec![first_reader, second_reader], false);
+
+    assert!(reader.block_signature(BlockNumber(0)).await.unwrap().is_some());
+    // The first reader is still used for the requests it supports.
+    assert!(reader.block(BlockNumber(0)).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn cross_checks_block_hashes() {
+    let block_hash = BlockHash(stark_felt!("0x1"));
+    let other_block_hash = BlockHash(stark_felt!("0x2"));
+    let mut first_reader = MockStarknetReader::new();
+    first_reader.expect_block().times(3).returning(move |_| Ok(Some(block_with_hash(block_hash))));
+    let mut second_reader = MockStarknetReader::new();
+    let mut second_reader_sequence = mockall::Sequence::new();
+    second_reader
+        .expect_block()
+        .times(1)
+        .in_sequence(&mut second_reader_sequence)
+        .returning(move |_| Ok(Some(block_with_hash(block_hash))));
+    second_reader
+        .expect_block()
+        .times(1)
+        .in_sequence(&mut second_reader_sequence)
+        .returning(|_| Ok(None));
+    second_reader
+        .expect_block()
+        .times(1)
+        .in_sequence(&mut second_reader_sequence)
+        .returning(move |_| Ok(Some(block_with_hash(other_block_hash))));
+    let reader = failover_reader(vec![first_reader, second_reader], true);
+
+    // Both readers agree on the block hash.
+    assert_eq!(reader.block(BlockNumber(0)).await.unwrap().unwrap().block_hash(), block_hash);
+    // The second reader doesn't have the block yet.
+    assert!(reader.block(BlockNumber(0)).await.unwrap().is_none());
+    // The readers disagree on the block hash.
+    assert_matches!(
+        reader.block(BlockNumber(0)).await,
+        Err(ReaderClientError::BlockHashMismatch { block_hash: first, other_block_hash: second, .. })
+            if first == block_hash && second == other_block_hash
+    );
+}
+
+#[tokio::test]
+async fn cross_check_with_an_unreachable_reader_delays_the_block() {
+    let block_hash = BlockHash(stark_felt!("0x1"));
+    let mut first_reader = MockStarknetReader::new();
+    first_reader.expect_block().times(1).returning(move |_| Ok(Some(block_with_hash(block_hash))));
+    let mut second_reader = MockStarknetReader::new();
+    second_reader.expect_block().times(1).returning(|_| Err(failed_request()));
+    let reader = failover_reader(vec![first_reader, second_reader], true);
+
+    assert!(reader.block(BlockNumber(0)).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn cross_check_with_a_reader_that_is_down_accepts_the_blocks() {
+    let block_hash = BlockHash(stark_felt!("0x1"));
+    let mut first_reader = MockStarknetReader::new();
+    first_reader
+        .expect_block()
+        .times(MAX_UNANSWERED_CROSS_CHECKS + 1)
+        .returning(move |_| Ok(Some(block_with_hash(block_hash))));
+    let mut second_reader = MockStarknetReader::new();
+    let mut second_reader_sequence = mockall::Sequence::new();
+    second_reader
+        .expect_block()
+        .times(MAX_UNANSWERED_CROSS_CHECKS)
+        .in_sequence(&mut second_reader_sequence)
+        .returning(|_| Err(failed_request()));
+    // The second reader is back up.
+    second_reader
+        .expect_block()
+        .times(1)
+        .in_sequence(&mut second_reader_sequence)
+        .returning(|_| Ok(None));
+    let reader = failover_reader(vec![first_reader, second_reader], true);
+
+    for _ in 1..MAX_UNANSWERED_CROSS_CHECKS {
+        assert!(reader.block(BlockNumber(0)).await.unwrap().is_none());
+    }
+    // The sync moves forward while the second reader is down.
+    assert_eq!(reader.block(BlockNumber(0)).await.unwrap().unwrap().block_hash(), block_hash);
+    // Once the second reader answers, the blocks are cross-checked again.
+    assert!(reader.block(BlockNumber(1)).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn is_alive_if_any_reader_is_alive() {
+    let mut first_reader = MockStarknetReader::new();
+    first_reader.expect_is_alive().times(1).returning(|| false);
+    let mut second_reader = MockStarknetReader::new();
+    second_reader.expect_is_alive().times(1).returning(|| true);
+    let reader = failover_reader(vec![first_reader, second_reader], false);
+
+    assert!(reader.is_alive().await);
+}
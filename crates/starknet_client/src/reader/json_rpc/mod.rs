@@ -0,0 +1,240 @@
+//! This module contains a client that can read data from a [`Starknet`] JSON-RPC node.
+//!
+//! [`Starknet`]: https://starknet.io/
+
+pub mod objects;
+#[cfg(test)]
+mod starknet_json_rpc_client_test;
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, SequencerPublicKey};
+use tracing::{debug, error, instrument};
+use url::Url;
+
+use self::objects::{
+    BlockWithReceipts,
+    GenericJsonRpcContractClass,
+    PendingBlockWithReceipts,
+    PendingStateUpdate,
+    StateUpdate,
+};
+use crate::reader::{
+    BlockOrDeprecated,
+    BlockSignatureData,
+    GenericContractClass,
+    PendingData,
+    ReaderClientError,
+    ReaderClientResult,
+    StarknetReader,
+    StateUpdate as ClientStateUpdate,
+};
+use crate::retry::RetryConfig;
+use crate::{ClientCreationError, StarknetClient};
+
+const JSON_RPC_VERSION: &str = "2.0";
+const GET_BLOCK_WITH_RECEIPTS_METHOD: &str = "starknet_getBlockWithReceipts";
+const GET_STATE_UPDATE_METHOD: &str = "starknet_getStateUpdate";
+const GET_CLASS_METHOD: &str = "starknet_getClass";
+const SPEC_VERSION_METHOD: &str = "starknet_specVersion";
+const LATEST_BLOCK_ID: &str = "latest";
+const PENDING_BLOCK_ID: &str = "pending";
+const BLOCK_NOT_FOUND_ERROR_CODE: i64 = 24;
+const CLASS_HASH_NOT_FOUND_ERROR_CODE: i64 = 28;
+
+/// A client for a [`Starknet`] JSON-RPC node that implements spec v0.7.
+///
+/// The JSON-RPC spec has no methods for block signatures, the sequencer public key and compiled
+/// classes, so these return [`ReaderClientError::UnsupportedMethod`].
+///
+/// [`Starknet`]: https://starknet.io/
+pub struct StarknetJsonRpcClient {
+    url: Url,
+    client: StarknetClient,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse {
+    result: Option<Value>,
+    error: Option<JsonRpcErrorObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+impl StarknetJsonRpcClient {
+    pub fn new(
+        url_str: &str,
+        http_headers: Option<HashMap<String, String>>,
+        node_version: &'static str,
+        retry_config: RetryConfig,
+    ) -> Result<Self, ClientCreationError> {
+        Ok(StarknetJsonRpcClient {
+            url: Url::parse(url_str)?,
+            client: StarknetClient::new(http_headers, node_version, retry_config)?,
+        })
+    }
+
+    /// Sends a request for `method` and loads its result. If the node returned an error with
+    /// `none_error_code`, returns None.
+    async fn request<Object: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+        none_error_code: Option<i64>,
+    ) -> ReaderClientResult<Option<Object>> {
+        let request_body =
+            json!({"jsonrpc": JSON_RPC_VERSION, "id": 1, "method": method, "params": params});
+        let raw_response = self
+            .client
+            .request_with_retry(
+                self.client.internal_client.post(self.url.clone()).json(&request_body),
+            )
+            .await?;
+        let response: JsonRpcResponse = serde_json::from_str(&raw_response).map_err(|err| {
+            error!("Failed to deserialize {raw_response:?}. Error: {err}");
+            err
+        })?;
+        match (response.result, response.error) {
+            (_, Some(JsonRpcErrorObject { code, message: _ })) if Some(code) == none_error_code => {
+                Ok(None)
+            }
+            (_, Some(JsonRpcErrorObject { code, message })) => {
+                debug!("Request {method} with params {params} failed.");
+                Err(ReaderClientError::JsonRpcError { code, message })
+            }
+            (Some(result), None) => {
+                let object = serde_json::from_value(result.clone());
+                if let Err(err) = &object {
+                    error!("Failed to deserialize {result:?}. Error: {err}");
+                }
+                Ok(Some(object?))
+            }
+            (None, None) => Err(ReaderClientError::JsonRpcError {
+                code: 0,
+                message: "Response has neither a result nor an error.".to_string(),
+            }),
+        }
+    }
+
+    async fn request_block(
+        &self,
+        block_id: Value,
+    ) -> ReaderClientResult<Option<BlockOrDeprecated>> {
+        self.request::<BlockWithReceipts>(
+            GET_BLOCK_WITH_RECEIPTS_METHOD,
+            json!({ "block_id": block_id }),
+            Some(BLOCK_NOT_FOUND_ERROR_CODE),
+        )
+        .await?
+        .map(BlockOrDeprecated::try_from)
+        .transpose()
+    }
+}
+
+#[async_trait]
+impl StarknetReader for StarknetJsonRpcClient {
+    #[instrument(skip(self), level = "debug")]
+    async fn latest_block(&self) -> ReaderClientResult<Option<BlockOrDeprecated>> {
+        self.request_block(json!(LATEST_BLOCK_ID)).await
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn block(
+        &self,
+        block_number: BlockNumber,
+    ) -> ReaderClientResult<Option<BlockOrDeprecated>> {
+        self.request_block(json!({ "block_number": block_number.0 })).await
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn class_by_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> ReaderClientResult<Option<GenericContractClass>> {
+        // Query the pending block like the feeder gateway client does.
+        self.request::<GenericJsonRpcContractClass>(
+            GET_CLASS_METHOD,
+            json!({ "block_id": PENDING_BLOCK_ID, "class_hash": class_hash }),
+            Some(CLASS_HASH_NOT_FOUND_ERROR_CODE),
+        )
+        .await?
+        .map(|class| class.into_client_class(class_hash))
+        .transpose()
+    }
+
+    async fn compiled_class_by_hash(
+        &self,
+        _class_hash: ClassHash,
+    ) -> ReaderClientResult<Option<CasmContractClass>> {
+        Err(ReaderClientError::UnsupportedMethod { method: "compiled_class_by_hash" })
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn state_update(
+        &self,
+        block_number: BlockNumber,
+    ) -> ReaderClientResult<Option<ClientStateUpdate>> {
+        Ok(self
+            .request::<StateUpdate>(
+                GET_STATE_UPDATE_METHOD,
+                json!({ "block_id": { "block_number": block_number.0 } }),
+                Some(BLOCK_NOT_FOUND_ERROR_CODE),
+            )
+            .await?
+            .map(ClientStateUpdate::from))
+    }
+
+    // Unlike the feeder gateway, the pending block and its state update are requested separately,
+    // so the state update might belong to a newer version of the pending block.
+    #[instrument(skip(self), level = "debug")]
+    async fn pending_data(&self) -> ReaderClientResult<Option<PendingData>> {
+        let Some(block) = self
+            .request::<PendingBlockWithReceipts>(
+                GET_BLOCK_WITH_RECEIPTS_METHOD,
+                json!({ "block_id": PENDING_BLOCK_ID }),
+                Some(BLOCK_NOT_FOUND_ERROR_CODE),
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        let Some(state_update) = self
+            .request::<PendingStateUpdate>(
+                GET_STATE_UPDATE_METHOD,
+                json!({ "block_id": PENDING_BLOCK_ID }),
+                Some(BLOCK_NOT_FOUND_ERROR_CODE),
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(PendingData { block: block.try_into()?, state_update: state_update.into() }))
+    }
+
+    async fn is_alive(&self) -> bool {
+        self.request::<String>(SPEC_VERSION_METHOD, json!([]), None)
+            .await
+            .is_ok_and(|spec_version| spec_version.is_some())
+    }
+
+    async fn block_signature(
+        &self,
+        _block_number: BlockNumber,
+    ) -> ReaderClientResult<Option<BlockSignatureData>> {
+        Err(ReaderClientError::UnsupportedMethod { method: "block_signature" })
+    }
+
+    async fn sequencer_pub_key(&self) -> ReaderClientResult<SequencerPublicKey> {
+        Err(ReaderClientError::UnsupportedMethod { method: "sequencer_pub_key" })
+    }
+}
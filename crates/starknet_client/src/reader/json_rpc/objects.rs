@@ -0,0 +1,639 @@
+//! Objects returned by a [`Starknet`] JSON-RPC node (spec v0.7) and their conversion into the
+//! objects returned by the feeder gateway.
+//!
+//! [`Starknet`]: https://starknet.io/
+
+use std::collections::{BTreeMap, HashMap};
+
+use flate2::read::GzDecoder;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use starknet_api::block::{BlockHash, BlockNumber, BlockTimestamp, GasPricePerToken};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    EntryPointSelector,
+    EventCommitment,
+    GlobalRoot,
+    Nonce,
+    SequencerContractAddress,
+    TransactionCommitment,
+};
+use starknet_api::data_availability::{DataAvailabilityMode, L1DataAvailabilityMode};
+use starknet_api::deprecated_contract_class::{
+    ContractClass as DeprecatedContractClass,
+    ContractClassAbiEntry,
+    EntryPoint as DeprecatedEntryPoint,
+    EntryPointType as DeprecatedEntryPointType,
+    Program,
+};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{
+    AccountDeploymentData,
+    Calldata,
+    ContractAddressSalt,
+    Event,
+    Fee,
+    PaymasterData,
+    Resource,
+    ResourceBounds,
+    Tip,
+    TransactionHash,
+    TransactionOffsetInBlock,
+    TransactionSignature,
+    TransactionVersion,
+};
+
+use crate::reader::objects::block::{Block, BlockStatus};
+use crate::reader::objects::pending_data::{
+    PendingBlock,
+    PendingBlockOrDeprecated,
+    PendingStateUpdate as ClientPendingStateUpdate,
+};
+use crate::reader::objects::transaction::{
+    Builtin,
+    DataAvailabilityResources,
+    DeployTransaction as ClientDeployTransaction,
+    ExecutionResources as ClientExecutionResources,
+    IntermediateDeclareTransaction,
+    IntermediateDeployAccountTransaction,
+    IntermediateInvokeTransaction,
+    L1HandlerTransaction as ClientL1HandlerTransaction,
+    L1ToL2Message,
+    L2ToL1Message,
+    ReservedDataAvailabilityMode,
+    Transaction as ClientTransaction,
+    TransactionExecutionStatus,
+    TransactionReceipt as ClientTransactionReceipt,
+};
+use crate::reader::{
+    BlockOrDeprecated,
+    ContractClass,
+    DeclaredClassHashEntry,
+    DeployedContract,
+    GenericContractClass,
+    ReaderClientError,
+    ReaderClientResult,
+    ReplacedClass as ClientReplacedClass,
+    StateDiff as ClientStateDiff,
+    StateUpdate as ClientStateUpdate,
+    StorageEntry,
+};
+
+/// A block with its transactions and their receipts, as returned by
+/// `starknet_getBlockWithReceipts`.
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct BlockWithReceipts {
+    pub status: BlockStatus,
+    pub block_hash: BlockHash,
+    pub parent_hash: BlockHash,
+    pub block_number: BlockNumber,
+    pub new_root: GlobalRoot,
+    pub timestamp: BlockTimestamp,
+    pub sequencer_address: SequencerContractAddress,
+    pub l1_gas_price: GasPricePerToken,
+    pub l1_data_gas_price: GasPricePerToken,
+    pub l1_da_mode: L1DataAvailabilityMode,
+    pub starknet_version: String,
+    pub transactions: Vec<TransactionWithReceipt>,
+}
+
+impl TryFrom<BlockWithReceipts> for BlockOrDeprecated {
+    type Error = ReaderClientError;
+
+    fn try_from(block: BlockWithReceipts) -> ReaderClientResult<Self> {
+        let (transactions, transaction_receipts) =
+            into_client_transactions_and_receipts(block.transactions)?;
+        Ok(BlockOrDeprecated::Current(Block {
+            block_hash: block.block_hash,
+            block_number: block.block_number,
+            parent_block_hash: block.parent_hash,
+            sequencer_address: block.sequencer_address,
+            state_root: block.new_root,
+            status: block.status,
+            timestamp: block.timestamp,
+            transactions,
+            transaction_receipts,
+            starknet_version: block.starknet_version,
+            l1_da_mode: block.l1_da_mode,
+            l1_gas_price: block.l1_gas_price,
+            l1_data_gas_price: block.l1_data_gas_price,
+            // The JSON-RPC block header doesn't contain the commitments. Empty commitments are
+            // converted to None when converting to a starknet_api block.
+            transaction_commitment: TransactionCommitment::default(),
+            event_commitment: EventCommitment::default(),
+        }))
+    }
+}
+
+/// The pending block with its transactions and their receipts, as returned by
+/// `starknet_getBlockWithReceipts` for the pending block.
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct PendingBlockWithReceipts {
+    pub parent_hash: BlockHash,
+    pub timestamp: BlockTimestamp,
+    pub sequencer_address: SequencerContractAddress,
+    pub l1_gas_price: GasPricePerToken,
+    pub l1_data_gas_price: GasPricePerToken,
+    pub l1_da_mode: L1DataAvailabilityMode,
+    pub starknet_version: String,
+    pub transactions: Vec<TransactionWithReceipt>,
+}
+
+impl TryFrom<PendingBlockWithReceipts> for PendingBlockOrDeprecated {
+    type Error = ReaderClientError;
+
+    fn try_from(block: PendingBlockWithReceipts) -> ReaderClientResult<Self> {
+        let (transactions, transaction_receipts) =
+            into_client_transactions_and_receipts(block.transactions)?;
+        Ok(PendingBlockOrDeprecated::Current(PendingBlock {
+            accepted_on_l2_extra_data: None,
+            parent_block_hash: block.parent_hash,
+            status: BlockStatus::Pending,
+            l1_gas_price: block.l1_gas_price,
+            l1_data_gas_price: block.l1_data_gas_price,
+            transactions,
+            timestamp: block.timestamp,
+            sequencer_address: block.sequencer_address,
+            transaction_receipts,
+            starknet_version: block.starknet_version,
+            l1_da_mode: block.l1_da_mode,
+            transaction_commitment: None,
+            event_commitment: None,
+        }))
+    }
+}
+
+pub(crate) fn into_client_transactions_and_receipts(
+    transactions: Vec<TransactionWithReceipt>,
+) -> ReaderClientResult<(Vec<ClientTransaction>, Vec<ClientTransactionReceipt>)> {
+    let mut client_transactions = Vec::with_capacity(transactions.len());
+    let mut client_receipts = Vec::with_capacity(transactions.len());
+    for (index, TransactionWithReceipt { transaction, receipt }) in
+        transactions.into_iter().enumerate()
+    {
+        client_transactions.push(
+            transaction
+                .into_client_transaction(receipt.transaction_hash, receipt.contract_address)?,
+        );
+        client_receipts.push(receipt.into_client_receipt(TransactionOffsetInBlock(index)));
+    }
+    Ok((client_transactions, client_receipts))
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct TransactionWithReceipt {
+    pub transaction: Transaction,
+    pub receipt: TransactionReceipt,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum Transaction {
+    #[serde(rename = "DECLARE")]
+    Declare(DeclareTransaction),
+    #[serde(rename = "DEPLOY")]
+    Deploy(DeployTransaction),
+    #[serde(rename = "DEPLOY_ACCOUNT")]
+    DeployAccount(DeployAccountTransaction),
+    #[serde(rename = "INVOKE")]
+    Invoke(InvokeTransaction),
+    #[serde(rename = "L1_HANDLER")]
+    L1Handler(L1HandlerTransaction),
+}
+
+impl Transaction {
+    // JSON-RPC transactions don't contain their hash, and deploy transactions don't contain the
+    // address of the deployed contract. These are taken from the transaction receipt.
+    fn into_client_transaction(
+        self,
+        transaction_hash: TransactionHash,
+        contract_address: Option<ContractAddress>,
+    ) -> ReaderClientResult<ClientTransaction> {
+        let contract_address = || {
+            contract_address.ok_or(ReaderClientError::BadTransaction {
+                tx_hash: transaction_hash,
+                msg: "The receipt of a deploy transaction must contain contract_address field."
+                    .to_string(),
+            })
+        };
+        Ok(match self {
+            Transaction::Declare(tx) => {
+                ClientTransaction::Declare(IntermediateDeclareTransaction {
+                    resource_bounds: tx.resource_bounds.map(Into::into),
+                    tip: tx.tip,
+                    signature: tx.signature,
+                    nonce: tx.nonce,
+                    class_hash: tx.class_hash,
+                    compiled_class_hash: tx.compiled_class_hash,
+                    sender_address: tx.sender_address,
+                    nonce_data_availability_mode: into_reserved_data_availability_mode(
+                        tx.nonce_data_availability_mode,
+                        transaction_hash,
+                    )?,
+                    fee_data_availability_mode: into_reserved_data_availability_mode(
+                        tx.fee_data_availability_mode,
+                        transaction_hash,
+                    )?,
+                    paymaster_data: tx.paymaster_data,
+                    account_deployment_data: tx.account_deployment_data,
+                    max_fee: tx.max_fee,
+                    version: tx.version,
+                    transaction_hash,
+                })
+            }
+            Transaction::Deploy(tx) => ClientTransaction::Deploy(ClientDeployTransaction {
+                contract_address: contract_address()?,
+                contract_address_salt: tx.contract_address_salt,
+                class_hash: tx.class_hash,
+                constructor_calldata: tx.constructor_calldata,
+                transaction_hash,
+                version: tx.version,
+            }),
+            Transaction::DeployAccount(tx) => {
+                ClientTransaction::DeployAccount(IntermediateDeployAccountTransaction {
+                    resource_bounds: tx.resource_bounds.map(Into::into),
+                    tip: tx.tip,
+                    signature: tx.signature,
+                    nonce: tx.nonce,
+                    class_hash: tx.class_hash,
+                    contract_address_salt: tx.contract_address_salt,
+                    constructor_calldata: tx.constructor_calldata,
+                    nonce_data_availability_mode: into_reserved_data_availability_mode(
+                        tx.nonce_data_availability_mode,
+                        transaction_hash,
+                    )?,
+                    fee_data_availability_mode: into_reserved_data_availability_mode(
+                        tx.fee_data_availability_mode,
+                        transaction_hash,
+                    )?,
+                    paymaster_data: tx.paymaster_data,
+                    sender_address: contract_address()?,
+                    max_fee: tx.max_fee,
+                    transaction_hash,
+                    version: tx.version,
+                })
+            }
+            Transaction::Invoke(tx) => ClientTransaction::Invoke(IntermediateInvokeTransaction {
+                resource_bounds: tx.resource_bounds.map(Into::into),
+                tip: tx.tip,
+                calldata: tx.calldata,
+                sender_address: tx.sender_address,
+                entry_point_selector: tx.entry_point_selector,
+                nonce: tx.nonce,
+                max_fee: tx.max_fee,
+                signature: tx.signature,
+                nonce_data_availability_mode: into_reserved_data_availability_mode(
+                    tx.nonce_data_availability_mode,
+                    transaction_hash,
+                )?,
+                fee_data_availability_mode: into_reserved_data_availability_mode(
+                    tx.fee_data_availability_mode,
+                    transaction_hash,
+                )?,
+                paymaster_data: tx.paymaster_data,
+                account_deployment_data: tx.account_deployment_data,
+                transaction_hash,
+                version: tx.version,
+            }),
+            Transaction::L1Handler(tx) => {
+                ClientTransaction::L1Handler(ClientL1HandlerTransaction {
+                    transaction_hash,
+                    version: tx.version,
+                    nonce: tx.nonce,
+                    contract_address: tx.contract_address,
+                    entry_point_selector: tx.entry_point_selector,
+                    calldata: tx.calldata,
+                })
+            }
+        })
+    }
+}
+
+// The feeder gateway objects support only the L1 data availability mode.
+fn into_reserved_data_availability_mode(
+    data_availability_mode: Option<DataAvailabilityMode>,
+    transaction_hash: TransactionHash,
+) -> ReaderClientResult<Option<ReservedDataAvailabilityMode>> {
+    match data_availability_mode {
+        None => Ok(None),
+        Some(DataAvailabilityMode::L1) => Ok(Some(ReservedDataAvailabilityMode::Reserved)),
+        Some(DataAvailabilityMode::L2) => Err(ReaderClientError::BadTransaction {
+            tx_hash: transaction_hash,
+            msg: "L2 data availability mode is not supported.".to_string(),
+        }),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct DeclareTransaction {
+    pub resource_bounds: Option<ResourceBoundsMapping>,
+    pub tip: Option<Tip>,
+    pub signature: TransactionSignature,
+    // Declare V0 transactions don't have a nonce.
+    #[serde(default)]
+    pub nonce: Nonce,
+    pub class_hash: ClassHash,
+    pub compiled_class_hash: Option<CompiledClassHash>,
+    pub sender_address: ContractAddress,
+    pub nonce_data_availability_mode: Option<DataAvailabilityMode>,
+    pub fee_data_availability_mode: Option<DataAvailabilityMode>,
+    pub paymaster_data: Option<PaymasterData>,
+    pub account_deployment_data: Option<AccountDeploymentData>,
+    pub max_fee: Option<Fee>,
+    pub version: TransactionVersion,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct DeployTransaction {
+    pub contract_address_salt: ContractAddressSalt,
+    pub class_hash: ClassHash,
+    pub constructor_calldata: Calldata,
+    pub version: TransactionVersion,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct DeployAccountTransaction {
+    pub resource_bounds: Option<ResourceBoundsMapping>,
+    pub tip: Option<Tip>,
+    pub signature: TransactionSignature,
+    pub nonce: Nonce,
+    pub class_hash: ClassHash,
+    pub contract_address_salt: ContractAddressSalt,
+    pub constructor_calldata: Calldata,
+    pub nonce_data_availability_mode: Option<DataAvailabilityMode>,
+    pub fee_data_availability_mode: Option<DataAvailabilityMode>,
+    pub paymaster_data: Option<PaymasterData>,
+    pub max_fee: Option<Fee>,
+    pub version: TransactionVersion,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct InvokeTransaction {
+    pub resource_bounds: Option<ResourceBoundsMapping>,
+    pub tip: Option<Tip>,
+    pub calldata: Calldata,
+    // Invoke V0 transactions name this field `contract_address`.
+    #[serde(alias = "contract_address")]
+    pub sender_address: ContractAddress,
+    pub entry_point_selector: Option<EntryPointSelector>,
+    pub nonce: Option<Nonce>,
+    pub max_fee: Option<Fee>,
+    pub signature: TransactionSignature,
+    pub nonce_data_availability_mode: Option<DataAvailabilityMode>,
+    pub fee_data_availability_mode: Option<DataAvailabilityMode>,
+    pub paymaster_data: Option<PaymasterData>,
+    pub account_deployment_data: Option<AccountDeploymentData>,
+    pub version: TransactionVersion,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct L1HandlerTransaction {
+    pub version: TransactionVersion,
+    #[serde(default)]
+    pub nonce: Nonce,
+    pub contract_address: ContractAddress,
+    pub entry_point_selector: EntryPointSelector,
+    pub calldata: Calldata,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq)]
+pub struct ResourceBoundsMapping {
+    pub l1_gas: ResourceBounds,
+    pub l2_gas: ResourceBounds,
+}
+
+impl From<ResourceBoundsMapping> for starknet_api::transaction::ResourceBoundsMapping {
+    fn from(resource_bounds: ResourceBoundsMapping) -> Self {
+        Self(BTreeMap::from([
+            (Resource::L1Gas, resource_bounds.l1_gas),
+            (Resource::L2Gas, resource_bounds.l2_gas),
+        ]))
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct TransactionReceipt {
+    pub transaction_hash: TransactionHash,
+    pub actual_fee: FeePayment,
+    pub execution_status: TransactionExecutionStatus,
+    pub revert_reason: Option<String>,
+    pub messages_sent: Vec<L2ToL1Message>,
+    pub events: Vec<Event>,
+    pub execution_resources: ExecutionResources,
+    // Only the receipts of deploy and deploy account transactions contain this field.
+    pub contract_address: Option<ContractAddress>,
+}
+
+impl TransactionReceipt {
+    fn into_client_receipt(
+        self,
+        transaction_index: TransactionOffsetInBlock,
+    ) -> ClientTransactionReceipt {
+        ClientTransactionReceipt {
+            transaction_index,
+            transaction_hash: self.transaction_hash,
+            // JSON-RPC receipts contain only the hash of the consumed message.
+            l1_to_l2_consumed_message: L1ToL2Message::default(),
+            l2_to_l1_messages: self.messages_sent,
+            events: self.events,
+            execution_resources: self.execution_resources.into(),
+            actual_fee: self.actual_fee.amount,
+            execution_status: self.execution_status,
+            revert_error: self.revert_reason,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct FeePayment {
+    pub amount: Fee,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Eq, PartialEq)]
+pub struct ExecutionResources {
+    pub steps: u64,
+    #[serde(default)]
+    pub memory_holes: u64,
+    pub range_check_builtin_applications: Option<u64>,
+    pub pedersen_builtin_applications: Option<u64>,
+    pub poseidon_builtin_applications: Option<u64>,
+    pub ec_op_builtin_applications: Option<u64>,
+    pub ecdsa_builtin_applications: Option<u64>,
+    pub bitwise_builtin_applications: Option<u64>,
+    pub keccak_builtin_applications: Option<u64>,
+    pub segment_arena_builtin: Option<u64>,
+    pub data_availability: DataAvailabilityResources,
+}
+
+impl From<ExecutionResources> for ClientExecutionResources {
+    fn from(execution_resources: ExecutionResources) -> Self {
+        let builtin_instance_counter: HashMap<Builtin, u64> = [
+            (Builtin::RangeCheck, execution_resources.range_check_builtin_applications),
+            (Builtin::Pedersen, execution_resources.pedersen_builtin_applications),
+            (Builtin::Poseidon, execution_resources.poseidon_builtin_applications),
+            (Builtin::EcOp, execution_resources.ec_op_builtin_applications),
+            (Builtin::Ecdsa, execution_resources.ecdsa_builtin_applications),
+            (Builtin::Bitwise, execution_resources.bitwise_builtin_applications),
+            (Builtin::Keccak, execution_resources.keccak_builtin_applications),
+            (Builtin::SegmentArena, execution_resources.segment_arena_builtin),
+        ]
+        .into_iter()
+        .filter_map(|(builtin, count)| count.map(|count| (builtin, count)))
+        .collect();
+        Self {
+            n_steps: execution_resources.steps,
+            builtin_instance_counter,
+            n_memory_holes: execution_resources.memory_holes,
+            data_availability: Some(execution_resources.data_availability),
+        }
+    }
+}
+
+/// A state update as returned by `starknet_getStateUpdate`.
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct StateUpdate {
+    pub block_hash: BlockHash,
+    pub new_root: GlobalRoot,
+    pub old_root: GlobalRoot,
+    pub state_diff: StateDiff,
+}
+
+impl From<StateUpdate> for ClientStateUpdate {
+    fn from(state_update: StateUpdate) -> Self {
+        Self {
+            block_hash: state_update.block_hash,
+            new_root: state_update.new_root,
+            old_root: state_update.old_root,
+            state_diff: state_update.state_diff.into(),
+        }
+    }
+}
+
+/// The state update of the pending block as returned by `starknet_getStateUpdate`.
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct PendingStateUpdate {
+    pub old_root: GlobalRoot,
+    pub state_diff: StateDiff,
+}
+
+impl From<PendingStateUpdate> for ClientPendingStateUpdate {
+    fn from(state_update: PendingStateUpdate) -> Self {
+        Self { old_root: state_update.old_root, state_diff: state_update.state_diff.into() }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Eq, PartialEq)]
+pub struct StateDiff {
+    pub storage_diffs: Vec<StorageDiff>,
+    pub deprecated_declared_classes: Vec<ClassHash>,
+    pub declared_classes: Vec<DeclaredClassHashEntry>,
+    pub deployed_contracts: Vec<DeployedContract>,
+    pub replaced_classes: Vec<ReplacedClass>,
+    pub nonces: Vec<ContractNonce>,
+}
+
+impl From<StateDiff> for ClientStateDiff {
+    fn from(state_diff: StateDiff) -> Self {
+        Self {
+            storage_diffs: state_diff
+                .storage_diffs
+                .into_iter()
+                .map(|StorageDiff { address, storage_entries }| {
+                    (
+                        address,
+                        storage_entries
+                            .into_iter()
+                            .map(|StorageDiffEntry { key, value }| StorageEntry { key, value })
+                            .collect(),
+                    )
+                })
+                .collect(),
+            deployed_contracts: state_diff.deployed_contracts,
+            declared_classes: state_diff.declared_classes,
+            old_declared_contracts: state_diff.deprecated_declared_classes,
+            nonces: state_diff
+                .nonces
+                .into_iter()
+                .map(|ContractNonce { contract_address, nonce }| (contract_address, nonce))
+                .collect::<IndexMap<_, _>>(),
+            replaced_classes: state_diff
+                .replaced_classes
+                .into_iter()
+                .map(|ReplacedClass { contract_address, class_hash }| ClientReplacedClass {
+                    address: contract_address,
+                    class_hash,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct StorageDiff {
+    pub address: ContractAddress,
+    pub storage_entries: Vec<StorageDiffEntry>,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct StorageDiffEntry {
+    pub key: StorageKey,
+    pub value: StarkFelt,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct ReplacedClass {
+    pub contract_address: ContractAddress,
+    pub class_hash: ClassHash,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct ContractNonce {
+    pub contract_address: ContractAddress,
+    pub nonce: Nonce,
+}
+
+/// A contract class as returned by `starknet_getClass`.
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum GenericJsonRpcContractClass {
+    Cairo0ContractClass(CompressedDeprecatedContractClass),
+    Cairo1ContractClass(ContractClass),
+}
+
+impl GenericJsonRpcContractClass {
+    pub(crate) fn into_client_class(
+        self,
+        class_hash: ClassHash,
+    ) -> ReaderClientResult<GenericContractClass> {
+        match self {
+            GenericJsonRpcContractClass::Cairo0ContractClass(class) => {
+                Ok(GenericContractClass::Cairo0ContractClass(DeprecatedContractClass {
+                    abi: class.abi,
+                    program: decompress_program(&class.program)
+                        .map_err(|msg| ReaderClientError::BadContractClass { class_hash, msg })?,
+                    entry_points_by_type: class.entry_points_by_type,
+                }))
+            }
+            GenericJsonRpcContractClass::Cairo1ContractClass(class) => {
+                Ok(GenericContractClass::Cairo1ContractClass(class))
+            }
+        }
+    }
+}
+
+/// A deprecated contract class whose program is gzip-compressed and base64-encoded.
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct CompressedDeprecatedContractClass {
+    pub abi: Option<Vec<ContractClassAbiEntry>>,
+    pub program: String,
+    pub entry_points_by_type: HashMap<DeprecatedEntryPointType, Vec<DeprecatedEntryPoint>>,
+}
+
+fn decompress_program(program: &str) -> Result<Program, String> {
+    let compressed_program = base64::decode(program).map_err(|err| err.to_string())?;
+    serde_json::from_reader(GzDecoder::new(compressed_program.as_slice()))
+        .map_err(|err| err.to_string())
+}
@@ -0,0 +1,275 @@
+use std::io::Write;
+
+use assert_matches::assert_matches;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use mockito::{mock, Matcher};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, EventCommitment, TransactionCommitment};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+
+use super::StarknetJsonRpcClient;
+use crate::reader::{
+    BlockOrDeprecated,
+    ContractClass,
+    GenericContractClass,
+    ReaderClientError,
+    StarknetReader,
+    StateUpdate,
+};
+use crate::test_utils::read_resource::read_resource_file;
+use crate::test_utils::retry::get_test_config;
+
+const NODE_VERSION: &str = "NODE VERSION";
+
+// Each test uses a different path since all the tests share the same mock server.
+fn json_rpc_client(path: &str) -> StarknetJsonRpcClient {
+    StarknetJsonRpcClient::new(
+        &format!("{}/{path}", mockito::server_url()),
+        None,
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap()
+}
+
+fn result_body(result: Value) -> String {
+    json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string()
+}
+
+fn error_body(code: i64, message: &str) -> String {
+    json!({"jsonrpc": "2.0", "id": 1, "error": {"code": code, "message": message}}).to_string()
+}
+
+fn request_matcher(method: &str, params: Value) -> Matcher {
+    Matcher::PartialJson(json!({"jsonrpc": "2.0", "method": method, "params": params}))
+}
+
+fn resource_value(path: &str) -> Value {
+    serde_json::from_str(&read_resource_file(path)).unwrap()
+}
+
+#[tokio::test]
+async fn get_block() {
+    let starknet_client = json_rpc_client("get_block");
+    // The JSON-RPC block is the same as the feeder gateway block, except for the commitments that
+    // are missing in the JSON-RPC block header.
+    let mut expected_block: BlockOrDeprecated =
+        serde_json::from_str(&read_resource_file("reader/block_post_0_13_1.json")).unwrap();
+    let BlockOrDeprecated::Current(block) = &mut expected_block else {
+        panic!("Expected a block post v0.13.1.");
+    };
+    block.transaction_commitment = TransactionCommitment::default();
+    block.event_commitment = EventCommitment::default();
+
+    let mock_block = mock("POST", "/get_block")
+        .match_body(request_matcher(
+            "starknet_getBlockWithReceipts",
+            json!({"block_id": {"block_number": 329525}}),
+        ))
+        .with_status(200)
+        .with_body(result_body(resource_value("reader/json_rpc/block_with_receipts.json")))
+        .create();
+    let block = starknet_client.block(BlockNumber(329525)).await.unwrap().unwrap();
+    mock_block.assert();
+    assert_eq!(block, expected_block);
+
+    // Non-existing block.
+    let mock_no_block = mock("POST", "/get_block")
+        .match_body(request_matcher(
+            "starknet_getBlockWithReceipts",
+            json!({"block_id": {"block_number": 9999999999_u64}}),
+        ))
+        .with_status(200)
+        .with_body(error_body(24, "Block not found"))
+        .create();
+    let block = starknet_client.block(BlockNumber(9999999999)).await.unwrap();
+    mock_no_block.assert();
+    assert!(block.is_none());
+}
+
+#[tokio::test]
+async fn latest_block() {
+    let starknet_client = json_rpc_client("latest_block");
+    let mock_block = mock("POST", "/latest_block")
+        .match_body(request_matcher("starknet_getBlockWithReceipts", json!({"block_id": "latest"})))
+        .with_status(200)
+        .with_body(result_body(resource_value("reader/json_rpc/block_with_receipts.json")))
+        .create();
+    let latest_block = starknet_client.latest_block().await.unwrap().unwrap();
+    mock_block.assert();
+    assert_eq!(latest_block.block_number(), BlockNumber(329525));
+}
+
+#[tokio::test]
+async fn state_update() {
+    let starknet_client = json_rpc_client("state_update");
+    let expected_state_update: StateUpdate =
+        serde_json::from_str(&read_resource_file("reader/block_state_update.json")).unwrap();
+
+    let mock_state_update = mock("POST", "/state_update")
+        .match_body(request_matcher(
+            "starknet_getStateUpdate",
+            json!({"block_id": {"block_number": 123456}}),
+        ))
+        .with_status(200)
+        .with_body(result_body(resource_value("reader/json_rpc/state_update.json")))
+        .create();
+    let state_update = starknet_client.state_update(BlockNumber(123456)).await.unwrap().unwrap();
+    mock_state_update.assert();
+    assert_eq!(state_update, expected_state_update);
+}
+
+#[tokio::test]
+async fn contract_class() {
+    let starknet_client = json_rpc_client("contract_class");
+    let class_hash = ClassHash(stark_felt!("0x1"));
+    let expected_contract_class: ContractClass =
+        serde_json::from_str(&read_resource_file("reader/contract_class.json")).unwrap();
+
+    let mock_class = mock("POST", "/contract_class")
+        .match_body(request_matcher(
+            "starknet_getClass",
+            json!({"block_id": "pending", "class_hash": class_hash}),
+        ))
+        .with_status(200)
+        .with_body(result_body(resource_value("reader/contract_class.json")))
+        .create();
+    let contract_class = starknet_client.class_by_hash(class_hash).await.unwrap().unwrap();
+    mock_class.assert();
+    assert_matches!(
+        contract_class,
+        GenericContractClass::Cairo1ContractClass(class) if class == expected_contract_class
+    );
+}
+
+#[tokio::test]
+async fn deprecated_contract_class() {
+    let starknet_client = json_rpc_client("deprecated_contract_class");
+    let class_hash = ClassHash(stark_felt!("0x1"));
+    let expected_contract_class: DeprecatedContractClass =
+        serde_json::from_str(&read_resource_file("reader/deprecated_contract_class.json")).unwrap();
+
+    // JSON-RPC nodes return the program of a deprecated class compressed and encoded in base64.
+    let mut json_rpc_class = resource_value("reader/deprecated_contract_class.json");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(json_rpc_class["program"].to_string().as_bytes()).unwrap();
+    json_rpc_class["program"] = Value::String(base64::encode(encoder.finish().unwrap()));
+
+    let mock_class = mock("POST", "/deprecated_contract_class")
+        .match_body(request_matcher(
+            "starknet_getClass",
+            json!({"block_id": "pending", "class_hash": class_hash}),
+        ))
+        .with_status(200)
+        .with_body(result_body(json_rpc_class))
+        .create();
+    let contract_class = starknet_client.class_by_hash(class_hash).await.unwrap().unwrap();
+    mock_class.assert();
+    assert_matches!(
+        contract_class,
+        GenericContractClass::Cairo0ContractClass(class) if class == expected_contract_class
+    );
+
+    // Undeclared class.
+    let undeclared_class_hash = ClassHash(stark_felt!("0x7"));
+    let mock_undeclared = mock("POST", "/deprecated_contract_class")
+        .match_body(request_matcher(
+            "starknet_getClass",
+            json!({"block_id": "pending", "class_hash": undeclared_class_hash}),
+        ))
+        .with_status(200)
+        .with_body(error_body(28, "Class hash not found"))
+        .create();
+    let class = starknet_client.class_by_hash(undeclared_class_hash).await.unwrap();
+    mock_undeclared.assert();
+    assert!(class.is_none());
+}
+
+#[tokio::test]
+async fn pending_data() {
+    let starknet_client = json_rpc_client("pending_data");
+    let expected_block: BlockOrDeprecated =
+        serde_json::from_str(&read_resource_file("reader/block_post_0_13_1.json")).unwrap();
+    let expected_state_update: StateUpdate =
+        serde_json::from_str(&read_resource_file("reader/block_state_update.json")).unwrap();
+
+    // The pending block and state update are the same as the accepted ones, without the fields
+    // that are set when the block is closed.
+    let mut pending_block = resource_value("reader/json_rpc/block_with_receipts.json");
+    for field in ["status", "block_hash", "block_number", "new_root"] {
+        pending_block.as_object_mut().unwrap().remove(field);
+    }
+    let mut pending_state_update = resource_value("reader/json_rpc/state_update.json");
+    for field in ["block_hash", "new_root"] {
+        pending_state_update.as_object_mut().unwrap().remove(field);
+    }
+    let mock_block = mock("POST", "/pending_data")
+        .match_body(request_matcher("starknet_getBlockWithReceipts", json!({"block_id": "pending"})))
+        .with_status(200)
+        .with_body(result_body(pending_block))
+        .create();
+    let mock_state_update = mock("POST", "/pending_data")
+        .match_body(request_matcher("starknet_getStateUpdate", json!({"block_id": "pending"})))
+        .with_status(200)
+        .with_body(result_body(pending_state_update))
+        .create();
+    let pending_data = starknet_client.pending_data().await.unwrap().unwrap();
+    mock_block.assert();
+    mock_state_update.assert();
+
+    assert_eq!(pending_data.block.parent_block_hash(), expected_block.parent_block_hash());
+    assert_eq!(pending_data.block.block_hash(), None);
+    assert_eq!(pending_data.block.transactions(), expected_block.transactions());
+    assert_eq!(pending_data.block.transaction_receipts(), expected_block.transaction_receipts());
+    assert_eq!(pending_data.state_update.old_root, expected_state_update.old_root);
+    assert_eq!(pending_data.state_update.state_diff, expected_state_update.state_diff);
+}
+
+#[tokio::test]
+async fn json_rpc_error() {
+    let starknet_client = json_rpc_client("json_rpc_error");
+    let mock_error = mock("POST", "/json_rpc_error")
+        .with_status(200)
+        .with_body(error_body(-32603, "Internal error"))
+        .create();
+    let err = starknet_client.state_update(BlockNumber(0)).await.unwrap_err();
+    mock_error.assert();
+    assert_matches!(
+        err,
+        ReaderClientError::JsonRpcError { code: -32603, message } if message == "Internal error"
+    );
+}
+
+#[tokio::test]
+async fn unsupported_methods() {
+    let starknet_client = json_rpc_client("unsupported_methods");
+    assert_matches!(
+        starknet_client.block_signature(BlockNumber(0)).await,
+        Err(ReaderClientError::UnsupportedMethod { .. })
+    );
+    assert_matches!(
+        starknet_client.sequencer_pub_key().await,
+        Err(ReaderClientError::UnsupportedMethod { .. })
+    );
+    assert_matches!(
+        starknet_client.compiled_class_by_hash(ClassHash(stark_felt!("0x1"))).await,
+        Err(ReaderClientError::UnsupportedMethod { .. })
+    );
+}
+
+#[tokio::test]
+async fn is_alive() {
+    let starknet_client = json_rpc_client("is_alive");
+    let mock_is_alive = mock("POST", "/is_alive")
+        .match_body(request_matcher("starknet_specVersion", json!([])))
+        .with_status(200)
+        .with_body(result_body(json!("0.7.1")))
+        .create();
+    assert!(starknet_client.is_alive().await);
+    mock_is_alive.assert();
+}
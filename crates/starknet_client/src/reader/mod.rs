@@ -2,6 +2,8 @@
 //!
 //! [`Starknet`]: https://starknet.io/
 
+pub mod failover;
+pub mod json_rpc;
 pub mod objects;
 #[cfg(test)]
 mod starknet_feeder_gateway_client_test;
@@ -14,7 +16,7 @@ use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use mockall::automock;
 use papyrus_common::pending_classes::ApiContractClass;
 use serde::{Deserialize, Serialize};
-use starknet_api::block::BlockNumber;
+use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::{ClassHash, SequencerPublicKey};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::transaction::TransactionHash;
@@ -22,6 +24,8 @@ use starknet_api::StarknetApiError;
 use tracing::{debug, error, instrument};
 use url::Url;
 
+pub use crate::reader::failover::FailoverStarknetReader;
+pub use crate::reader::json_rpc::StarknetJsonRpcClient;
 pub use crate::reader::objects::block::{
     BlockOrDeprecated,
     BlockSignatureData,
@@ -64,6 +68,26 @@ pub enum ReaderClientError {
     TransactionReceiptsError(#[from] TransactionReceiptsError),
     #[error("Invalid transaction: {:?}, error: {:?}.", tx_hash, msg)]
     BadTransaction { tx_hash: TransactionHash, msg: String },
+    #[error("Invalid contract class: {:?}, error: {:?}.", class_hash, msg)]
+    BadContractClass { class_hash: ClassHash, msg: String },
+    /// A client error representing errors returned by a Starknet JSON-RPC node.
+    #[error("JSON-RPC error code: {:?}, message: {:?}.", code, message)]
+    JsonRpcError { code: i64, message: String },
+    /// A client error representing requests that the reader can't serve.
+    #[error("The reader doesn't support {method}.")]
+    UnsupportedMethod { method: &'static str },
+    /// A client error representing two readers that returned different hashes for a block.
+    #[error(
+        "Block {} has hash {:?} in one source and {:?} in another.",
+        block_number,
+        block_hash,
+        other_block_hash
+    )]
+    BlockHashMismatch {
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        other_block_hash: BlockHash,
+    },
 }
 
 pub type ReaderClientResult<T> = Result<T, ReaderClientError>;